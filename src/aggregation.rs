@@ -0,0 +1,483 @@
+//! aggregation of fibonacci proofs
+//!
+//! [`AggregationCircuit`] verifies the inner proofs with [`snark_verifier`]'s halo2
+//! loader and exposes the KZG accumulator as its instances, so a single outer proof
+//! (plus one pairing check on the accumulator) attests to all of them. the instances of
+//! the inner proofs follow the accumulator, copy constrained to the ones they were
+//! verified with, so the outer proof also says which `n` and `fib(n)` were proven.
+//!
+//! inner proofs are read in-circuit, so they have to be created with the poseidon
+//! transcript by [`prove_fib`] and share the SRS of the outer proof (see [`inner_params`]).
+//! outer proofs use the same transcript and are checked natively by [`verify`].
+
+use crate::{
+    fib::{self, FibCircuit, PublicInputs},
+    prover,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
+    plonk::{self, create_proof, Circuit, ConstraintSystem, ProvingKey},
+    poly::{
+        commitment::Params,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::ProverGWC,
+        },
+    },
+    transcript::TranscriptWriterBuffer,
+};
+use rand_core::OsRng;
+use snark_verifier::{
+    loader::{
+        self,
+        halo2::halo2_wrong_ecc::{
+            self,
+            integer::rns::Rns,
+            maingate::{
+                MainGate, MainGateConfig, MainGateInstructions, RangeChip, RangeConfig,
+                RangeInstructions, RegionCtx,
+            },
+            EccConfig,
+        },
+        native::NativeLoader,
+    },
+    pcs::{
+        kzg::{
+            Gwc19, Kzg, KzgAccumulator, KzgAs, KzgDecidingKey, KzgSuccinctVerifyingKey,
+            LimbsEncoding,
+        },
+        AccumulationScheme, AccumulationSchemeProver,
+    },
+    system::halo2::{self as system, compile, Config},
+    util::arithmetic::fe_to_limbs,
+    verifier::{self, PlonkVerifier},
+    Protocol,
+};
+use std::rc::Rc;
+
+/// k of the outer aggregation proof
+pub const AGG_K: u32 = 22;
+
+const LIMBS: usize = 4;
+const BITS: usize = 68;
+const T: usize = 5;
+const RATE: usize = 4;
+const R_F: usize = 8;
+const R_P: usize = 60;
+
+type Pcs = Kzg<Bn256, Gwc19>;
+type As = KzgAs<Pcs>;
+type Plonk = verifier::Plonk<Pcs, LimbsEncoding<LIMBS, BITS>>;
+type Svk = KzgSuccinctVerifyingKey<G1Affine>;
+type BaseFieldEccChip = halo2_wrong_ecc::BaseFieldEccChip<G1Affine, LIMBS, BITS>;
+type Halo2Loader<'a> = loader::halo2::Halo2Loader<'a, G1Affine, BaseFieldEccChip>;
+type LoadedScalar<'a> = loader::halo2::Scalar<'a, G1Affine, BaseFieldEccChip>;
+type PoseidonTranscript<L, S> =
+    system::transcript::halo2::PoseidonTranscript<G1Affine, L, S, T, RATE, R_F, R_P>;
+
+/// a proof together with what is needed to verify it in-circuit
+#[derive(Clone)]
+pub struct Snark {
+    pub protocol: Protocol<G1Affine>,
+    pub instances: Vec<Vec<Fr>>,
+    pub proof: Vec<u8>,
+}
+
+impl Snark {
+    /// the instances that are not accumulator limbs, which an aggregation of this snark
+    /// exposes after its own accumulator
+    pub fn carried_instances(&self) -> Vec<Fr> {
+        carried(&self.protocol, &self.instances)
+    }
+}
+
+#[derive(Clone)]
+struct SnarkWitness {
+    protocol: Protocol<G1Affine>,
+    instances: Vec<Vec<Value<Fr>>>,
+    proof: Value<Vec<u8>>,
+}
+
+impl From<&Snark> for SnarkWitness {
+    fn from(snark: &Snark) -> Self {
+        Self {
+            protocol: snark.protocol.clone(),
+            instances: snark
+                .instances
+                .iter()
+                .map(|column| column.iter().copied().map(Value::known).collect())
+                .collect(),
+            proof: Value::known(snark.proof.clone()),
+        }
+    }
+}
+
+impl SnarkWitness {
+    fn without_witnesses(&self) -> Self {
+        Self {
+            protocol: self.protocol.clone(),
+            instances: self
+                .instances
+                .iter()
+                .map(|column| vec![Value::unknown(); column.len()])
+                .collect(),
+            proof: Value::unknown(),
+        }
+    }
+
+    fn proof(&self) -> Value<&[u8]> {
+        self.proof.as_ref().map(Vec::as_slice)
+    }
+}
+
+/// the params for the inner proofs, downsized from the params of the outer proof to
+/// [`FibCircuit::k`]
+pub fn inner_params(params: &ParamsKZG<Bn256>) -> ParamsKZG<Bn256> {
+    let mut params = params.clone();
+    params.downsize(FibCircuit::<Fr>::k());
+    params
+}
+
+/// prove fib(n) with the poseidon transcript, so the proof can be aggregated
+pub fn prove_fib(params: &ParamsKZG<Bn256>, n: u64) -> Result<Snark, plonk::Error> {
+    let (n_0, n_1) = (Fr::zero(), Fr::one());
+    let circuit: FibCircuit<Fr> = FibCircuit {
+        n: Fr::from(n),
+        n_0,
+        n_1,
+    };
+    let instances = PublicInputs::new()
+        .with_initials(n_0, n_1)
+        .with_n(Fr::from(n))
+        .with_result(fib::expected(n_0, n_1, n))
+        .to_instance_columns();
+
+    let pk = prover::keygen(params, &circuit)?;
+    let protocol = compile(
+        params,
+        pk.get_vk(),
+        Config::kzg().with_num_instance(vec![instances[0].len()]),
+    );
+    let proof = prove_poseidon(params, &pk, circuit, &instances)?;
+    Ok(Snark {
+        protocol,
+        instances,
+        proof,
+    })
+}
+
+fn prove_poseidon<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[Vec<Fr>],
+) -> Result<Vec<u8>, plonk::Error> {
+    let columns: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = PoseidonTranscript::<NativeLoader, _>::init(Vec::new());
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[columns.as_slice()],
+        OsRng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+/// verify `snark` natively, including the pairing check on any accumulator it carries
+pub fn verify(params: &ParamsKZG<Bn256>, snark: &Snark) -> bool {
+    let svk: Svk = params.get_g()[0].into();
+    let dk: KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
+    let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
+    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+        .and_then(|proof| Plonk::verify(&svk, &dk, &snark.protocol, &snark.instances, &proof))
+        .unwrap_or(false)
+}
+
+/// the instances of a proof that are not the limbs of an accumulator it carries, column
+/// by column. these are what an aggregation of the proof passes on as its own instances
+fn carried<T: Clone>(protocol: &Protocol<G1Affine>, instances: &[Vec<T>]) -> Vec<T> {
+    let limbs: Vec<_> = protocol.accumulator_indices.iter().flatten().collect();
+    let limbs = &limbs;
+    instances
+        .iter()
+        .enumerate()
+        .flat_map(|(column, values)| {
+            values
+                .iter()
+                .enumerate()
+                .filter(move |(row, _)| !limbs.contains(&&(column, *row)))
+                .map(|(_, value)| value.clone())
+        })
+        .collect()
+}
+
+/// the accumulator of `snarks`, and the loaded instances they carry in order
+fn aggregate_in_circuit<'a>(
+    svk: &Svk,
+    loader: &Rc<Halo2Loader<'a>>,
+    snarks: &[SnarkWitness],
+    as_proof: Value<&'_ [u8]>,
+) -> (
+    KzgAccumulator<G1Affine, Rc<Halo2Loader<'a>>>,
+    Vec<LoadedScalar<'a>>,
+) {
+    let mut accumulators = vec![];
+    let mut instances = vec![];
+    for snark in snarks {
+        let protocol = snark.protocol.loaded(loader);
+        let assigned: Vec<Vec<_>> = snark
+            .instances
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|value| loader.assign_scalar(*value))
+                    .collect()
+            })
+            .collect();
+        let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, snark.proof());
+        let proof = Plonk::read_proof(svk, &protocol, &assigned, &mut transcript).unwrap();
+        accumulators.extend(Plonk::succinct_verify(svk, &protocol, &assigned, &proof).unwrap());
+        instances.extend(carried(&snark.protocol, &assigned));
+    }
+
+    let mut transcript = PoseidonTranscript::<Rc<Halo2Loader>, _>::new(loader, as_proof);
+    let proof = As::read_proof(&Default::default(), &accumulators, &mut transcript).unwrap();
+    let accumulator = As::verify(&Default::default(), &accumulators, &proof).unwrap();
+    (accumulator, instances)
+}
+
+#[derive(Clone)]
+pub struct AggregationConfig {
+    main_gate_config: MainGateConfig,
+    range_config: RangeConfig,
+}
+
+impl AggregationConfig {
+    fn ecc_chip(&self) -> BaseFieldEccChip {
+        BaseFieldEccChip::new(EccConfig::new(
+            self.range_config.clone(),
+            self.main_gate_config.clone(),
+        ))
+    }
+}
+
+/// verifies a batch of [`Snark`]s in-circuit, exposing the accumulator limbs and then the
+/// instances of the snarks as instances
+#[derive(Clone)]
+pub struct AggregationCircuit {
+    svk: Svk,
+    snarks: Vec<SnarkWitness>,
+    instances: Vec<Fr>,
+    as_proof: Value<Vec<u8>>,
+}
+
+impl AggregationCircuit {
+    pub fn new(params: &ParamsKZG<Bn256>, snarks: &[Snark]) -> Self {
+        let svk: Svk = params.get_g()[0].into();
+
+        let accumulators = snarks
+            .iter()
+            .flat_map(|snark| {
+                let mut transcript =
+                    PoseidonTranscript::<NativeLoader, _>::new(snark.proof.as_slice());
+                let proof =
+                    Plonk::read_proof(&svk, &snark.protocol, &snark.instances, &mut transcript)
+                        .unwrap();
+                Plonk::succinct_verify(&svk, &snark.protocol, &snark.instances, &proof).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let mut transcript = PoseidonTranscript::<NativeLoader, _>::new(Vec::new());
+        let accumulator =
+            As::create_proof(&Default::default(), &accumulators, &mut transcript, OsRng).unwrap();
+        let as_proof = transcript.finalize();
+
+        let KzgAccumulator { lhs, rhs } = accumulator;
+        let mut instances = [lhs.x, lhs.y, rhs.x, rhs.y]
+            .map(fe_to_limbs::<_, _, LIMBS, BITS>)
+            .concat();
+        for snark in snarks {
+            instances.extend(snark.carried_instances());
+        }
+
+        Self {
+            svk,
+            snarks: snarks.iter().map(SnarkWitness::from).collect(),
+            instances,
+            as_proof: Value::known(as_proof),
+        }
+    }
+
+    /// positions of the accumulator limbs in the instance columns
+    pub fn accumulator_indices() -> Vec<(usize, usize)> {
+        (0..4 * LIMBS).map(|idx| (0, idx)).collect()
+    }
+
+    /// the accumulator limbs, then the instances carried by every snark
+    pub fn num_instance(&self) -> Vec<usize> {
+        let carried: usize = self
+            .snarks
+            .iter()
+            .map(|snark| carried(&snark.protocol, &snark.instances).len())
+            .sum();
+        vec![4 * LIMBS + carried]
+    }
+
+    pub fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![self.instances.clone()]
+    }
+
+    fn as_proof(&self) -> Value<&[u8]> {
+        self.as_proof.as_ref().map(Vec::as_slice)
+    }
+}
+
+impl Circuit<Fr> for AggregationCircuit {
+    type Config = AggregationConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            svk: self.svk,
+            snarks: self
+                .snarks
+                .iter()
+                .map(SnarkWitness::without_witnesses)
+                .collect(),
+            instances: Vec::new(),
+            as_proof: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let main_gate_config = MainGate::<Fr>::configure(meta);
+        let range_config = RangeChip::<Fr>::configure(
+            meta,
+            &main_gate_config,
+            vec![BITS / LIMBS],
+            Rns::<Fq, Fr, LIMBS, BITS>::construct().overflow_lengths(),
+        );
+        AggregationConfig {
+            main_gate_config,
+            range_config,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), plonk::Error> {
+        let main_gate = MainGate::<Fr>::new(config.main_gate_config.clone());
+        let range_chip = RangeChip::<Fr>::new(config.range_config.clone());
+        range_chip.load_table(&mut layouter)?;
+
+        let (accumulator_limbs, instances) = layouter.assign_region(
+            || "aggregate",
+            |region| {
+                let ctx = RegionCtx::new(region, 0);
+                let loader = Halo2Loader::new(config.ecc_chip(), ctx);
+                let (accumulator, instances) =
+                    aggregate_in_circuit(&self.svk, &loader, &self.snarks, self.as_proof());
+
+                let limbs = [accumulator.lhs, accumulator.rhs]
+                    .iter()
+                    .map(|point| {
+                        loader
+                            .ecc_chip()
+                            .assign_ec_point_to_limbs(&mut loader.ctx_mut(), point.assigned())
+                    })
+                    .collect::<Result<Vec<_>, plonk::Error>>()?;
+                let instances: Vec<_> = instances.iter().map(|scalar| scalar.assigned()).collect();
+                Ok((limbs.into_iter().flatten(), instances))
+            },
+        )?;
+
+        for (row, limb) in accumulator_limbs.enumerate() {
+            main_gate.expose_public(layouter.namespace(|| "accumulator"), limb, row)?;
+        }
+        for (row, instance) in instances.into_iter().enumerate() {
+            main_gate.expose_public(
+                layouter.namespace(|| "instances"),
+                instance,
+                4 * LIMBS + row,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// aggregate `snarks` into a single proof of [`AggregationCircuit`]
+///
+/// `params` are the params of the outer proof, see [`inner_params`] for the inner ones.
+/// the result carries its accumulator, so it can itself be aggregated again, and the
+/// instances of `snarks` after it.
+pub fn aggregate(params: &ParamsKZG<Bn256>, snarks: &[Snark]) -> Result<Snark, plonk::Error> {
+    let circuit = AggregationCircuit::new(params, snarks);
+    let instances = circuit.instances();
+    let pk = prover::keygen(params, &circuit)?;
+    let protocol = compile(
+        params,
+        pk.get_vk(),
+        Config::kzg()
+            .with_num_instance(circuit.num_instance())
+            .with_accumulator_indices(Some(AggregationCircuit::accumulator_indices())),
+    );
+    let proof = prove_poseidon(params, &pk, circuit, &instances)?;
+    Ok(Snark {
+        protocol,
+        instances,
+        proof,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{params, testing};
+    use halo2_proofs::dev::MockProver;
+
+    #[test]
+    fn aggregates_four_fib_proofs_in_mock_prover() {
+        // the in-circuit verifier only needs the first point of the SRS
+        let params = prover::setup(FibCircuit::<Fr>::k());
+        let snarks = [3, 5, 10, 100]
+            .into_iter()
+            .map(|n| prove_fib(&params, n).unwrap())
+            .collect::<Vec<_>>();
+        let circuit = AggregationCircuit::new(&params, &snarks);
+        let instances = circuit.instances();
+        assert_eq!(instances[0].len(), circuit.num_instance()[0]);
+        let carried: Vec<_> = snarks
+            .iter()
+            .flat_map(|snark| snark.instances.concat())
+            .collect();
+        assert_eq!(instances[0][4 * LIMBS..], carried);
+        MockProver::run(AGG_K, &circuit, instances.clone())
+            .unwrap()
+            .assert_satisfied();
+
+        // the last proof claimed for another fib(n)
+        let mut wrong = instances;
+        *wrong[0].last_mut().unwrap() += Fr::one();
+        testing::assert_fails_permutation(&MockProver::run(AGG_K, &circuit, wrong).unwrap());
+    }
+
+    #[test]
+    #[ignore = "needs k = 22 params, takes minutes"]
+    fn aggregate_four_fib_proofs() {
+        let params = params::load_or_setup(AGG_K).unwrap();
+        let fib_params = inner_params(&params);
+        let snarks = [3, 5, 10, 100]
+            .into_iter()
+            .map(|n| prove_fib(&fib_params, n).unwrap())
+            .collect::<Vec<_>>();
+
+        let aggregated = aggregate(&params, &snarks).unwrap();
+        assert!(verify(&params, &aggregated));
+    }
+}
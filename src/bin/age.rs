@@ -0,0 +1,331 @@
+//! age circuit
+//!
+//! we are going to prove that the holder of a private birthdate, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is at least 18 years old
+//! at a public date, without revealing the birthdate
+//!
+//! | instance                      |
+//! |-------------------------------|
+//! | H(salt, year, month, day)     |
+//! | today.year                    |
+//! | today.month                   |
+//! | today.day                     |
+//!
+//! a date is packed into one number that sorts like the date, after adding a fixed number
+//! of years, 18 to the birthdate for the 18th birthday:
+//!
+//! | row | year       | month       | day       | years | packed     | q_date |
+//! |-----|------------|-------------|-----------|-------|------------|--------|
+//! | 0   | year       | month       | day       | 18    | birthday   | 1      |
+//! | 1   | today.year | today.month | today.day | 0     | today      | 1      |
+//!
+//! - `q_date * (packed - ((year + years) * 2^9 + month * 2^5 + day)) = 0`
+//!
+//! the packing only sorts dates with `month < 2^4` and `day < 2^5`, the birthdate is
+//! checked to be a year of `1..10000`, a month of `1..13` and a day of `1..32` by the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 3 bytes, two comparisons each, and
+//! today is public. the last comparison is the claim:
+//!
+//! | row    | lhs    | rhs      | lt | q_compare |
+//! |--------|--------|----------|----|-----------|
+//! | 2i     | 0      | field    | 1  | 1         |
+//! | 2i + 1 | field  | bound    | 1  | 1         |
+//! | 6      | today  | birthday | 0  | 1         |
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+const N_BYTES: usize = 3;
+const ADULT: u64 = 18;
+/// the exclusive upper bounds of the year, the month and the day
+const BOUNDS: [u64; 3] = [10000, 13, 32];
+
+type Date = [u64; 3];
+
+#[derive(Debug, Clone)]
+struct AgeConfig {
+    salt: Column<Advice>,
+    /// year, month and day
+    date: [Column<Advice>; 3],
+    years: Column<Fixed>,
+    packed: Column<Advice>,
+    q_date: Selector,
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    q_compare: Selector,
+    lt: LtConfig<N_BYTES>,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct AgeCircuit<F> {
+    salt: Value<F>,
+    birthdate: [Value<F>; 3],
+}
+
+impl<F> Default for AgeCircuit<F> {
+    fn default() -> Self {
+        Self {
+            salt: Value::unknown(),
+            birthdate: [(); 3].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AgeCircuit<F> {
+    type Config = AgeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AgeConfig {
+        let salt = meta.advice_column();
+        let date = [(); 3].map(|_| meta.advice_column());
+        let years = meta.fixed_column();
+        let packed = meta.advice_column();
+        let lhs = meta.advice_column();
+        let rhs = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_date = meta.selector();
+        let q_compare = meta.complex_selector();
+        for column in date.into_iter().chain([salt, packed, lhs, rhs]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("date", |meta| {
+            let q_date = meta.query_selector(q_date);
+            let [year, month, day] = date.map(|column| meta.query_advice(column, Rotation::cur()));
+            let years = meta.query_fixed(years, Rotation::cur());
+            let packed = meta.query_advice(packed, Rotation::cur());
+            let shift = |bits: u64| Expression::Constant(F::from(1 << bits));
+            vec![q_date * (packed - ((year + years) * shift(9) + month * shift(5) + day))]
+        });
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| meta.query_advice(lhs, Rotation::cur()),
+            |meta| meta.query_advice(rhs, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        AgeConfig {
+            salt,
+            date,
+            years,
+            packed,
+            q_date,
+            lhs,
+            rhs,
+            q_compare,
+            lt,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: AgeConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let lt = LtChip::<F, N_BYTES>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "u8"))?;
+
+        let (opening, birthday, today) = layouter.assign_region(
+            || "dates",
+            |mut region| {
+                let salt = region.assign_advice(|| "salt", config.salt, 0, || self.salt)?;
+                let mut opening = vec![salt];
+                let mut packed = vec![];
+                for (offset, years) in [ADULT, 0].into_iter().enumerate() {
+                    config.q_date.enable(&mut region, offset)?;
+                    let mut total = Value::known(F::zero());
+                    for (i, (column, bits)) in config.date.iter().zip([9, 5, 0]).enumerate() {
+                        let cell = if offset == 0 {
+                            let field = self.birthdate[i];
+                            let cell =
+                                region.assign_advice(|| "birthdate", *column, 0, || field)?;
+                            opening.push(cell.clone());
+                            cell
+                        } else {
+                            region.assign_advice_from_instance(
+                                || "today",
+                                config.instance,
+                                1 + i,
+                                *column,
+                                offset,
+                            )?
+                        };
+                        let mut field = cell.value().copied();
+                        if i == 0 {
+                            field = field + Value::known(F::from(years));
+                        }
+                        total = total + field * Value::known(F::from(1 << bits));
+                    }
+                    region.assign_fixed(
+                        || "years",
+                        config.years,
+                        offset,
+                        || Value::known(F::from(years)),
+                    )?;
+                    packed.push(region.assign_advice(
+                        || "packed",
+                        config.packed,
+                        offset,
+                        || total,
+                    )?);
+                }
+                Ok((opening, packed[0].clone(), packed[1].clone()))
+            },
+        )?;
+
+        layouter.assign_region(
+            || "compare",
+            |mut region| {
+                let mut rows = vec![];
+                for (field, bound) in opening[1..].iter().zip(BOUNDS) {
+                    // 0 < field < bound
+                    let offset = rows.len();
+                    let zero = region.assign_advice_from_constant(
+                        || "0",
+                        config.lhs,
+                        offset,
+                        F::zero(),
+                    )?;
+                    let above = field.copy_advice(|| "field", &mut region, config.rhs, offset)?;
+                    rows.push((zero, above, F::one()));
+                    let below =
+                        field.copy_advice(|| "field", &mut region, config.lhs, offset + 1)?;
+                    let bound = region.assign_advice_from_constant(
+                        || "bound",
+                        config.rhs,
+                        offset + 1,
+                        F::from(bound),
+                    )?;
+                    rows.push((below, bound, F::one()));
+                }
+                // today >= birthday
+                let offset = rows.len();
+                let lhs = today.copy_advice(|| "today", &mut region, config.lhs, offset)?;
+                let rhs = birthday.copy_advice(|| "birthday", &mut region, config.rhs, offset)?;
+                rows.push((lhs, rhs, F::zero()));
+
+                for (offset, (lhs, rhs, expected)) in rows.iter().enumerate() {
+                    config.q_compare.enable(&mut region, offset)?;
+                    let lt = lt.assign(
+                        &mut region,
+                        offset,
+                        lhs.value().copied(),
+                        rhs.value().copied(),
+                    )?;
+                    region.constrain_constant(lt.cell(), *expected)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment =
+            poseidon.hash(layouter.namespace(|| "H(salt, year, month, day)"), &opening)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+fn commit(salt: u64, birthdate: Date) -> Fp {
+    let inputs: Vec<_> = [salt].into_iter().chain(birthdate).map(Fp::from).collect();
+    poseidon::hash(&inputs)
+}
+
+fn prover(salt: u64, birthdate: Date, commitment: Fp, today: Date) -> MockProver<Fp> {
+    let circuit = AgeCircuit {
+        salt: Value::known(Fp::from(salt)),
+        birthdate: birthdate.map(|field| Value::known(Fp::from(field))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = [commitment]
+        .into_iter()
+        .chain(today.map(Fp::from))
+        .collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, AgeCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let [year, month, day] = config.date;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.salt, "salt")
+                    .with_advice(year, "year")
+                    .with_advice(month, "month")
+                    .with_advice(day, "day")
+                    .with_fixed(config.years, "years")
+                    .with_advice(config.packed, "packed")
+                    .with_advice(config.lhs, "lhs")
+                    .with_advice(config.rhs, "rhs")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_date, "q_date")
+                    .with_selector(config.q_compare, "q_compare")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let salt = 0x5eed;
+    let today = [2024, 6, 1];
+    let birthdate = [2000, 5, 17];
+    let commitment = commit(salt, birthdate);
+    prover(salt, birthdate, commitment, today).assert_satisfied();
+    // the 18th birthday
+    let birthdate = [2006, 6, 1];
+    let commitment = commit(salt, birthdate);
+    prover(salt, birthdate, commitment, today).assert_satisfied();
+
+    // the day before the 18th birthday
+    testing::assert_fails_permutation(&prover(salt, birthdate, commitment, [2024, 5, 31]));
+
+    // a year older than committed
+    testing::assert_fails_permutation(&prover(salt, [2005, 6, 1], commitment, today));
+
+    // a 13th month
+    let thirteenth = [2005, 13, 1];
+    testing::assert_fails_permutation(&prover(salt, thirteenth, commit(salt, thirteenth), today));
+}
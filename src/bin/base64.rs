@@ -0,0 +1,312 @@
+//! base64 decoding circuit
+//!
+//! we are going to prove that a private base64 string of `C` characters, without padding,
+//! decodes to a private string of `3 * C / 4` bytes, both committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), the way an email body
+//! is checked against the hash of its decoded contents
+//!
+//! | instance  |
+//! |-----------|
+//! | H(input)  |
+//! | H(bytes)  |
+//!
+//! every 4 characters are 4 sextets, the 24 bits of 3 bytes, a row per group. a character
+//! is decoded by a lookup into fixed table columns of the 64 characters of the alphabet
+//! and their sextets, with a tag and a first row of 0s:
+//!
+//! | c[k]     | v[k]        | b[k]      | q_decode |
+//! |----------|-------------|-----------|----------|
+//! | char     | its sextet  | byte      | 1        |
+//!
+//! - `(q_decode, q_decode * c, q_decode * v)` in `(tag, char, sextet)` for every `k`
+//! - `q_decode * (v[0] * 2^18 + v[1] * 2^12 + v[2] * 2^6 + v[3]
+//!   - (b[0] * 2^16 + b[1] * 2^8 + b[2])) = 0`
+//!
+//! the bytes are copied from a column range checked to 8 bits by the
+//! [`RangeCheckChip`](learn_halo2::gadgets::range::RangeCheckChip), so both sides are
+//! below `2^24` and the decomposition of the group is the only one.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+    },
+    testing, util,
+};
+
+/// the number of groups
+const G: usize = 4;
+const C: usize = 4 * G;
+const BYTES: usize = 3 * G;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone)]
+struct Base64Config {
+    c: [Column<Advice>; 4],
+    v: [Column<Advice>; 4],
+    b: [Column<Advice>; 3],
+    q_decode: Selector,
+    /// tag, char and sextet of the alphabet
+    table: [TableColumn; 3],
+    range: RangeCheckConfig,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct Base64Circuit<F> {
+    input: [Value<F>; C],
+}
+
+impl<F> Default for Base64Circuit<F> {
+    fn default() -> Self {
+        Self {
+            input: [(); C].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for Base64Circuit<F> {
+    type Config = Base64Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Base64Config {
+        let c = [(); 4].map(|_| meta.advice_column());
+        let v = [(); 4].map(|_| meta.advice_column());
+        let b = [(); 3].map(|_| meta.advice_column());
+        let byte = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_decode = meta.complex_selector();
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        for column in c.into_iter().chain(b).chain([byte]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        let range = RangeCheckChip::<F, 8>::configure(meta, byte);
+
+        for (c, v) in c.into_iter().zip(v) {
+            meta.lookup(|meta| {
+                let q_decode = meta.query_selector(q_decode);
+                let c = meta.query_advice(c, Rotation::cur());
+                let v = meta.query_advice(v, Rotation::cur());
+                let [tag, char_table, sextet_table] = table;
+                vec![
+                    (q_decode.clone(), tag),
+                    (q_decode.clone() * c, char_table),
+                    (q_decode * v, sextet_table),
+                ]
+            });
+        }
+
+        meta.create_gate("decode", |meta| {
+            let q_decode = meta.query_selector(q_decode);
+            let mut bits = |columns: &[Column<Advice>], width: u64| {
+                columns
+                    .iter()
+                    .fold(Expression::Constant(F::zero()), |acc, column| {
+                        acc * Expression::Constant(F::from(1 << width))
+                            + meta.query_advice(*column, Rotation::cur())
+                    })
+            };
+            vec![q_decode * (bits(&v, 6) - bits(&b, 8))]
+        });
+
+        Base64Config {
+            c,
+            v,
+            b,
+            q_decode,
+            table,
+            range,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Base64Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "alphabet",
+            |mut table| {
+                let entries = ALPHABET
+                    .iter()
+                    .enumerate()
+                    .map(|(v, c)| [1, u64::from(*c), v as u64]);
+                for (row, entry) in [[0; 3]].into_iter().chain(entries).enumerate() {
+                    for (column, value) in config.table.iter().zip(entry) {
+                        table.assign_cell(
+                            || "entry",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        let range = RangeCheckChip::<F, 8>::construct(config.range);
+        range.load_table(layouter.namespace(|| "bytes"))?;
+
+        let sextets: Vec<_> = self
+            .input
+            .iter()
+            .map(|c| c.map(|c| sextet(c.get_lower_128())))
+            .collect();
+        let mut decoded = vec![];
+        for group in sextets.chunks(4) {
+            let bits: Value<Vec<u64>> = group.iter().copied().collect();
+            let bits = bits.map(|bits| bits.iter().fold(0, |acc, v| (acc << 6) | v));
+            for shift in [16, 8, 0] {
+                decoded.push(bits.map(|bits| F::from((bits >> shift) & 0xff)));
+            }
+        }
+
+        let bytes = layouter.assign_region(
+            || "bytes",
+            |mut region| {
+                decoded
+                    .iter()
+                    .enumerate()
+                    .map(|(i, byte)| range.assign_checked(&mut region, i, *byte))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let input = layouter.assign_region(
+            || "decode",
+            |mut region| {
+                let mut input = vec![];
+                for g in 0..G {
+                    config.q_decode.enable(&mut region, g)?;
+                    for k in 0..4 {
+                        let i = 4 * g + k;
+                        let c = region.assign_advice(|| "c", config.c[k], g, || self.input[i])?;
+                        input.push(c);
+                        let v = sextets[i].map(F::from);
+                        region.assign_advice(|| "v", config.v[k], g, || v)?;
+                    }
+                    for (k, column) in config.b.iter().enumerate() {
+                        bytes[3 * g + k].copy_advice(|| "b", &mut region, *column, g)?;
+                    }
+                }
+                Ok(input)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(input)"), &input)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+        let commitment = poseidon.hash(layouter.namespace(|| "H(bytes)"), &bytes)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 1)
+    }
+}
+
+/// the sextet of a character of the alphabet, 0 for any other
+fn sextet(c: u128) -> u64 {
+    ALPHABET
+        .iter()
+        .position(|a| u128::from(*a) == c)
+        .unwrap_or(0) as u64
+}
+
+/// the decoding outside of the circuit
+fn decode(input: &[u8; C]) -> [u8; BYTES] {
+    let mut bytes = [0; BYTES];
+    for (group, out) in input.chunks(4).zip(bytes.chunks_mut(3)) {
+        let bits = group
+            .iter()
+            .fold(0, |acc, c| (acc << 6) | sextet(u128::from(*c)));
+        out.copy_from_slice(&bits.to_be_bytes()[5..]);
+    }
+    bytes
+}
+
+fn commit(bytes: &[u8]) -> Fp {
+    let inputs: Vec<_> = bytes.iter().map(|b| Fp::from(u64::from(*b))).collect();
+    poseidon::hash(&inputs)
+}
+
+fn prover(input: &[u8; C], commitments: [Fp; 2]) -> MockProver<Fp> {
+    let circuit = Base64Circuit {
+        input: input.map(|c| Value::known(Fp::from(u64::from(c)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![commitments.to_vec()]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, Base64Circuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new().with_advice(config.range.value, "byte");
+                for (k, (c, v)) in config.c.iter().zip(&config.v).enumerate() {
+                    names = names
+                        .with_advice(*c, format!("c[{}]", k))
+                        .with_advice(*v, format!("v[{}]", k));
+                }
+                for (k, column) in config.b.iter().enumerate() {
+                    names = names.with_advice(*column, format!("b[{}]", k));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_decode, "q_decode")
+                    .with_selector(config.range.q_lookup, "q_lookup")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let input = b"aGFsbzIgcHJvb2Zz";
+    assert_eq!(&decode(input), b"halo2 proofs");
+    let commitments = [commit(input), commit(b"halo2 proofs")];
+    prover(input, commitments).assert_satisfied();
+    // the last characters of the alphabet
+    let other = b"emstZW1haWwgb2sh";
+    assert_eq!(&decode(other), b"zk-email ok!");
+    prover(other, [commit(other), commit(b"zk-email ok!")]).assert_satisfied();
+    let ends = b"AAAA////+++/0009";
+    prover(ends, [commit(ends), commit(&decode(ends))]).assert_satisfied();
+
+    // the bytes of another string
+    testing::assert_fails_permutation(&prover(input, [commitments[0], commit(b"halo2 proofz")]));
+
+    // the url safe alphabet is another one
+    let mut url_safe = *input;
+    url_safe[9] = b'-';
+    let bytes = decode(&url_safe);
+    testing::assert_fails_lookup(&prover(&url_safe, [commit(&url_safe), commit(&bytes)]), 2);
+}
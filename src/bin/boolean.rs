@@ -0,0 +1,103 @@
+//! majority vote
+//!
+//! we are going to prove the result of a vote of three secret ballots without revealing
+//! them, with the boolean formula `maj(a, b, c) = (a AND b) OR (c AND (a XOR b))`
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::boolean::{BooleanChip, BooleanConfig},
+    testing, util,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+struct MajorityConfig {
+    boolean: BooleanConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct MajorityCircuit<F> {
+    ballots: [Value<bool>; 3],
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MajorityCircuit<F> {
+    type Config = MajorityConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MajorityConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        MajorityConfig {
+            boolean: BooleanChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MajorityConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = BooleanChip::construct(config.boolean);
+        let [a, b, c] = self.ballots;
+        let a = chip.witness(layouter.namespace(|| "a"), a)?;
+        let b = chip.witness(layouter.namespace(|| "b"), b)?;
+        let c = chip.witness(layouter.namespace(|| "c"), c)?;
+
+        let a_and_b = chip.and(layouter.namespace(|| "a and b"), &a, &b)?;
+        let a_xor_b = chip.xor(layouter.namespace(|| "a xor b"), &a, &b)?;
+        let tie_break = chip.and(layouter.namespace(|| "c and (a xor b)"), &c, &a_xor_b)?;
+        let majority = chip.or(layouter.namespace(|| "majority"), &a_and_b, &tie_break)?;
+
+        layouter.constrain_instance(majority.cell(), config.instance, 0)
+    }
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MajorityCircuit<Fp>>(|config| {
+                let [a, b, out] = config.boolean.advice;
+                ColumnNames::new()
+                    .with_advice(a, "a")
+                    .with_advice(b, "b")
+                    .with_advice(out, "out")
+            })
+        );
+    }
+
+    let k = util::min_k_for(&MajorityCircuit::<Fp>::default()).unwrap();
+    for ballots in 0..8 {
+        let ballots = [0, 1, 2].map(|i| ballots >> i & 1 == 1);
+        let majority = ballots.iter().filter(|&&ballot| ballot).count() >= 2;
+        let circuit = MajorityCircuit::<Fp> {
+            ballots: ballots.map(Value::known),
+            _marker: PhantomData,
+        };
+
+        let prover_success =
+            MockProver::run(k, &circuit, vec![vec![Fp::from(majority as u64)]]).unwrap();
+        prover_success.assert_satisfied();
+
+        let prover_failure =
+            MockProver::run(k, &circuit, vec![vec![Fp::from(!majority as u64)]]).unwrap();
+        testing::assert_fails_permutation(&prover_failure);
+    }
+}
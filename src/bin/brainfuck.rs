@@ -0,0 +1,568 @@
+//! brainfuck circuit
+//!
+//! we are going to prove that a brainfuck program halts within `T` steps and prints a public
+//! output, on a tape of `TAPE` cells. the program is in the ROM of the circuit, the cells
+//! are field elements rather than bytes, and there is no input, so `,` is not supported.
+//!
+//! | instance      |
+//! |---------------|
+//! | count         |
+//! | output[0]     |
+//! | ...           |
+//! | output[MAX-1] |
+//!
+//! one row per step, like the stack machine of [`learn_halo2::zkvm`], with the whole tape
+//! in `TAPE` columns and the pointer one-hot in `TAPE` more:
+//!
+//! | row | ip    | mp    | tape    | at          | out   | opcode | target | flags | q_step |
+//! |-----|-------|-------|---------|-------------|-------|--------|--------|-------|--------|
+//! | 0   | 0     | 0     | 0, ...  | 1, 0, ...   | 0     | op[0]  | ...    | ...   | 1      |
+//! | i   | ip[i] | mp[i] | tape[i] | e(mp[i])    | out[i]| op[i]  | ...    | ...   | 1      |
+//! | T   | len   | ...   | ...     | ...         | count |        |        |       | 0      |
+//!
+//! three lookups tie a row to the program and the memory:
+//!
+//! - `(ip, opcode, target)` is in the ROM, the bytes of the program with 0 after its end,
+//!   and the instruction after the matching bracket as the `target` of `[` and `]`
+//! - `(opcode, flags)` is in the decoding table, the one-hot
+//!   `[>, <, +, -, [, ], ., halt]` of the opcode
+//! - `(mp, at)` is in the table of the one-hot vectors of `0..TAPE`, so `mv`, the cell under
+//!   the pointer, is `sum at[j] * tape[j]`, and a pointer off the tape fails the lookup
+//!
+//! with `z = (mv == 0)` and `jump = is_open * z + is_close * (1 - z)`, the gate is
+//!
+//! - `ip' = is_halt ? ip : (jump ? target : ip + 1)`
+//! - `mp' = mp + is_right - is_left`
+//! - `tape[j]' = tape[j] + at[j] * (is_inc - is_dec)`
+//! - `out' = out + is_out`
+//!
+//! a `.` looks up `(out, mv)` in the rows of the output, copied from the instance next to a
+//! fixed index. the program has halted when the last `ip` is its length, and the last
+//! `out` is the public count.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+        TableColumn, VirtualCells,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        select,
+    },
+    testing, util,
+};
+use std::marker::PhantomData;
+
+/// steps the circuit has room for
+const T: usize = 160;
+/// cells of the tape
+const TAPE: usize = 8;
+/// outputs the instance has room for
+const MAX: usize = 8;
+
+/// the opcodes, in the order of the flags, the end of the program is 0
+const OPCODES: [u8; 8] = *b"><+-[].\0";
+
+/// the machine between two steps
+#[derive(Debug, Clone)]
+struct State<F> {
+    ip: usize,
+    mp: i64,
+    tape: [F; TAPE],
+    out: usize,
+}
+
+impl<F: FieldExt> State<F> {
+    /// the cell under the pointer, 0 off the tape
+    fn mv(&self) -> F {
+        usize::try_from(self.mp)
+            .ok()
+            .and_then(|mp| self.tape.get(mp).copied())
+            .unwrap_or_else(F::zero)
+    }
+}
+
+/// the position after the bracket matching each bracket of `program`
+fn targets(program: &[u8]) -> Vec<usize> {
+    let mut targets = vec![0; program.len()];
+    let mut open = vec![];
+    for (ip, op) in program.iter().enumerate() {
+        match op {
+            b'[' => open.push(ip),
+            b']' => {
+                let start = open.pop().expect("unbalanced ]");
+                targets[start] = ip + 1;
+                targets[ip] = start + 1;
+            }
+            _ => {}
+        }
+    }
+    assert!(open.is_empty(), "unbalanced [");
+    targets
+}
+
+/// the states of `rows` steps, held once the program has halted, and the output
+fn interpret<F: FieldExt>(program: &[u8], rows: usize) -> (Vec<State<F>>, Vec<u64>) {
+    let targets = targets(program);
+    let mut states = vec![State {
+        ip: 0,
+        mp: 0,
+        tape: [F::zero(); TAPE],
+        out: 0,
+    }];
+    let mut output = vec![];
+    for _ in 0..rows {
+        let mut state = states.last().unwrap().clone();
+        if let Some(op) = program.get(state.ip) {
+            let mv = state.mv();
+            let cell = usize::try_from(state.mp)
+                .ok()
+                .and_then(|mp| state.tape.get_mut(mp));
+            let mut ip = state.ip + 1;
+            match (op, cell) {
+                (b'>', _) => state.mp += 1,
+                (b'<', _) => state.mp -= 1,
+                (b'+', Some(cell)) => *cell += F::one(),
+                (b'-', Some(cell)) => *cell -= F::one(),
+                (b'[', _) if mv == F::zero() => ip = targets[state.ip],
+                (b']', _) if mv != F::zero() => ip = targets[state.ip],
+                (b'.', _) => {
+                    output.push(mv.get_lower_128() as u64);
+                    state.out += 1;
+                }
+                _ => {}
+            }
+            state.ip = ip;
+        }
+        states.push(state);
+    }
+    (states, output)
+}
+
+/// `x` as a field element
+fn field<F: FieldExt>(x: i64) -> F {
+    if x < 0 {
+        -F::from(x.unsigned_abs())
+    } else {
+        F::from(x as u64)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BrainfuckConfig {
+    ip: Column<Advice>,
+    mp: Column<Advice>,
+    tape: [Column<Advice>; TAPE],
+    at: [Column<Advice>; TAPE],
+    out: Column<Advice>,
+    opcode: Column<Advice>,
+    target: Column<Advice>,
+    flags: [Column<Advice>; OPCODES.len()],
+    mv_is_zero: IsZeroConfig,
+    q_step: Selector,
+    /// tag, ip, opcode and target
+    rom: [TableColumn; 4],
+    /// tag, opcode and flags
+    decode: [TableColumn; 2 + OPCODES.len()],
+    /// tag, mp and at
+    one_hot: [TableColumn; 2 + TAPE],
+    index: Column<Fixed>,
+    output: Column<Advice>,
+    q_output: Selector,
+    instance: Column<Instance>,
+}
+
+struct BrainfuckCircuit<F> {
+    program: Vec<u8>,
+    _marker: PhantomData<F>,
+}
+
+impl<F> BrainfuckCircuit<F> {
+    /// the instructions of `source`, without the other characters
+    fn new(source: &str) -> Self {
+        Self {
+            program: source
+                .bytes()
+                .filter(|b| OPCODES[..7].contains(b))
+                .collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for BrainfuckCircuit<F> {
+    type Config = BrainfuckConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    /// the program is in the ROM, nothing else is a witness
+    fn without_witnesses(&self) -> Self {
+        Self {
+            program: self.program.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> BrainfuckConfig {
+        let ip = meta.advice_column();
+        let mp = meta.advice_column();
+        let tape = [(); TAPE].map(|_| meta.advice_column());
+        let at = [(); TAPE].map(|_| meta.advice_column());
+        let out = meta.advice_column();
+        let opcode = meta.advice_column();
+        let target = meta.advice_column();
+        let flags = [(); OPCODES.len()].map(|_| meta.advice_column());
+        let mv_inv = meta.advice_column();
+        let mv_is_zero = meta.advice_column();
+        let output = meta.advice_column();
+        let index = meta.fixed_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        let q_step = meta.complex_selector();
+        let q_output = meta.complex_selector();
+        let rom = [(); 4].map(|_| meta.lookup_table_column());
+        let decode = [(); 2 + OPCODES.len()].map(|_| meta.lookup_table_column());
+        let one_hot = [(); 2 + TAPE].map(|_| meta.lookup_table_column());
+        for column in [ip, mp, out, output].into_iter().chain(tape) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let mv = move |meta: &mut VirtualCells<'_, F>| {
+            tape.iter()
+                .zip(&at)
+                .fold(Expression::Constant(F::zero()), |acc, (cell, at)| {
+                    acc + meta.query_advice(*at, Rotation::cur())
+                        * meta.query_advice(*cell, Rotation::cur())
+                })
+        };
+        let mv_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_step),
+            mv,
+            mv_inv,
+            mv_is_zero,
+        );
+
+        // `(q_step, q_step * column...)` in `tables`
+        let lookup = |meta: &mut ConstraintSystem<F>,
+                      columns: Vec<Column<Advice>>,
+                      tables: Vec<TableColumn>| {
+            meta.lookup(|meta| {
+                let q_step = meta.query_selector(q_step);
+                let inputs = columns
+                    .iter()
+                    .map(|column| q_step.clone() * meta.query_advice(*column, Rotation::cur()));
+                [q_step.clone()]
+                    .into_iter()
+                    .chain(inputs)
+                    .zip(tables)
+                    .collect()
+            });
+        };
+        lookup(meta, vec![ip, opcode, target], rom.to_vec());
+        lookup(
+            meta,
+            [opcode].into_iter().chain(flags).collect(),
+            decode.to_vec(),
+        );
+        lookup(meta, [mp].into_iter().chain(at).collect(), one_hot.to_vec());
+
+        meta.lookup_any(|meta| {
+            let q_step = meta.query_selector(q_step);
+            let is_out = meta.query_advice(flags[6], Rotation::cur());
+            let printing = q_step * is_out;
+            let out = meta.query_advice(out, Rotation::cur());
+            let q_output = meta.query_selector(q_output);
+            let index = meta.query_fixed(index, Rotation::cur());
+            let output = meta.query_advice(output, Rotation::cur());
+            vec![
+                (printing.clone(), q_output.clone()),
+                (printing.clone() * out, q_output.clone() * index),
+                (printing * mv(meta), q_output * output),
+            ]
+        });
+
+        meta.create_gate("brainfuck", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let one = Expression::Constant(F::one());
+            let z = mv_is_zero.expr(meta, Rotation::cur());
+            let [is_right, is_left, is_inc, is_dec, is_open, is_close, is_out, is_halt] =
+                flags.map(|flag| meta.query_advice(flag, Rotation::cur()));
+            let cur =
+                |meta: &mut VirtualCells<'_, F>, column| meta.query_advice(column, Rotation::cur());
+            let next = |meta: &mut VirtualCells<'_, F>, column| {
+                meta.query_advice(column, Rotation::next())
+            };
+
+            let jump = is_open * z.clone() + is_close * (one.clone() - z);
+            let ip_cur = cur(meta, ip);
+            let target = cur(meta, target);
+            let mut constraints = vec![
+                next(meta, ip)
+                    - select::expr(
+                        is_halt,
+                        ip_cur.clone(),
+                        select::expr(jump, target, ip_cur + one),
+                    ),
+                next(meta, mp) - cur(meta, mp) - is_right + is_left,
+                next(meta, out) - cur(meta, out) - is_out,
+            ];
+            for (cell, at) in tape.iter().zip(at) {
+                constraints.push(
+                    next(meta, *cell)
+                        - cur(meta, *cell)
+                        - cur(meta, at) * (is_inc.clone() - is_dec.clone()),
+                );
+            }
+            constraints
+                .into_iter()
+                .map(|constraint| q_step.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        BrainfuckConfig {
+            ip,
+            mp,
+            tape,
+            at,
+            out,
+            opcode,
+            target,
+            flags,
+            mv_is_zero,
+            q_step,
+            rom,
+            decode,
+            one_hot,
+            index,
+            output,
+            q_output,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: BrainfuckConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let targets = targets(&self.program);
+        let rom = (0..=self.program.len()).map(|ip| {
+            let op = self.program.get(ip).copied().unwrap_or(0);
+            [
+                1,
+                ip as u64,
+                u64::from(op),
+                targets.get(ip).copied().unwrap_or(0) as u64,
+            ]
+        });
+        let decode = OPCODES.iter().enumerate().map(|(i, op)| {
+            let mut row = [0; 2 + OPCODES.len()];
+            row[0] = 1;
+            row[1] = u64::from(*op);
+            row[2 + i] = 1;
+            row
+        });
+        let one_hot = (0..TAPE).map(|mp| {
+            let mut row = [0; 2 + TAPE];
+            row[0] = 1;
+            row[1] = mp as u64;
+            row[2 + mp] = 1;
+            row
+        });
+        load_table(&mut layouter, "rom", &config.rom, rom)?;
+        load_table(&mut layouter, "decode", &config.decode, decode)?;
+        load_table(&mut layouter, "one hot", &config.one_hot, one_hot)?;
+
+        layouter.assign_region(
+            || "output",
+            |mut region| {
+                for row in 0..MAX {
+                    config.q_output.enable(&mut region, row)?;
+                    region.assign_fixed(
+                        || "index",
+                        config.index,
+                        row,
+                        || Value::known(F::from(row as u64)),
+                    )?;
+                    region.assign_advice_from_instance(
+                        || "output",
+                        config.instance,
+                        1 + row,
+                        config.output,
+                        row,
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let (states, _) = interpret::<F>(&self.program, T);
+        let (ip, out) = layouter.assign_region(
+            || "run",
+            |mut region| {
+                let mut last = None;
+                for (row, state) in states.iter().enumerate() {
+                    last = Some(self.assign_row(&config, &mut region, row, state)?);
+                    if row == T {
+                        break;
+                    }
+
+                    config.q_step.enable(&mut region, row)?;
+                    let op = self.program.get(state.ip).copied().unwrap_or(0);
+                    let flag = OPCODES.iter().position(|o| *o == op).unwrap();
+                    let target = targets.get(state.ip).copied().unwrap_or(0);
+                    let known = |x: u64| Value::known(F::from(x));
+                    region.assign_advice(|| "opcode", config.opcode, row, || known(op.into()))?;
+                    region.assign_advice(
+                        || "target",
+                        config.target,
+                        row,
+                        || known(target as u64),
+                    )?;
+                    for (i, column) in config.flags.iter().enumerate() {
+                        let value = u64::from(i == flag);
+                        region.assign_advice(|| "flag", *column, row, || known(value))?;
+                    }
+                    IsZeroChip::construct(config.mv_is_zero).assign(
+                        &mut region,
+                        row,
+                        Value::known(state.mv()),
+                    )?;
+                }
+                Ok(last.unwrap())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "halted",
+            |mut region| region.constrain_constant(ip.cell(), F::from(self.program.len() as u64)),
+        )?;
+        layouter.constrain_instance(out.cell(), config.instance, 0)
+    }
+}
+
+impl<F: FieldExt> BrainfuckCircuit<F> {
+    /// assign the state of `row`, from constants on the first one, the cells of `ip` and
+    /// `out` are returned
+    fn assign_row(
+        &self,
+        config: &BrainfuckConfig,
+        region: &mut Region<'_, F>,
+        row: usize,
+        state: &State<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let mut assign = |name: &str, column, value: F| {
+            if row == 0 {
+                region.assign_advice_from_constant(|| name, column, row, value)
+            } else {
+                region.assign_advice(|| name, column, row, || Value::known(value))
+            }
+        };
+        let ip = assign("ip", config.ip, F::from(state.ip as u64))?;
+        assign("mp", config.mp, field(state.mp))?;
+        let out = assign("out", config.out, F::from(state.out as u64))?;
+        for (column, cell) in config.tape.iter().zip(state.tape) {
+            assign("tape", *column, cell)?;
+        }
+        for (j, column) in config.at.iter().enumerate() {
+            let at = u64::from(state.mp == j as i64);
+            region.assign_advice(|| "at", *column, row, || Value::known(F::from(at)))?;
+        }
+        Ok((ip, out))
+    }
+}
+
+/// fill `columns` with `rows`, after a first row of zeros for the rows not looking up
+fn load_table<F: FieldExt, const N: usize>(
+    layouter: &mut impl Layouter<F>,
+    name: &'static str,
+    columns: &[TableColumn; N],
+    rows: impl Iterator<Item = [u64; N]> + Clone,
+) -> Result<(), Error> {
+    layouter.assign_table(
+        || name,
+        |mut table| {
+            for (row, values) in [[0; N]].into_iter().chain(rows.clone()).enumerate() {
+                for (column, value) in columns.iter().zip(values) {
+                    table.assign_cell(|| name, *column, row, || Value::known(F::from(value)))?;
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+fn prover(source: &str, output: &[u64]) -> MockProver<Fp> {
+    let circuit = BrainfuckCircuit::new(source);
+    let k = util::min_k_for(&circuit).unwrap();
+    let mut instance = vec![Fp::from(output.len() as u64)];
+    instance.extend(output.iter().map(|byte| Fp::from(*byte)));
+    instance.resize(1 + MAX, Fp::zero());
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, BrainfuckCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new()
+                    .with_advice(config.ip, "ip")
+                    .with_advice(config.mp, "mp")
+                    .with_advice(config.out, "out")
+                    .with_advice(config.opcode, "opcode")
+                    .with_advice(config.target, "target")
+                    .with_advice(config.mv_is_zero.value_inv, "mv_inv")
+                    .with_advice(config.mv_is_zero.is_zero, "mv_is_zero")
+                    .with_advice(config.output, "output")
+                    .with_fixed(config.index, "index")
+                    .with_selector(config.q_step, "q_step")
+                    .with_selector(config.q_output, "q_output")
+                    .with_instance(config.instance, "instance");
+                for (j, (cell, at)) in config.tape.iter().zip(&config.at).enumerate() {
+                    names = names
+                        .with_advice(*cell, format!("tape[{}]", j))
+                        .with_advice(*at, format!("at[{}]", j));
+                }
+                let flags = [
+                    "is_right", "is_left", "is_inc", "is_dec", "is_open", "is_close", "is_out",
+                    "is_halt",
+                ];
+                for (column, name) in config.flags.iter().zip(flags) {
+                    names = names.with_advice(*column, name);
+                }
+                names
+            })
+        );
+    }
+
+    // 8 * 9 = 72 is 'H', 33 more is 'i'
+    let hi = "++++++++[>+++++++++<-]>. +++++++++++++++++++++++++++++++++.";
+    let (_, output) = interpret::<Fp>(&BrainfuckCircuit::<Fp>::new(hi).program, T);
+    assert_eq!(output, [u64::from(b'H'), u64::from(b'i')]);
+    prover(hi, &output).assert_satisfied();
+
+    // another output, the lookup of the second `.` fails
+    testing::assert_fails_lookup(&prover(hi, b"Ho".map(u64::from).as_slice()), 148);
+
+    // one output left out of the count
+    testing::assert_fails_permutation(&prover(hi, &output[..1]));
+
+    // an endless loop never reaches the end of the program
+    testing::assert_fails_permutation(&prover("+[]", &[]));
+
+    // off the left end of the tape
+    testing::assert_fails_lookup(&prover("<+", &[]), 1);
+}
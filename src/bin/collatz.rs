@@ -0,0 +1,216 @@
+//! collatz circuit
+//!
+//! we are going to prove that a public starting value reaches 1 within MAX_STEPS steps of
+//! `x' = x is even ? x / 2 : 3x + 1`
+//!
+//! | row       | x      | half        | bit    | is_one |
+//! |-----------|--------|-------------|--------|--------|
+//! | 0         | n      | n >> 1      | n & 1  | 0      |
+//! | i         | x(i)   | x(i) >> 1   | parity | 0      |
+//! | steps     | 1      | 0           | 1      | 1      |
+//! | ...       | 1      | 0           | 1      | 1      |
+//! | MAX_STEPS | 1      | 0           | 1      | 1      |
+//!
+//! - `x = 2 * half + bit` with a boolean `bit`, and `half < 2^14` by a lookup, so the
+//!   parity can not be faked by wrapping around the field. every value on the trajectory
+//!   has to be smaller than `2^15`
+//! - `x' = is_one ? x : (bit ? 3x + 1 : half)`, once it reaches 1 the rest are padding
+//!   rows, like the rows after `n` reaches 0 in the fibonacci circuit
+//! - the last row is 1
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+        select,
+    },
+    testing, util,
+};
+
+const MAX_STEPS: usize = 128;
+/// `half < 2^HALF_BITS`
+const HALF_BITS: usize = 14;
+
+#[derive(Debug, Clone)]
+struct CollatzConfig {
+    x: Column<Advice>,
+    bit: Column<Advice>,
+    // every row
+    q_row: Selector,
+    // every row but the last
+    q_step: Selector,
+    is_one: IsZeroConfig,
+    half: RangeCheckConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct CollatzCircuit<F> {
+    n: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for CollatzCircuit<F> {
+    type Config = CollatzConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> CollatzConfig {
+        let x = meta.advice_column();
+        let half = meta.advice_column();
+        let bit = meta.advice_column();
+        let x_inv = meta.advice_column();
+        let is_one = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(x);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        let q_row = meta.selector();
+        let q_step = meta.selector();
+
+        let is_one = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_row),
+            |meta| meta.query_advice(x, Rotation::cur()) - Expression::Constant(F::one()),
+            x_inv,
+            is_one,
+        );
+        let half = RangeCheckChip::<F, HALF_BITS>::configure(meta, half);
+
+        meta.create_gate("parity", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let x = meta.query_advice(x, Rotation::cur());
+            let half = meta.query_advice(half.value, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            vec![
+                q_row.clone() * bit.clone() * (Expression::Constant(F::one()) - bit.clone()),
+                q_row * (x - (half * Expression::Constant(F::from(2)) + bit)),
+            ]
+        });
+
+        meta.create_gate("collatz", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let x_next = meta.query_advice(x, Rotation::next());
+            let x = meta.query_advice(x, Rotation::cur());
+            let half = meta.query_advice(half.value, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let is_one = is_one.expr(meta, Rotation::cur());
+
+            let odd = x.clone() * Expression::Constant(F::from(3)) + Expression::Constant(F::one());
+            vec![q_step * (x_next - select::expr(is_one, x, select::expr(bit, odd, half)))]
+        });
+
+        CollatzConfig {
+            x,
+            bit,
+            q_row,
+            q_step,
+            is_one,
+            half,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: CollatzConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let half_chip = RangeCheckChip::<F, HALF_BITS>::construct(config.half);
+        let is_one_chip = IsZeroChip::construct(config.is_one);
+        half_chip.load_table(layouter.namespace(|| "half range"))?;
+
+        let (n, last) = layouter.assign_region(
+            || "trajectory",
+            |mut region| {
+                let mut x = self.n.map(|n| n.get_lower_128() as u64);
+                let mut first = None;
+                let mut last = None;
+                for offset in 0..=MAX_STEPS {
+                    config.q_row.enable(&mut region, offset)?;
+                    if offset < MAX_STEPS {
+                        config.q_step.enable(&mut region, offset)?;
+                    }
+
+                    let x_cell =
+                        region.assign_advice(|| "x", config.x, offset, || x.map(F::from))?;
+                    half_chip.assign_checked(&mut region, offset, x.map(|x| F::from(x >> 1)))?;
+                    region.assign_advice(
+                        || "bit",
+                        config.bit,
+                        offset,
+                        || x.map(|x| F::from(x & 1)),
+                    )?;
+                    is_one_chip.assign(&mut region, offset, x.map(|x| F::from(x) - F::one()))?;
+
+                    if offset == 0 {
+                        first = Some(x_cell.clone());
+                    }
+                    last = Some(x_cell);
+                    x = x.map(|x| match x {
+                        1 => 1,
+                        x if x % 2 == 0 => x / 2,
+                        x => 3 * x + 1,
+                    });
+                }
+                Ok((first.unwrap(), last.unwrap()))
+            },
+        )?;
+
+        layouter.assign_region(
+            || "reaches 1",
+            |mut region| region.constrain_constant(last.cell(), F::one()),
+        )?;
+        layouter.constrain_instance(n.cell(), config.instance, 0)
+    }
+}
+
+fn prover(n: u64, instance: u64) -> MockProver<Fp> {
+    let circuit = CollatzCircuit {
+        n: Value::known(Fp::from(n)),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(instance)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, CollatzCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.x, "x")
+                    .with_advice(config.half.value, "half")
+                    .with_advice(config.bit, "bit")
+                    .with_advice(config.is_one.is_zero, "is_one")
+                    .with_selector(config.q_row, "q_row")
+                    .with_selector(config.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // 27 takes 111 steps and climbs up to 9232
+    for n in [1, 6, 7, 27, 97] {
+        prover(n, n).assert_satisfied();
+    }
+
+    testing::assert_fails_permutation(&prover(27, 28));
+
+    // 327 takes 143 steps, the last row is not 1
+    testing::assert_fails_permutation(&prover(327, 327));
+}
@@ -0,0 +1,256 @@
+//! graph coloring circuit
+//!
+//! we are going to prove that we know a private proper 3-coloring of a public graph, here
+//! the petersen graph, no edge joins two vertices of the same color
+//!
+//! the edges are part of the circuit, in fixed columns, there is no instance. the colors
+//! are `0`, `1` and `2`, each vertex takes two rows of a
+//! [`RangeCheckChip`](learn_halo2::gadgets::range::RangeCheckChip) of 2 bits, for its color
+//! and for `2 - color`, like the remainder of `src/gadgets/divmod.rs`:
+//!
+//! | row    | value     | index | q_vertex | q_color |
+//! |--------|-----------|-------|----------|---------|
+//! | 2v     | color     | v     | 1        | 1       |
+//! | 2v + 1 | 2 - color |       | 0        | 0       |
+//!
+//! - `q_color * (value + value' - 2) = 0`
+//!
+//! both are below 4, so the color is at most 2. each edge is a row with the colors of its
+//! ends, which are looked up in the vertices, `(q_edge, q_edge * from, q_edge * color_from)`
+//! in `(q_vertex, q_vertex * index, q_vertex * value)` and the same for `to`:
+//!
+//! | row | from | to | color_from  | color_to  | inv                               | q_edge |
+//! |-----|------|----|-------------|-----------|-----------------------------------|--------|
+//! | e   | u    | v  | color[u]    | color[v]  | `1 / (color_from - color_to)`     | 1      |
+//!
+//! - `q_edge * ((color_from - color_to) * inv - 1) = 0`
+//!
+//! the difference has an inverse only when it isn't 0.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::range::{RangeCheckChip, RangeCheckConfig},
+    testing, util,
+};
+
+const V: usize = 10;
+
+/// the outer cycle, the spokes and the inner pentagram
+const EDGES: [(usize, usize); 15] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 4),
+    (4, 0),
+    (0, 5),
+    (1, 6),
+    (2, 7),
+    (3, 8),
+    (4, 9),
+    (5, 7),
+    (7, 9),
+    (9, 6),
+    (6, 8),
+    (8, 5),
+];
+
+#[derive(Debug, Clone)]
+struct ColoringConfig {
+    range: RangeCheckConfig,
+    index: Column<Fixed>,
+    q_vertex: Selector,
+    q_color: Selector,
+    from: Column<Fixed>,
+    to: Column<Fixed>,
+    color_from: Column<Advice>,
+    color_to: Column<Advice>,
+    inv: Column<Advice>,
+    q_edge: Selector,
+}
+
+struct ColoringCircuit<F> {
+    colors: [Value<F>; V],
+}
+
+impl<F> Default for ColoringCircuit<F> {
+    fn default() -> Self {
+        Self {
+            colors: [(); V].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ColoringCircuit<F> {
+    type Config = ColoringConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ColoringConfig {
+        let value = meta.advice_column();
+        let index = meta.fixed_column();
+        let from = meta.fixed_column();
+        let to = meta.fixed_column();
+        let color_from = meta.advice_column();
+        let color_to = meta.advice_column();
+        let inv = meta.advice_column();
+        let q_vertex = meta.complex_selector();
+        let q_color = meta.selector();
+        let q_edge = meta.complex_selector();
+        let range = RangeCheckChip::<F, 2>::configure(meta, value);
+
+        meta.create_gate("color", |meta| {
+            let q_color = meta.query_selector(q_color);
+            let color = meta.query_advice(value, Rotation::cur());
+            let slack = meta.query_advice(value, Rotation::next());
+            vec![q_color * (color + slack - Expression::Constant(F::from(2)))]
+        });
+
+        for (end, color) in [(from, color_from), (to, color_to)] {
+            meta.lookup_any(|meta| {
+                let q_edge = meta.query_selector(q_edge);
+                let q_vertex = meta.query_selector(q_vertex);
+                let end = meta.query_fixed(end, Rotation::cur());
+                let color = meta.query_advice(color, Rotation::cur());
+                let index = meta.query_fixed(index, Rotation::cur());
+                let value = meta.query_advice(value, Rotation::cur());
+                vec![
+                    (q_edge.clone(), q_vertex.clone()),
+                    (q_edge.clone() * end, q_vertex.clone() * index),
+                    (q_edge * color, q_vertex * value),
+                ]
+            });
+        }
+
+        meta.create_gate("edge", |meta| {
+            let q_edge = meta.query_selector(q_edge);
+            let color_from = meta.query_advice(color_from, Rotation::cur());
+            let color_to = meta.query_advice(color_to, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            vec![q_edge * ((color_from - color_to) * inv - Expression::Constant(F::one()))]
+        });
+
+        ColoringConfig {
+            range,
+            index,
+            q_vertex,
+            q_color,
+            from,
+            to,
+            color_from,
+            color_to,
+            inv,
+            q_edge,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: ColoringConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::<F, 2>::construct(config.range);
+        range.load_table(layouter.namespace(|| "2 bits"))?;
+
+        layouter.assign_region(
+            || "vertices",
+            |mut region| {
+                for (v, color) in self.colors.iter().enumerate() {
+                    config.q_vertex.enable(&mut region, 2 * v)?;
+                    config.q_color.enable(&mut region, 2 * v)?;
+                    region.assign_fixed(
+                        || "index",
+                        config.index,
+                        2 * v,
+                        || Value::known(F::from(v as u64)),
+                    )?;
+                    range.assign_checked(&mut region, 2 * v, *color)?;
+                    let slack = Value::known(F::from(2)) - *color;
+                    range.assign_checked(&mut region, 2 * v + 1, slack)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "edges",
+            |mut region| {
+                for (offset, (from, to)) in EDGES.iter().enumerate() {
+                    config.q_edge.enable(&mut region, offset)?;
+                    for (column, end) in [(config.from, from), (config.to, to)] {
+                        region.assign_fixed(
+                            || "end",
+                            column,
+                            offset,
+                            || Value::known(F::from(*end as u64)),
+                        )?;
+                    }
+                    let (color_from, color_to) = (self.colors[*from], self.colors[*to]);
+                    region.assign_advice(|| "color", config.color_from, offset, || color_from)?;
+                    region.assign_advice(|| "color", config.color_to, offset, || color_to)?;
+                    let inv =
+                        (color_from - color_to).map(|diff| diff.invert().unwrap_or(F::zero()));
+                    region.assign_advice(|| "inv", config.inv, offset, || inv)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prover(colors: [u64; V]) -> MockProver<Fp> {
+    let circuit = ColoringCircuit {
+        colors: colors.map(|color| Value::known(Fp::from(color))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, ColoringCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.range.value, "value")
+                    .with_fixed(config.index, "index")
+                    .with_fixed(config.from, "from")
+                    .with_fixed(config.to, "to")
+                    .with_advice(config.color_from, "color_from")
+                    .with_advice(config.color_to, "color_to")
+                    .with_advice(config.inv, "inv")
+                    .with_selector(config.range.q_lookup, "q_lookup")
+                    .with_selector(config.q_vertex, "q_vertex")
+                    .with_selector(config.q_color, "q_color")
+                    .with_selector(config.q_edge, "q_edge")
+            })
+        );
+    }
+
+    let colors = [0, 1, 0, 1, 2, 1, 0, 2, 2, 1];
+    prover(colors).assert_satisfied();
+    // the colors renamed
+    prover(colors.map(|color| (color + 1) % 3)).assert_satisfied();
+
+    // vertex 4 takes the color of vertex 0, the edge (4, 0) has no inverse
+    let mut clash = colors;
+    clash[4] = 0;
+    testing::assert_fails_gate(&prover(clash), "edge", 4);
+
+    // a fourth color, 2 - 3 isn't in the range
+    let mut fourth = colors;
+    fourth[4] = 3;
+    testing::assert_fails_lookup(&prover(fourth), 2 * 4 + 1);
+}
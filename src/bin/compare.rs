@@ -0,0 +1,235 @@
+//! salary below a threshold
+//!
+//! we are going to prove that a secret salary, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is smaller than a public
+//! threshold, without revealing the salary. both have to fit in 4 bytes.
+//!
+//! | instance        |
+//! |-----------------|
+//! | H(salt, salary) |
+//! | threshold       |
+//!
+//! | row | salt | salary | byte[0..4]   | threshold | lt | q_enable |
+//! |-----|------|--------|--------------|-----------|----|----------|
+//! | 0   | salt | salary | salary bytes | threshold | 1  | 1        |
+//!
+//! - `salary = sum byte[i] * 2^(8i)`, each byte looked up in the u8 table of the
+//!   [`LtChip`](learn_halo2::gadgets::compare::LtChip), so the salary fits in 4 bytes.
+//!   otherwise `salary - threshold` of a "negative" salary could wrap into 4 bytes too
+//! - `lt` is constrained to 1
+//!
+//! the threshold is public, the verifier checks that it fits in 4 bytes.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+const N_BYTES: usize = 4;
+
+#[derive(Debug, Clone)]
+struct CompareConfig {
+    q_enable: Selector,
+    salt: Column<Advice>,
+    salary: Column<Advice>,
+    // little endian bytes of the salary
+    salary_bytes: [Column<Advice>; N_BYTES],
+    threshold: Column<Advice>,
+    instance: Column<Instance>,
+    lt: LtConfig<N_BYTES>,
+    poseidon: PoseidonConfig,
+}
+
+#[derive(Default)]
+struct SalaryCircuit<F> {
+    salt: Value<F>,
+    salary: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SalaryCircuit<F> {
+    type Config = CompareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> CompareConfig {
+        let q_enable = meta.complex_selector();
+        let salt = meta.advice_column();
+        let salary = meta.advice_column();
+        let salary_bytes = [(); N_BYTES].map(|_| meta.advice_column());
+        let threshold = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        for column in [salt, salary, threshold] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_enable),
+            |meta| meta.query_advice(salary, Rotation::cur()),
+            |meta| meta.query_advice(threshold, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        meta.create_gate("salary bytes", |meta| {
+            let q_enable = meta.query_selector(q_enable);
+            let salary = meta.query_advice(salary, Rotation::cur());
+            let bytes =
+                salary_bytes
+                    .iter()
+                    .rev()
+                    .fold(Expression::Constant(F::zero()), |acc, byte| {
+                        acc * Expression::Constant(F::from(256))
+                            + meta.query_advice(*byte, Rotation::cur())
+                    });
+            vec![q_enable * (salary - bytes)]
+        });
+        for byte in salary_bytes {
+            meta.lookup(|meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(q_enable * byte, lt.u8_table)]
+            });
+        }
+
+        CompareConfig {
+            q_enable,
+            salt,
+            salary,
+            salary_bytes,
+            threshold,
+            instance,
+            lt,
+            poseidon: PoseidonChip::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: CompareConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = LtChip::<F, N_BYTES>::construct(config.lt);
+        chip.load_table(layouter.namespace(|| "u8"))?;
+
+        let opening = layouter.assign_region(
+            || "salary < threshold",
+            |mut region| {
+                config.q_enable.enable(&mut region, 0)?;
+                let salt = region.assign_advice(|| "salt", config.salt, 0, || self.salt)?;
+                let salary = region.assign_advice(|| "salary", config.salary, 0, || self.salary)?;
+                let repr = self.salary.map(|salary| salary.to_repr());
+                for (i, column) in config.salary_bytes.iter().enumerate() {
+                    region.assign_advice(
+                        || format!("salary byte {}", i),
+                        *column,
+                        0,
+                        || repr.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64)),
+                    )?;
+                }
+                let threshold = region.assign_advice_from_instance(
+                    || "threshold",
+                    config.instance,
+                    1,
+                    config.threshold,
+                    0,
+                )?;
+                let lt = chip.assign(&mut region, 0, self.salary, threshold.value().copied())?;
+                region.constrain_constant(lt.cell(), F::one())?;
+                Ok([salt, salary])
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(salt, salary)"), &opening)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+fn prover(salt: u64, salary: Fp, commitment: Fp, threshold: u64) -> MockProver<Fp> {
+    let circuit = SalaryCircuit {
+        salt: Value::known(Fp::from(salt)),
+        salary: Value::known(salary),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![commitment, Fp::from(threshold)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SalaryCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.salt, "salt")
+                    .with_advice(config.salary, "salary")
+                    .with_advice(config.threshold, "threshold")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.salary_bytes.iter().enumerate() {
+                    names = names.with_advice(*column, format!("byte[{}]", i));
+                }
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_enable, "q_enable")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (salt, salary) = (0x5eed, Fp::from(50_000));
+    let commitment = poseidon::hash(&[Fp::from(salt), salary]);
+    prover(salt, salary, commitment, 60_000).assert_satisfied();
+
+    // the flag is 0, which does not match the constant 1
+    testing::assert_fails_permutation(&prover(salt, salary, commitment, 40_000));
+
+    // a smaller salary than the committed one
+    let lower = Fp::from(30_000);
+    testing::assert_fails_permutation(&prover(salt, lower, commitment, 40_000));
+
+    // a "negative" salary, whose difference to the threshold wraps into 4 bytes, does not
+    // fit in its bytes
+    let negative = -Fp::one();
+    let commitment = poseidon::hash(&[Fp::from(salt), negative]);
+    testing::assert_fails_gate(
+        &prover(salt, negative, commitment, 60_000),
+        "salary bytes",
+        0,
+    );
+}
@@ -0,0 +1,133 @@
+//! secp256k1 point addition circuit
+//!
+//! we are going to prove that we know two points `p` and `q` on secp256k1 whose sum is a
+//! public point, with the [`EccChip`](learn_halo2::gadgets::ecc::EccChip)
+//!
+//! | instance |
+//! |----------|
+//! | r.x      |
+//! | r.y      |
+//!
+//! the coordinates of secp256k1 points are in its base field, so this circuit is over the
+//! secp256k1 `Fp` whatever the `curve-*` feature, and is only run by the `MockProver`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::{
+        group::{prime::PrimeCurveAffine, Curve},
+        secp256k1::{Fp, Fq, Secp256k1Affine},
+    },
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    gadgets::ecc::{EccChip, EccConfig},
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct EccCircuitConfig {
+    ecc: EccConfig,
+    instance: Column<Instance>,
+}
+
+struct EccCircuit<F> {
+    p: Value<(F, F)>,
+    q: Value<(F, F)>,
+}
+
+impl<F> Default for EccCircuit<F> {
+    fn default() -> Self {
+        Self {
+            p: Value::unknown(),
+            q: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for EccCircuit<F> {
+    type Config = EccCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> EccCircuitConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        EccCircuitConfig {
+            ecc: EccChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: EccCircuitConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = EccChip::construct(config.ecc);
+        let p = chip.witness_point(layouter.namespace(|| "p"), self.p)?;
+        let q = chip.witness_point(layouter.namespace(|| "q"), self.q)?;
+        let r = chip.add(layouter.namespace(|| "p + q"), &p, &q)?;
+        layouter.constrain_instance(r.x.cell(), config.instance, 0)?;
+        layouter.constrain_instance(r.y.cell(), config.instance, 1)
+    }
+}
+
+/// the affine coordinates of `n * G`
+fn multiple(n: u64) -> (Fp, Fp) {
+    let point = (Secp256k1Affine::generator() * Fq::from(n)).to_affine();
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+fn prover(p: (Fp, Fp), q: (Fp, Fp), r: (Fp, Fp)) -> MockProver<Fp> {
+    let circuit = EccCircuit {
+        p: Value::known(p),
+        q: Value::known(q),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![r.0, r.1]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, EccCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.ecc.x, "x")
+                    .with_advice(config.ecc.y, "y")
+                    .with_advice(config.ecc.lambda, "lambda")
+                    .with_advice(config.ecc.inv, "inv")
+                    .with_selector(config.ecc.q_on_curve, "q_on_curve")
+                    .with_selector(config.ecc.q_add, "q_add")
+                    .with_selector(config.ecc.q_double, "q_double")
+                    .with_selector(config.ecc.q_select, "q_select")
+                    .with_selector(config.ecc.q_neg, "q_neg")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (p, q, r) = (multiple(3), multiple(4), multiple(7));
+    println!(
+        "3G + 4G = ({}, {})",
+        util::display_field(r.0),
+        util::display_field(r.1)
+    );
+    prover(p, q, r).assert_satisfied();
+
+    // a point off the curve
+    let off_curve = (p.0, p.1 + Fp::one());
+    testing::assert_fails_gate(&prover(off_curve, q, r), "ecc on curve", 0);
+
+    // points summing to another point
+    testing::assert_fails_permutation(&prover(p, multiple(5), r));
+}
@@ -0,0 +1,354 @@
+//! edit distance circuit
+//!
+//! we are going to prove that the levenshtein distance between a private string `s` of `M`
+//! bytes and a private string `t` of `N` bytes, both committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is a public value
+//!
+//! | instance  |
+//! |-----------|
+//! | H(s)      |
+//! | H(t)      |
+//! | distance  |
+//!
+//! the distances between the prefixes are the dynamic programming table
+//!
+//! ```text
+//! d[0][j] = j
+//! d[i][0] = i
+//! d[i][j] = min(d[i-1][j] + 1, d[i][j-1] + 1, d[i-1][j-1] + (s[i-1] != t[j-1]))
+//! ```
+//!
+//! and a row of the table is a row of the circuit, `t` copied down from the first one:
+//!
+//! | row | s      | t[j]     | d[j]     | same[j]           | m[j]               | q_edit |
+//! |-----|--------|----------|----------|-------------------|--------------------|--------|
+//! | 0   |        | t[j-1]   | j        |                   |                    | 0      |
+//! | i   | s[i-1] | t[j-1]   | d[i][j]  | s[i-1] == t[j-1]  | min(up, left)      | 1      |
+//!
+//! with `up = d[j]' + 1`, `left = d[j-1] + 1` and `diag = d[j-1]' + 1 - same`, where `'`
+//! is the row before. `same` is an [`IsZeroChip`](learn_halo2::gadgets::is_zero::IsZeroChip)
+//! and the min of three is two comparisons by the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 1 byte, each followed by a
+//! [`select::expr`](learn_halo2::gadgets::select::expr), for every `j >= 1`:
+//!
+//! - `q_edit * (m - (up < left ? up : left)) = 0`
+//! - `q_edit * (d - (diag < m ? diag : m)) = 0`
+//!
+//! `d[0]` is the constant `i`. every distance is at most `M + N`, so the comparisons stay
+//! in a byte.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        is_zero::{IsZeroChip, IsZeroConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        select,
+    },
+    testing, util,
+};
+
+const M: usize = 6;
+const N: usize = 7;
+
+#[derive(Debug, Clone)]
+struct EditConfig {
+    s: Column<Advice>,
+    t: [Column<Advice>; N],
+    d: [Column<Advice>; N + 1],
+    /// the columns of `j = 1..=N`, from here on
+    same: [IsZeroConfig; N],
+    m: [Column<Advice>; N],
+    /// `up < left`
+    lt_left: [LtConfig<1>; N],
+    /// `diag < m`
+    lt_diag: [LtConfig<1>; N],
+    q_edit: Selector,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct EditCircuit<F> {
+    s: [Value<F>; M],
+    t: [Value<F>; N],
+}
+
+impl<F> Default for EditCircuit<F> {
+    fn default() -> Self {
+        Self {
+            s: [(); M].map(|_| Value::unknown()),
+            t: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for EditCircuit<F> {
+    type Config = EditConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> EditConfig {
+        let s = meta.advice_column();
+        let t = [(); N].map(|_| meta.advice_column());
+        let d = [(); N + 1].map(|_| meta.advice_column());
+        let m = [(); N].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_edit = meta.complex_selector();
+        for column in [s].into_iter().chain(t).chain(d) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let one = || Expression::Constant(F::one());
+        let same: [IsZeroConfig; N] = std::array::from_fn(|j| {
+            let value_inv = meta.advice_column();
+            let is_zero = meta.advice_column();
+            IsZeroChip::configure(
+                meta,
+                |meta| meta.query_selector(q_edit),
+                |meta| {
+                    meta.query_advice(s, Rotation::cur()) - meta.query_advice(t[j], Rotation::cur())
+                },
+                value_inv,
+                is_zero,
+            )
+        });
+        let lt_left: [LtConfig<1>; N] = std::array::from_fn(|j| {
+            LtChip::configure(
+                meta,
+                |meta| meta.query_selector(q_edit),
+                |meta| meta.query_advice(d[j + 1], Rotation::prev()) + one(),
+                |meta| meta.query_advice(d[j], Rotation::cur()) + one(),
+            )
+        });
+        let lt_diag: [LtConfig<1>; N] = std::array::from_fn(|j| {
+            LtChip::configure(
+                meta,
+                |meta| meta.query_selector(q_edit),
+                |meta| {
+                    meta.query_advice(d[j], Rotation::prev()) + one()
+                        - same[j].expr(meta, Rotation::cur())
+                },
+                |meta| meta.query_advice(m[j], Rotation::cur()),
+            )
+        });
+
+        meta.create_gate("edit", |meta| {
+            let q_edit = meta.query_selector(q_edit);
+            let mut constraints = vec![];
+            for j in 0..N {
+                let up = meta.query_advice(d[j + 1], Rotation::prev()) + one();
+                let left = meta.query_advice(d[j], Rotation::cur()) + one();
+                let diag = meta.query_advice(d[j], Rotation::prev()) + one()
+                    - same[j].expr(meta, Rotation::cur());
+                let m = meta.query_advice(m[j], Rotation::cur());
+                let d = meta.query_advice(d[j + 1], Rotation::cur());
+                let lt_left = meta.query_advice(lt_left[j].lt, Rotation::cur());
+                let lt_diag = meta.query_advice(lt_diag[j].lt, Rotation::cur());
+                constraints.push(q_edit.clone() * (m.clone() - select::expr(lt_left, up, left)));
+                constraints.push(q_edit.clone() * (d - select::expr(lt_diag, diag, m)));
+            }
+            constraints
+        });
+
+        EditConfig {
+            s,
+            t,
+            d,
+            same,
+            m,
+            lt_left,
+            lt_diag,
+            q_edit,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: EditConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        for lt in config.lt_left.iter().chain(&config.lt_diag) {
+            LtChip::<F, 1>::construct(*lt).load_table(layouter.namespace(|| "u8"))?;
+        }
+
+        let (s, t, distance) = layouter.assign_region(
+            || "distances",
+            |mut region| {
+                let one = Value::known(F::one());
+                let mut t = vec![];
+                let mut prev: Vec<AssignedCell<F, F>> = vec![];
+                for (j, column) in config.d.iter().enumerate() {
+                    let d = F::from(j as u64);
+                    prev.push(region.assign_advice_from_constant(|| "d", *column, 0, d)?);
+                }
+                for (column, c) in config.t.iter().zip(&self.t) {
+                    t.push(region.assign_advice(|| "t", *column, 0, || *c)?);
+                }
+
+                let mut s = vec![];
+                for (i, c) in self.s.iter().enumerate() {
+                    let offset = i + 1;
+                    config.q_edit.enable(&mut region, offset)?;
+                    let c = region.assign_advice(|| "s", config.s, offset, || *c)?;
+                    let d = F::from(offset as u64);
+                    let mut row =
+                        vec![region.assign_advice_from_constant(|| "d", config.d[0], offset, d)?];
+                    for j in 0..N {
+                        let t = t[j].copy_advice(|| "t", &mut region, config.t[j], offset)?;
+                        let same = IsZeroChip::construct(config.same[j]).assign(
+                            &mut region,
+                            offset,
+                            c.value().copied() - t.value().copied(),
+                        )?;
+                        let up = prev[j + 1].value().copied() + one;
+                        let left = row[j].value().copied() + one;
+                        let diag = prev[j].value().copied() + one - same.value().copied();
+
+                        let lt = LtChip::<F, 1>::construct(config.lt_left[j]).assign(
+                            &mut region,
+                            offset,
+                            up,
+                            left,
+                        )?;
+                        let m = choose(lt.value().copied(), up, left);
+                        region.assign_advice(|| "m", config.m[j], offset, || m)?;
+                        let lt = LtChip::<F, 1>::construct(config.lt_diag[j]).assign(
+                            &mut region,
+                            offset,
+                            diag,
+                            m,
+                        )?;
+                        let d = choose(lt.value().copied(), diag, m);
+                        row.push(region.assign_advice(|| "d", config.d[j + 1], offset, || d)?);
+                    }
+                    s.push(c);
+                    prev = row;
+                }
+                Ok((s, t, prev[N].clone()))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(s)"), &s)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+        let commitment = poseidon.hash(layouter.namespace(|| "H(t)"), &t)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 1)?;
+        layouter.constrain_instance(distance.cell(), config.instance, 2)
+    }
+}
+
+/// `cond ? a : b` of the witness
+fn choose<F: FieldExt>(cond: Value<F>, a: Value<F>, b: Value<F>) -> Value<F> {
+    cond.zip(a)
+        .zip(b)
+        .map(|((cond, a), b)| if cond == F::one() { a } else { b })
+}
+
+/// the levenshtein distance outside of the circuit
+fn distance(s: &[u8], t: &[u8]) -> u64 {
+    let mut prev: Vec<u64> = (0..=t.len() as u64).collect();
+    for (i, a) in s.iter().enumerate() {
+        let mut row = vec![i as u64 + 1];
+        for (j, b) in t.iter().enumerate() {
+            let diag = prev[j] + u64::from(a != b);
+            row.push(diag.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[t.len()]
+}
+
+fn commit(bytes: &[u8]) -> Fp {
+    let inputs: Vec<_> = bytes.iter().map(|b| Fp::from(u64::from(*b))).collect();
+    poseidon::hash(&inputs)
+}
+
+fn prover(s: &[u8; M], t: &[u8; N], commitments: [Fp; 2], distance: u64) -> MockProver<Fp> {
+    let known = |c: u8| Value::known(Fp::from(u64::from(c)));
+    let circuit = EditCircuit {
+        s: s.map(known),
+        t: t.map(known),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let [hs, ht] = commitments;
+    MockProver::run(k, &circuit, vec![vec![hs, ht, Fp::from(distance)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, EditCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.s, "s")
+                    .with_advice(config.d[0], "d[0]");
+                for j in 0..N {
+                    let (same, lt_left, lt_diag) =
+                        (config.same[j], config.lt_left[j], config.lt_diag[j]);
+                    names = names
+                        .with_advice(config.t[j], format!("t[{}]", j + 1))
+                        .with_advice(config.d[j + 1], format!("d[{}]", j + 1))
+                        .with_advice(same.value_inv, format!("same_inv[{}]", j + 1))
+                        .with_advice(same.is_zero, format!("same[{}]", j + 1))
+                        .with_advice(config.m[j], format!("m[{}]", j + 1))
+                        .with_advice(lt_left.lt, format!("lt_left[{}]", j + 1))
+                        .with_advice(lt_left.diff[0], format!("diff_left[{}]", j + 1))
+                        .with_advice(lt_diag.lt, format!("lt_diag[{}]", j + 1))
+                        .with_advice(lt_diag.diff[0], format!("diff_diag[{}]", j + 1));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_edit, "q_edit")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (s, t) = (b"kitten", b"sitting");
+    assert_eq!(distance(s, t), 3);
+    let commitments = [commit(s), commit(t)];
+    prover(s, t, commitments, 3).assert_satisfied();
+    // one insertion
+    let s = b"sittng";
+    assert_eq!(distance(s, t), 1);
+    prover(s, t, [commit(s), commit(t)], 1).assert_satisfied();
+    // nothing in common
+    let s = b"abcdef";
+    assert_eq!(distance(s, t), N as u64);
+    prover(s, t, [commit(s), commit(t)], N as u64).assert_satisfied();
+
+    // closer than they are
+    let s = b"kitten";
+    testing::assert_fails_permutation(&prover(s, t, commitments, 2));
+
+    // the distance of another string
+    testing::assert_fails_permutation(&prover(b"sittin", t, commitments, 1));
+}
@@ -0,0 +1,249 @@
+//! ethereum address circuit
+//!
+//! we are going to prove that we know the secp256k1 public key behind a public ethereum
+//! address, the last 20 bytes of `keccak256(x || y)` with both coordinates as 32 big endian
+//! bytes
+//!
+//! | instance |
+//! |----------|
+//! | address  |
+//!
+//! the address is a 160 bit big endian integer. the key is checked to be on the curve by the
+//! [`EccChip`](learn_halo2::gadgets::ecc::EccChip), its coordinates are split into their 256
+//! bits by the [`CanonicalChip`](learn_halo2::gadgets::bits::CanonicalChip), as 256 bits
+//! also hold a small coordinate plus `p`, and those bits are the message of the
+//! [`KeccakChip`](learn_halo2::gadgets::keccak::KeccakChip). the address is decomposed too,
+//! and its bits copied from the last 20 bytes of the digest.
+//!
+//! the coordinates of secp256k1 points are in its base field, so this circuit is over the
+//! secp256k1 `Fp` whatever the `curve-*` feature, and is only run by the `MockProver`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::{
+        group::{prime::PrimeCurveAffine, Curve},
+        secp256k1::{Fp, Fq, Secp256k1Affine},
+    },
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    gadgets::{
+        bits::{CanonicalChip, CanonicalConfig, DecomposeChip},
+        ecc::{EccChip, EccConfig},
+        keccak::{keccak256, KeccakChip, KeccakConfig},
+    },
+    testing, util,
+};
+
+/// bytes of a coordinate
+const COORDINATE_BYTES: usize = 32;
+/// bytes of an address, the end of the digest
+const ADDRESS_BYTES: usize = 20;
+
+#[derive(Debug, Clone)]
+struct AddressConfig {
+    ecc: EccConfig,
+    keccak: KeccakConfig,
+    canonical: CanonicalConfig,
+    address: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+struct AddressCircuit<F> {
+    pubkey: Value<(F, F)>,
+}
+
+impl<F> Default for AddressCircuit<F> {
+    fn default() -> Self {
+        Self {
+            pubkey: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AddressCircuit<F> {
+    type Config = AddressConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AddressConfig {
+        let address = meta.advice_column();
+        let bits = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(address);
+        meta.enable_equality(instance);
+        AddressConfig {
+            ecc: EccChip::configure(meta),
+            keccak: KeccakChip::configure(meta),
+            canonical: CanonicalChip::configure(meta, bits),
+            address,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: AddressConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let ecc = EccChip::construct(config.ecc);
+        let keccak = KeccakChip::construct(config.keccak);
+        let canonical = CanonicalChip::construct(config.canonical);
+        keccak.load_table(layouter.namespace(|| "chi"))?;
+
+        let pubkey = ecc.witness_point(layouter.namespace(|| "pubkey"), self.pubkey)?;
+        let mut message = Vec::with_capacity(2 * COORDINATE_BYTES * 8);
+        for (name, coordinate) in [("x", &pubkey.x), ("y", &pubkey.y)] {
+            let bits = canonical.decompose(
+                layouter.namespace(|| format!("bits of {}", name)),
+                coordinate,
+            )?;
+            // big endian bytes, each of them least significant bit first
+            for byte in bits.chunks(8).rev() {
+                message.extend_from_slice(byte);
+            }
+        }
+        let digest = keccak.hash(layouter.namespace(|| "keccak"), &message)?;
+
+        let value = self
+            .pubkey
+            .map(|(x, y)| address::<F>(&keccak256(&pubkey_bytes(x, y))));
+        let address = layouter.assign_region(
+            || "address",
+            |mut region| region.assign_advice(|| "address", config.address, 0, || value),
+        )?;
+        let bits = DecomposeChip::construct(config.canonical.decompose).decompose(
+            layouter.namespace(|| "bits of address"),
+            &address,
+            ADDRESS_BYTES * 8,
+        )?;
+        layouter.assign_region(
+            || "address bits",
+            |mut region| {
+                // bit k of the address is bit k % 8 of the digest byte 31 - k / 8
+                for (k, bit) in bits.iter().enumerate() {
+                    let byte = 31 - k / 8;
+                    region.constrain_equal(bit.cell(), digest[8 * byte + k % 8].cell())?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.constrain_instance(address.cell(), config.instance, 0)
+    }
+}
+
+/// `x || y` as big endian bytes
+fn pubkey_bytes<F: FieldExt>(x: F, y: F) -> Vec<u8> {
+    [x, y]
+        .iter()
+        .flat_map(|coordinate| {
+            let repr = coordinate.to_repr();
+            repr.as_ref().iter().rev().copied().collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// the last 20 bytes of `digest` as a big endian integer
+fn address<F: FieldExt>(digest: &[u8; 32]) -> F {
+    digest[32 - ADDRESS_BYTES..]
+        .iter()
+        .fold(F::zero(), |acc, byte| {
+            acc * F::from(256) + F::from(u64::from(*byte))
+        })
+}
+
+/// the public key of `secret`
+fn pubkey(secret: u64) -> (Fp, Fp) {
+    let point = (Secp256k1Affine::generator() * Fq::from(secret)).to_affine();
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+fn prover(pubkey: (Fp, Fp), address: Fp) -> MockProver<Fp> {
+    let circuit = AddressCircuit {
+        pubkey: Value::known(pubkey),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![address]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, AddressCircuit<Fp>>(|config| {
+                let keccak = config.keccak;
+                let canonical = config.canonical;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.ecc.x, "x")
+                    .with_advice(config.ecc.y, "y")
+                    .with_advice(config.ecc.lambda, "lambda")
+                    .with_advice(config.ecc.inv, "inv")
+                    .with_advice(canonical.decompose.bit, "bit")
+                    .with_advice(canonical.decompose.acc, "acc")
+                    .with_advice(canonical.advice[2], "lt")
+                    .with_advice(config.address, "address");
+                let lanes = keccak.a.iter().zip(&keccak.b).zip(&keccak.t);
+                for (i, ((a, b), t)) in lanes.enumerate() {
+                    names = names
+                        .with_advice(*a, format!("a[{}]", i))
+                        .with_advice(*b, format!("b[{}]", i))
+                        .with_advice(*t, format!("t[{}]", i));
+                }
+                for (x, (c, h)) in keccak.c.iter().zip(&keccak.h).enumerate() {
+                    names = names
+                        .with_advice(*c, format!("c[{}]", x))
+                        .with_advice(*h, format!("h[{}]", x));
+                }
+                names
+                    .with_fixed(keccak.rc, "rc")
+                    .with_fixed(canonical.p_bit, "p_bit")
+                    .with_selector(config.ecc.q_on_curve, "q_on_curve")
+                    .with_selector(config.ecc.q_add, "q_add")
+                    .with_selector(config.ecc.q_double, "q_double")
+                    .with_selector(config.ecc.q_select, "q_select")
+                    .with_selector(config.ecc.q_neg, "q_neg")
+                    .with_selector(keccak.q_bool, "q_bool")
+                    .with_selector(keccak.q_theta, "q_theta")
+                    .with_selector(keccak.q_chi, "q_chi")
+                    .with_selector(canonical.decompose.q_bit, "q_bit")
+                    .with_selector(canonical.decompose.q_first, "q_first")
+                    .with_selector(canonical.decompose.q_step, "q_step")
+                    .with_selector(canonical.q_first, "q_cmp_first")
+                    .with_selector(canonical.q_step, "q_cmp_step")
+                    .with_selector(canonical.q_last, "q_cmp_last")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let key = pubkey(0x1234_5678);
+    let digest = keccak256(&pubkey_bytes(key.0, key.1));
+    println!(
+        "address = 0x{}",
+        digest[32 - ADDRESS_BYTES..]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+    let address = address(&digest);
+    prover(key, address).assert_satisfied();
+
+    // the key of another address
+    testing::assert_fails_permutation(&prover(pubkey(0x1234_5679), address));
+
+    // a point off the curve
+    testing::assert_fails_gate(
+        &prover((key.0, key.1 + Fp::one()), address),
+        "ecc on curve",
+        0,
+    );
+}
@@ -0,0 +1,202 @@
+//! factorial circuit
+//!
+//! we are going to prove that n! = result for public n < 32 and result
+//!
+//! | row | counter | is_zero | x                       | acc            |
+//! |-----|---------|---------|-------------------------|----------------|
+//! | 0   | n       | 0       | n                       | 1              |
+//! | 1   | n - 1   | 0       | n - 1                   | n              |
+//! | ... | ...     | ...     | ...                     | ...            |
+//! | n   | 0       | 1       | 1                       | n!             |
+//! | ... | 0       | 1       | 1                       | n!             |
+//! | MAX |         |         |                         | n!             |
+//!
+//! the counter counts down like `n` in the fibonacci circuit and holds at 0, `x` is the
+//! counter with 0 replaced by 1, and [`RunningProductChip`] multiplies them up.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        product::{RunningProductChip, RunningProductConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+        select,
+    },
+    testing, util,
+};
+
+/// `n < 2^N_BITS`
+const N_BITS: usize = 5;
+const MAX_N: usize = 1 << N_BITS;
+
+#[derive(Debug, Clone)]
+struct FactorialConfig {
+    counter: Column<Advice>,
+    q_x: Selector,
+    q_step: Selector,
+    is_zero: IsZeroConfig,
+    product: RunningProductConfig,
+    range: RangeCheckConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct FactorialCircuit<F> {
+    n: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for FactorialCircuit<F> {
+    type Config = FactorialConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> FactorialConfig {
+        let counter = meta.advice_column();
+        let n_inv = meta.advice_column();
+        let flag = meta.advice_column();
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(counter);
+        meta.enable_equality(instance);
+        let q_x = meta.selector();
+        let q_step = meta.selector();
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_x),
+            |meta| meta.query_advice(counter, Rotation::cur()),
+            n_inv,
+            flag,
+        );
+        let product = RunningProductChip::configure(meta, x, acc);
+        // n < MAX_N, otherwise the rows run out before the counter reaches 0
+        let range = RangeCheckChip::<F, N_BITS>::configure(meta, counter);
+
+        meta.create_gate("factorial", |meta| {
+            let q_x = meta.query_selector(q_x);
+            let q_step = meta.query_selector(q_step);
+            let counter_next = meta.query_advice(counter, Rotation::next());
+            let counter = meta.query_advice(counter, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            let is_zero = is_zero.expr(meta, Rotation::cur());
+            let one = Expression::Constant(F::one());
+
+            vec![
+                // x = counter == 0 ? 1 : counter
+                q_x * (x - select::expr(is_zero.clone(), one.clone(), counter.clone())),
+                // counter' = counter == 0 ? 0 : counter - 1
+                q_step
+                    * (counter_next
+                        - select::expr(is_zero, Expression::Constant(F::zero()), counter - one)),
+            ]
+        });
+
+        FactorialConfig {
+            counter,
+            q_x,
+            q_step,
+            is_zero,
+            product,
+            range,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: FactorialConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::<F, N_BITS>::construct(config.range);
+        let is_zero = IsZeroChip::construct(config.is_zero);
+        let product = RunningProductChip::construct(config.product);
+        range.load_table(layouter.namespace(|| "n range"))?;
+
+        let (n, result) = layouter.assign_region(
+            || "factorial",
+            |mut region| {
+                let n = range.assign_checked(&mut region, 0, self.n)?;
+                let mut counter = self.n;
+                let mut xs = Vec::with_capacity(MAX_N);
+                for offset in 0..MAX_N {
+                    if offset > 0 {
+                        region.assign_advice(|| "counter", config.counter, offset, || counter)?;
+                    }
+                    config.q_x.enable(&mut region, offset)?;
+                    if offset < MAX_N - 1 {
+                        config.q_step.enable(&mut region, offset)?;
+                    }
+                    is_zero.assign(&mut region, offset, counter)?;
+
+                    xs.push(counter.map(|c| if c == F::zero() { F::one() } else { c }));
+                    counter = counter.map(|c| if c == F::zero() { c } else { c - F::one() });
+                }
+                let result = product.assign(&mut region, &xs)?;
+                Ok((n, result))
+            },
+        )?;
+
+        layouter.constrain_instance(n.cell(), config.instance, 0)?;
+        layouter.constrain_instance(result.cell(), config.instance, 1)
+    }
+}
+
+fn factorial(n: u64) -> Fp {
+    (1..=n).fold(Fp::one(), |acc, i| acc * Fp::from(i))
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, FactorialCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.counter, "counter")
+                    .with_advice(config.is_zero.is_zero, "is_zero")
+                    .with_advice(config.product.x, "x")
+                    .with_advice(config.product.acc, "acc")
+                    .with_selector(config.q_x, "q_x")
+                    .with_selector(config.q_step, "q_step")
+            })
+        );
+    }
+
+    let k = util::min_k_for(&FactorialCircuit::<Fp>::default()).unwrap();
+    for n in [0, 1, 5, 20, MAX_N as u64 - 1] {
+        let circuit = FactorialCircuit {
+            n: Value::known(Fp::from(n)),
+        };
+        let prover_success =
+            MockProver::run(k, &circuit, vec![vec![Fp::from(n), factorial(n)]]).unwrap();
+        prover_success.assert_satisfied();
+    }
+
+    let circuit = FactorialCircuit {
+        n: Value::known(Fp::from(5)),
+    };
+    let prover_failure =
+        MockProver::run(k, &circuit, vec![vec![Fp::from(5), Fp::from(119)]]).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+
+    // the counter does not reach 0, the product would be 40 * 39 * ... * 9
+    let circuit = FactorialCircuit {
+        n: Value::known(Fp::from(40)),
+    };
+    let prover_failure =
+        MockProver::run(k, &circuit, vec![vec![Fp::from(40), factorial(40)]]).unwrap();
+    testing::assert_fails_lookup(&prover_failure, 0);
+}
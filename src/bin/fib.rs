@@ -0,0 +1,446 @@
+//! fibonacci cli
+//!
+//! - `fib mock --n 5`: check fib(5) with the `MockProver`
+//! - `fib simple --n 5`: check fib(6) with the textbook circuit of [`learn_halo2::fib::simple`]
+//! - `fib prove --n 42 --out proof.bin`: create a real proof of fib(42), or reuse the one in
+//!   the [`learn_halo2::store`] unless `--no-cache` is passed. `--timeout 60` gives up
+//!   after a minute
+//! - `fib verify --proof proof.bin --instances inst.json`: verify it
+//! - `fib cost`: print the cost of the circuit for a few capacities, `--multiopen` adds the
+//!   proof size and verify time of real KZG proofs with GWC and with SHPLONK
+//! - `fib trace --n 5 --out trace.csv`: dump the assigned witness, see [`learn_halo2::trace`]
+//! - `fib layout --n 10 --out layout.svg`: render the layout (needs the `dev-graph` feature)
+//!
+//! instead of `--n`, `--witness witness.json` loads `fib(0)`, `fib(1)` and `n` from json,
+//! see [`learn_halo2::json`] for the format.
+//!
+//! `--backend kzg-shplonk` opens the KZG commitments with SHPLONK instead of GWC, and
+//! `--backend ipa` proves with IPA over pasta instead of KZG over bn256 (needs the `ipa` feature)
+//!
+//! `--dump-gates` prints the gates, lookups and permutation of the circuit first,
+//! see [`learn_halo2::constraints`]
+//!
+//! `--timing` prints the time spent in each proving phase, see [`learn_halo2::telemetry`]
+//!
+//! `--progress` shows the proving phases and the assigned rows on stderr while proving
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use halo2_proofs::{arithmetic::FieldExt, dev::MockProver, halo2curves::bn256::Fr};
+use learn_halo2::{
+    constraints,
+    cost::{CostReport, MultiOpenCost},
+    fib::{
+        self,
+        simple::{SimpleFibCircuit, SimpleFibConfig},
+        FibCircuit, PublicInputs, DEFAULT_MAX_N,
+    },
+    json, keys, proof,
+    prover::{self, CancelToken, Kzg, KzgShplonk, ProvingBackend},
+    session::{Proof, ProverSession},
+    store::ProofStore,
+    telemetry::{ProgressObserver, Timings},
+    trace, util,
+};
+use std::{error::Error, path::PathBuf, sync::Arc, time::Duration};
+
+#[derive(Parser)]
+#[command(about = "prove fibonacci numbers with halo2")]
+struct Cli {
+    /// commitment scheme to prove with
+    #[arg(long, value_enum, default_value_t = Backend::Kzg, global = true)]
+    backend: Backend,
+    /// print a breakdown of the time spent in each phase
+    #[arg(long, global = true)]
+    timing: bool,
+    /// show the progress of proving on stderr
+    #[arg(long, global = true)]
+    progress: bool,
+    /// print the constraints of the circuit before running the command
+    #[arg(long, global = true)]
+    dump_gates: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    Kzg,
+    KzgShplonk,
+    #[cfg(feature = "ipa")]
+    Ipa,
+}
+
+#[derive(Args)]
+struct Witness {
+    /// prove fib(n) with fib(0) = 0 and fib(1) = 1
+    #[arg(long, required_unless_present = "witness", conflicts_with = "witness")]
+    n: Option<u64>,
+    /// json file with the circuit witness
+    #[arg(long)]
+    witness: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// check fib(n) with the MockProver
+    Mock {
+        #[command(flatten)]
+        witness: Witness,
+    },
+    /// create a proof of fib(n)
+    Prove {
+        #[command(flatten)]
+        witness: Witness,
+        /// where to write the proof, together with its instances
+        #[arg(long)]
+        out: PathBuf,
+        /// also write the instances as json
+        #[arg(long)]
+        instances: Option<PathBuf>,
+        /// write the verifying key after keygen
+        #[arg(long)]
+        dump_vk: Option<PathBuf>,
+        /// prove again even if the proof store holds a proof of the same statement
+        #[arg(long)]
+        no_cache: bool,
+        /// give up keygen and proving after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// verify a proof
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        /// instances as json, defaults to the ones stored in the proof file
+        #[arg(long)]
+        instances: Option<PathBuf>,
+    },
+    /// check fib(n + 1) in n rows of the three column circuit with the MockProver
+    Simple {
+        #[arg(long, default_value_t = 5)]
+        n: u64,
+    },
+    /// print rows, columns, lookups and proof size of the circuit
+    Cost {
+        /// also compare real KZG proofs of fib(42) with GWC and with SHPLONK
+        #[arg(long)]
+        multiopen: bool,
+    },
+    /// write the assigned cells as a table, tab separated if `out` ends with `.tsv`
+    Trace {
+        #[command(flatten)]
+        witness: Witness,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// render the circuit layout, as SVG if `out` ends with `.svg` and PNG otherwise
+    #[cfg(feature = "dev-graph")]
+    Layout {
+        #[command(flatten)]
+        witness: Witness,
+        #[arg(long)]
+        out: PathBuf,
+        /// capacity of the circuit, one of 100, 370 or 1000
+        #[arg(long, default_value_t = DEFAULT_MAX_N)]
+        max_n: usize,
+        #[arg(long, default_value_t = 1024)]
+        width: u32,
+        #[arg(long, default_value_t = 3096)]
+        height: u32,
+        /// label regions with their names
+        #[arg(long)]
+        labels: bool,
+    },
+}
+
+fn circuit_for<F: FieldExt>(
+    witness: Witness,
+) -> Result<(FibCircuit<F>, Vec<Vec<F>>), Box<dyn Error>> {
+    let circuit: FibCircuit<F> = match (witness.n, witness.witness) {
+        (_, Some(path)) => json::read(path)?,
+        (Some(n), None) => FibCircuit {
+            n: F::from(n),
+            n_0: F::zero(),
+            n_1: F::one(),
+        },
+        (None, None) => unreachable!("clap requires --n or --witness"),
+    };
+
+    let n = circuit.n.get_lower_128();
+    if circuit.n != F::from_u128(n) || n >= DEFAULT_MAX_N as u128 {
+        return Err(format!("n should be smaller than {}", DEFAULT_MAX_N).into());
+    }
+    let instances = PublicInputs::new()
+        .with_initials(circuit.n_0, circuit.n_1)
+        .with_n(circuit.n)
+        .with_result(fib::expected(circuit.n_0, circuit.n_1, n as u64))
+        .to_instance_columns();
+    Ok((circuit, instances))
+}
+
+/// params for [`FibCircuit::k`] rows and the proving key of the circuit, keygen stops
+/// with `cancel`
+fn session<B: ProvingBackend>(cancel: &CancelToken) -> Result<ProverSession<B>, Box<dyn Error>> {
+    let params = B::setup(FibCircuit::<B::Scalar>::k())?;
+    Ok(prover::cancellable(cancel, || ProverSession::new(params))?)
+}
+
+/// the proving phases, and a bar of the rows assigned while synthesizing
+struct ProgressBar;
+
+impl ProgressObserver for ProgressBar {
+    fn phase_started(&self, phase: &'static str) {
+        eprintln!("{}...", phase);
+    }
+
+    fn phase_finished(&self, phase: &'static str, elapsed: Duration) {
+        eprintln!("{} done in {:.3} ms", phase, elapsed.as_secs_f64() * 1000.0);
+    }
+
+    fn rows_assigned(&self, assigned: usize, total: usize) {
+        const WIDTH: usize = 40;
+        let filled = assigned * WIDTH / total.max(1);
+        eprint!(
+            "\r  [{}{}] {}/{} rows",
+            "#".repeat(filled),
+            " ".repeat(WIDTH - filled),
+            assigned,
+            total
+        );
+        if assigned == total {
+            eprintln!();
+        }
+    }
+}
+
+fn run<B: ProvingBackend>(
+    command: Command,
+    dump_gates: bool,
+    observer: Option<Arc<dyn ProgressObserver>>,
+) -> Result<(), Box<dyn Error>> {
+    if dump_gates {
+        let gates = if matches!(command, Command::Simple { .. }) {
+            constraints::dump_circuit::<B::Scalar, SimpleFibCircuit<_>>(
+                SimpleFibConfig::column_names,
+            )
+        } else {
+            constraints::dump_circuit::<B::Scalar, FibCircuit<_>>(fib::FibConfig::column_names)
+        };
+        print!("{}", gates);
+    }
+    match command {
+        Command::Mock { witness } => {
+            let (circuit, instances) = circuit_for::<B::Scalar>(witness)?;
+            let k = util::min_k_for(&circuit)?;
+            MockProver::run(k, &circuit, instances.clone())?.assert_satisfied();
+            println!("fib({:?}) = {:?}", instances[0][2], instances[0][3]);
+        }
+        Command::Simple { n } => {
+            if n == 0 {
+                return Err("the simple circuit needs at least one row".into());
+            }
+            let circuit = SimpleFibCircuit {
+                n: B::Scalar::from(n),
+                n_0: B::Scalar::zero(),
+                n_1: B::Scalar::one(),
+            };
+            let result = fib::expected(circuit.n_0, circuit.n_1, n + 1);
+            let instances = vec![vec![circuit.n_0, circuit.n_1, result]];
+            let k = util::min_k_for(&circuit)?;
+            MockProver::run(k, &circuit, instances)?.assert_satisfied();
+            println!("fib({}) = {:?} in {} rows", n + 1, result, n);
+        }
+        Command::Prove {
+            witness,
+            out,
+            instances: instances_path,
+            dump_vk,
+            no_cache,
+            timeout,
+        } => {
+            let (circuit, instances) = circuit_for::<B::Scalar>(witness)?;
+            let columns: Vec<&[B::Scalar]> = instances.iter().map(Vec::as_slice).collect();
+            let cancel = match timeout {
+                Some(secs) => CancelToken::new().with_timeout(Duration::from_secs(secs)),
+                None => CancelToken::new(),
+            };
+            let mut session = session::<B>(&cancel)?;
+            if let Some(observer) = observer {
+                session = session.with_observer(observer);
+            }
+            if let Some(path) = dump_vk {
+                keys::export_vk(path, session.vk())?;
+            }
+
+            let store = ProofStore::default();
+            let key = format!("fib-{}-{}", B::NAME, DEFAULT_MAX_N);
+            let k = FibCircuit::<B::Scalar>::k();
+            let cached = if no_cache {
+                None
+            } else {
+                store.get(&key, k, &columns)?
+            };
+            // the params or keys may have changed since the proof was stored
+            let cached = cached.filter(|proof| {
+                let proof = Proof {
+                    proof: proof.clone(),
+                    instances: instances.clone(),
+                };
+                session.verify(&proof).is_ok()
+            });
+            let (proof, source) = match cached {
+                Some(proof) => (proof, " (from the proof store)"),
+                None => {
+                    let Proof { proof, .. } =
+                        prover::cancellable(&cancel, || session.prove_circuit(circuit))?;
+                    store.put(&key, k, &columns, &proof)?;
+                    (proof, "")
+                }
+            };
+            proof::write_proof(&out, &proof, &columns)?;
+            if let Some(path) = instances_path {
+                json::write_instances(path, &instances)?;
+            }
+            println!(
+                "fib({:?}) = {:?}, {} proof of {} bytes written to {}{}",
+                instances[0][2],
+                instances[0][3],
+                B::NAME,
+                proof.len(),
+                out.display(),
+                source
+            );
+        }
+        Command::Verify {
+            proof: proof_path,
+            instances: instances_path,
+        } => {
+            let (proof, stored) = proof::read_proof::<B::Scalar>(proof_path)?;
+            let instances = match instances_path {
+                Some(path) => json::read_instances(path)?,
+                None => stored,
+            };
+            if PublicInputs::from_instance_columns(&instances).is_none() {
+                return Err("expected a single instance column [fib(0), fib(1), n, fib(n)]".into());
+            }
+            session::<B>(&CancelToken::new())?.verify(&Proof { proof, instances })?;
+            println!("proof is valid");
+        }
+        Command::Cost { multiopen } => {
+            let reports = [
+                CostReport::measure::<B::Curve, _>(
+                    "fib (MAX_N = 100)",
+                    &FibCircuit::<B::Scalar, 100>::default(),
+                    4,
+                )?,
+                CostReport::measure::<B::Curve, _>(
+                    format!("fib (MAX_N = {})", DEFAULT_MAX_N),
+                    &FibCircuit::<B::Scalar>::default(),
+                    4,
+                )?,
+                CostReport::measure::<B::Curve, _>(
+                    "fib (MAX_N = 1000)",
+                    &FibCircuit::<B::Scalar, 1000>::default(),
+                    4,
+                )?,
+            ];
+            for report in reports {
+                println!("{}", report);
+            }
+            if multiopen {
+                print_multiopen_costs()?;
+            }
+        }
+        Command::Trace { witness, out } => {
+            let (circuit, _) = circuit_for::<B::Scalar>(witness)?;
+            let trace = trace::trace(&circuit)?;
+            trace.write(&out)?;
+            println!(
+                "{} assignments written to {}",
+                trace.records.len(),
+                out.display()
+            );
+        }
+        #[cfg(feature = "dev-graph")]
+        Command::Layout {
+            witness,
+            out,
+            max_n,
+            width,
+            height,
+            labels,
+        } => {
+            use learn_halo2::layout::{self, LayoutOptions};
+
+            let (circuit, _) = circuit_for::<B::Scalar>(witness)?;
+            let FibCircuit { n_0, n_1, n } = circuit;
+            let options = LayoutOptions {
+                title: format!("Fib Layout (MAX_N = {})", max_n),
+                width,
+                height,
+                labels,
+            };
+            match max_n {
+                100 => {
+                    let circuit = FibCircuit::<_, 100> { n_0, n_1, n };
+                    layout::render(&out, util::min_k_for(&circuit)?, &circuit, &options)?
+                }
+                DEFAULT_MAX_N => {
+                    let circuit = FibCircuit::<_, DEFAULT_MAX_N> { n_0, n_1, n };
+                    layout::render(&out, util::min_k_for(&circuit)?, &circuit, &options)?
+                }
+                1000 => {
+                    let circuit = FibCircuit::<_, 1000> { n_0, n_1, n };
+                    layout::render(&out, util::min_k_for(&circuit)?, &circuit, &options)?
+                }
+                max_n => return Err(format!("unsupported capacity {}", max_n).into()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// GWC against SHPLONK on the default capacity, the same keys for both
+fn print_multiopen_costs() -> Result<(), Box<dyn Error>> {
+    let n = 42;
+    let circuit = || FibCircuit::<Fr> {
+        n: Fr::from(n),
+        n_0: Fr::zero(),
+        n_1: Fr::one(),
+    };
+    let instances = PublicInputs::new()
+        .with_n(Fr::from(n))
+        .with_result(fib::expected(Fr::zero(), Fr::one(), n))
+        .to_instance_columns();
+    let params = Kzg::setup(FibCircuit::<Fr>::k())?;
+    println!("multi-open (MAX_N = {}, 10 verifications)", DEFAULT_MAX_N);
+    for cost in MultiOpenCost::compare(&params, circuit, &[instances[0].as_slice()], 10)? {
+        println!("  {}", cost);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let timings = if cli.timing {
+        Some(Timings::init()?)
+    } else {
+        None
+    };
+    // the observer replaces the global subscriber while proving, so it records the timings
+    let observer: Option<Arc<dyn ProgressObserver>> = match (cli.progress, &timings) {
+        (false, _) => None,
+        (true, None) => Some(Arc::new(ProgressBar)),
+        (true, Some(timings)) => Some(Arc::new((ProgressBar, timings.clone()))),
+    };
+    match cli.backend {
+        Backend::Kzg => run::<Kzg>(cli.command, cli.dump_gates, observer)?,
+        Backend::KzgShplonk => run::<KzgShplonk>(cli.command, cli.dump_gates, observer)?,
+        #[cfg(feature = "ipa")]
+        Backend::Ipa => run::<learn_halo2::prover_ipa::Ipa>(cli.command, cli.dump_gates, observer)?,
+    }
+    if let Some(timings) = timings {
+        print!("{}", timings);
+    }
+    Ok(())
+}
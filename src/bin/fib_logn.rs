@@ -0,0 +1,203 @@
+//! fibonacci by matrix exponentiation
+//!
+//! we are going to prove that fib(n) = result for public n < 2^8 and result, in one row
+//! per bit of n instead of one row per step.
+//!
+//! `M = [[1, 1], [1, 0]]` has `M^k = [[fib(k+1), fib(k)], [fib(k), fib(k-1)]]`, so the
+//! power is kept as `(a, b) = (fib(k), fib(k+1))`. squaring it and multiplying by `M`
+//! when the next bit of n is set gives
+//!
+//! - `c = fib(2k) = a * (2b - a)`
+//! - `d = fib(2k+1) = a^2 + b^2`
+//! - `(a', b') = bit ? (d, c + d) : (c, d)`
+//!
+//! | row    | bit       | a        | b          |
+//! |--------|-----------|----------|------------|
+//! | 0      | b[7]      | 0        | 1          |
+//! | i      | b[7-i]    | fib(k)   | fib(k+1)   |
+//! | 8      |           | fib(n)   | fib(n+1)   |
+//!
+//! where k is the top i bits of n. the bits come from the [`DecomposeChip`], which also
+//! checks that they recompose to n.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    fib::FibCircuit,
+    gadgets::{
+        bits::{DecomposeChip, DecomposeConfig},
+        select,
+    },
+    testing, util,
+};
+
+/// `n < 2^N_BITS`
+const N_BITS: usize = 8;
+
+#[derive(Debug, Clone)]
+struct FibLogConfig {
+    bit: Column<Advice>,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q_step: Selector,
+    decompose: DecomposeConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct FibLogCircuit<F> {
+    n: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for FibLogCircuit<F> {
+    type Config = FibLogConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> FibLogConfig {
+        let bit = meta.advice_column();
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_equality(bit);
+        meta.enable_equality(a);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        let q_step = meta.selector();
+
+        let n_bit = meta.advice_column();
+        let n_acc = meta.advice_column();
+        let decompose = DecomposeChip::configure(meta, n_bit, n_acc);
+
+        meta.create_gate("square and multiply", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+
+            let c = a.clone() * (b.clone() * Expression::Constant(F::from(2)) - a.clone());
+            let d = a.clone() * a + b.clone() * b;
+            vec![
+                q_step.clone() * (a_next - select::expr(bit.clone(), d.clone(), c.clone())),
+                q_step * (b_next - select::expr(bit, c + d.clone(), d)),
+            ]
+        });
+
+        FibLogConfig {
+            bit,
+            a,
+            b,
+            q_step,
+            decompose,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: FibLogConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let n = layouter.assign_region(
+            || "n",
+            |mut region| {
+                region.assign_advice_from_instance(|| "n", config.instance, 0, config.a, 0)
+            },
+        )?;
+        let bits = DecomposeChip::construct(config.decompose).decompose(
+            layouter.namespace(|| "bits of n"),
+            &n,
+            N_BITS,
+        )?;
+
+        let result = layouter.assign_region(
+            || "fib(n)",
+            |mut region| {
+                let mut a = region.assign_advice_from_constant(|| "a", config.a, 0, F::zero())?;
+                let mut b = region.assign_advice_from_constant(|| "b", config.b, 0, F::one())?;
+                for (offset, bit) in bits.iter().rev().enumerate() {
+                    config.q_step.enable(&mut region, offset)?;
+                    let bit = bit.copy_advice(|| "bit", &mut region, config.bit, offset)?;
+
+                    let (a_value, b_value) = (a.value().copied(), b.value().copied());
+                    let c = a_value * (b_value + b_value - a_value);
+                    let d = a_value * a_value + b_value * b_value;
+                    let set = bit.value().map(|bit| *bit == F::one());
+                    let a_next = set
+                        .zip(c.zip(d))
+                        .map(|(set, (c, d))| if set { d } else { c });
+                    let b_next = set
+                        .zip(c.zip(d))
+                        .map(|(set, (c, d))| if set { c + d } else { d });
+
+                    a = region.assign_advice(|| "a", config.a, offset + 1, || a_next)?;
+                    b = region.assign_advice(|| "b", config.b, offset + 1, || b_next)?;
+                }
+                Ok(a)
+            },
+        )?;
+        layouter.constrain_instance(result.cell(), config.instance, 1)
+    }
+}
+
+fn fib(n: u64) -> Fp {
+    (0..n)
+        .fold((Fp::zero(), Fp::one()), |(a, b), _| (b, a + b))
+        .0
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, FibLogCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.bit, "bit")
+                    .with_advice(config.a, "a")
+                    .with_advice(config.b, "b")
+                    .with_advice(config.decompose.bit, "n_bit")
+                    .with_advice(config.decompose.acc, "n_acc")
+                    .with_selector(config.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let k = util::min_k_for(&FibLogCircuit::<Fp>::default()).unwrap();
+    for n in [0, 1, 2, 5, 100, (1 << N_BITS) - 1] {
+        let circuit = FibLogCircuit {
+            n: Value::known(Fp::from(n)),
+        };
+        let prover_success = MockProver::run(k, &circuit, vec![vec![Fp::from(n), fib(n)]]).unwrap();
+        prover_success.assert_satisfied();
+    }
+
+    let circuit = FibLogCircuit {
+        n: Value::known(Fp::from(5)),
+    };
+    let prover_failure =
+        MockProver::run(k, &circuit, vec![vec![Fp::from(5), Fp::from(8)]]).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+
+    println!(
+        "k = {} for n < {}, the linear circuit needs k = {}",
+        k,
+        1 << N_BITS,
+        util::min_k_for(&FibCircuit::<Fp>::default()).unwrap()
+    );
+}
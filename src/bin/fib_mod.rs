@@ -0,0 +1,326 @@
+//! pisano period circuit
+//!
+//! we are going to prove that the fibonacci sequence modulo a public `2 <= m <= 256`
+//! has period `p`, i.e. `(fib(i), fib(i+1)) mod m` comes back to `(0, 1)` for the first
+//! time at `i = p`, for a public `p < MAX_P`.
+//!
+//! | row | counter | m   | a              | b                | carry | slack     |
+//! |-----|---------|-----|----------------|------------------|-------|-----------|
+//! | 0   | p       | m   | 0              | 1                | 0/1   | m - 1 - b |
+//! | i   | p - i   | m   | fib(i) mod m   | fib(i+1) mod m   | 0/1   | m - 1 - b |
+//! | p   | 0       | m   | 0              | 1                |       | m - 1 - b |
+//! | ... | 0       | m   | 0              | 1                |       | m - 1 - b |
+//!
+//! - `b' = a + b - carry * m` with a boolean `carry`, `b` and `slack` are range checked to
+//!   8 bits, so `b' = (a + b) mod m`
+//! - like `n` in the fibonacci circuit, the counter stops at 0 and the state is held from
+//!   then on, the last row has to be `(0, 1)`
+//! - the state is not `(0, 1)` on the rows `0 < i < p`, checked with two [`IsZeroChip`]s,
+//!   so `p` is the smallest period and not just a multiple of it
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+        select,
+    },
+    testing, util,
+};
+
+const MAX_P: usize = 512;
+
+#[derive(Debug, Clone)]
+struct PisanoConfig {
+    counter: Column<Advice>,
+    m: Column<Advice>,
+    a: Column<Advice>,
+    carry: Column<Advice>,
+    // every row
+    q_row: Selector,
+    // every row but the last
+    q_step: Selector,
+    // every row but the first
+    q_min: Selector,
+    done: IsZeroConfig,
+    a_zero: IsZeroConfig,
+    b_one: IsZeroConfig,
+    b_range: RangeCheckConfig,
+    slack_range: RangeCheckConfig,
+    instance: Column<Instance>,
+}
+
+/// one row of the table above, computed natively
+struct Row {
+    counter: u64,
+    a: u64,
+    b: u64,
+    carry: u64,
+}
+
+#[derive(Default)]
+struct PisanoCircuit<F> {
+    m: Value<F>,
+    p: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PisanoCircuit<F> {
+    type Config = PisanoConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> PisanoConfig {
+        let [counter, m, a, b, carry, slack] = [(); 6].map(|_| meta.advice_column());
+        let [counter_inv, done, a_inv, a_zero, b_inv, b_one] =
+            [(); 6].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        for column in [counter, m, a, b, done] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        let q_row = meta.selector();
+        let q_step = meta.selector();
+        let q_min = meta.selector();
+
+        let done = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_row),
+            |meta| meta.query_advice(counter, Rotation::cur()),
+            counter_inv,
+            done,
+        );
+        let a_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_row),
+            |meta| meta.query_advice(a, Rotation::cur()),
+            a_inv,
+            a_zero,
+        );
+        let b_one = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_row),
+            |meta| meta.query_advice(b, Rotation::cur()) - Expression::Constant(F::one()),
+            b_inv,
+            b_one,
+        );
+        let b_range = RangeCheckChip::<F, 8>::configure(meta, b);
+        let slack_range = RangeCheckChip::<F, 8>::configure(meta, slack);
+
+        meta.create_gate("slack", |meta| {
+            let q_row = meta.query_selector(q_row);
+            let m = meta.query_advice(m, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let slack = meta.query_advice(slack, Rotation::cur());
+            vec![q_row * (slack - (m - Expression::Constant(F::one()) - b))]
+        });
+
+        meta.create_gate("pisano step", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let one = Expression::Constant(F::one());
+            let counter_next = meta.query_advice(counter, Rotation::next());
+            let m_next = meta.query_advice(m, Rotation::next());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+            let counter = meta.query_advice(counter, Rotation::cur());
+            let m = meta.query_advice(m, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let carry = meta.query_advice(carry, Rotation::cur());
+            let done = done.expr(meta, Rotation::cur());
+
+            let sum = a.clone() + b.clone() - carry.clone() * m.clone();
+            vec![
+                q_step.clone() * (m_next - m),
+                q_step.clone() * carry.clone() * (one.clone() - carry),
+                q_step.clone()
+                    * (counter_next
+                        - select::expr(
+                            done.clone(),
+                            Expression::Constant(F::zero()),
+                            counter - one,
+                        )),
+                q_step.clone() * (a_next - select::expr(done.clone(), a, b.clone())),
+                q_step * (b_next - select::expr(done, b, sum)),
+            ]
+        });
+
+        meta.create_gate("first return", |meta| {
+            let q_min = meta.query_selector(q_min);
+            let done = done.expr(meta, Rotation::cur());
+            let a_zero = a_zero.expr(meta, Rotation::cur());
+            let b_one = b_one.expr(meta, Rotation::cur());
+            // before the counter reaches 0, the state is not (0, 1)
+            vec![q_min * (Expression::Constant(F::one()) - done) * a_zero * b_one]
+        });
+
+        PisanoConfig {
+            counter,
+            m,
+            a,
+            carry,
+            q_row,
+            q_step,
+            q_min,
+            done,
+            a_zero,
+            b_one,
+            b_range,
+            slack_range,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: PisanoConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let b_range = RangeCheckChip::<F, 8>::construct(config.b_range);
+        let slack_range = RangeCheckChip::<F, 8>::construct(config.slack_range);
+        b_range.load_table(layouter.namespace(|| "b range"))?;
+        slack_range.load_table(layouter.namespace(|| "slack range"))?;
+        let done_chip = IsZeroChip::construct(config.done);
+        let a_zero_chip = IsZeroChip::construct(config.a_zero);
+        let b_one_chip = IsZeroChip::construct(config.b_one);
+
+        let rows: Value<Vec<Row>> = self.m.zip(self.p).map(|(m, p)| {
+            let (m, p) = (m.get_lower_128() as u64, p.get_lower_128() as u64);
+            let (mut counter, mut a, mut b) = (p, 0, 1);
+            (0..MAX_P)
+                .map(|_| {
+                    let carry = u64::from(counter != 0 && a + b >= m);
+                    let row = Row {
+                        counter,
+                        a,
+                        b,
+                        carry,
+                    };
+                    if counter != 0 {
+                        (counter, a, b) = (counter - 1, b, a + b - carry * m);
+                    }
+                    row
+                })
+                .collect()
+        });
+        let cell =
+            |f: fn(&Row) -> u64, offset: usize| rows.as_ref().map(|rows| F::from(f(&rows[offset])));
+
+        let (m, p, last) = layouter.assign_region(
+            || "pisano",
+            |mut region| {
+                let mut first = None;
+                let mut last = None;
+                for offset in 0..MAX_P {
+                    config.q_row.enable(&mut region, offset)?;
+                    if offset + 1 < MAX_P {
+                        config.q_step.enable(&mut region, offset)?;
+                    }
+                    if offset > 0 {
+                        config.q_min.enable(&mut region, offset)?;
+                    }
+
+                    let counter = cell(|row| row.counter, offset);
+                    let a = cell(|row| row.a, offset);
+                    let b = cell(|row| row.b, offset);
+                    let counter_cell =
+                        region.assign_advice(|| "counter", config.counter, offset, || counter)?;
+                    let m_cell = region.assign_advice(|| "m", config.m, offset, || self.m)?;
+                    let a_cell = region.assign_advice(|| "a", config.a, offset, || a)?;
+                    let b_cell = b_range.assign_checked(&mut region, offset, b)?;
+                    region.assign_advice(
+                        || "carry",
+                        config.carry,
+                        offset,
+                        || cell(|row| row.carry, offset),
+                    )?;
+                    slack_range.assign_checked(
+                        &mut region,
+                        offset,
+                        self.m.zip(b).map(|(m, b)| m - F::one() - b),
+                    )?;
+                    let done = done_chip.assign(&mut region, offset, counter)?;
+                    a_zero_chip.assign(&mut region, offset, a)?;
+                    b_one_chip.assign(&mut region, offset, b.map(|b| b - F::one()))?;
+
+                    if offset == 0 {
+                        // p > 0, and the sequence starts from (0, 1)
+                        region.constrain_constant(done.cell(), F::zero())?;
+                        region.constrain_constant(a_cell.cell(), F::zero())?;
+                        region.constrain_constant(b_cell.cell(), F::one())?;
+                        first = Some((m_cell, counter_cell));
+                    } else if offset + 1 == MAX_P {
+                        last = Some((a_cell, b_cell));
+                    }
+                }
+                let (m, p) = first.unwrap();
+                Ok((m, p, last.unwrap()))
+            },
+        )?;
+
+        layouter.assign_region(
+            || "back to (0, 1)",
+            |mut region| {
+                region.constrain_constant(last.0.cell(), F::zero())?;
+                region.constrain_constant(last.1.cell(), F::one())
+            },
+        )?;
+        layouter.constrain_instance(m.cell(), config.instance, 0)?;
+        layouter.constrain_instance(p.cell(), config.instance, 1)
+    }
+}
+
+fn prover(m: u64, p: u64) -> MockProver<Fp> {
+    let circuit = PisanoCircuit {
+        m: Value::known(Fp::from(m)),
+        p: Value::known(Fp::from(p)),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(m), Fp::from(p)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, PisanoCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.counter, "counter")
+                    .with_advice(config.m, "m")
+                    .with_advice(config.a, "a")
+                    .with_advice(config.b_range.value, "b")
+                    .with_advice(config.carry, "carry")
+                    .with_advice(config.slack_range.value, "slack")
+                    .with_advice(config.done.is_zero, "done")
+                    .with_advice(config.a_zero.is_zero, "a_zero")
+                    .with_advice(config.b_one.is_zero, "b_one")
+                    .with_selector(config.q_row, "q_row")
+                    .with_selector(config.q_step, "q_step")
+                    .with_selector(config.q_min, "q_min")
+            })
+        );
+    }
+
+    for (m, p) in [(2, 3), (7, 16), (10, 60), (100, 300), (256, 384)] {
+        prover(m, p).assert_satisfied();
+        println!("pisano period of {} is {}", m, p);
+    }
+
+    // 120 is a period of fib mod 10, but not the smallest
+    let prover_failure = prover(10, 120);
+    testing::assert_fails_gate(&prover_failure, "first return", 60);
+}
@@ -0,0 +1,236 @@
+//! wide fibonacci circuit
+//!
+//! we are going to prove that fib(N) = result when fib(0) = 0, fib(1) = 1, doing `K`
+//! steps per row across `2K` advice columns, so it needs `N / K` rows instead of `N`:
+//!
+//! | row   | l[0]      | r[0]        | l[1]        | r[1]        | ... | r[K-1]      |
+//! |-------|-----------|-------------|-------------|-------------|-----|-------------|
+//! | 0     | fib(0)    | fib(1)      | fib(1)      | fib(2)      | ... | fib(K)      |
+//! | i     | fib(Ki)   | fib(Ki+1)   | fib(Ki+1)   | fib(Ki+2)   | ... | fib(Ki+K)   |
+//! | N / K | fib(N)    | fib(N+1)    |             |             |     |             |
+//!
+//! - `l[j+1] = r[j]`, `r[j+1] = l[j] + r[j]` within a row
+//! - `l[0]' = r[K-1]`, `r[0]' = l[K-1] + r[K-1]` to the next row
+//!
+//! unlike [`learn_halo2::fib`], N is fixed at keygen, since the result is copied from a fixed
+//! cell.
+//!
+//! pass `--bench` to compare rows, columns and proving time against [`learn_halo2::fib`] for
+//! a few `K`, and `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct WideConfig<const K: usize> {
+    l: [Column<Advice>; K],
+    r: [Column<Advice>; K],
+    selector: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct FibWideCircuit<F, const K: usize, const N: usize> {
+    n_0: Value<F>,
+    n_1: Value<F>,
+}
+
+impl<F: FieldExt, const K: usize, const N: usize> Circuit<F> for FibWideCircuit<F, K, N> {
+    type Config = WideConfig<K>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            n_0: Value::unknown(),
+            n_1: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WideConfig<K> {
+        assert!(K > 0 && N % K == 0, "N has to be a multiple of K");
+        let l = [(); K].map(|_| meta.advice_column());
+        let r = [(); K].map(|_| meta.advice_column());
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(l[0]);
+        meta.enable_equality(r[0]);
+        meta.enable_equality(instance);
+
+        meta.create_gate("fib wide", |meta| {
+            let s = meta.query_selector(selector);
+            let mut constraints = Vec::with_capacity(2 * K);
+            for j in 0..K {
+                let l_cur = meta.query_advice(l[j], Rotation::cur());
+                let r_cur = meta.query_advice(r[j], Rotation::cur());
+                // the next pair is in the same row, or the first one of the next row
+                let (l_next, r_next) = if j + 1 < K {
+                    (
+                        meta.query_advice(l[j + 1], Rotation::cur()),
+                        meta.query_advice(r[j + 1], Rotation::cur()),
+                    )
+                } else {
+                    (
+                        meta.query_advice(l[0], Rotation::next()),
+                        meta.query_advice(r[0], Rotation::next()),
+                    )
+                };
+                constraints.push(s.clone() * (l_next - r_cur.clone()));
+                constraints.push(s.clone() * (r_next - (l_cur + r_cur)));
+            }
+            constraints
+        });
+
+        WideConfig {
+            l,
+            r,
+            selector,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: WideConfig<K>,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (n_0, n_1, result) = layouter.assign_region(
+            || "fib wide",
+            |mut region| {
+                let n_0 = region.assign_advice(|| "l", config.l[0], 0, || self.n_0)?;
+                let n_1 = region.assign_advice(|| "r", config.r[0], 0, || self.n_1)?;
+                let (mut l, mut r) = (self.n_0, self.n_1);
+                for row in 0..N / K {
+                    config.selector.enable(&mut region, row)?;
+                    for j in 0..K {
+                        if row > 0 || j > 0 {
+                            region.assign_advice(|| "l", config.l[j], row, || l)?;
+                            region.assign_advice(|| "r", config.r[j], row, || r)?;
+                        }
+                        (l, r) = (r, l + r);
+                    }
+                }
+                let result = region.assign_advice(|| "l", config.l[0], N / K, || l)?;
+                region.assign_advice(|| "r", config.r[0], N / K, || r)?;
+                Ok((n_0, n_1, result))
+            },
+        )?;
+
+        layouter.constrain_instance(n_0.cell(), config.instance, 0)?;
+        layouter.constrain_instance(n_1.cell(), config.instance, 1)?;
+        layouter.constrain_instance(result.cell(), config.instance, 2)
+    }
+}
+
+/// the N used by the examples and the benchmark
+const N: usize = 360;
+
+fn fib(n: usize) -> Fp {
+    (0..n)
+        .fold((Fp::zero(), Fp::one()), |(a, b), _| (b, a + b))
+        .0
+}
+
+fn check<const K: usize>() {
+    let circuit = FibWideCircuit::<Fp, K, N> {
+        n_0: Value::known(Fp::zero()),
+        n_1: Value::known(Fp::one()),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+
+    let prover_success =
+        MockProver::run(k, &circuit, vec![vec![Fp::zero(), Fp::one(), fib(N)]]).unwrap();
+    prover_success.assert_satisfied();
+    let prover_failure =
+        MockProver::run(k, &circuit, vec![vec![Fp::zero(), Fp::one(), fib(N - 1)]]).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+}
+
+/// print the cost and proving time of `circuit`
+#[cfg(not(feature = "curve-secp256k1"))]
+fn bench<C: Circuit<Fp>>(name: &str, circuit: C, instances: Vec<Fp>) {
+    use learn_halo2::{cost::CostReport, curve::Backend, prover::ProvingBackend};
+    use std::time::Instant;
+
+    let report = CostReport::measure::<<Backend as ProvingBackend>::Curve, _>(
+        name,
+        &circuit,
+        instances.len(),
+    )
+    .unwrap();
+    let params = Backend::setup(report.k).unwrap();
+    let pk = Backend::keygen(&params, &circuit).unwrap();
+    let start = Instant::now();
+    Backend::prove(&params, &pk, circuit, &[instances.as_slice()]).unwrap();
+    print!("{}", report);
+    println!("  proving:     {:?}", start.elapsed());
+}
+
+#[cfg(not(feature = "curve-secp256k1"))]
+fn bench_all() {
+    use learn_halo2::fib::{FibCircuit, PublicInputs};
+
+    let dynamic = FibCircuit::<Fp> {
+        n: Fp::from(N as u64),
+        n_0: Fp::zero(),
+        n_1: Fp::one(),
+    };
+    let instances = PublicInputs::new()
+        .with_n(Fp::from(N as u64))
+        .with_result(fib(N))
+        .to_instance_columns()
+        .remove(0);
+    bench("fib", dynamic, instances);
+
+    fn wide<const K: usize>() {
+        let circuit = FibWideCircuit::<Fp, K, N> {
+            n_0: Value::known(Fp::zero()),
+            n_1: Value::known(Fp::one()),
+        };
+        let instances = vec![Fp::zero(), Fp::one(), fib(N)];
+        bench(&format!("fib_wide (K = {})", K), circuit, instances);
+    }
+    wide::<1>();
+    wide::<2>();
+    wide::<4>();
+    wide::<8>();
+}
+
+#[cfg(feature = "curve-secp256k1")]
+fn bench_all() {
+    println!("secp256k1 has no commitment scheme, skipping the benchmark");
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, FibWideCircuit<Fp, 2, N>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.l[0], "l0")
+                    .with_advice(config.r[0], "r0")
+                    .with_advice(config.l[1], "l1")
+                    .with_advice(config.r[1], "r1")
+                    .with_selector(config.selector, "s")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    check::<1>();
+    check::<4>();
+    check::<8>();
+
+    if std::env::args().any(|arg| arg == "--bench") {
+        bench_all();
+    }
+}
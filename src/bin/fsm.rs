@@ -0,0 +1,173 @@
+//! token stream circuit
+//!
+//! we are going to prove that a public stream of `LEN` tokens is an arithmetic expression,
+//! numbers joined by operators with at most one level of parentheses, with the
+//! [`FsmChip`](learn_halo2::gadgets::fsm::FsmChip)
+//!
+//! | instance      |
+//! |---------------|
+//! | token[0]      |
+//! | ...           |
+//! | token[LEN-1]  |
+//!
+//! the automaton has a state for expecting an operand and one for after an operand, at the
+//! top level and inside the parentheses:
+//!
+//! | state   | NUM     | OP      | LPAREN  | RPAREN | PAD  |
+//! |---------|---------|---------|---------|--------|------|
+//! | OPERAND | AFTER   |         | INNER   |        |      |
+//! | AFTER   |         | OPERAND |         |        | DONE |
+//! | INNER   | INNER_2 |         |         |        |      |
+//! | INNER_2 |         | INNER   |         | AFTER  |      |
+//! | DONE    |         |         |         |        | DONE |
+//!
+//! a shorter expression is padded to `LEN` tokens, and the last state is constrained to
+//! `DONE`, so the stream has at least one `PAD` and nothing but `PAD` after it.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::fsm::{FsmChip, FsmConfig, Transition},
+    testing, util,
+};
+use std::marker::PhantomData;
+
+const LEN: usize = 12;
+
+const PAD: u64 = 0;
+const NUM: u64 = 1;
+const OP: u64 = 2;
+const LPAREN: u64 = 3;
+const RPAREN: u64 = 4;
+
+const OPERAND: u64 = 1;
+const AFTER: u64 = 2;
+const INNER: u64 = 3;
+const INNER_2: u64 = 4;
+const DONE: u64 = 5;
+
+const TRANSITIONS: [Transition; 8] = [
+    (OPERAND, NUM, AFTER),
+    (OPERAND, LPAREN, INNER),
+    (AFTER, OP, OPERAND),
+    (AFTER, PAD, DONE),
+    (INNER, NUM, INNER_2),
+    (INNER_2, OP, INNER),
+    (INNER_2, RPAREN, AFTER),
+    (DONE, PAD, DONE),
+];
+
+#[derive(Debug, Clone)]
+struct TokensConfig {
+    fsm: FsmConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct TokensCircuit<F>(PhantomData<F>);
+
+impl<F: FieldExt> Circuit<F> for TokensCircuit<F> {
+    type Config = TokensConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> TokensConfig {
+        let state = meta.advice_column();
+        let input = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        TokensConfig {
+            fsm: FsmChip::configure(meta, state, input),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: TokensConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FsmChip::construct(config.fsm, &TRANSITIONS);
+        chip.load_table(layouter.namespace(|| "transitions"))?;
+
+        let (start, tokens) = layouter.assign_region(
+            || "tokens",
+            |mut region| {
+                let start = region.assign_advice_from_constant(
+                    || "start",
+                    config.fsm.state,
+                    0,
+                    F::from(OPERAND),
+                )?;
+                let tokens = (0..LEN)
+                    .map(|row| {
+                        region.assign_advice_from_instance(
+                            || "token",
+                            config.instance,
+                            row,
+                            config.fsm.input,
+                            row,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((start, tokens))
+            },
+        )?;
+        let last = chip.run(layouter.namespace(|| "parse"), &start, &tokens)?;
+        layouter.assign_region(
+            || "accept",
+            |mut region| region.constrain_constant(last.cell(), F::from(DONE)),
+        )
+    }
+}
+
+fn prover(tokens: &[u64]) -> MockProver<Fp> {
+    let circuit = TokensCircuit::default();
+    let k = util::min_k_for(&circuit).unwrap();
+    let mut instance: Vec<_> = tokens.iter().map(|token| Fp::from(*token)).collect();
+    instance.resize(LEN, Fp::from(PAD));
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, TokensCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.fsm.state, "state")
+                    .with_advice(config.fsm.input, "input")
+                    .with_selector(config.fsm.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // 1 + (2 * 3) - 4
+    prover(&[NUM, OP, LPAREN, NUM, OP, NUM, RPAREN, OP, NUM]).assert_satisfied();
+    // 42
+    prover(&[NUM]).assert_satisfied();
+
+    // 1 + ) 2, no transition from OPERAND on RPAREN
+    testing::assert_fails_lookup(&prover(&[NUM, OP, RPAREN, NUM]), 2);
+
+    // 1 + (2, the padding starts inside the parentheses
+    testing::assert_fails_lookup(&prover(&[NUM, OP, LPAREN, NUM]), 4);
+
+    // 1 + 2 + 3 + 4 + 5 + 6 +, every step is valid but the stream has no room for a PAD
+    let tokens: Vec<_> = [NUM, OP].iter().copied().cycle().take(LEN).collect();
+    testing::assert_fails_permutation(&prover(&tokens));
+}
@@ -0,0 +1,251 @@
+//! hamiltonian cycle circuit
+//!
+//! we are going to prove that we know a private ordering of the vertices of a public graph,
+//! here the cube, that is a hamiltonian cycle: it visits every vertex once, and goes from
+//! each vertex to the next and from the last back to the first along edges
+//!
+//! the adjacency matrix is part of the circuit, there is no instance. its edges `(u, v)`,
+//! both ways, are in fixed table columns, with a tag and a first row of 0s, and every step
+//! of the cycle is looked up in them:
+//!
+//! | row | order      | index | x_in               | acc_in | x_out          | acc_out | q_step |
+//! |-----|------------|-------|--------------------|--------|----------------|---------|--------|
+//! | i   | order[i]   | i     | order[i] + gamma   | ...    | i + gamma      | ...     | 1      |
+//! | V   | order[0]   |       |                    | prod   |                | prod    | 0      |
+//!
+//! - `(q_step, q_step * order, q_step * order')` in `(tag, u, v)`
+//!
+//! the last row is a copy of the first one, which closes the cycle. the order visits every
+//! vertex once when it is a permutation of `0..V`, checked like the entries of
+//! `src/bin/magic_square.rs`, with a challenge `gamma` and two running products of the
+//! [`RunningProductChip`](learn_halo2::gadgets::product::RunningProductChip):
+//!
+//! - `q_step * (x_in - order - gamma) = 0`
+//! - `q_step * (x_out - index - gamma) = 0`
+//!
+//! and the two products are copy constrained to be equal.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, Fixed,
+        SecondPhase, Selector, TableColumn,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::product::{RunningProductChip, RunningProductConfig},
+    testing, util,
+};
+
+const V: usize = 8;
+
+/// the cube, two vertices are adjacent when they differ in one bit
+const ADJACENCY: [[u8; V]; V] = [
+    [0, 1, 1, 0, 1, 0, 0, 0],
+    [1, 0, 0, 1, 0, 1, 0, 0],
+    [1, 0, 0, 1, 0, 0, 1, 0],
+    [0, 1, 1, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 0],
+    [0, 1, 0, 0, 1, 0, 0, 1],
+    [0, 0, 1, 0, 1, 0, 0, 1],
+    [0, 0, 0, 1, 0, 1, 1, 0],
+];
+
+#[derive(Debug, Clone)]
+struct HamiltonianConfig {
+    order: Column<Advice>,
+    index: Column<Fixed>,
+    q_step: Selector,
+    gamma: Challenge,
+    product_in: RunningProductConfig,
+    product_out: RunningProductConfig,
+    /// tag, u and v of the edges
+    edges: [TableColumn; 3],
+}
+
+struct HamiltonianCircuit<F> {
+    order: [Value<F>; V],
+}
+
+impl<F> Default for HamiltonianCircuit<F> {
+    fn default() -> Self {
+        Self {
+            order: [(); V].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for HamiltonianCircuit<F> {
+    type Config = HamiltonianConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> HamiltonianConfig {
+        let order = meta.advice_column_in(FirstPhase);
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let x_in = meta.advice_column_in(SecondPhase);
+        let acc_in = meta.advice_column_in(SecondPhase);
+        let x_out = meta.advice_column_in(SecondPhase);
+        let acc_out = meta.advice_column_in(SecondPhase);
+        let index = meta.fixed_column();
+        let q_step = meta.complex_selector();
+        let edges = [(); 3].map(|_| meta.lookup_table_column());
+        meta.enable_equality(order);
+
+        let product_in = RunningProductChip::configure(meta, x_in, acc_in);
+        let product_out = RunningProductChip::configure(meta, x_out, acc_out);
+        meta.create_gate("shift", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let gamma = meta.query_challenge(gamma);
+            let order = meta.query_advice(order, Rotation::cur());
+            let index = meta.query_fixed(index, Rotation::cur());
+            let x_in = meta.query_advice(x_in, Rotation::cur());
+            let x_out = meta.query_advice(x_out, Rotation::cur());
+            vec![
+                q_step.clone() * (x_in - order - gamma.clone()),
+                q_step * (x_out - index - gamma),
+            ]
+        });
+
+        meta.lookup(|meta| {
+            let q_step = meta.query_selector(q_step);
+            let from = meta.query_advice(order, Rotation::cur());
+            let to = meta.query_advice(order, Rotation::next());
+            let [tag, u, v] = edges;
+            vec![
+                (q_step.clone(), tag),
+                (q_step.clone() * from, u),
+                (q_step * to, v),
+            ]
+        });
+
+        HamiltonianConfig {
+            order,
+            index,
+            q_step,
+            gamma,
+            product_in,
+            product_out,
+            edges,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: HamiltonianConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "edges",
+            |mut table| {
+                let edges = ADJACENCY.iter().enumerate().flat_map(|(u, row)| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, adjacent)| **adjacent == 1)
+                        .map(move |(v, _)| (1, u as u64, v as u64))
+                });
+                for (row, (tag, u, v)) in [(0, 0, 0)].into_iter().chain(edges).enumerate() {
+                    for (column, value) in config.edges.iter().zip([tag, u, v]) {
+                        table.assign_cell(
+                            || "edge",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        // unknown until the first phase is committed
+        let gamma = layouter.get_challenge(config.gamma);
+        let x_in: Vec<_> = self.order.iter().map(|vertex| *vertex + gamma).collect();
+        let x_out: Vec<_> = (0..V)
+            .map(|index| gamma.map(|gamma| F::from(index as u64) + gamma))
+            .collect();
+
+        let product_in = RunningProductChip::construct(config.product_in);
+        let product_out = RunningProductChip::construct(config.product_out);
+        layouter.assign_region(
+            || "cycle",
+            |mut region| {
+                let mut first = None;
+                for (offset, vertex) in self.order.iter().enumerate() {
+                    config.q_step.enable(&mut region, offset)?;
+                    let cell =
+                        region.assign_advice(|| "order", config.order, offset, || *vertex)?;
+                    first.get_or_insert(cell);
+                    region.assign_fixed(
+                        || "index",
+                        config.index,
+                        offset,
+                        || Value::known(F::from(offset as u64)),
+                    )?;
+                }
+                first
+                    .unwrap()
+                    .copy_advice(|| "back to the first", &mut region, config.order, V)?;
+
+                let total_in = product_in.assign(&mut region, &x_in)?;
+                let total_out = product_out.assign(&mut region, &x_out)?;
+                region.constrain_equal(total_in.cell(), total_out.cell())
+            },
+        )
+    }
+}
+
+fn prover(order: [u64; V]) -> MockProver<Fp> {
+    let circuit = HamiltonianCircuit {
+        order: order.map(|vertex| Value::known(Fp::from(vertex))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, HamiltonianCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.order, "order")
+                    .with_fixed(config.index, "index")
+                    .with_advice(config.product_in.x, "x_in")
+                    .with_advice(config.product_in.acc, "acc_in")
+                    .with_advice(config.product_out.x, "x_out")
+                    .with_advice(config.product_out.acc, "acc_out")
+                    .with_selector(config.q_step, "q_step")
+                    .with_selector(config.product_in.q_first, "q_first_in")
+                    .with_selector(config.product_in.q_product, "q_product_in")
+                    .with_selector(config.product_out.q_first, "q_first_out")
+                    .with_selector(config.product_out.q_product, "q_product_out")
+            })
+        );
+    }
+
+    // the gray code
+    let gray = [0, 1, 3, 2, 6, 7, 5, 4];
+    prover(gray).assert_satisfied();
+    // the other way around, from another vertex
+    prover([5, 7, 6, 2, 3, 1, 0, 4]).assert_satisfied();
+
+    // counting, 1 and 2 differ in two bits
+    testing::assert_fails_lookup(&prover([0, 1, 2, 3, 4, 5, 6, 7]), 1);
+
+    // a hamiltonian path, 7 isn't adjacent to 0
+    testing::assert_fails_lookup(&prover([0, 1, 3, 2, 6, 4, 5, 7]), V - 1);
+
+    // back and forth along one edge, every step is an edge but 0 and 1 are visited 4 times
+    testing::assert_fails_permutation(&prover([0, 1, 0, 1, 0, 1, 0, 1]));
+}
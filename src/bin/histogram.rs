@@ -0,0 +1,272 @@
+//! histogram circuit
+//!
+//! we are going to prove that public bucket counts are the histogram of a private dataset
+//! of `N` values in `0..EDGES[B]`, the bucket `b` holding `EDGES[b]..EDGES[b+1]`
+//!
+//! | instance    |
+//! |-------------|
+//! | count[0]    |
+//! | ...         |
+//! | count[B-1]  |
+//!
+//! every value is put in a bucket by a lookup into fixed table columns of every value and
+//! its bucket, with a tag and a first row of 0s, and the bucket is one-hot in the flags:
+//!
+//! | row | value    | bucket    | flag[b]                | q_bucket |
+//! |-----|----------|-----------|------------------------|----------|
+//! | i   | data[i]  | bucket[i] | bucket[i] == b         | 1        |
+//!
+//! - `(q_bucket, q_bucket * value, q_bucket * bucket)` in `(tag, value, bucket)`
+//! - `q_bucket * flag * (1 - flag) = 0` for every bucket
+//! - `q_bucket * (flag[0] + ... + flag[B-1] - 1) = 0`
+//! - `q_bucket * (0 * flag[0] + ... + (B-1) * flag[B-1] - bucket) = 0`
+//!
+//! the table also range checks the values. every flag column is added up by a running sum
+//! of the [`RunningSumChip`](learn_halo2::gadgets::accumulator::RunningSumChip), copy
+//! constrained to the flags, and the totals are the counts.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::accumulator::{RunningSumChip, RunningSumConfig},
+    testing, util,
+};
+
+const N: usize = 16;
+const B: usize = 4;
+/// the bounds of the buckets
+const EDGES: [u64; B + 1] = [0, 8, 16, 32, 64];
+
+#[derive(Debug, Clone)]
+struct HistogramConfig {
+    value: Column<Advice>,
+    bucket: Column<Advice>,
+    flag: [Column<Advice>; B],
+    q_bucket: Selector,
+    /// tag, value and bucket of every value
+    table: [TableColumn; 3],
+    sum: RunningSumConfig,
+    instance: Column<Instance>,
+}
+
+struct HistogramCircuit<F> {
+    data: [Value<F>; N],
+    /// the bucket of every value, chosen by the prover
+    buckets: [Value<F>; N],
+}
+
+impl<F> Default for HistogramCircuit<F> {
+    fn default() -> Self {
+        Self {
+            data: [(); N].map(|_| Value::unknown()),
+            buckets: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for HistogramCircuit<F> {
+    type Config = HistogramConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> HistogramConfig {
+        let value = meta.advice_column();
+        let bucket = meta.advice_column();
+        let flag = [(); B].map(|_| meta.advice_column());
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_bucket = meta.complex_selector();
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        for column in flag.into_iter().chain([x]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.lookup(|meta| {
+            let q_bucket = meta.query_selector(q_bucket);
+            let value = meta.query_advice(value, Rotation::cur());
+            let bucket = meta.query_advice(bucket, Rotation::cur());
+            let [tag, value_table, bucket_table] = table;
+            vec![
+                (q_bucket.clone(), tag),
+                (q_bucket.clone() * value, value_table),
+                (q_bucket * bucket, bucket_table),
+            ]
+        });
+
+        meta.create_gate("bucket", |meta| {
+            let q_bucket = meta.query_selector(q_bucket);
+            let one = Expression::Constant(F::one());
+            let bucket = meta.query_advice(bucket, Rotation::cur());
+            let mut constraints = vec![];
+            let mut count = Expression::Constant(F::zero());
+            let mut encoded = Expression::Constant(F::zero());
+            for (b, column) in flag.iter().enumerate() {
+                let flag = meta.query_advice(*column, Rotation::cur());
+                constraints.push(q_bucket.clone() * flag.clone() * (one.clone() - flag.clone()));
+                count = count + flag.clone();
+                encoded = encoded + Expression::Constant(F::from(b as u64)) * flag;
+            }
+            constraints.push(q_bucket.clone() * (count - one));
+            constraints.push(q_bucket * (encoded - bucket));
+            constraints
+        });
+
+        HistogramConfig {
+            value,
+            bucket,
+            flag,
+            q_bucket,
+            table,
+            sum: RunningSumChip::configure(meta, x, acc),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: HistogramConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "buckets",
+            |mut table| {
+                let entries = (0..EDGES[B]).map(|value| [1, value, bucket_of(value) as u64]);
+                for (row, entry) in [[0; 3]].into_iter().chain(entries).enumerate() {
+                    for (column, value) in config.table.iter().zip(entry) {
+                        table.assign_cell(
+                            || "entry",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let flags = layouter.assign_region(
+            || "dataset",
+            |mut region| {
+                let mut flags = vec![vec![]; B];
+                for (i, (value, bucket)) in self.data.iter().zip(&self.buckets).enumerate() {
+                    config.q_bucket.enable(&mut region, i)?;
+                    region.assign_advice(|| "value", config.value, i, || *value)?;
+                    region.assign_advice(|| "bucket", config.bucket, i, || *bucket)?;
+                    for (b, (column, cells)) in config.flag.iter().zip(&mut flags).enumerate() {
+                        let flag =
+                            bucket.map(|bucket| F::from((bucket == F::from(b as u64)) as u64));
+                        cells.push(region.assign_advice(|| "flag", *column, i, || flag)?);
+                    }
+                }
+                Ok(flags)
+            },
+        )?;
+
+        let sum = RunningSumChip::construct(config.sum);
+        for (b, cells) in flags.iter().enumerate() {
+            let xs: Vec<_> = cells.iter().map(|cell| cell.value().copied()).collect();
+            let (x_cells, accs) = sum.assign_cells(layouter.namespace(|| "count"), &xs)?;
+            layouter.assign_region(
+                || "members",
+                |mut region| {
+                    for (x, cell) in x_cells.iter().zip(cells) {
+                        region.constrain_equal(x.cell(), cell.cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.constrain_instance(accs.last().unwrap().cell(), config.instance, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// the bucket holding `value`, which has to be below `EDGES[B]`
+fn bucket_of(value: u64) -> usize {
+    EDGES.iter().rposition(|edge| *edge <= value).unwrap()
+}
+
+fn histogram(data: &[u64; N]) -> [u64; B] {
+    let mut counts = [0; B];
+    for value in data {
+        counts[bucket_of(*value)] += 1;
+    }
+    counts
+}
+
+fn prover(data: [u64; N], buckets: [usize; N], counts: [u64; B]) -> MockProver<Fp> {
+    let circuit = HistogramCircuit {
+        data: data.map(|value| Value::known(Fp::from(value))),
+        buckets: buckets.map(|bucket| Value::known(Fp::from(bucket as u64))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![counts.map(Fp::from).to_vec()]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, HistogramCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new()
+                    .with_advice(config.value, "value")
+                    .with_advice(config.bucket, "bucket");
+                for (b, column) in config.flag.iter().enumerate() {
+                    names = names.with_advice(*column, format!("flag[{}]", b));
+                }
+                names
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_selector(config.q_bucket, "q_bucket")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let data = [3, 17, 42, 8, 0, 15, 63, 31, 22, 7, 16, 9, 50, 32, 1, 12];
+    let buckets = data.map(bucket_of);
+    let counts = histogram(&data);
+    assert_eq!(counts, [4, 4, 4, 4]);
+    prover(data, buckets, counts).assert_satisfied();
+    // all in one bucket
+    let same = [20; N];
+    prover(same, same.map(bucket_of), [0, 0, N as u64, 0]).assert_satisfied();
+
+    // a value moved to the next bucket
+    testing::assert_fails_permutation(&prover(data, buckets, [3, 5, 4, 4]));
+
+    // 8 counted in the bucket below it, with the counts to match
+    let mut misfiled = buckets;
+    misfiled[3] = 0;
+    testing::assert_fails_lookup(&prover(data, misfiled, [5, 3, 4, 4]), 3);
+
+    // a value out of the range of the buckets
+    let mut outlier = data;
+    outlier[6] = 64;
+    testing::assert_fails_lookup(&prover(outlier, buckets, counts), 6);
+
+    // a bucket that does not exist
+    let mut missing = buckets;
+    missing[6] = B;
+    testing::assert_fails_gate(&prover(data, missing, [4, 4, 4, 3]), "bucket", 6);
+}
@@ -0,0 +1,186 @@
+//! hmac-sha256 circuit
+//!
+//! we are going to prove that we know the key of a public hmac-sha256 tag of a public
+//! message, the way an api credential signs a request, with the
+//! [`HmacChip`](learn_halo2::gadgets::hmac::HmacChip)
+//!
+//! | instance         |
+//! |------------------|
+//! | message[0]       |
+//! | ...              |
+//! | message[7]       |
+//! | tag[0]           |
+//! | ...              |
+//! | tag[7]           |
+//!
+//! the message and the tag are big endian 32 bit words. the key is private, `KEY_WORDS`
+//! words, and is zero padded to a block inside the circuit.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        hmac::{hmac_sha256, HmacChip},
+        sha256::{Sha256Chip, Sha256Config},
+    },
+    testing, util,
+};
+
+const KEY_WORDS: usize = 4;
+const MESSAGE_WORDS: usize = 8;
+
+#[derive(Debug, Clone)]
+struct HmacConfig {
+    sha256: Sha256Config,
+    key: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+struct HmacCircuit<F> {
+    key: [Value<F>; KEY_WORDS],
+}
+
+impl<F> Default for HmacCircuit<F> {
+    fn default() -> Self {
+        Self {
+            key: [(); KEY_WORDS].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for HmacCircuit<F> {
+    type Config = HmacConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> HmacConfig {
+        let key = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(key);
+        meta.enable_equality(instance);
+        HmacConfig {
+            sha256: HmacChip::configure(meta),
+            key,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: HmacConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let sha256 = Sha256Chip::construct(config.sha256);
+        let (key, message) = layouter.assign_region(
+            || "key and message",
+            |mut region| {
+                let key = self
+                    .key
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, word)| {
+                        region.assign_advice(|| "key", config.key, offset, || *word)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let message = (0..MESSAGE_WORDS)
+                    .map(|row| {
+                        region.assign_advice_from_instance(
+                            || "message",
+                            config.instance,
+                            row,
+                            config.key,
+                            KEY_WORDS + row,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((key, message))
+            },
+        )?;
+        let key = key
+            .iter()
+            .map(|cell| sha256.word(layouter.namespace(|| "key word"), cell))
+            .collect::<Result<Vec<_>, _>>()?;
+        let message = message
+            .iter()
+            .map(|cell| sha256.word(layouter.namespace(|| "message word"), cell))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tag = HmacChip::construct(config.sha256).mac(
+            layouter.namespace(|| "hmac"),
+            &key,
+            &message,
+        )?;
+        for (row, word) in tag.iter().enumerate() {
+            layouter.constrain_instance(word.value.cell(), config.instance, MESSAGE_WORDS + row)?;
+        }
+        Ok(())
+    }
+}
+
+/// big endian words of `bytes`
+fn words(bytes: &[u8]) -> Vec<Fp> {
+    bytes
+        .chunks(4)
+        .map(|word| Fp::from(u64::from(u32::from_be_bytes(word.try_into().unwrap()))))
+        .collect()
+}
+
+fn prover(
+    key: &[u8; 4 * KEY_WORDS],
+    message: &[u8; 4 * MESSAGE_WORDS],
+    tag: [u8; 32],
+) -> MockProver<Fp> {
+    let key: Vec<_> = words(key).into_iter().map(Value::known).collect();
+    let circuit = HmacCircuit {
+        key: key.try_into().unwrap(),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = [words(message), words(&tag)].concat();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, HmacCircuit<Fp>>(|config| {
+                let sha256 = config.sha256;
+                let [x, y, z] = sha256.input;
+                ColumnNames::new()
+                    .with_advice(config.key, "key")
+                    .with_advice(x, "x")
+                    .with_advice(y, "y")
+                    .with_advice(z, "z")
+                    .with_advice(sha256.out, "out")
+                    .with_advice(sha256.acc, "acc")
+                    .with_selector(sha256.q_first, "q_first")
+                    .with_selector(sha256.q_step, "q_step")
+                    .with_selector(sha256.q_xor, "q_xor")
+                    .with_selector(sha256.q_ch, "q_ch")
+                    .with_selector(sha256.q_maj, "q_maj")
+                    .with_selector(sha256.q_bool, "q_bool")
+                    .with_selector(sha256.q_add, "q_add")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let key = b"api secret 0001!";
+    let message = b"GET /v1/accounts?limit=10 HTTP/1";
+    let tag = hmac_sha256(key, message);
+    println!(
+        "tag = 0x{}",
+        tag.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    );
+    prover(key, message, tag).assert_satisfied();
+
+    // another key
+    testing::assert_fails_permutation(&prover(b"api secret 0002!", message, tag));
+}
@@ -0,0 +1,157 @@
+//! compound interest circuit
+//!
+//! we are going to prove that a private principal put at a private annual rate, compounded
+//! monthly for YEARS years, grows to a public amount
+//!
+//! ```text
+//! monthly = rate / 12
+//! amount  = principal * (1 + monthly)^(12 * YEARS)
+//! ```
+//!
+//! all numbers are signed Q32.32 fixed point, assigned by the
+//! [`FixedPointChip`](learn_halo2::gadgets::fixed_point::FixedPointChip), each
+//! multiplication rounded to the nearest multiple of `2^-32` exactly like
+//! [`mul_rounded`] does. the constants 1 and 12 are pinned by `constrain_constant`, and
+//! the amount is exposed as its field encoding.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::fixed_point::{
+        self, div_rounded, from_f64, mul_rounded, to_f64, FixedPointChip, FixedPointConfig,
+    },
+    testing, util,
+};
+
+const YEARS: usize = 5;
+
+#[derive(Debug, Clone)]
+struct InterestConfig {
+    fixed_point: FixedPointConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct InterestCircuit {
+    principal: Value<i64>,
+    rate: Value<i64>,
+}
+
+impl<F: FieldExt> Circuit<F> for InterestCircuit {
+    type Config = InterestConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> InterestConfig {
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_constant(constant);
+        meta.enable_equality(instance);
+        InterestConfig {
+            fixed_point: FixedPointChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: InterestConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FixedPointChip::construct(config.fixed_point);
+        chip.load_table(layouter.namespace(|| "u8"))?;
+
+        let mut constant = |name: &'static str, x: f64| {
+            let x = from_f64(x);
+            let cell = chip.assign(layouter.namespace(|| name), Value::known(x))?;
+            layouter.assign_region(
+                || name,
+                |mut region| region.constrain_constant(cell.cell(), fixed_point::encode::<F>(x)),
+            )?;
+            Ok::<_, Error>(cell)
+        };
+        let one = constant("one", 1.0)?;
+        let twelve = constant("twelve", 12.0)?;
+
+        let principal = chip.assign(layouter.namespace(|| "principal"), self.principal)?;
+        let rate = chip.assign(layouter.namespace(|| "rate"), self.rate)?;
+        let monthly = chip.div(layouter.namespace(|| "rate / 12"), &rate, &twelve)?;
+        let factor = chip.add(layouter.namespace(|| "1 + monthly"), &one, &monthly)?;
+
+        let mut amount = principal;
+        for _ in 0..12 * YEARS {
+            amount = chip.mul(layouter.namespace(|| "compound"), &amount, &factor)?;
+        }
+        layouter.constrain_instance(amount.cell(), config.instance, 0)
+    }
+}
+
+/// the amount computed outside of the circuit, rounded like the circuit
+fn compound(principal: i64, rate: i64) -> i64 {
+    let monthly = div_rounded(rate, from_f64(12.0));
+    let factor = from_f64(1.0) + monthly;
+    (0..12 * YEARS).fold(principal, |amount, _| mul_rounded(amount, factor))
+}
+
+fn prover(principal: f64, rate: f64, amount: i64) -> MockProver<Fp> {
+    let circuit = InterestCircuit {
+        principal: Value::known(from_f64(principal)),
+        rate: Value::known(from_f64(rate)),
+    };
+    let k = util::min_k_for::<Fp, _>(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![fixed_point::encode(amount)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, InterestCircuit>(|config| {
+                let fixed_point = config.fixed_point;
+                ColumnNames::new()
+                    .with_advice(fixed_point.a, "a")
+                    .with_advice(fixed_point.b, "b")
+                    .with_advice(fixed_point.z, "z")
+                    .with_advice(fixed_point.rem, "rem")
+                    .with_advice(fixed_point.slack, "slack")
+                    .with_advice(fixed_point.shifted, "shifted")
+                    .with_selector(fixed_point.q_range, "q_range")
+                    .with_selector(fixed_point.q_add, "q_add")
+                    .with_selector(fixed_point.q_mul, "q_mul")
+                    .with_selector(fixed_point.q_div, "q_div")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (principal, rate) = (1000.0, 0.05);
+    let amount = compound(from_f64(principal), from_f64(rate));
+    println!(
+        "{} at {}% for {} years: {:.6}",
+        principal,
+        rate * 100.0,
+        YEARS,
+        to_f64(amount)
+    );
+    assert!(
+        (to_f64(amount) - principal * (1.0 + rate / 12.0).powi(12 * YEARS as i32)).abs() < 1e-4
+    );
+    prover(principal, rate, amount).assert_satisfied();
+
+    // a negative rate shrinks the principal
+    prover(1000.0, -0.02, compound(from_f64(1000.0), from_f64(-0.02))).assert_satisfied();
+
+    // off by the smallest step
+    testing::assert_fails_permutation(&prover(principal, rate, amount + 1));
+}
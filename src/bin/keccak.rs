@@ -0,0 +1,189 @@
+//! keccak circuit
+//!
+//! we are going to prove that we know a LEN byte preimage of a public keccak-256 digest,
+//! hashed in a single block by the [`KeccakChip`](learn_halo2::gadgets::keccak::KeccakChip)
+//!
+//! | instance           |
+//! |--------------------|
+//! | digest[0..16]      |
+//! | digest[16..32]     |
+//!
+//! the digest is exposed as two 128 bit big endian halves, as solidity would split a
+//! `bytes32`. each half is decomposed by the
+//! [`DecomposeChip`](learn_halo2::gadgets::bits::DecomposeChip), whose bits are copied
+//! from the output bits of the chip.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        bits::{DecomposeChip, DecomposeConfig},
+        keccak::{keccak256, KeccakChip, KeccakConfig},
+    },
+    testing, util,
+};
+
+const LEN: usize = 32;
+
+#[derive(Debug, Clone)]
+struct KeccakCircuitConfig {
+    keccak: KeccakConfig,
+    message: Column<Advice>,
+    half: Column<Advice>,
+    decompose: DecomposeConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct KeccakCircuit {
+    preimage: Value<[u8; LEN]>,
+}
+
+/// the halves of `digest` as big endian integers
+fn halves<F: FieldExt>(digest: [u8; 32]) -> [F; 2] {
+    [0, 16].map(|start| {
+        let half = u128::from_be_bytes(digest[start..start + 16].try_into().unwrap());
+        F::from_u128(half)
+    })
+}
+
+impl<F: FieldExt> Circuit<F> for KeccakCircuit {
+    type Config = KeccakCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> KeccakCircuitConfig {
+        let message = meta.advice_column();
+        let half = meta.advice_column();
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(message);
+        meta.enable_equality(half);
+        meta.enable_equality(instance);
+        KeccakCircuitConfig {
+            keccak: KeccakChip::configure(meta),
+            message,
+            half,
+            decompose: DecomposeChip::configure(meta, bit, acc),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: KeccakCircuitConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = KeccakChip::construct(config.keccak);
+        chip.load_table(layouter.namespace(|| "chi"))?;
+
+        let message = layouter.assign_region(
+            || "message",
+            |mut region| {
+                (0..LEN * 8)
+                    .map(|i| {
+                        let bit = self
+                            .preimage
+                            .map(|preimage| F::from(u64::from((preimage[i / 8] >> (i % 8)) & 1)));
+                        region.assign_advice(|| "message", config.message, i, || bit)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        let digest = chip.hash(layouter.namespace(|| "keccak"), &message)?;
+
+        let values = self
+            .preimage
+            .map(|preimage| halves::<F>(keccak256(&preimage)));
+        let decompose = DecomposeChip::construct(config.decompose);
+        for (row, start) in [0, 16].into_iter().enumerate() {
+            let half = layouter.assign_region(
+                || "half",
+                |mut region| {
+                    region.assign_advice(|| "half", config.half, 0, || values.map(|v| v[row]))
+                },
+            )?;
+            let bits = decompose.decompose(layouter.namespace(|| "decompose"), &half, 128)?;
+            layouter.assign_region(
+                || "digest bits",
+                |mut region| {
+                    // bit k of the half is bit k % 8 of its byte 15 - k / 8
+                    for (k, bit) in bits.iter().enumerate() {
+                        let byte = start + 15 - k / 8;
+                        region.constrain_equal(bit.cell(), digest[8 * byte + k % 8].cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.constrain_instance(half.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn prover(preimage: [u8; LEN], digest: [u8; 32]) -> MockProver<Fp> {
+    let circuit = KeccakCircuit {
+        preimage: Value::known(preimage),
+    };
+    let k = util::min_k_for::<Fp, _>(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![halves(digest).to_vec()]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, KeccakCircuit>(|config| {
+                let keccak = config.keccak;
+                let mut names = ColumnNames::new();
+                let lanes = keccak.a.iter().zip(&keccak.b).zip(&keccak.t);
+                for (i, ((a, b), t)) in lanes.enumerate() {
+                    names = names
+                        .with_advice(*a, format!("a[{}]", i))
+                        .with_advice(*b, format!("b[{}]", i))
+                        .with_advice(*t, format!("t[{}]", i));
+                }
+                for (x, (c, h)) in keccak.c.iter().zip(&keccak.h).enumerate() {
+                    names = names
+                        .with_advice(*c, format!("c[{}]", x))
+                        .with_advice(*h, format!("h[{}]", x));
+                }
+                names
+                    .with_fixed(keccak.rc, "rc")
+                    .with_selector(keccak.q_bool, "q_bool")
+                    .with_selector(keccak.q_theta, "q_theta")
+                    .with_selector(keccak.q_chi, "q_chi")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let mut preimage = [0; LEN];
+    preimage[..11].copy_from_slice(b"hello halo2");
+    let digest = keccak256(&preimage);
+    println!(
+        "keccak256 = 0x{}",
+        digest
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+    prover(preimage, digest).assert_satisfied();
+
+    // a digest of another preimage
+    let mut other = preimage;
+    other[LEN - 1] = 1;
+    testing::assert_fails_permutation(&prover(other, digest));
+}
@@ -0,0 +1,307 @@
+//! knapsack circuit
+//!
+//! we are going to prove that we know a private selection of items, each taken at most once,
+//! worth at least a public target and weighing at most a public capacity
+//!
+//! | instance |
+//! |----------|
+//! | capacity |
+//! | target   |
+//!
+//! the items are part of the circuit, their weights and values in fixed columns, and the
+//! selection is one boolean per item:
+//!
+//! | row | select    | weight    | value    | picked_weight      | picked_value      | q_pick |
+//! |-----|-----------|-----------|----------|--------------------|-------------------|--------|
+//! | i   | select[i] | weight[i] | value[i] | select * weight    | select * value    | 1      |
+//!
+//! - `q_pick * select * (1 - select) = 0`
+//! - `q_pick * (picked_weight - select * weight) = 0`
+//! - `q_pick * (picked_value - select * value) = 0`
+//!
+//! the picked weights and values are added up by two running sums of the
+//! [`RunningSumChip`](learn_halo2::gadgets::accumulator::RunningSumChip), copy constrained to
+//! the rows above, and the totals are compared with the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip):
+//!
+//! | row | lhs      | rhs      | lt | q_compare |
+//! |-----|----------|----------|----|-----------|
+//! | 0   | capacity | weight   | 0  | 1         |
+//! | 1   | value    | target   | 0  | 1         |
+//!
+//! both `lt` are constrained to 0, `capacity >= weight` and `value >= target`. the
+//! comparison needs both sides below `2^32`, the items are small enough, and so have to be
+//! the capacity and the target.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        accumulator::{RunningSumChip, RunningSumConfig},
+        compare::{LtChip, LtConfig},
+    },
+    testing, util,
+};
+
+const N_BYTES: usize = 4;
+
+/// `(weight, value)`
+const ITEMS: [(u64, u64); 6] = [(12, 4), (2, 2), (1, 1), (1, 2), (4, 10), (3, 7)];
+
+#[derive(Debug, Clone)]
+struct KnapsackConfig {
+    select: Column<Advice>,
+    weight: Column<Fixed>,
+    value: Column<Fixed>,
+    picked_weight: Column<Advice>,
+    picked_value: Column<Advice>,
+    q_pick: Selector,
+    sum: RunningSumConfig,
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    q_compare: Selector,
+    lt: LtConfig<N_BYTES>,
+    instance: Column<Instance>,
+}
+
+struct KnapsackCircuit<F> {
+    select: [Value<F>; ITEMS.len()],
+}
+
+impl<F> Default for KnapsackCircuit<F> {
+    fn default() -> Self {
+        Self {
+            select: [(); ITEMS.len()].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for KnapsackCircuit<F> {
+    type Config = KnapsackConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> KnapsackConfig {
+        let select = meta.advice_column();
+        let weight = meta.fixed_column();
+        let value = meta.fixed_column();
+        let picked_weight = meta.advice_column();
+        let picked_value = meta.advice_column();
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let lhs = meta.advice_column();
+        let rhs = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        let q_pick = meta.selector();
+        let q_compare = meta.complex_selector();
+        for column in [picked_weight, picked_value, x, lhs, rhs] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("pick", |meta| {
+            let q_pick = meta.query_selector(q_pick);
+            let select = meta.query_advice(select, Rotation::cur());
+            let weight = meta.query_fixed(weight, Rotation::cur());
+            let value = meta.query_fixed(value, Rotation::cur());
+            let picked_weight = meta.query_advice(picked_weight, Rotation::cur());
+            let picked_value = meta.query_advice(picked_value, Rotation::cur());
+            vec![
+                q_pick.clone() * select.clone() * (Expression::Constant(F::one()) - select.clone()),
+                q_pick.clone() * (picked_weight - select.clone() * weight),
+                q_pick * (picked_value - select * value),
+            ]
+        });
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| meta.query_advice(lhs, Rotation::cur()),
+            |meta| meta.query_advice(rhs, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        KnapsackConfig {
+            select,
+            weight,
+            value,
+            picked_weight,
+            picked_value,
+            q_pick,
+            sum: RunningSumChip::configure(meta, x, acc),
+            lhs,
+            rhs,
+            q_compare,
+            lt,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: KnapsackConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let lt = LtChip::<F, N_BYTES>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "u8"))?;
+
+        let picked = layouter.assign_region(
+            || "items",
+            |mut region| {
+                let mut picked = vec![];
+                for (offset, (select, (weight, value))) in self.select.iter().zip(ITEMS).enumerate()
+                {
+                    config.q_pick.enable(&mut region, offset)?;
+                    region.assign_advice(|| "select", config.select, offset, || *select)?;
+                    let mut cells = vec![];
+                    for (fixed, advice, x) in [
+                        (config.weight, config.picked_weight, weight),
+                        (config.value, config.picked_value, value),
+                    ] {
+                        let x = Value::known(F::from(x));
+                        region.assign_fixed(|| "item", fixed, offset, || x)?;
+                        cells.push(region.assign_advice(
+                            || "picked",
+                            advice,
+                            offset,
+                            || *select * x,
+                        )?);
+                    }
+                    picked.push(cells);
+                }
+                Ok(picked)
+            },
+        )?;
+
+        let sum = RunningSumChip::construct(config.sum);
+        let mut totals = vec![];
+        for (i, name) in ["weight", "value"].into_iter().enumerate() {
+            let xs: Vec<_> = picked
+                .iter()
+                .map(|cells| cells[i].value().copied())
+                .collect();
+            let (x_cells, accs) = sum.assign_cells(layouter.namespace(|| name), &xs)?;
+            layouter.assign_region(
+                || "picked",
+                |mut region| {
+                    for (x, cells) in x_cells.iter().zip(&picked) {
+                        region.constrain_equal(x.cell(), cells[i].cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            totals.push(accs.last().unwrap().clone());
+        }
+
+        layouter.assign_region(
+            || "compare",
+            |mut region| {
+                config.q_compare.enable(&mut region, 0)?;
+                let capacity = region.assign_advice_from_instance(
+                    || "capacity",
+                    config.instance,
+                    0,
+                    config.lhs,
+                    0,
+                )?;
+                let weight = totals[0].copy_advice(|| "weight", &mut region, config.rhs, 0)?;
+                config.q_compare.enable(&mut region, 1)?;
+                let value = totals[1].copy_advice(|| "value", &mut region, config.lhs, 1)?;
+                let target = region.assign_advice_from_instance(
+                    || "target",
+                    config.instance,
+                    1,
+                    config.rhs,
+                    1,
+                )?;
+
+                // capacity >= weight and value >= target
+                for (offset, (lhs, rhs)) in [(capacity, weight), (value, target)].iter().enumerate()
+                {
+                    let lt = lt.assign(
+                        &mut region,
+                        offset,
+                        lhs.value().copied(),
+                        rhs.value().copied(),
+                    )?;
+                    region.constrain_constant(lt.cell(), F::zero())?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prover(select: [u64; ITEMS.len()], capacity: u64, target: u64) -> MockProver<Fp> {
+    let circuit = KnapsackCircuit {
+        select: select.map(|select| Value::known(Fp::from(select))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(
+        k,
+        &circuit,
+        vec![vec![Fp::from(capacity), Fp::from(target)]],
+    )
+    .unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, KnapsackCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new()
+                    .with_advice(config.select, "select")
+                    .with_fixed(config.weight, "weight")
+                    .with_fixed(config.value, "value")
+                    .with_advice(config.picked_weight, "picked_weight")
+                    .with_advice(config.picked_value, "picked_value")
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_advice(config.lhs, "lhs")
+                    .with_advice(config.rhs, "rhs")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                names
+                    .with_selector(config.q_pick, "q_pick")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+                    .with_selector(config.q_compare, "q_compare")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // everything but the heavy one, weighs 11 and is worth 22
+    let (capacity, target) = (15, 22);
+    prover([0, 1, 1, 1, 1, 1], capacity, target).assert_satisfied();
+    // just fits
+    prover([0, 1, 1, 1, 1, 1], 11, target).assert_satisfied();
+
+    // everything, worth 26 but weighs 23
+    testing::assert_fails_permutation(&prover([1; 6], capacity, target));
+
+    // light but only worth 15
+    testing::assert_fails_permutation(&prover([0, 1, 1, 1, 1, 0], capacity, target));
+
+    // the second item taken twice
+    testing::assert_fails_gate(&prover([0, 2, 1, 1, 1, 1], capacity, target), "pick", 1);
+}
@@ -0,0 +1,365 @@
+//! longest common subsequence circuit
+//!
+//! we are going to prove that the longest common subsequence of a private sequence `s` of
+//! `M` bytes and a private sequence `t` of `N` bytes, both committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is at least a public `K`
+//!
+//! | instance  |
+//! |-----------|
+//! | H(s)      |
+//! | H(t)      |
+//! | K         |
+//!
+//! the lengths for the prefixes are the dynamic programming table
+//!
+//! ```text
+//! l[0][j] = l[i][0] = 0
+//! l[i][j] = s[i-1] == t[j-1] ? l[i-1][j-1] + 1 : max(l[i-1][j], l[i][j-1])
+//! ```
+//!
+//! like the fibonacci circuits, the recurrence is a gate between adjacent rows, one row of
+//! the table per row of the circuit, `t` copied down from the first one:
+//!
+//! | row | s      | t[j]   | l[j]     | same[j]          | lt[j]     | k | q_lcs | q_final |
+//! |-----|--------|--------|----------|------------------|-----------|---|-------|---------|
+//! | 0   |        | t[j-1] | 0        |                  |           |   | 0     | 0       |
+//! | i   | s[i-1] | t[j-1] | l[i][j]  | s[i-1] == t[j-1] | up < left |   | 1     | 0       |
+//! | M   | ...    | ...    | ...      | ...              | ...       | K | 1     | 1       |
+//!
+//! with `up = l[j]'`, `left = l[j-1]` and `diag = l[j-1]'`, where `'` is the row before,
+//! queried at `Rotation::prev()`. `same` is an
+//! [`IsZeroChip`](learn_halo2::gadgets::is_zero::IsZeroChip) and `lt` the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 1 byte, for every `j >= 1`:
+//!
+//! - `q_lcs * (l - (same ? diag + 1 : (lt ? left : up))) = 0`
+//!
+//! with [`select::expr`](learn_halo2::gadgets::select::expr). the claim is another `LtChip`
+//! on the last row, `l[N] < K` constrained to 0. every length is at most `min(M, N)`, so the
+//! comparisons stay in a byte.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        is_zero::{IsZeroChip, IsZeroConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        select,
+    },
+    testing, util,
+};
+
+const M: usize = 7;
+const N: usize = 6;
+
+#[derive(Debug, Clone)]
+struct LcsConfig {
+    s: Column<Advice>,
+    t: [Column<Advice>; N],
+    l: [Column<Advice>; N + 1],
+    /// the columns of `j = 1..=N`, from here on
+    same: [IsZeroConfig; N],
+    /// `up < left`
+    lt: [LtConfig<1>; N],
+    q_lcs: Selector,
+    k: Column<Advice>,
+    /// `l[N] < K`
+    short: LtConfig<1>,
+    q_final: Selector,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct LcsCircuit<F> {
+    s: [Value<F>; M],
+    t: [Value<F>; N],
+}
+
+impl<F> Default for LcsCircuit<F> {
+    fn default() -> Self {
+        Self {
+            s: [(); M].map(|_| Value::unknown()),
+            t: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for LcsCircuit<F> {
+    type Config = LcsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> LcsConfig {
+        let s = meta.advice_column();
+        let t = [(); N].map(|_| meta.advice_column());
+        let l = [(); N + 1].map(|_| meta.advice_column());
+        let k = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_lcs = meta.complex_selector();
+        let q_final = meta.complex_selector();
+        for column in [s, k].into_iter().chain(t).chain(l) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let same: [IsZeroConfig; N] = std::array::from_fn(|j| {
+            let value_inv = meta.advice_column();
+            let is_zero = meta.advice_column();
+            IsZeroChip::configure(
+                meta,
+                |meta| meta.query_selector(q_lcs),
+                |meta| {
+                    meta.query_advice(s, Rotation::cur()) - meta.query_advice(t[j], Rotation::cur())
+                },
+                value_inv,
+                is_zero,
+            )
+        });
+        let lt: [LtConfig<1>; N] = std::array::from_fn(|j| {
+            LtChip::configure(
+                meta,
+                |meta| meta.query_selector(q_lcs),
+                |meta| meta.query_advice(l[j + 1], Rotation::prev()),
+                |meta| meta.query_advice(l[j], Rotation::cur()),
+            )
+        });
+
+        meta.create_gate("lcs", |meta| {
+            let q_lcs = meta.query_selector(q_lcs);
+            let mut constraints = vec![];
+            for j in 0..N {
+                let up = meta.query_advice(l[j + 1], Rotation::prev());
+                let left = meta.query_advice(l[j], Rotation::cur());
+                let diag = meta.query_advice(l[j], Rotation::prev());
+                let current = meta.query_advice(l[j + 1], Rotation::cur());
+                let same = same[j].expr(meta, Rotation::cur());
+                let lt = meta.query_advice(lt[j].lt, Rotation::cur());
+                let longest = select::expr(
+                    same,
+                    diag + Expression::Constant(F::one()),
+                    select::expr(lt, left, up),
+                );
+                constraints.push(q_lcs.clone() * (current - longest));
+            }
+            constraints
+        });
+
+        let short = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_final),
+            |meta| meta.query_advice(l[N], Rotation::cur()),
+            |meta| meta.query_advice(k, Rotation::cur()),
+        );
+        meta.enable_equality(short.lt);
+
+        LcsConfig {
+            s,
+            t,
+            l,
+            same,
+            lt,
+            q_lcs,
+            k,
+            short,
+            q_final,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: LcsConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        for lt in config.lt.iter().chain([&config.short]) {
+            LtChip::<F, 1>::construct(*lt).load_table(layouter.namespace(|| "u8"))?;
+        }
+
+        let (s, t) = layouter.assign_region(
+            || "lengths",
+            |mut region| {
+                let mut t = vec![];
+                let mut prev: Vec<AssignedCell<F, F>> = vec![];
+                for column in config.l {
+                    prev.push(region.assign_advice_from_constant(|| "l", column, 0, F::zero())?);
+                }
+                for (column, c) in config.t.iter().zip(&self.t) {
+                    t.push(region.assign_advice(|| "t", *column, 0, || *c)?);
+                }
+
+                let mut s = vec![];
+                for (i, c) in self.s.iter().enumerate() {
+                    let offset = i + 1;
+                    config.q_lcs.enable(&mut region, offset)?;
+                    let c = region.assign_advice(|| "s", config.s, offset, || *c)?;
+                    let mut row = vec![region.assign_advice_from_constant(
+                        || "l",
+                        config.l[0],
+                        offset,
+                        F::zero(),
+                    )?];
+                    for j in 0..N {
+                        let t = t[j].copy_advice(|| "t", &mut region, config.t[j], offset)?;
+                        let same = IsZeroChip::construct(config.same[j]).assign(
+                            &mut region,
+                            offset,
+                            c.value().copied() - t.value().copied(),
+                        )?;
+                        let up = prev[j + 1].value().copied();
+                        let left = row[j].value().copied();
+                        let diag = prev[j].value().copied() + Value::known(F::one());
+                        let lt = LtChip::<F, 1>::construct(config.lt[j]).assign(
+                            &mut region,
+                            offset,
+                            up,
+                            left,
+                        )?;
+                        let longer = choose(lt.value().copied(), left, up);
+                        let longest = choose(same.value().copied(), diag, longer);
+                        row.push(region.assign_advice(
+                            || "l",
+                            config.l[j + 1],
+                            offset,
+                            || longest,
+                        )?);
+                    }
+                    s.push(c);
+                    prev = row;
+                }
+
+                // l[N] >= K
+                config.q_final.enable(&mut region, M)?;
+                let k =
+                    region.assign_advice_from_instance(|| "K", config.instance, 2, config.k, M)?;
+                let short = LtChip::<F, 1>::construct(config.short).assign(
+                    &mut region,
+                    M,
+                    prev[N].value().copied(),
+                    k.value().copied(),
+                )?;
+                region.constrain_constant(short.cell(), F::zero())?;
+                Ok((s, t))
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(s)"), &s)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)?;
+        let commitment = poseidon.hash(layouter.namespace(|| "H(t)"), &t)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 1)
+    }
+}
+
+/// `cond ? a : b` of the witness
+fn choose<F: FieldExt>(cond: Value<F>, a: Value<F>, b: Value<F>) -> Value<F> {
+    cond.zip(a)
+        .zip(b)
+        .map(|((cond, a), b)| if cond == F::one() { a } else { b })
+}
+
+/// the length of the longest common subsequence outside of the circuit
+fn lcs(s: &[u8], t: &[u8]) -> u64 {
+    let mut prev = vec![0; t.len() + 1];
+    for a in s {
+        let mut row = vec![0];
+        for (j, b) in t.iter().enumerate() {
+            row.push(if a == b {
+                prev[j] + 1
+            } else {
+                prev[j + 1].max(row[j])
+            });
+        }
+        prev = row;
+    }
+    prev[t.len()]
+}
+
+fn commit(bytes: &[u8]) -> Fp {
+    let inputs: Vec<_> = bytes.iter().map(|b| Fp::from(u64::from(*b))).collect();
+    poseidon::hash(&inputs)
+}
+
+fn prover(s: &[u8; M], t: &[u8; N], commitments: [Fp; 2], k: u64) -> MockProver<Fp> {
+    let known = |c: u8| Value::known(Fp::from(u64::from(c)));
+    let circuit = LcsCircuit {
+        s: s.map(known),
+        t: t.map(known),
+    };
+    let min_k = util::min_k_for(&circuit).unwrap();
+    let [hs, ht] = commitments;
+    MockProver::run(min_k, &circuit, vec![vec![hs, ht, Fp::from(k)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, LcsCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.s, "s")
+                    .with_advice(config.l[0], "l[0]")
+                    .with_advice(config.k, "k")
+                    .with_advice(config.short.lt, "short")
+                    .with_advice(config.short.diff[0], "diff_short");
+                for j in 0..N {
+                    let (same, lt) = (config.same[j], config.lt[j]);
+                    names = names
+                        .with_advice(config.t[j], format!("t[{}]", j + 1))
+                        .with_advice(config.l[j + 1], format!("l[{}]", j + 1))
+                        .with_advice(same.value_inv, format!("same_inv[{}]", j + 1))
+                        .with_advice(same.is_zero, format!("same[{}]", j + 1))
+                        .with_advice(lt.lt, format!("lt[{}]", j + 1))
+                        .with_advice(lt.diff[0], format!("diff[{}]", j + 1));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_lcs, "q_lcs")
+                    .with_selector(config.q_final, "q_final")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (s, t) = (b"ABCBDAB", b"BDCABA");
+    assert_eq!(lcs(s, t), 4);
+    let commitments = [commit(s), commit(t)];
+    prover(s, t, commitments, 4).assert_satisfied();
+    // any smaller bound holds too
+    prover(s, t, commitments, 2).assert_satisfied();
+    prover(s, t, commitments, 0).assert_satisfied();
+    // nothing in common
+    let other = b"XYZXYZ";
+    assert_eq!(lcs(s, other), 0);
+    prover(s, other, [commit(s), commit(other)], 0).assert_satisfied();
+
+    // one more than the longest
+    testing::assert_fails_permutation(&prover(s, t, commitments, 5));
+
+    // another sequence with a longer common subsequence
+    testing::assert_fails_permutation(&prover(b"BDCABAX", t, commitments, 6));
+}
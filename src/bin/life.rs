@@ -0,0 +1,304 @@
+//! game of life circuit
+//!
+//! we are going to prove that a public grid is the next generation of a private grid in
+//! conway's game of life, with the cells outside of the grid dead
+//!
+//! | instance          |
+//! |-------------------|
+//! | next[0][0]        |
+//! | ...               |
+//! | next[H-1][W-1]    |
+//!
+//! a row of the grid is a row of the circuit, between two rows of constant 0s, so the 8
+//! neighbors of a cell are in the rows above and below and in the columns next to it,
+//! queried at `Rotation::prev()`, `Rotation::cur()` and `Rotation::next()`:
+//!
+//! | row   | cell[j]   | next[j]       | two[j]     | three[j]   | q_life |
+//! |-------|-----------|---------------|------------|------------|--------|
+//! | 0     | 0         |               |            |            | 0      |
+//! | i + 1 | grid[i]   | next[i]       | `n == 2`   | `n == 3`   | 1      |
+//! | H + 1 | 0         |               |            |            | 0      |
+//!
+//! where `n` is the sum of the neighbors, and the flags are two
+//! [`IsZeroChip`](learn_halo2::gadgets::is_zero::IsZeroChip)s per column. a live cell
+//! survives with 2 or 3 neighbors, a dead one is born with 3:
+//!
+//! - `q_life * cell * (1 - cell) = 0`
+//! - `q_life * (next - select(cell, two + three, three)) = 0`
+//!
+//! with [`select::expr`](learn_halo2::gadgets::select::expr).
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        select,
+    },
+    testing, util,
+};
+
+const W: usize = 6;
+const H: usize = 6;
+
+type Grid = [[bool; W]; H];
+
+/// the number of live neighbors of every cell
+fn neighbors(grid: &Grid) -> [[u64; W]; H] {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            let mut n = 0;
+            for (y, row) in grid.iter().enumerate().filter(|(y, _)| y.abs_diff(i) <= 1) {
+                for (x, alive) in row.iter().enumerate().filter(|(x, _)| x.abs_diff(j) <= 1) {
+                    n += u64::from((y, x) != (i, j) && *alive);
+                }
+            }
+            n
+        })
+    })
+}
+
+/// the next generation
+fn step(grid: &Grid) -> Grid {
+    let n = neighbors(grid);
+    std::array::from_fn(|i| std::array::from_fn(|j| n[i][j] == 3 || (grid[i][j] && n[i][j] == 2)))
+}
+
+#[derive(Debug, Clone)]
+struct LifeConfig {
+    cell: [Column<Advice>; W],
+    next: [Column<Advice>; W],
+    two: [IsZeroConfig; W],
+    three: [IsZeroConfig; W],
+    q_life: Selector,
+    instance: Column<Instance>,
+}
+
+struct LifeCircuit<F> {
+    grid: [[Value<F>; W]; H],
+}
+
+impl<F> Default for LifeCircuit<F> {
+    fn default() -> Self {
+        Self {
+            grid: [(); H].map(|_| [(); W].map(|_| Value::unknown())),
+        }
+    }
+}
+
+/// the sum of the neighbors of the cell in column `j`
+fn neighbor_sum<F: FieldExt>(
+    meta: &mut VirtualCells<'_, F>,
+    cell: &[Column<Advice>; W],
+    j: usize,
+) -> Expression<F> {
+    let mut sum = Expression::Constant(F::zero());
+    for (x, column) in cell.iter().enumerate().filter(|(x, _)| x.abs_diff(j) <= 1) {
+        for rotation in [Rotation::prev(), Rotation::cur(), Rotation::next()] {
+            if x != j || rotation != Rotation::cur() {
+                sum = sum + meta.query_advice(*column, rotation);
+            }
+        }
+    }
+    sum
+}
+
+impl<F: FieldExt> Circuit<F> for LifeCircuit<F> {
+    type Config = LifeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> LifeConfig {
+        let cell = [(); W].map(|_| meta.advice_column());
+        let next = [(); W].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_life = meta.selector();
+        for column in cell.into_iter().chain(next) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let mut is_count = |count: u64| -> [IsZeroConfig; W] {
+            std::array::from_fn(|j| {
+                let value_inv = meta.advice_column();
+                let is_zero = meta.advice_column();
+                IsZeroChip::configure(
+                    meta,
+                    |meta| meta.query_selector(q_life),
+                    |meta| neighbor_sum(meta, &cell, j) - Expression::Constant(F::from(count)),
+                    value_inv,
+                    is_zero,
+                )
+            })
+        };
+        let two = is_count(2);
+        let three = is_count(3);
+
+        meta.create_gate("life", |meta| {
+            let q_life = meta.query_selector(q_life);
+            let one = Expression::Constant(F::one());
+            let mut constraints = vec![];
+            for (((cell, next), two), three) in cell.iter().zip(&next).zip(&two).zip(&three) {
+                let alive = meta.query_advice(*cell, Rotation::cur());
+                let next = meta.query_advice(*next, Rotation::cur());
+                let two = two.expr(meta, Rotation::cur());
+                let three = three.expr(meta, Rotation::cur());
+                constraints.push(q_life.clone() * alive.clone() * (one.clone() - alive.clone()));
+                constraints.push(
+                    q_life.clone() * (next - select::expr(alive, two + three.clone(), three)),
+                );
+            }
+            constraints
+        });
+
+        LifeConfig {
+            cell,
+            next,
+            two,
+            three,
+            q_life,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: LifeConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "life",
+            |mut region| {
+                for column in config.cell {
+                    region.assign_advice_from_constant(|| "border", column, 0, F::zero())?;
+                    region.assign_advice_from_constant(|| "border", column, H + 1, F::zero())?;
+                }
+                for (i, row) in self.grid.iter().enumerate() {
+                    config.q_life.enable(&mut region, i + 1)?;
+                    for (column, alive) in config.cell.iter().zip(row) {
+                        region.assign_advice(|| "cell", *column, i + 1, || *alive)?;
+                    }
+                    for (j, column) in config.next.iter().enumerate() {
+                        region.assign_advice_from_instance(
+                            || "next",
+                            config.instance,
+                            i * W + j,
+                            *column,
+                            i + 1,
+                        )?;
+                    }
+                }
+
+                // the neighbor sums, with 0 outside of the grid
+                let at = |i: usize, j: usize| {
+                    self.grid
+                        .get(i)
+                        .and_then(|row| row.get(j))
+                        .copied()
+                        .unwrap_or_else(|| Value::known(F::zero()))
+                };
+                for i in 0..H {
+                    for (j, (two, three)) in config.two.iter().zip(&config.three).enumerate() {
+                        let mut n = Value::known(F::zero());
+                        for y in i.saturating_sub(1)..=i + 1 {
+                            for x in j.saturating_sub(1)..=j + 1 {
+                                if (y, x) != (i, j) {
+                                    n = n + at(y, x);
+                                }
+                            }
+                        }
+                        for (flags, count) in [(two, 2), (three, 3)] {
+                            IsZeroChip::construct(*flags).assign(
+                                &mut region,
+                                i + 1,
+                                n - Value::known(F::from(count)),
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn parse(rows: [&str; H]) -> Grid {
+    rows.map(|row| {
+        let mut cells = [false; W];
+        for (cell, c) in cells.iter_mut().zip(row.chars()) {
+            *cell = c == '#';
+        }
+        cells
+    })
+}
+
+fn prover(grid: [[u64; W]; H], next: &Grid) -> MockProver<Fp> {
+    let circuit = LifeCircuit {
+        grid: grid.map(|row| row.map(|cell| Value::known(Fp::from(cell)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = next
+        .iter()
+        .flatten()
+        .map(|cell| Fp::from(u64::from(*cell)))
+        .collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, LifeCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new();
+                let columns = config.cell.iter().zip(&config.next);
+                let flags = config.two.iter().zip(&config.three);
+                for (j, ((cell, next), (two, three))) in columns.zip(flags).enumerate() {
+                    names = names
+                        .with_advice(*cell, format!("cell[{}]", j))
+                        .with_advice(*next, format!("next[{}]", j))
+                        .with_advice(two.value_inv, format!("two_inv[{}]", j))
+                        .with_advice(two.is_zero, format!("two[{}]", j))
+                        .with_advice(three.value_inv, format!("three_inv[{}]", j))
+                        .with_advice(three.is_zero, format!("three[{}]", j));
+                }
+                names
+                    .with_selector(config.q_life, "q_life")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let glider = parse([".#....", "..#...", "###...", "......", "......", "......"]);
+    let next = step(&glider);
+    assert_eq!(
+        next,
+        parse(["......", "#.#...", ".##...", ".#....", "......", "......"])
+    );
+    let cells = |grid: &Grid| grid.map(|row| row.map(u64::from));
+    prover(cells(&glider), &next).assert_satisfied();
+    // a blinker in the corner, half of it is cut off by the border
+    let corner = parse(["##....", "......", "......", "......", "......", "......"]);
+    prover(cells(&corner), &step(&corner)).assert_satisfied();
+
+    // the glider claimed to stand still, the cell in the top row dies
+    testing::assert_fails_gate(&prover(cells(&glider), &glider), "life", 1);
+
+    // a cell of 2, which would count as two neighbors
+    let mut two = cells(&glider);
+    two[0][1] = 2;
+    testing::assert_fails_gate(&prover(two, &next), "life", 1);
+}
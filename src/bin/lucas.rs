@@ -0,0 +1,124 @@
+//! lucas numbers
+//!
+//! we are going to prove that L(N) = result for the public result, where
+//! `L(i) = L(i-1) + L(i-2)`, `L(0) = 2, L(1) = 1`
+//!
+//! the [`LinearRecurrenceChip`] is the one used for fibonacci with coefficients `[1, 1]`,
+//! only the initial values differ. they are pinned to constants here, so the instance
+//! column is just `[L(N)]`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::recurrence::{LinearRecurrenceChip, LinearRecurrenceConfig},
+    testing, util,
+};
+
+const N: usize = 30;
+
+#[derive(Debug, Clone)]
+struct LucasConfig {
+    recurrence: LinearRecurrenceConfig<2>,
+    instance: Column<Instance>,
+}
+
+/// every cell follows from the constants, there is no secret witness
+#[derive(Default)]
+struct LucasCircuit<F> {
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for LucasCircuit<F> {
+    type Config = LucasConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> LucasConfig {
+        let x = meta.advice_column();
+        let coeffs = [meta.fixed_column(), meta.fixed_column()];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_constant(constant);
+        meta.enable_equality(instance);
+        LucasConfig {
+            recurrence: LinearRecurrenceChip::configure(meta, x, coeffs),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: LucasConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = LinearRecurrenceChip::<F, 2>::construct(config.recurrence);
+        let initial = [F::from(2), F::one()];
+        let terms = chip.assign(
+            layouter.namespace(|| "lucas"),
+            [F::one(), F::one()],
+            initial.map(Value::known),
+            N + 1,
+        )?;
+        layouter.assign_region(
+            || "L(0), L(1)",
+            |mut region| {
+                region.constrain_constant(terms[0].cell(), initial[0])?;
+                region.constrain_constant(terms[1].cell(), initial[1])
+            },
+        )?;
+        layouter.constrain_instance(terms[N].cell(), config.instance, 0)
+    }
+}
+
+fn lucas(n: usize) -> Fp {
+    (0..n)
+        .fold((Fp::from(2), Fp::one()), |(a, b), _| (b, a + b))
+        .0
+}
+
+fn prover(result: Fp) -> MockProver<Fp> {
+    let circuit = LucasCircuit::<Fp>::default();
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![result]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, LucasCircuit<Fp>>(|config| {
+                let [c1, c2] = config.recurrence.coeffs;
+                ColumnNames::new()
+                    .with_advice(config.recurrence.x, "x")
+                    .with_fixed(c1, "c1")
+                    .with_fixed(c2, "c2")
+                    .with_selector(config.recurrence.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    prover(lucas(N)).assert_satisfied();
+    println!("L({}) = {:?}", N, lucas(N));
+}
+
+#[test]
+fn lucas_30() {
+    assert_eq!(lucas(10), Fp::from(123));
+    prover(Fp::from(1860498)).assert_satisfied();
+}
+
+#[test]
+fn fibonacci_is_not_lucas() {
+    let fib_30 = (0..N)
+        .fold((Fp::zero(), Fp::one()), |(a, b), _| (b, a + b))
+        .0;
+    testing::assert_fails_permutation(&prover(fib_30));
+}
@@ -0,0 +1,252 @@
+//! magic square circuit
+//!
+//! we are going to prove that we know a private `N x N` magic square, the numbers
+//! `1..=N^2` arranged so that every row, every column and both diagonals add up to the
+//! same public sum
+//!
+//! | instance |
+//! |----------|
+//! | sum      |
+//!
+//! every line is a running sum of the
+//! [`RunningSumChip`](learn_halo2::gadgets::accumulator::RunningSumChip), in a region of its
+//! own, with its cells copy constrained to the entries of the square and its total to the
+//! instance.
+//!
+//! the sums alone are satisfied by a square of `sum / N` everywhere, the entries also have
+//! to be `1..=N^2` in some order. like in `src/bin/sorted.rs`, two lists are the same
+//! multiset when, for a random `gamma`,
+//!
+//! `(entry[0] + gamma) * ... * (entry[N^2-1] + gamma) = (1 + gamma) * ... * (N^2 + gamma)`
+//!
+//! here `gamma` is a challenge drawn after the entries are committed, like `r` in
+//! `src/bin/rlc.rs`, and both products are running products of the
+//! [`RunningProductChip`](learn_halo2::gadgets::product::RunningProductChip) in second phase
+//! columns:
+//!
+//! | row   | entry    | index | x_in             | acc_in | x_out         | acc_out | q_shift |
+//! |-------|----------|-------|------------------|--------|---------------|---------|---------|
+//! | i     | entry[i] | i + 1 | entry[i] + gamma | ...    | i + 1 + gamma | ...     | 1       |
+//! | N^2   |          |       |                  | prod   |               | prod    | 0       |
+//!
+//! - `q_shift * (x_in - entry - gamma) = 0`
+//! - `q_shift * (x_out - index - gamma) = 0`
+//!
+//! and the two products are copy constrained to be equal.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, Fixed, Instance,
+        SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        accumulator::{RunningSumChip, RunningSumConfig},
+        product::{RunningProductChip, RunningProductConfig},
+    },
+    testing, util,
+};
+
+const N: usize = 3;
+
+#[derive(Debug, Clone)]
+struct MagicSquareConfig {
+    entry: Column<Advice>,
+    index: Column<Fixed>,
+    q_shift: Selector,
+    gamma: Challenge,
+    product_in: RunningProductConfig,
+    product_out: RunningProductConfig,
+    sum: RunningSumConfig,
+    instance: Column<Instance>,
+}
+
+struct MagicSquareCircuit<F> {
+    square: [[Value<F>; N]; N],
+}
+
+impl<F> Default for MagicSquareCircuit<F> {
+    fn default() -> Self {
+        Self {
+            square: [(); N].map(|_| [(); N].map(|_| Value::unknown())),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MagicSquareCircuit<F> {
+    type Config = MagicSquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MagicSquareConfig {
+        let entry = meta.advice_column_in(FirstPhase);
+        let x = meta.advice_column_in(FirstPhase);
+        let acc = meta.advice_column_in(FirstPhase);
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let x_in = meta.advice_column_in(SecondPhase);
+        let acc_in = meta.advice_column_in(SecondPhase);
+        let x_out = meta.advice_column_in(SecondPhase);
+        let acc_out = meta.advice_column_in(SecondPhase);
+        let index = meta.fixed_column();
+        let instance = meta.instance_column();
+        let q_shift = meta.selector();
+        meta.enable_equality(entry);
+        meta.enable_equality(x);
+        meta.enable_equality(instance);
+
+        let product_in = RunningProductChip::configure(meta, x_in, acc_in);
+        let product_out = RunningProductChip::configure(meta, x_out, acc_out);
+        meta.create_gate("shift", |meta| {
+            let q_shift = meta.query_selector(q_shift);
+            let gamma = meta.query_challenge(gamma);
+            let entry = meta.query_advice(entry, Rotation::cur());
+            let index = meta.query_fixed(index, Rotation::cur());
+            let x_in = meta.query_advice(x_in, Rotation::cur());
+            let x_out = meta.query_advice(x_out, Rotation::cur());
+            vec![
+                q_shift.clone() * (x_in - entry - gamma.clone()),
+                q_shift * (x_out - index - gamma),
+            ]
+        });
+
+        MagicSquareConfig {
+            entry,
+            index,
+            q_shift,
+            gamma,
+            product_in,
+            product_out,
+            sum: RunningSumChip::configure(meta, x, acc),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MagicSquareConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // unknown until the first phase is committed
+        let gamma = layouter.get_challenge(config.gamma);
+        let entries: Vec<_> = self.square.iter().flatten().copied().collect();
+        let x_in: Vec<_> = entries.iter().map(|entry| *entry + gamma).collect();
+        let x_out: Vec<_> = (1..=N * N)
+            .map(|index| gamma.map(|gamma| F::from(index as u64) + gamma))
+            .collect();
+
+        let product_in = RunningProductChip::construct(config.product_in);
+        let product_out = RunningProductChip::construct(config.product_out);
+        let cells = layouter.assign_region(
+            || "distinct",
+            |mut region| {
+                let mut cells = Vec::with_capacity(N * N);
+                for (offset, entry) in entries.iter().enumerate() {
+                    config.q_shift.enable(&mut region, offset)?;
+                    cells.push(region.assign_advice(
+                        || "entry",
+                        config.entry,
+                        offset,
+                        || *entry,
+                    )?);
+                    region.assign_fixed(
+                        || "index",
+                        config.index,
+                        offset,
+                        || Value::known(F::from(offset as u64 + 1)),
+                    )?;
+                }
+                let total_in = product_in.assign(&mut region, &x_in)?;
+                let total_out = product_out.assign(&mut region, &x_out)?;
+                region.constrain_equal(total_in.cell(), total_out.cell())?;
+                Ok(cells)
+            },
+        )?;
+
+        // rows, columns and the two diagonals, as indices into `cells`
+        let lines = (0..N)
+            .map(|i| (0..N).map(|j| i * N + j).collect::<Vec<_>>())
+            .chain((0..N).map(|j| (0..N).map(|i| i * N + j).collect()))
+            .chain([
+                (0..N).map(|i| i * N + i).collect(),
+                (0..N).map(|i| i * N + N - 1 - i).collect(),
+            ]);
+        let sum = RunningSumChip::construct(config.sum);
+        for line in lines {
+            let xs: Vec<_> = line.iter().map(|i| cells[*i].value().copied()).collect();
+            let (x_cells, accs) = sum.assign_cells(layouter.namespace(|| "line"), &xs)?;
+            layouter.assign_region(
+                || "line entries",
+                |mut region| {
+                    for (x, i) in x_cells.iter().zip(&line) {
+                        region.constrain_equal(x.cell(), cells[*i].cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.constrain_instance(accs[N].cell(), config.instance, 0)?;
+        }
+        Ok(())
+    }
+}
+
+fn prover(square: [[u64; N]; N], sum: u64) -> MockProver<Fp> {
+    let circuit = MagicSquareCircuit {
+        square: square.map(|row| row.map(|entry| Value::known(Fp::from(entry)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(sum)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MagicSquareCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.entry, "entry")
+                    .with_fixed(config.index, "index")
+                    .with_advice(config.product_in.x, "x_in")
+                    .with_advice(config.product_in.acc, "acc_in")
+                    .with_advice(config.product_out.x, "x_out")
+                    .with_advice(config.product_out.acc, "acc_out")
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_selector(config.q_shift, "q_shift")
+                    .with_selector(config.product_in.q_first, "q_first_in")
+                    .with_selector(config.product_in.q_product, "q_product_in")
+                    .with_selector(config.product_out.q_first, "q_first_out")
+                    .with_selector(config.product_out.q_product, "q_product_out")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // the lo shu square
+    let lo_shu = [[2, 7, 6], [9, 5, 1], [4, 3, 8]];
+    prover(lo_shu, 15).assert_satisfied();
+    // its mirror image
+    prover(lo_shu.map(|[a, b, c]| [c, b, a]), 15).assert_satisfied();
+
+    // another sum
+    testing::assert_fails_permutation(&prover(lo_shu, 16));
+
+    // every line adds up to 15, but the entries aren't 1 to 9
+    testing::assert_fails_permutation(&prover([[5; N]; N], 15));
+
+    // two rows swapped, the rows and columns still add up to 15 but not the diagonals
+    testing::assert_fails_permutation(&prover([lo_shu[0], lo_shu[2], lo_shu[1]], 15));
+}
@@ -0,0 +1,268 @@
+//! matrix multiplication circuit
+//!
+//! we are going to prove that `C = A * B` for private `N x N` matrices `A` and `B`, with
+//! `C` public through its hash by the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip)
+//!
+//! | instance                          |
+//! |-----------------------------------|
+//! | H(C[0][0], ..., C[N-1][N-1])      |
+//!
+//! `A` and `B` are assigned row-major across `N` advice columns, one region each, and every
+//! entry of `C` is the inner product of a row of `A` and a column of `B` by the
+//! [`InnerProductChip`](learn_halo2::gadgets::inner_product::InnerProductChip), which
+//! copies them in, all in one region:
+//!
+//! | row                   | a        | b        | acc       |
+//! |-----------------------|----------|----------|-----------|
+//! | (i * N + j) * (N + 1) | A[i][0]  | B[0][j]  | 0         |
+//! | ...                   | A[i][k]  | B[k][j]  | ...       |
+//! | ... + N               |          |          | C[i][j]   |
+//!
+//! the products take `N^2 (N + 1)` rows, the hash of `C` about `N^2 / 2` permutations.
+//!
+//! pass `--bench` to compare rows, columns and proving time for a few `N`, and
+//! `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        inner_product::{InnerProductChip, InnerProductConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+type Matrix<const N: usize> = [[u64; N]; N];
+
+#[derive(Debug, Clone)]
+struct MatMulConfig<const N: usize> {
+    /// the entries of a row of `A` or `B`
+    entry: [Column<Advice>; N],
+    inner_product: InnerProductConfig,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct MatMulCircuit<F, const N: usize> {
+    a: [[Value<F>; N]; N],
+    b: [[Value<F>; N]; N],
+}
+
+impl<F, const N: usize> Default for MatMulCircuit<F, N> {
+    fn default() -> Self {
+        let unknown = || [(); N].map(|_| [(); N].map(|_| Value::unknown()));
+        Self {
+            a: unknown(),
+            b: unknown(),
+        }
+    }
+}
+
+/// assign `matrix` row-major in `columns`
+fn assign_matrix<F: FieldExt, const N: usize>(
+    mut layouter: impl Layouter<F>,
+    columns: &[Column<Advice>; N],
+    matrix: &[[Value<F>; N]; N],
+) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+    layouter.assign_region(
+        || "matrix",
+        |mut region| {
+            matrix
+                .iter()
+                .enumerate()
+                .map(|(offset, row)| {
+                    columns
+                        .iter()
+                        .zip(row)
+                        .map(|(column, entry)| {
+                            region.assign_advice(|| "entry", *column, offset, || *entry)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect()
+        },
+    )
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for MatMulCircuit<F, N> {
+    type Config = MatMulConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MatMulConfig<N> {
+        let entry = [(); N].map(|_| meta.advice_column());
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        for column in entry {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        MatMulConfig {
+            entry,
+            inner_product: InnerProductChip::configure(meta, a, b, acc),
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MatMulConfig<N>,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let a = assign_matrix(layouter.namespace(|| "A"), &config.entry, &self.a)?;
+        let b = assign_matrix(layouter.namespace(|| "B"), &config.entry, &self.b)?;
+
+        let chip = InnerProductChip::construct(config.inner_product);
+        let c = layouter.assign_region(
+            || "C = A * B",
+            |mut region| {
+                let mut c = vec![];
+                for (i, row) in a.iter().enumerate() {
+                    for j in 0..N {
+                        let column: Vec<_> = b.iter().map(|row| row[j].clone()).collect();
+                        let offset = (i * N + j) * (N + 1);
+                        c.push(chip.assign(&mut region, offset, row, &column)?);
+                    }
+                }
+                Ok(c)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let digest = poseidon.hash(layouter.namespace(|| "H(C)"), &c)?;
+        layouter.constrain_instance(digest.cell(), config.instance, 0)
+    }
+}
+
+fn multiply<const N: usize>(a: &Matrix<N>, b: &Matrix<N>) -> Matrix<N> {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| a[i].iter().zip(b).map(|(x, row)| x * row[j]).sum())
+    })
+}
+
+fn digest<const N: usize>(c: &Matrix<N>) -> Fp {
+    let entries: Vec<_> = c.iter().flatten().map(|entry| Fp::from(*entry)).collect();
+    poseidon::hash(&entries)
+}
+
+fn circuit<const N: usize>(a: &Matrix<N>, b: &Matrix<N>) -> MatMulCircuit<Fp, N> {
+    let known = |m: &Matrix<N>| m.map(|row| row.map(|entry| Value::known(Fp::from(entry))));
+    MatMulCircuit {
+        a: known(a),
+        b: known(b),
+    }
+}
+
+fn prover<const N: usize>(a: &Matrix<N>, b: &Matrix<N>, digest: Fp) -> MockProver<Fp> {
+    let circuit = circuit(a, b);
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![digest]]).unwrap()
+}
+
+/// print the cost and proving time of the product of two `N x N` matrices
+#[cfg(not(feature = "curve-secp256k1"))]
+fn bench<const N: usize>() {
+    use learn_halo2::{cost::CostReport, curve::Backend, prover::ProvingBackend};
+    use std::time::Instant;
+
+    let a: Matrix<N> = std::array::from_fn(|i| std::array::from_fn(|j| (i * N + j) as u64));
+    let b: Matrix<N> = std::array::from_fn(|i| std::array::from_fn(|j| (i + j) as u64));
+    let circuit = circuit(&a, &b);
+    let instances = vec![digest(&multiply(&a, &b))];
+
+    let report = CostReport::measure::<<Backend as ProvingBackend>::Curve, _>(
+        format!("matmul (N = {})", N),
+        &circuit,
+        instances.len(),
+    )
+    .unwrap();
+    let params = Backend::setup(report.k).unwrap();
+    let pk = Backend::keygen(&params, &circuit).unwrap();
+    let start = Instant::now();
+    Backend::prove(&params, &pk, circuit, &[instances.as_slice()]).unwrap();
+    print!("{}", report);
+    println!("  proving:     {:?}", start.elapsed());
+}
+
+#[cfg(not(feature = "curve-secp256k1"))]
+fn bench_all() {
+    bench::<2>();
+    bench::<4>();
+    bench::<8>();
+}
+
+#[cfg(feature = "curve-secp256k1")]
+fn bench_all() {
+    println!("secp256k1 has no commitment scheme, skipping the benchmark");
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MatMulCircuit<Fp, 2>>(|config| {
+                let poseidon = config.poseidon;
+                let inner_product = config.inner_product;
+                let mut names = ColumnNames::new()
+                    .with_advice(inner_product.a, "a")
+                    .with_advice(inner_product.b, "b")
+                    .with_advice(inner_product.acc, "acc");
+                for (k, column) in config.entry.iter().enumerate() {
+                    names = names.with_advice(*column, format!("entry[{}]", k));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(inner_product.q_first, "q_first")
+                    .with_selector(inner_product.q_step, "q_step")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let a = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+    let b = [[9, 8, 7], [6, 5, 4], [3, 2, 1]];
+    let c = multiply(&a, &b);
+    assert_eq!(c, [[30, 24, 18], [84, 69, 54], [138, 114, 90]]);
+    prover(&a, &b, digest(&c)).assert_satisfied();
+    // by the identity
+    let identity = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+    prover(&a, &identity, digest(&a)).assert_satisfied();
+
+    // one entry off
+    let mut off = c;
+    off[1][2] += 1;
+    testing::assert_fails_permutation(&prover(&a, &b, digest(&off)));
+
+    // the product the other way around
+    testing::assert_fails_permutation(&prover(&b, &a, digest(&c)));
+
+    if std::env::args().any(|arg| arg == "--bench") {
+        bench_all();
+    }
+}
@@ -0,0 +1,258 @@
+//! maze circuit
+//!
+//! we are going to prove that we know a private sequence of at most `T` moves that walks
+//! from a start to an exit of a maze without going through a wall
+//!
+//! | instance |
+//! |----------|
+//! | start_x  |
+//! | start_y  |
+//! | exit_x   |
+//! | exit_y   |
+//!
+//! the maze is part of the circuit, its open cells `(x, y)` are in fixed table columns. like
+//! the fibonacci circuits, the walk is one row per step, each row holds the position and
+//! the move that leads to the next row:
+//!
+//! | row | x      | y      | right | left | down | up | q_step | q_open |
+//! |-----|--------|--------|-------|------|------|----|--------|--------|
+//! | 0   | start  | start  | 1     | 0    | 0    | 0  | 1      | 1      |
+//! | i   | x[i]   | y[i]   | 0     | 0    | 1    | 0  | 1      | 1      |
+//! | T   | exit   | exit   |       |      |      |    | 0      | 1      |
+//!
+//! - `q_step * flag * (1 - flag) = 0` for every flag, and for their sum, at most one move
+//! - `q_step * (x' - x - right + left) = 0`
+//! - `q_step * (y' - y - down + up) = 0`
+//!
+//! a row without a move stays in place, so shorter walks are padded. every position is
+//! looked up in the open cells, `(q_open, q_open * x, q_open * y)` in `(tag, x, y)` with a
+//! first row of 0s for the rows without `q_open`, which also keeps the walk in the maze.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+
+/// steps the circuit has room for
+const T: usize = 12;
+
+/// `#` is a wall
+const MAZE: [&str; 5] = ["..#..", "#.#.#", ".....", ".###.", "...#."];
+
+#[derive(Debug, Clone)]
+struct MazeConfig {
+    x: Column<Advice>,
+    y: Column<Advice>,
+    /// right, left, down and up
+    moves: [Column<Advice>; 4],
+    q_step: Selector,
+    q_open: Selector,
+    /// tag, x and y of the open cells
+    table: [TableColumn; 3],
+    instance: Column<Instance>,
+}
+
+struct MazeCircuit<F> {
+    moves: [[Value<F>; 4]; T],
+}
+
+impl<F> Default for MazeCircuit<F> {
+    fn default() -> Self {
+        Self {
+            moves: [(); T].map(|_| [(); 4].map(|_| Value::unknown())),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MazeCircuit<F> {
+    type Config = MazeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MazeConfig {
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let moves = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let q_step = meta.selector();
+        let q_open = meta.complex_selector();
+        let table = [(); 3].map(|_| meta.lookup_table_column());
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+        meta.enable_equality(instance);
+
+        meta.create_gate("maze", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let one = Expression::Constant(F::one());
+            let [right, left, down, up] =
+                moves.map(|column| meta.query_advice(column, Rotation::cur()));
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let x_next = meta.query_advice(x, Rotation::next());
+            let y_cur = meta.query_advice(y, Rotation::cur());
+            let y_next = meta.query_advice(y, Rotation::next());
+
+            let any = right.clone() + left.clone() + down.clone() + up.clone();
+            let mut constraints: Vec<_> = [&right, &left, &down, &up, &any]
+                .into_iter()
+                .map(|flag| q_step.clone() * flag.clone() * (one.clone() - flag.clone()))
+                .collect();
+            constraints.push(q_step.clone() * (x_next - x_cur - right + left));
+            constraints.push(q_step * (y_next - y_cur - down + up));
+            constraints
+        });
+
+        meta.lookup(|meta| {
+            let q_open = meta.query_selector(q_open);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            let [tag, table_x, table_y] = table;
+            vec![
+                (q_open.clone(), tag),
+                (q_open.clone() * x, table_x),
+                (q_open * y, table_y),
+            ]
+        });
+
+        MazeConfig {
+            x,
+            y,
+            moves,
+            q_step,
+            q_open,
+            table,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: MazeConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "open cells",
+            |mut table| {
+                let open = MAZE.iter().enumerate().flat_map(|(y, row)| {
+                    row.chars()
+                        .enumerate()
+                        .filter(|(_, c)| *c != '#')
+                        .map(move |(x, _)| (1, x as u64, y as u64))
+                });
+                for (row, (tag, x, y)) in [(0, 0, 0)].into_iter().chain(open).enumerate() {
+                    for (column, value) in config.table.iter().zip([tag, x, y]) {
+                        table.assign_cell(
+                            || "open",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let (x, y) = layouter.assign_region(
+            || "walk",
+            |mut region| {
+                let mut x =
+                    region.assign_advice_from_instance(|| "x", config.instance, 0, config.x, 0)?;
+                let mut y =
+                    region.assign_advice_from_instance(|| "y", config.instance, 1, config.y, 0)?;
+                for (offset, flags) in self.moves.iter().enumerate() {
+                    config.q_step.enable(&mut region, offset)?;
+                    config.q_open.enable(&mut region, offset)?;
+                    for (column, flag) in config.moves.iter().zip(flags) {
+                        region.assign_advice(|| "move", *column, offset, || *flag)?;
+                    }
+                    let [right, left, down, up] = *flags;
+                    let x_next = x.value().copied() + right - left;
+                    let y_next = y.value().copied() + down - up;
+                    x = region.assign_advice(|| "x", config.x, offset + 1, || x_next)?;
+                    y = region.assign_advice(|| "y", config.y, offset + 1, || y_next)?;
+                }
+                config.q_open.enable(&mut region, T)?;
+                Ok((x, y))
+            },
+        )?;
+        layouter.constrain_instance(x.cell(), config.instance, 2)?;
+        layouter.constrain_instance(y.cell(), config.instance, 3)
+    }
+}
+
+/// `R`, `L`, `D` and `U` move, anything else stays in place
+fn flags(moves: &str) -> Vec<[u64; 4]> {
+    moves
+        .chars()
+        .map(|c| match c {
+            'R' => [1, 0, 0, 0],
+            'L' => [0, 1, 0, 0],
+            'D' => [0, 0, 1, 0],
+            'U' => [0, 0, 0, 1],
+            _ => [0; 4],
+        })
+        .collect()
+}
+
+fn prover(moves: &[[u64; 4]], exit: (u64, u64)) -> MockProver<Fp> {
+    let mut padded = [[Value::known(Fp::zero()); 4]; T];
+    for (row, flags) in padded.iter_mut().zip(moves) {
+        *row = flags.map(|flag| Value::known(Fp::from(flag)));
+    }
+    let circuit = MazeCircuit { moves: padded };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = [0, 0, exit.0, exit.1].map(Fp::from).to_vec();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MazeCircuit<Fp>>(|config| {
+                let [right, left, down, up] = config.moves;
+                ColumnNames::new()
+                    .with_advice(config.x, "x")
+                    .with_advice(config.y, "y")
+                    .with_advice(right, "right")
+                    .with_advice(left, "left")
+                    .with_advice(down, "down")
+                    .with_advice(up, "up")
+                    .with_selector(config.q_step, "q_step")
+                    .with_selector(config.q_open, "q_open")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let exit = (4, 4);
+    prover(&flags("RDDRRRDD"), exit).assert_satisfied();
+    // a detour, back and forth
+    prover(&flags("RDDRLRRRDD"), exit).assert_satisfied();
+
+    // the walk stops short of the exit
+    testing::assert_fails_permutation(&prover(&flags("RDDRRRD"), exit));
+
+    // through the wall below the start
+    testing::assert_fails_lookup(&prover(&flags("DRRRRDD"), exit), 1);
+
+    // off the left edge, x is -1
+    testing::assert_fails_lookup(&prover(&flags("LRRDDRRRDD"), exit), 1);
+
+    // right and down at once, a diagonal step around the corner
+    let mut diagonal = flags("XDRRRDD");
+    diagonal[0] = [1, 0, 1, 0];
+    testing::assert_fails_gate(&prover(&diagonal, exit), "maze", 0);
+}
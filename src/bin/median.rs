@@ -0,0 +1,262 @@
+//! median circuit
+//!
+//! we are going to prove that the median of a private list of `N` bytes, `N` odd, committed
+//! to with the [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is a public
+//! value
+//!
+//! | instance      |
+//! |---------------|
+//! | H(list)       |
+//! | median        |
+//!
+//! the list is range checked to bytes by the
+//! [`RangeCheckChip`](learn_halo2::gadgets::range::RangeCheckChip) of 8 bits and sorted by
+//! a sorting network, the odd-even transposition sort: `N` rounds of compare-and-swaps of
+//! the neighbors `(i, i + 1)`, `i` even in the even rounds and odd in the odd ones. the
+//! median is the middle wire at the end. a compare-and-swap is a row, with the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 1 byte and two
+//! [`select`](learn_halo2::gadgets::select::expr)s:
+//!
+//! | a        | b          | lo           | hi               | lt    | q_swap |
+//! |----------|------------|--------------|------------------|-------|--------|
+//! | wire[i]  | wire[i+1]  | wire[i]'     | wire[i+1]'       | b < a | 1      |
+//!
+//! - `lt = b < a`
+//! - `q_swap * (lo - (lt ? b : a)) = 0`
+//! - `q_swap * (hi - (lt ? a : b)) = 0`
+//!
+//! `a` and `b` are copied from the wires of the round before, `lo` and `hi` only ever hold
+//! a byte of the list, so every comparison stays in range. the network takes
+//! `N * (N - 1) / 2` rows.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+        select,
+    },
+    testing, util,
+};
+
+const N: usize = 7;
+
+#[derive(Debug, Clone)]
+struct MedianConfig {
+    range: RangeCheckConfig,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    lo: Column<Advice>,
+    hi: Column<Advice>,
+    q_swap: Selector,
+    lt: LtConfig<1>,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct MedianCircuit<F> {
+    list: [Value<F>; N],
+}
+
+impl<F> Default for MedianCircuit<F> {
+    fn default() -> Self {
+        Self {
+            list: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MedianCircuit<F> {
+    type Config = MedianConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MedianConfig {
+        let value = meta.advice_column();
+        let [a, b, lo, hi] = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let q_swap = meta.complex_selector();
+        for column in [value, a, b, lo, hi] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        let range = RangeCheckChip::<F, 8>::configure(meta, value);
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_swap),
+            |meta| meta.query_advice(b, Rotation::cur()),
+            |meta| meta.query_advice(a, Rotation::cur()),
+        );
+
+        meta.create_gate("swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let [a, b, lo, hi, lt] =
+                [a, b, lo, hi, lt.lt].map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![
+                q_swap.clone() * (lo - select::expr(lt.clone(), b.clone(), a.clone())),
+                q_swap * (hi - select::expr(lt, a, b)),
+            ]
+        });
+
+        MedianConfig {
+            range,
+            a,
+            b,
+            lo,
+            hi,
+            q_swap,
+            lt,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MedianConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let range = RangeCheckChip::<F, 8>::construct(config.range);
+        range.load_table(layouter.namespace(|| "bytes"))?;
+        let lt = LtChip::<F, 1>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "u8"))?;
+
+        let list = layouter.assign_region(
+            || "list",
+            |mut region| {
+                self.list
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| range.assign_checked(&mut region, i, *value))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let sorted = layouter.assign_region(
+            || "sorting network",
+            |mut region| {
+                let mut wires = list.clone();
+                let mut offset = 0;
+                for round in 0..N {
+                    for i in (round % 2..N - 1).step_by(2) {
+                        config.q_swap.enable(&mut region, offset)?;
+                        let a = wires[i].copy_advice(|| "a", &mut region, config.a, offset)?;
+                        let b = wires[i + 1].copy_advice(|| "b", &mut region, config.b, offset)?;
+                        let (a, b) = (a.value().copied(), b.value().copied());
+                        let swap = lt.assign(&mut region, offset, b, a)?;
+                        let swap = swap.value().map(|lt| *lt == F::one());
+                        let lo = swap
+                            .zip(a)
+                            .zip(b)
+                            .map(|((swap, a), b)| if swap { b } else { a });
+                        let hi = swap
+                            .zip(a)
+                            .zip(b)
+                            .map(|((swap, a), b)| if swap { a } else { b });
+                        wires[i] = region.assign_advice(|| "lo", config.lo, offset, || lo)?;
+                        wires[i + 1] = region.assign_advice(|| "hi", config.hi, offset, || hi)?;
+                        offset += 1;
+                    }
+                }
+                Ok(wires)
+            },
+        )?;
+        layouter.constrain_instance(sorted[N / 2].cell(), config.instance, 1)?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(list)"), &list)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+fn commit(list: &[u64; N]) -> Fp {
+    poseidon::hash(&list.map(Fp::from))
+}
+
+fn median(list: &[u64; N]) -> u64 {
+    let mut sorted = *list;
+    sorted.sort_unstable();
+    sorted[N / 2]
+}
+
+fn prover(list: [u64; N], commitment: Fp, median: u64) -> MockProver<Fp> {
+    let circuit = MedianCircuit {
+        list: list.map(|value| Value::known(Fp::from(value))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![commitment, Fp::from(median)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MedianCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.range.value, "value")
+                    .with_advice(config.a, "a")
+                    .with_advice(config.b, "b")
+                    .with_advice(config.lo, "lo")
+                    .with_advice(config.hi, "hi")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.range.q_lookup, "q_lookup")
+                    .with_selector(config.q_swap, "q_swap")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let list = [42, 7, 255, 0, 19, 7, 100];
+    let commitment = commit(&list);
+    assert_eq!(median(&list), 19);
+    prover(list, commitment, 19).assert_satisfied();
+    // sorted the other way around, and with duplicates around the middle
+    let reversed = [9, 8, 7, 6, 5, 4, 3];
+    prover(reversed, commit(&reversed), 6).assert_satisfied();
+    let repeated = [5, 1, 5, 9, 5, 0, 5];
+    prover(repeated, commit(&repeated), 5).assert_satisfied();
+
+    // the element next to the median
+    testing::assert_fails_permutation(&prover(list, commitment, 42));
+
+    // another list with the claimed median
+    let other = [42, 7, 255, 0, 20, 7, 100];
+    testing::assert_fails_permutation(&prover(other, commitment, 20));
+
+    // not a byte
+    let mut large = list;
+    large[2] = 256;
+    testing::assert_fails_lookup(&prover(large, commit(&large), 19), 2);
+}
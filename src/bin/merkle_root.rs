@@ -0,0 +1,161 @@
+//! merkle root circuit
+//!
+//! we are going to prove that we know `2^DEPTH` leaves whose merkle tree has a public
+//! root, building the whole tree in the circuit with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip) hashing every pair of
+//! nodes
+//!
+//! | instance |
+//! |----------|
+//! | root     |
+//!
+//! the leaves are witnessed in one region, then every layer of the tree is a namespace
+//! with a region per node, which is a poseidon hash of the two nodes below it:
+//!
+//! ```text
+//! leaves
+//! layer 1 / node 0  = H(leaf[0], leaf[1])
+//! layer 1 / node 1  = H(leaf[2], leaf[3])
+//! ...
+//! layer DEPTH / node 0 = root
+//! ```
+//!
+//! the children are copied into the absorbing row of their parent, so nothing but the
+//! leaves is witnessed freely.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::poseidon::{self, PoseidonChip, PoseidonConfig},
+    testing, util,
+};
+
+const DEPTH: usize = 3;
+const LEAVES: usize = 1 << DEPTH;
+
+#[derive(Debug, Clone)]
+struct MerkleRootConfig {
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct MerkleRootCircuit<F> {
+    leaves: [Value<F>; LEAVES],
+}
+
+impl<F> Default for MerkleRootCircuit<F> {
+    fn default() -> Self {
+        Self {
+            leaves: [(); LEAVES].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MerkleRootCircuit<F> {
+    type Config = MerkleRootConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MerkleRootConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        MerkleRootConfig {
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MerkleRootConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PoseidonChip::construct(config.poseidon);
+        let mut nodes = layouter.assign_region(
+            || "leaves",
+            |mut region| {
+                self.leaves
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, leaf)| {
+                        region.assign_advice(|| "leaf", config.poseidon.input[0], offset, || *leaf)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        for depth in 1..=DEPTH {
+            let mut layer = layouter.namespace(|| format!("layer {}", depth));
+            nodes = nodes
+                .chunks(2)
+                .enumerate()
+                .map(|(i, pair)| chip.hash(layer.namespace(|| format!("node {}", i)), pair))
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+        layouter.constrain_instance(nodes[0].cell(), config.instance, 0)
+    }
+}
+
+/// the root of the tree over `leaves`, outside of the circuit
+fn root<F: FieldExt>(leaves: &[F]) -> F {
+    let mut nodes = leaves.to_vec();
+    while nodes.len() > 1 {
+        nodes = nodes.chunks(2).map(poseidon::hash).collect();
+    }
+    nodes[0]
+}
+
+fn prover(leaves: [Fp; LEAVES], root: Fp) -> MockProver<Fp> {
+    let circuit = MerkleRootCircuit {
+        leaves: leaves.map(Value::known),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![root]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MerkleRootCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new();
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let leaves: [Fp; LEAVES] = std::array::from_fn(|i| Fp::from(i as u64 + 1));
+    let expected = root(&leaves);
+    println!("root of 1..={} = {}", LEAVES, util::display_field(expected));
+    prover(leaves, expected).assert_satisfied();
+
+    // swapping two leaves changes the root
+    let mut other = leaves;
+    other.swap(0, 1);
+    testing::assert_fails_permutation(&prover(other, expected));
+}
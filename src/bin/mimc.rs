@@ -0,0 +1,246 @@
+//! MiMC circuit
+//!
+//! we are going to prove that we know the key `k` encrypting a public pair `(xl, xr)` to a
+//! public pair `(yl, yr)` with the MiMC-Feistel permutation
+//!
+//! ```text
+//! l' = r + (l + k + c[i])^5
+//! r' = l
+//! ```
+//!
+//! one round per row. the round constants `c[i]` differ from row to row but are the same
+//! for every proof, so they are assigned to a fixed column: they are part of the circuit,
+//! committed to by the verifying key, and the prover can't pick them.
+//!
+//! | row    | l      | r      | k | c        | q_round |
+//! |--------|--------|--------|---|----------|---------|
+//! | 0      | xl     | xr     | k | c[0]     | 1       |
+//! | i      | l(i)   | r(i)   | k | c[i]     | 1       |
+//! | ROUNDS | yl     | yr     | k |          | 0       |
+//!
+//! - `l[next] = r + (l + k + c)^5`
+//! - `r[next] = l`
+//! - `k[next] = k`
+//!
+//! the instance is `[xl, xr, yl, yr]`. the constants here are `c[0] = 0` and
+//! `c[i] = i^7`, not the ones of any standard instantiation.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+use std::marker::PhantomData;
+
+/// `2 * log_5(p)` for a 254 bit `p`
+const ROUNDS: usize = 220;
+
+#[derive(Debug, Clone, Copy)]
+struct MimcConfig {
+    l: Column<Advice>,
+    r: Column<Advice>,
+    k: Column<Advice>,
+    c: Column<Fixed>,
+    q_round: Selector,
+}
+
+struct MimcChip<F: FieldExt> {
+    config: MimcConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MimcChip<F> {
+    fn construct(config: MimcConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MimcConfig {
+        let l = meta.advice_column();
+        let r = meta.advice_column();
+        let k = meta.advice_column();
+        let c = meta.fixed_column();
+        let q_round = meta.selector();
+        meta.enable_equality(l);
+        meta.enable_equality(r);
+
+        meta.create_gate("mimc round", |meta| {
+            let q_round = meta.query_selector(q_round);
+            let l_cur = meta.query_advice(l, Rotation::cur());
+            let r_cur = meta.query_advice(r, Rotation::cur());
+            let k_cur = meta.query_advice(k, Rotation::cur());
+            let c = meta.query_fixed(c, Rotation::cur());
+            let l_next = meta.query_advice(l, Rotation::next());
+            let r_next = meta.query_advice(r, Rotation::next());
+            let k_next = meta.query_advice(k, Rotation::next());
+
+            let t = l_cur.clone() + k_cur.clone() + c;
+            let t2 = t.clone() * t.clone();
+            vec![
+                q_round.clone() * (l_next - (r_cur + t2.clone() * t2 * t)),
+                q_round.clone() * (r_next - l_cur),
+                q_round * (k_next - k_cur),
+            ]
+        });
+
+        MimcConfig {
+            l,
+            r,
+            k,
+            c,
+            q_round,
+        }
+    }
+
+    /// encrypt `(xl, xr)` under `key`, the assigned input and output pairs are returned
+    fn encrypt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (xl, xr): (Value<F>, Value<F>),
+        key: Value<F>,
+    ) -> Result<[AssignedCell<F, F>; 4], Error> {
+        let config = self.config;
+        layouter.assign_region(
+            || "mimc",
+            |mut region| {
+                let (mut l, mut r) = (xl, xr);
+                let mut input = None;
+                let mut output = None;
+                for offset in 0..=ROUNDS {
+                    let l_cell = region.assign_advice(|| "l", config.l, offset, || l)?;
+                    let r_cell = region.assign_advice(|| "r", config.r, offset, || r)?;
+                    region.assign_advice(|| "k", config.k, offset, || key)?;
+                    if offset == 0 {
+                        input = Some((l_cell.clone(), r_cell.clone()));
+                    }
+                    if offset == ROUNDS {
+                        output = Some((l_cell, r_cell));
+                        break;
+                    }
+
+                    config.q_round.enable(&mut region, offset)?;
+                    let c = round_constant::<F>(offset);
+                    region.assign_fixed(|| "c", config.c, offset, || Value::known(c))?;
+                    (l, r) = (
+                        l.zip(r).zip(key).map(|((l, r), key)| round(l, r, key, c)),
+                        l,
+                    );
+                }
+                let (xl, xr) = input.unwrap();
+                let (yl, yr) = output.unwrap();
+                Ok([xl, xr, yl, yr])
+            },
+        )
+    }
+}
+
+fn round_constant<F: FieldExt>(i: usize) -> F {
+    let i = F::from(i as u64);
+    i.square().square() * i.square() * i
+}
+
+fn round<F: FieldExt>(l: F, r: F, key: F, c: F) -> F {
+    let t = l + key + c;
+    r + t.square().square() * t
+}
+
+/// `(yl, yr)`, outside of the circuit
+fn encrypt<F: FieldExt>((xl, xr): (F, F), key: F) -> (F, F) {
+    (0..ROUNDS).fold((xl, xr), |(l, r), i| {
+        (round(l, r, key, round_constant(i)), l)
+    })
+}
+
+#[derive(Debug, Clone)]
+struct MimcCircuitConfig {
+    mimc: MimcConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct MimcCircuit<F> {
+    plaintext: (Value<F>, Value<F>),
+    key: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MimcCircuit<F> {
+    type Config = MimcCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MimcCircuitConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        MimcCircuitConfig {
+            mimc: MimcChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MimcCircuitConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = MimcChip::construct(config.mimc);
+        let cells = chip.encrypt(layouter.namespace(|| "encrypt"), self.plaintext, self.key)?;
+        for (row, cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn prover(plaintext: (Fp, Fp), key: Fp, ciphertext: (Fp, Fp)) -> MockProver<Fp> {
+    let circuit = MimcCircuit {
+        plaintext: (Value::known(plaintext.0), Value::known(plaintext.1)),
+        key: Value::known(key),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = vec![plaintext.0, plaintext.1, ciphertext.0, ciphertext.1];
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MimcCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.mimc.l, "l")
+                    .with_advice(config.mimc.r, "r")
+                    .with_advice(config.mimc.k, "k")
+                    .with_fixed(config.mimc.c, "c")
+                    .with_selector(config.mimc.q_round, "q_round")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let plaintext = (Fp::from(1), Fp::from(2));
+    let key = Fp::from(0xdead_beef);
+    let ciphertext = encrypt(plaintext, key);
+    println!(
+        "MiMC_k(1, 2) = ({}, {})",
+        util::display_field(ciphertext.0),
+        util::display_field(ciphertext.1)
+    );
+    prover(plaintext, key, ciphertext).assert_satisfied();
+
+    // another key encrypts to another pair
+    testing::assert_fails_permutation(&prover(plaintext, key + Fp::one(), ciphertext));
+}
@@ -0,0 +1,429 @@
+//! neural network inference circuit
+//!
+//! we are going to prove that a tiny neural network, a 2 layer perceptron with its weights
+//! built into the circuit, classifies a private input as a public label
+//!
+//! | instance |
+//! |----------|
+//! | label    |
+//!
+//! ```text
+//! hidden = relu(W1 * input / 2^SCALE_BITS + B1)
+//! logits = W2 * hidden / 2^SCALE_BITS + B2
+//! label  = argmax(logits)
+//! ```
+//!
+//! the input, the biases and everything computed are signed Q32.32 fixed point numbers of
+//! the [`FixedPointChip`](learn_halo2::gadgets::fixed_point::FixedPointChip), the weights
+//! are quantized to small integers, scaled by `2^-SCALE_BITS`. the parameters are pinned
+//! by `assign_advice_from_constant` in a column of their own, and every neuron is
+//!
+//! - the inner product of its weights and the input by the
+//!   [`InnerProductChip`](learn_halo2::gadgets::inner_product::InnerProductChip), a Q32.32
+//!   number as the weights are integers
+//! - the scaling and the bias by the `mul` and the `add` of the `FixedPointChip`, which
+//!   also range check the result to 64 bits
+//! - `max(x, 0)` by the [`ReluChip`](learn_halo2::gadgets::relu::ReluChip), in the hidden
+//!   layer only
+//!
+//! the inner products are not range checked themselves, with weights below 8 they stay
+//! below `2^68`, far from wrapping around in the `mul`. the label is the argmax of the
+//! logits, picked by one-hot flags and compared with every logit by the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 8 bytes:
+//!
+//! | row | flag[c]    | label | best | logit     | lt | q_argmax | q_compare |
+//! |-----|------------|-------|------|-----------|----|----------|-----------|
+//! | 0   | c == label | label | best | logits[0] | 0  | 1        | 1         |
+//! | c   |            |       | best | logits[c] | 0  | 0        | 1         |
+//!
+//! - `q_argmax * flag * (1 - flag) = 0` for every class
+//! - `q_argmax * (flag[0] + ... + flag[CLASSES-1] - 1) = 0`
+//! - `q_argmax * (0 * flag[0] + ... + (CLASSES-1) * flag[CLASSES-1] - label) = 0`
+//! - `q_argmax * (flag[0] * logit[0] + ... + flag[CLASSES-1] * logit[CLASSES-1] - best) = 0`,
+//!   the logits queried at the rows below
+//! - `lt = best < logit` is 0 on every row, `best` is copied down
+//!
+//! the comparisons of the signed logits are the ones of the logits shifted by `2^63`, the
+//! differences are the same, so only the witness is shifted. with a tie any of the best
+//! classes is a valid label.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        fixed_point::{
+            self, from_f64, mul_rounded, to_f64, FixedPointChip, FixedPointConfig, FRAC_BITS,
+        },
+        inner_product::{InnerProductChip, InnerProductConfig},
+        relu::{ReluChip, ReluConfig},
+    },
+    testing, util,
+};
+
+const INPUTS: usize = 4;
+const HIDDEN: usize = 4;
+const CLASSES: usize = 3;
+/// the weights and the biases are multiples of `2^-SCALE_BITS`
+const SCALE_BITS: u32 = 2;
+
+const W1: [[i64; INPUTS]; HIDDEN] = [[3, -2, 1, 0], [-1, 4, -2, 1], [2, 1, -3, 2], [-2, -1, 2, 3]];
+const B1: [i64; HIDDEN] = [1, -2, 0, 1];
+const W2: [[i64; HIDDEN]; CLASSES] = [[4, -3, -2, -1], [-2, 3, -1, 2], [-1, -2, 4, 1]];
+const B2: [i64; CLASSES] = [0, 1, -1];
+
+/// the Q32.32 number of `2^-SCALE_BITS`
+const SCALE: i64 = 1 << (FRAC_BITS - SCALE_BITS);
+
+#[derive(Debug, Clone)]
+struct MlpConfig {
+    /// the weights, the biases and the scale
+    param: Column<Advice>,
+    fixed_point: FixedPointConfig,
+    inner_product: InnerProductConfig,
+    relu: ReluConfig,
+    flag: [Column<Advice>; CLASSES],
+    label: Column<Advice>,
+    best: Column<Advice>,
+    logit: Column<Advice>,
+    q_argmax: Selector,
+    q_compare: Selector,
+    lt: LtConfig<8>,
+    instance: Column<Instance>,
+}
+
+/// the cells of the parameters of a layer
+struct Layer<F: FieldExt> {
+    weights: Vec<Vec<AssignedCell<F, F>>>,
+    biases: Vec<AssignedCell<F, F>>,
+}
+
+struct MlpCircuit {
+    input: [Value<i64>; INPUTS],
+    /// the class of the flags, the public label unless the prover cheats
+    label: Value<usize>,
+}
+
+impl Default for MlpCircuit {
+    fn default() -> Self {
+        Self {
+            input: [(); INPUTS].map(|_| Value::unknown()),
+            label: Value::unknown(),
+        }
+    }
+}
+
+impl MlpCircuit {
+    /// `W * x / 2^SCALE_BITS + B` of every neuron of `layer`
+    fn layer<F: FieldExt>(
+        config: &MlpConfig,
+        mut layouter: impl Layouter<F>,
+        layer: &Layer<F>,
+        scale: &AssignedCell<F, F>,
+        x: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let inner_product = InnerProductChip::construct(config.inner_product);
+        let fixed_point = FixedPointChip::construct(config.fixed_point);
+        layer
+            .weights
+            .iter()
+            .zip(&layer.biases)
+            .map(|(weights, bias)| {
+                let dot =
+                    inner_product.inner_product(layouter.namespace(|| "w . x"), weights, x)?;
+                let scaled = fixed_point.mul(layouter.namespace(|| "scale"), &dot, scale)?;
+                fixed_point.add(layouter.namespace(|| "bias"), &scaled, bias)
+            })
+            .collect()
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MlpCircuit {
+    type Config = MlpConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MlpConfig {
+        let param = meta.advice_column();
+        let [a, b, acc, x, out] = [(); 5].map(|_| meta.advice_column());
+        let flag = [(); CLASSES].map(|_| meta.advice_column());
+        let label = meta.advice_column();
+        let best = meta.advice_column();
+        let logit = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_argmax = meta.selector();
+        let q_compare = meta.complex_selector();
+        for column in [param, label, best, logit] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("argmax", |meta| {
+            let q_argmax = meta.query_selector(q_argmax);
+            let one = Expression::Constant(F::one());
+            let label = meta.query_advice(label, Rotation::cur());
+            let best = meta.query_advice(best, Rotation::cur());
+            let mut constraints = vec![];
+            let mut count = Expression::Constant(F::zero());
+            let mut encoded = Expression::Constant(F::zero());
+            let mut selected = Expression::Constant(F::zero());
+            for (c, column) in flag.iter().enumerate() {
+                let flag = meta.query_advice(*column, Rotation::cur());
+                let logit = meta.query_advice(logit, Rotation(c as i32));
+                constraints.push(q_argmax.clone() * flag.clone() * (one.clone() - flag.clone()));
+                count = count + flag.clone();
+                encoded = encoded + Expression::Constant(F::from(c as u64)) * flag.clone();
+                selected = selected + flag * logit;
+            }
+            constraints.push(q_argmax.clone() * (count - one));
+            constraints.push(q_argmax.clone() * (encoded - label));
+            constraints.push(q_argmax * (selected - best));
+            constraints
+        });
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| meta.query_advice(best, Rotation::cur()),
+            |meta| meta.query_advice(logit, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        MlpConfig {
+            param,
+            fixed_point: FixedPointChip::configure(meta),
+            inner_product: InnerProductChip::configure(meta, a, b, acc),
+            relu: ReluChip::configure(meta, x, out),
+            flag,
+            label,
+            best,
+            logit,
+            q_argmax,
+            q_compare,
+            lt,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: MlpConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fixed_point = FixedPointChip::construct(config.fixed_point);
+        fixed_point.load_table(layouter.namespace(|| "u8"))?;
+        let relu = ReluChip::construct(config.relu);
+        relu.load_table(layouter.namespace(|| "relu u8"))?;
+        let lt = LtChip::<F, 8>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "argmax u8"))?;
+
+        let (first, second, scale) = layouter.assign_region(
+            || "parameters",
+            |mut region| {
+                let mut offset = 0;
+                let mut constant = |x: i64| {
+                    offset += 1;
+                    region.assign_advice_from_constant(
+                        || "param",
+                        config.param,
+                        offset - 1,
+                        fixed_point::encode::<F>(x),
+                    )
+                };
+                let scale = constant(SCALE)?;
+                let bias = |b: i64| b << (FRAC_BITS - SCALE_BITS);
+                let mut layer = |weights: &[Vec<i64>], biases: &[i64]| {
+                    let weights = weights
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|w| constant(*w))
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let biases = biases
+                        .iter()
+                        .map(|b| constant(bias(*b)))
+                        .collect::<Result<_, _>>()?;
+                    Ok::<_, Error>(Layer { weights, biases })
+                };
+                let first = layer(&W1.map(Vec::from), &B1)?;
+                let second = layer(&W2.map(Vec::from), &B2)?;
+                Ok((first, second, scale))
+            },
+        )?;
+
+        let input = self
+            .input
+            .iter()
+            .map(|x| fixed_point.assign(layouter.namespace(|| "input"), *x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hidden = Self::layer(&config, layouter.namespace(|| "W1"), &first, &scale, &input)?
+            .iter()
+            .map(|x| relu.relu(layouter.namespace(|| "relu"), x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let logits = Self::layer(
+            &config,
+            layouter.namespace(|| "W2"),
+            &second,
+            &scale,
+            &hidden,
+        )?;
+
+        layouter.assign_region(
+            || "argmax",
+            |mut region| {
+                config.q_argmax.enable(&mut region, 0)?;
+                let mut best = Value::known(F::zero());
+                for (c, (column, logit)) in config.flag.iter().zip(&logits).enumerate() {
+                    let flag = self.label.map(|label| F::from((label == c) as u64));
+                    region.assign_advice(|| "flag", *column, 0, || flag)?;
+                    best = best + flag * logit.value().copied();
+                }
+                let best = region.assign_advice(|| "best", config.best, 0, || best)?;
+                region.assign_advice_from_instance(
+                    || "label",
+                    config.instance,
+                    0,
+                    config.label,
+                    0,
+                )?;
+
+                let shift = Value::known(F::from_u128(1 << 63));
+                for (offset, logit) in logits.iter().enumerate() {
+                    config.q_compare.enable(&mut region, offset)?;
+                    if offset > 0 {
+                        best.copy_advice(|| "best", &mut region, config.best, offset)?;
+                    }
+                    let logit = logit.copy_advice(|| "logit", &mut region, config.logit, offset)?;
+                    let lt = lt.assign(
+                        &mut region,
+                        offset,
+                        best.value().copied() + shift,
+                        logit.value().copied() + shift,
+                    )?;
+                    region.constrain_constant(lt.cell(), F::zero())?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// `W * x / 2^SCALE_BITS + B` outside of the circuit, rounded like the circuit
+fn forward<const I: usize, const O: usize>(
+    weights: &[[i64; I]; O],
+    biases: &[i64; O],
+    x: &[i64; I],
+) -> [i64; O] {
+    std::array::from_fn(|j| {
+        let dot: i64 = weights[j].iter().zip(x).map(|(w, x)| w * x).sum();
+        mul_rounded(dot, SCALE) + (biases[j] << (FRAC_BITS - SCALE_BITS))
+    })
+}
+
+/// the logits of `input` outside of the circuit
+fn infer(input: [f64; INPUTS]) -> [i64; CLASSES] {
+    let hidden = forward(&W1, &B1, &input.map(from_f64)).map(|x| x.max(0));
+    forward(&W2, &B2, &hidden)
+}
+
+fn argmax(logits: &[i64; CLASSES]) -> usize {
+    (0..CLASSES).max_by_key(|c| logits[*c]).unwrap()
+}
+
+fn prover(input: [f64; INPUTS], claim: usize, label: usize) -> MockProver<Fp> {
+    let circuit = MlpCircuit {
+        input: input.map(|x| Value::known(from_f64(x))),
+        label: Value::known(claim),
+    };
+    let k = util::min_k_for::<Fp, _>(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(label as u64)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MlpCircuit>(|config| {
+                let fixed_point = config.fixed_point;
+                let inner_product = config.inner_product;
+                let relu = config.relu;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.param, "param")
+                    .with_advice(fixed_point.a, "fixed a")
+                    .with_advice(fixed_point.b, "fixed b")
+                    .with_advice(fixed_point.z, "z")
+                    .with_advice(fixed_point.rem, "rem")
+                    .with_advice(fixed_point.slack, "slack")
+                    .with_advice(fixed_point.shifted, "shifted")
+                    .with_advice(inner_product.a, "a")
+                    .with_advice(inner_product.b, "b")
+                    .with_advice(inner_product.acc, "acc")
+                    .with_advice(relu.x, "x")
+                    .with_advice(relu.out, "out")
+                    .with_fixed(relu.bound, "bound")
+                    .with_fixed(relu.sign, "sign")
+                    .with_advice(relu.lt.lt, "relu lt")
+                    .with_advice(config.label, "label")
+                    .with_advice(config.best, "best")
+                    .with_advice(config.logit, "logit")
+                    .with_advice(config.lt.lt, "lt");
+                for (c, column) in config.flag.iter().enumerate() {
+                    names = names.with_advice(*column, format!("flag[{}]", c));
+                }
+                for (i, column) in relu.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("relu diff[{}]", i));
+                }
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                names
+                    .with_selector(fixed_point.q_range, "q_range")
+                    .with_selector(fixed_point.q_add, "q_add")
+                    .with_selector(fixed_point.q_mul, "q_mul")
+                    .with_selector(inner_product.q_first, "q_first")
+                    .with_selector(inner_product.q_step, "q_step")
+                    .with_selector(relu.q_relu, "q_relu")
+                    .with_selector(config.q_argmax, "q_argmax")
+                    .with_selector(config.q_compare, "q_compare")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let inputs = [
+        [1.0, 0.5, 0.0, 0.25],
+        [0.0, 1.0, 0.5, 0.0],
+        [1.0, 1.0, 0.0, 0.5],
+        [0.0, 1.0, -1.0, 1.0],
+    ];
+    for (input, expected) in inputs.into_iter().zip([0, 1, 2, 1]) {
+        let logits = infer(input);
+        println!("{:?}: logits {:?}", input, logits.map(to_f64));
+        assert_eq!(argmax(&logits), expected);
+        prover(input, expected, expected).assert_satisfied();
+    }
+
+    let input = inputs[0];
+    // a public label other than the one of the flags
+    testing::assert_fails_gate(&prover(input, 0, 2), "argmax", 0);
+
+    // the runner up claimed, its logit is below the best one
+    testing::assert_fails_permutation(&prover(input, 2, 2));
+
+    // not a class
+    testing::assert_fails_gate(&prover(input, CLASSES, CLASSES), "argmax", 0);
+}
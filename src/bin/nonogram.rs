@@ -0,0 +1,257 @@
+//! nonogram circuit
+//!
+//! we are going to prove that we know a private grid of pixels whose rows and columns have
+//! the runs of filled pixels given by the clues of a nonogram, here a heart:
+//!
+//! | clues | 2 | 4 | 4 | 4 | 2 |
+//! |-------|---|---|---|---|---|
+//! | 1 1   |   | # |   | # |   |
+//! | 5     | # | # | # | # | # |
+//! | 5     | # | # | # | # | # |
+//! | 3     |   | # | # | # |   |
+//! | 1     |   |   | # |   |   |
+//!
+//! the clues are part of the circuit, like the program in the ROM of `src/bin/zkvm.rs`, there
+//! is no instance. a line matches its clue `c[0] c[1] ... c[k-1]` when it is in
+//! `0* 1^c[0] 0+ 1^c[1] 0+ ... 1^c[k-1] 0*`, which an automaton of the
+//! [`FsmChip`](learn_halo2::gadgets::fsm::FsmChip) checks. every line has its own automaton,
+//! with states numbered apart from the other ones so that all of them share one table:
+//!
+//! - `gap[r]` waits for the run `r`, loops on 0 and starts it on 1
+//! - `run[r][t]` has seen `t` pixels of the run `r`, a 1 goes on while `t < c[r]`, once the
+//!   run is complete a 0 goes to `gap[r + 1]`, or to `end` after the last run
+//! - `end` loops on 0
+//!
+//! a line is fed to its automaton with a constant 0 after its last pixel, so a line that
+//! matches always ends in `end`, and the last state is constrained to it. the pixels are
+//! boolean since there are no transitions on anything else.
+//!
+//! the grid is in `W` columns, one row per row of pixels, and all the `H + W` runs are laid
+//! out one after the other in a single region, with
+//! [`FsmChip::assign`](learn_halo2::gadgets::fsm::FsmChip::assign):
+//!
+//! | row       | state    | input    | q_step |
+//! |-----------|----------|----------|--------|
+//! | 0         | start[0] | pixel    | 1      |
+//! | ...       | ...      | ...      | 1      |
+//! | W         | ...      | 0        | 1      |
+//! | W + 1     | end[0]   |          | 0      |
+//! | W + 2     | start[1] | pixel    | 1      |
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::fsm::{self, FsmChip, FsmConfig, Transition},
+    testing, util,
+};
+
+const W: usize = 5;
+const H: usize = 5;
+
+const ROWS: [&[u64]; H] = [&[1, 1], &[5], &[5], &[3], &[1]];
+const COLUMNS: [&[u64]; W] = [&[2], &[4], &[4], &[4], &[2]];
+
+/// the transitions of the automata of all the lines, rows first, and their start and end
+/// states. the states start from 1, 0 is the state of a step without a transition
+fn automata() -> (Vec<Transition>, Vec<(u64, u64)>) {
+    let mut transitions = vec![];
+    let mut lines = vec![];
+    let mut next = 1;
+    for clue in ROWS.iter().chain(&COLUMNS) {
+        let mut new_state = || {
+            next += 1;
+            next - 1
+        };
+        let start = new_state();
+        let mut gap = start;
+        for len in clue.iter() {
+            transitions.push((gap, 0, gap));
+            let mut state = gap;
+            for _ in 0..*len {
+                let filled = new_state();
+                transitions.push((state, 1, filled));
+                state = filled;
+            }
+            // `end` after the last run
+            gap = new_state();
+            transitions.push((state, 0, gap));
+        }
+        transitions.push((gap, 0, gap));
+        lines.push((start, gap));
+    }
+    (transitions, lines)
+}
+
+#[derive(Debug, Clone)]
+struct NonogramConfig {
+    pixel: [Column<Advice>; W],
+    fsm: FsmConfig,
+}
+
+struct NonogramCircuit<F> {
+    grid: [[Value<F>; W]; H],
+}
+
+impl<F> Default for NonogramCircuit<F> {
+    fn default() -> Self {
+        Self {
+            grid: [(); H].map(|_| [(); W].map(|_| Value::unknown())),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for NonogramCircuit<F> {
+    type Config = NonogramConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> NonogramConfig {
+        let pixel = [(); W].map(|_| meta.advice_column());
+        let state = meta.advice_column();
+        let input = meta.advice_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        for column in pixel {
+            meta.enable_equality(column);
+        }
+        meta.enable_constant(constant);
+        NonogramConfig {
+            pixel,
+            fsm: FsmChip::configure(meta, state, input),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: NonogramConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (transitions, lines) = automata();
+        let chip = FsmChip::construct(config.fsm, &transitions);
+        chip.load_table(layouter.namespace(|| "transitions"))?;
+
+        let (grid, starts, zero) = layouter.assign_region(
+            || "grid",
+            |mut region| {
+                let mut grid = vec![];
+                for (offset, row) in self.grid.iter().enumerate() {
+                    let cells = config
+                        .pixel
+                        .iter()
+                        .zip(row)
+                        .map(|(column, pixel)| {
+                            region.assign_advice(|| "pixel", *column, offset, || *pixel)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    grid.push(cells);
+                }
+                let starts = lines
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, (start, _))| {
+                        region.assign_advice_from_constant(
+                            || "start",
+                            config.fsm.state,
+                            offset,
+                            F::from(*start),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let zero = region.assign_advice_from_constant(
+                    || "zero",
+                    config.fsm.input,
+                    0,
+                    F::zero(),
+                )?;
+                Ok((grid, starts, zero))
+            },
+        )?;
+
+        let rows = grid.iter().cloned();
+        let columns = (0..W).map(|j| grid.iter().map(|row| row[j].clone()).collect::<Vec<_>>());
+        layouter.assign_region(
+            || "lines",
+            |mut region| {
+                let mut offset = 0;
+                for ((mut inputs, start), (_, end)) in
+                    rows.clone().chain(columns.clone()).zip(&starts).zip(&lines)
+                {
+                    inputs.push(zero.clone());
+                    let last = chip.assign(&mut region, offset, start, &inputs)?;
+                    region.constrain_constant(last.cell(), F::from(*end))?;
+                    offset += inputs.len() + 1;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prover(grid: [&str; H]) -> MockProver<Fp> {
+    let circuit = NonogramCircuit {
+        grid: grid.map(|row| {
+            let mut pixels = [Value::known(Fp::zero()); W];
+            for (pixel, c) in pixels.iter_mut().zip(row.chars()) {
+                *pixel = Value::known(Fp::from(match c {
+                    '#' => 1,
+                    '.' => 0,
+                    _ => 2,
+                }));
+            }
+            pixels
+        }),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, NonogramCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new()
+                    .with_advice(config.fsm.state, "state")
+                    .with_advice(config.fsm.input, "input")
+                    .with_selector(config.fsm.q_step, "q_step");
+                for (j, column) in config.pixel.iter().enumerate() {
+                    names = names.with_advice(*column, format!("pixel[{}]", j));
+                }
+                names
+            })
+        );
+    }
+
+    let heart = [".#.#.", "#####", "#####", ".###.", "..#.."];
+    let (transitions, lines) = automata();
+    let accepts = |(start, end): (u64, u64), line: &[u64]| {
+        line.iter()
+            .chain(&[0])
+            .try_fold(start, |state, pixel| fsm::step(&transitions, state, *pixel))
+            == Some(end)
+    };
+    assert!(accepts(lines[0], &[0, 1, 0, 1, 0]));
+    assert!(!accepts(lines[0], &[0, 1, 1, 0, 0]));
+    prover(heart).assert_satisfied();
+
+    // a missing pixel at the bottom, the bottom row never leaves its gap and the column of
+    // the pixel is one short
+    testing::assert_fails_permutation(&prover([".#.#.", "#####", "#####", ".###.", "....."]));
+
+    // a pixel that is neither filled nor empty, the first step of the first row
+    testing::assert_fails_lookup(&prover(["?#.#.", "#####", "#####", ".###.", "..#.."]), 0);
+
+    // the rows match, but the first column is 3 long, its third 1 has no transition
+    let shifted = [".#.#.", "#####", "#####", "###..", "..#.."];
+    testing::assert_fails_lookup(&prover(shifted), H * (W + 2) + 3);
+}
@@ -0,0 +1,144 @@
+//! pedersen commitment circuit
+//!
+//! we are going to prove that we know the opening `(value, blinding)` of a public
+//! pedersen commitment `value * G + blinding * H` with the
+//! [`PedersenChip`](learn_halo2::gadgets::pedersen::PedersenChip)
+//!
+//! | instance |
+//! |----------|
+//! | C.x      |
+//! | C.y      |
+//!
+//! the value and the blinding are private, the value is 64 bits, the blinding 252.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::pedersen::{Curve, PedersenChip, PedersenConfig, Point},
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct PedersenCircuitConfig {
+    pedersen: PedersenConfig,
+    opening: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+struct PedersenCircuit<F> {
+    value: Value<F>,
+    blinding: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PedersenCircuit<F> {
+    type Config = PedersenCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+            blinding: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> PedersenCircuitConfig {
+        let opening = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(opening);
+        meta.enable_equality(instance);
+        PedersenCircuitConfig {
+            pedersen: PedersenChip::configure(meta),
+            opening,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: PedersenCircuitConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PedersenChip::construct(config.pedersen);
+        let (value, blinding) = layouter.assign_region(
+            || "opening",
+            |mut region| {
+                let value = region.assign_advice(|| "value", config.opening, 0, || self.value)?;
+                let blinding =
+                    region.assign_advice(|| "blinding", config.opening, 1, || self.blinding)?;
+                Ok((value, blinding))
+            },
+        )?;
+        let commitment = chip.commit(layouter.namespace(|| "commit"), &value, &blinding)?;
+        for (row, cell) in commitment.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn prover(value: u64, blinding: Fp, commitment: Point<Fp>) -> MockProver<Fp> {
+    let circuit = PedersenCircuit {
+        value: Value::known(Fp::from(value)),
+        blinding: Value::known(blinding),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![commitment.x, commitment.y]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, PedersenCircuit<Fp>>(|config| {
+                let pedersen = config.pedersen;
+                let [b0, b1] = pedersen.bits;
+                let [px, py] = pedersen.p;
+                let [ax, ay] = pedersen.acc;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.opening, "opening")
+                    .with_advice(b0, "b0")
+                    .with_advice(b1, "b1")
+                    .with_advice(px, "px")
+                    .with_advice(py, "py")
+                    .with_advice(ax, "ax")
+                    .with_advice(ay, "ay")
+                    .with_advice(pedersen.z, "z");
+                let [xs, ys] = pedersen.table;
+                for (j, (x, y)) in xs.iter().zip(&ys).enumerate() {
+                    names = names
+                        .with_fixed(*x, format!("x[{}]", j))
+                        .with_fixed(*y, format!("y[{}]", j));
+                }
+                names
+                    .with_selector(pedersen.q_window, "q_window")
+                    .with_selector(pedersen.q_z_first, "q_z_first")
+                    .with_selector(pedersen.q_z_step, "q_z_step")
+                    .with_selector(pedersen.q_acc_first, "q_acc_first")
+                    .with_selector(pedersen.q_add, "q_add")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let curve = Curve::<Fp>::new();
+    let value = 1_000_000;
+    let blinding = Fp::from_u128(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+    let commitment = curve.commit(value, blinding);
+    println!(
+        "C = ({}, {})",
+        util::display_field(commitment.x),
+        util::display_field(commitment.y)
+    );
+    prover(value, blinding, commitment).assert_satisfied();
+
+    // the commitment can't be opened to another value
+    testing::assert_fails_permutation(&prover(value + 1, blinding, commitment));
+}
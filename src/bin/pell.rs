@@ -0,0 +1,134 @@
+//! pell numbers
+//!
+//! we are going to prove that `(P(N-1), P(N))` are the public pair, where
+//! `P(i) = 2 * P(i-1) + P(i-2)`, `P(0) = 0, P(1) = 1`
+//!
+//! the [`LinearRecurrenceChip`] is the one used for fibonacci with coefficients `[2, 1]`.
+//! the initial values are pinned to constants, and the instance column is
+//! `[P(N-1), P(N)]`: `x = P(N-1) + P(N)`, `y = P(N)` is a solution of the pell equation
+//! `x^2 - 2y^2 = ±1`, which the verifier can check on the public inputs.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::recurrence::{LinearRecurrenceChip, LinearRecurrenceConfig},
+    testing, util,
+};
+
+const N: usize = 20;
+
+#[derive(Debug, Clone)]
+struct PellConfig {
+    recurrence: LinearRecurrenceConfig<2>,
+    instance: Column<Instance>,
+}
+
+/// every cell follows from the constants, there is no secret witness
+#[derive(Default)]
+struct PellCircuit<F> {
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PellCircuit<F> {
+    type Config = PellConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> PellConfig {
+        let x = meta.advice_column();
+        let coeffs = [meta.fixed_column(), meta.fixed_column()];
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_constant(constant);
+        meta.enable_equality(instance);
+        PellConfig {
+            recurrence: LinearRecurrenceChip::configure(meta, x, coeffs),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: PellConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = LinearRecurrenceChip::<F, 2>::construct(config.recurrence);
+        let initial = [F::zero(), F::one()];
+        let terms = chip.assign(
+            layouter.namespace(|| "pell"),
+            [F::from(2), F::one()],
+            initial.map(Value::known),
+            N + 1,
+        )?;
+        layouter.assign_region(
+            || "P(0), P(1)",
+            |mut region| {
+                region.constrain_constant(terms[0].cell(), initial[0])?;
+                region.constrain_constant(terms[1].cell(), initial[1])
+            },
+        )?;
+        layouter.constrain_instance(terms[N - 1].cell(), config.instance, 0)?;
+        layouter.constrain_instance(terms[N].cell(), config.instance, 1)
+    }
+}
+
+/// `(P(n-1), P(n))`, native
+fn pell(n: u64) -> (u64, u64) {
+    (1..n).fold((0, 1), |(a, b), _| (b, 2 * b + a))
+}
+
+fn prover((previous, last): (u64, u64)) -> MockProver<Fp> {
+    let circuit = PellCircuit::<Fp>::default();
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(previous), Fp::from(last)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, PellCircuit<Fp>>(|config| {
+                let [c1, c2] = config.recurrence.coeffs;
+                ColumnNames::new()
+                    .with_advice(config.recurrence.x, "x")
+                    .with_fixed(c1, "c1")
+                    .with_fixed(c2, "c2")
+                    .with_selector(config.recurrence.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (previous, last) = pell(N as u64);
+    prover((previous, last)).assert_satisfied();
+    println!(
+        "P({}) = {}, ({}, {}) solves x^2 - 2y^2 = ±1",
+        N,
+        last,
+        previous + last,
+        last
+    );
+}
+
+#[test]
+fn pell_20() {
+    let (previous, last) = pell(N as u64);
+    assert_eq!(last, 15994428);
+    // the pair gives a solution of the pell equation
+    let (x, y) = ((previous + last) as i128, last as i128);
+    assert_eq!((x * x - 2 * y * y).abs(), 1);
+    prover((previous, last)).assert_satisfied();
+}
+
+#[test]
+fn swapped_pair() {
+    let (previous, last) = pell(N as u64);
+    testing::assert_fails_permutation(&prover((last, previous)));
+}
@@ -0,0 +1,129 @@
+//! poseidon hash circuit
+//!
+//! we are going to prove that we know a preimage of L field elements hashing to a public
+//! digest with the [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip)
+//!
+//! | instance |
+//! |----------|
+//! | digest   |
+//!
+//! the preimage is witnessed in the input columns of the chip and copied into its
+//! absorbing rows, the last state of the sponge is copied to the instance.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::poseidon::{self, PoseidonChip, PoseidonConfig},
+    testing, util,
+};
+
+const L: usize = 4;
+
+#[derive(Debug, Clone)]
+struct PoseidonHashConfig {
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct PoseidonHashCircuit<F> {
+    preimage: [Value<F>; L],
+}
+
+impl<F> Default for PoseidonHashCircuit<F> {
+    fn default() -> Self {
+        Self {
+            preimage: [(); L].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PoseidonHashCircuit<F> {
+    type Config = PoseidonHashConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> PoseidonHashConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PoseidonHashConfig {
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: PoseidonHashConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PoseidonChip::construct(config.poseidon);
+        let preimage = layouter.assign_region(
+            || "preimage",
+            |mut region| {
+                self.preimage
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, x)| {
+                        region.assign_advice(|| "preimage", config.poseidon.input[0], offset, || *x)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        let digest = chip.hash(layouter.namespace(|| "hash"), &preimage)?;
+        layouter.constrain_instance(digest.cell(), config.instance, 0)
+    }
+}
+
+fn prover(preimage: [Fp; L], digest: Fp) -> MockProver<Fp> {
+    let circuit = PoseidonHashCircuit {
+        preimage: preimage.map(Value::known),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![digest]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, PoseidonHashCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new();
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let preimage = [1, 2, 3, 4].map(Fp::from);
+    let digest = poseidon::hash(&preimage);
+    println!("H(1, 2, 3, 4) = {}", util::display_field(digest));
+    prover(preimage, digest).assert_satisfied();
+
+    // the digest of another preimage
+    let other = [1, 2, 3, 5].map(Fp::from);
+    testing::assert_fails_permutation(&prover(other, digest));
+}
@@ -0,0 +1,295 @@
+//! proof of reserves circuit
+//!
+//! we are going to prove that the balance of a user is included in a merkle sum tree of
+//! DEPTH levels, whose root has a public hash and a public total. every node holds a
+//! hash and the sum of the balances below it:
+//!
+//! - leaf: `(H(id, balance), balance)`
+//! - parent: `(H(left.hash, left.sum, right.hash, right.sum), left.sum + right.sum)`
+//!
+//! | instance  |
+//! |-----------|
+//! | root hash |
+//! | total     |
+//! | id        |
+//! | balance   |
+//!
+//! the position of the user and the siblings on the path are private. whichever side the
+//! sibling is on, the sum of the parent is the sum of the node plus the one of the
+//! sibling, so the sums on the path are the running sum of
+//! `[balance, sibling[0].sum, ..., sibling[DEPTH-1].sum]` in the
+//! [`RunningSumChip`](learn_halo2::gadgets::accumulator::RunningSumChip). the balance and
+//! the sums of the siblings are range checked to 64 bits by the
+//! [`U64Chip`](learn_halo2::gadgets::uint::U64Chip), so no negative balance can hide a
+//! liability, and the bits of the position pick the sides with the
+//! [`SelectChip`](learn_halo2::gadgets::select::SelectChip) before hashing with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip).
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        accumulator::{RunningSumChip, RunningSumConfig},
+        bits::{DecomposeChip, DecomposeConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        select::{SelectChip, SelectConfig},
+        uint::{U64Chip, UintConfig},
+    },
+    testing, util,
+};
+
+const DEPTH: usize = 3;
+
+#[derive(Debug, Clone)]
+struct ReservesConfig {
+    witness: Column<Advice>,
+    poseidon: PoseidonConfig,
+    sum: RunningSumConfig,
+    select: SelectConfig,
+    decompose: DecomposeConfig,
+    uint: UintConfig<8>,
+    instance: Column<Instance>,
+}
+
+/// `(hash, sum)`
+type Node<F> = (F, F);
+
+struct ReservesCircuit<F> {
+    index: Value<F>,
+    siblings: [Value<Node<F>>; DEPTH],
+}
+
+impl<F> Default for ReservesCircuit<F> {
+    fn default() -> Self {
+        Self {
+            index: Value::unknown(),
+            siblings: [(); DEPTH].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ReservesCircuit<F> {
+    type Config = ReservesConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ReservesConfig {
+        let witness = meta.advice_column();
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let select = [(); 4].map(|_| meta.advice_column());
+        let bit = meta.advice_column();
+        let bit_acc = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(witness);
+        meta.enable_equality(x);
+        meta.enable_equality(instance);
+        ReservesConfig {
+            witness,
+            poseidon: PoseidonChip::configure(meta),
+            sum: RunningSumChip::configure(meta, x, acc),
+            select: SelectChip::configure(meta, select),
+            decompose: DecomposeChip::configure(meta, bit, bit_acc),
+            uint: U64Chip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: ReservesConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let select = SelectChip::construct(config.select);
+        let uint = U64Chip::construct(config.uint);
+        uint.load_table(layouter.namespace(|| "u8"))?;
+
+        let (id, index) = layouter.assign_region(
+            || "user",
+            |mut region| {
+                let id = region.assign_advice_from_instance(
+                    || "id",
+                    config.instance,
+                    2,
+                    config.witness,
+                    0,
+                )?;
+                let index = region.assign_advice(|| "index", config.witness, 1, || self.index)?;
+                Ok((id, index))
+            },
+        )?;
+
+        // the sums on the path, from the balance up to the total
+        let balance = layouter.assign_region(
+            || "balance",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "balance",
+                    config.instance,
+                    3,
+                    config.witness,
+                    0,
+                )
+            },
+        )?;
+        let summands: Vec<_> = [balance.value().copied()]
+            .into_iter()
+            .chain(
+                self.siblings
+                    .iter()
+                    .map(|sibling| sibling.map(|(_, sum)| sum)),
+            )
+            .collect();
+        let (summands, sums) = RunningSumChip::construct(config.sum)
+            .assign_cells(layouter.namespace(|| "sums"), &summands)?;
+        layouter.assign_region(
+            || "balance summand",
+            |mut region| region.constrain_equal(balance.cell(), summands[0].cell()),
+        )?;
+        uint.range_check(layouter.namespace(|| "balances"), &summands)?;
+
+        let bits = DecomposeChip::construct(config.decompose).decompose(
+            layouter.namespace(|| "index"),
+            &index,
+            DEPTH,
+        )?;
+        let mut hash = poseidon.hash(layouter.namespace(|| "leaf"), &[id, balance])?;
+        for (level, bit) in bits.iter().enumerate() {
+            let sum = &sums[level + 1];
+            let sibling_sum = &summands[level + 1];
+            let inputs = layouter.assign_region(
+                || format!("level {}", level),
+                |mut region| {
+                    let sibling_hash = region.assign_advice(
+                        || "sibling hash",
+                        config.witness,
+                        0,
+                        || self.siblings[level].map(|(hash, _)| hash),
+                    )?;
+                    Ok(vec![
+                        select.select(&mut region, 0, bit, &sibling_hash, &hash)?,
+                        select.select(&mut region, 1, bit, sibling_sum, sum)?,
+                        select.select(&mut region, 2, bit, &hash, &sibling_hash)?,
+                        select.select(&mut region, 3, bit, sum, sibling_sum)?,
+                    ])
+                },
+            )?;
+            hash = poseidon.hash(layouter.namespace(|| format!("node {}", level)), &inputs)?;
+        }
+
+        layouter.constrain_instance(hash.cell(), config.instance, 0)?;
+        layouter.constrain_instance(sums[DEPTH + 1].cell(), config.instance, 1)
+    }
+}
+
+/// the layers of the tree over `(id, balance)` accounts, from the leaves up to the root
+fn build<F: FieldExt>(accounts: &[(u64, u64)]) -> Vec<Vec<Node<F>>> {
+    assert_eq!(accounts.len(), 1 << DEPTH);
+    let leaves = accounts
+        .iter()
+        .map(|(id, balance)| {
+            let (id, balance) = (F::from(*id), F::from(*balance));
+            (poseidon::hash(&[id, balance]), balance)
+        })
+        .collect();
+    let mut layers: Vec<Vec<Node<F>>> = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let parents = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| {
+                let ((l_hash, l_sum), (r_hash, r_sum)) = (pair[0], pair[1]);
+                (
+                    poseidon::hash(&[l_hash, l_sum, r_hash, r_sum]),
+                    l_sum + r_sum,
+                )
+            })
+            .collect();
+        layers.push(parents);
+    }
+    layers
+}
+
+fn prover(layers: &[Vec<Node<Fp>>], index: usize, (id, balance): (u64, u64)) -> MockProver<Fp> {
+    let siblings: Vec<_> = (0..DEPTH)
+        .map(|level| Value::known(layers[level][(index >> level) ^ 1]))
+        .collect();
+    let circuit = ReservesCircuit {
+        index: Value::known(Fp::from(index as u64)),
+        siblings: siblings.try_into().unwrap(),
+    };
+    let (root, total) = layers[DEPTH][0];
+    let instance = vec![root, total, Fp::from(id), Fp::from(balance)];
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, ReservesCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new()
+                    .with_advice(config.witness, "witness")
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_advice(config.decompose.bit, "bit")
+                    .with_advice(config.decompose.acc, "bits acc")
+                    .with_advice(config.uint.value, "value")
+                    .with_advice(config.uint.aux, "aux");
+                for (i, column) in config.poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in config.poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("input[{}]", i));
+                }
+                for (i, column) in config.poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                for (name, column) in ["cond", "a", "b", "out"].iter().zip(config.select.advice) {
+                    names = names.with_advice(column, *name);
+                }
+                for (i, column) in config.uint.limbs.iter().enumerate() {
+                    names = names.with_advice(*column, format!("limb[{}]", i));
+                }
+                names
+                    .with_selector(config.poseidon.q_absorb, "q_absorb")
+                    .with_selector(config.poseidon.q_full, "q_full")
+                    .with_selector(config.poseidon.q_partial, "q_partial")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+                    .with_selector(config.select.q_select, "q_select")
+                    .with_selector(config.decompose.q_bit, "q_bit")
+                    .with_selector(config.decompose.q_first, "q_bit_first")
+                    .with_selector(config.decompose.q_step, "q_bit_step")
+                    .with_selector(config.uint.q_decompose, "q_decompose")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let accounts: Vec<_> = (0..1 << DEPTH).map(|i| (1000 + i, 10 * i + 5)).collect();
+    let layers = build::<Fp>(&accounts);
+    let (_, total) = layers[DEPTH][0];
+    println!("total = {}", util::display_field(total));
+    for (index, account) in accounts.iter().enumerate() {
+        prover(&layers, index, *account).assert_satisfied();
+    }
+
+    // another balance for the user
+    let (id, balance) = accounts[5];
+    testing::assert_fails_permutation(&prover(&layers, 5, (id, balance + 1)));
+}
@@ -0,0 +1,307 @@
+//! n-queens circuit
+//!
+//! we are going to prove that we know a private placement of `N` queens on an `N x N`
+//! board where no two queens attack each other, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip)
+//!
+//! | instance      |
+//! |---------------|
+//! | H(position)   |
+//!
+//! where `position[i]` is the column of the queen on row `i`. the board is one row of
+//! boolean cells per row of the circuit, every row has exactly one queen:
+//!
+//! - `cell[j] * (1 - cell[j]) = 0`
+//! - `cell[0] + ... + cell[N-1] = 1`
+//! - `position = 0 * cell[0] + ... + (N-1) * cell[N-1]`
+//!
+//! columns and diagonals span rows, so they are counted with running sums. `col[j]` is
+//! the number of queens in column `j` so far, `diag[j]` the number on the diagonal going
+//! down-right into `(i, j)`, `anti[j]` the one going down-left into `(i, j)`:
+//!
+//! | row   | cell    | col               | diag                     | anti                     | position    |
+//! |-------|---------|-------------------|--------------------------|--------------------------|-------------|
+//! | 0     | cell[j] | cell[j]           | cell[j]                  | cell[j]                  | position[0] |
+//! | i     | cell[j] | col[j]' + cell[j] | diag[j - 1]' + cell[j]   | anti[j + 1]' + cell[j]   | position[i] |
+//!
+//! where `'` is the row above, and a diagonal starting on the left or right edge starts
+//! from 0. the counts only grow, so a queen attacks another one exactly when a count
+//! reaches 2, all of them are constrained to be boolean, and `col[j] = 1` on the last row.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::poseidon::{self, PoseidonChip, PoseidonConfig},
+    testing, util,
+};
+
+const N: usize = 8;
+
+#[derive(Debug, Clone)]
+struct QueensConfig {
+    cell: [Column<Advice>; N],
+    col: [Column<Advice>; N],
+    diag: [Column<Advice>; N],
+    anti: [Column<Advice>; N],
+    position: Column<Advice>,
+    q_board: Selector,
+    q_first: Selector,
+    q_next: Selector,
+    q_last: Selector,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct QueensCircuit<F> {
+    board: [[Value<F>; N]; N],
+}
+
+impl<F> Default for QueensCircuit<F> {
+    fn default() -> Self {
+        Self {
+            board: [(); N].map(|_| [(); N].map(|_| Value::unknown())),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for QueensCircuit<F> {
+    type Config = QueensConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> QueensConfig {
+        let cell = [(); N].map(|_| meta.advice_column());
+        let col = [(); N].map(|_| meta.advice_column());
+        let diag = [(); N].map(|_| meta.advice_column());
+        let anti = [(); N].map(|_| meta.advice_column());
+        let position = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_board = meta.selector();
+        let q_first = meta.selector();
+        let q_next = meta.selector();
+        let q_last = meta.selector();
+        meta.enable_equality(position);
+        meta.enable_equality(instance);
+
+        meta.create_gate("board", |meta| {
+            let q_board = meta.query_selector(q_board);
+            let one = Expression::Constant(F::one());
+            let cells = cell.map(|column| meta.query_advice(column, Rotation::cur()));
+            let position = meta.query_advice(position, Rotation::cur());
+
+            let mut constraints: Vec<_> = cells
+                .iter()
+                .map(|cell| q_board.clone() * cell.clone() * (one.clone() - cell.clone()))
+                .collect();
+            let (count, weighted) = cells.iter().enumerate().fold(
+                (
+                    Expression::Constant(F::zero()),
+                    Expression::Constant(F::zero()),
+                ),
+                |(count, weighted), (j, cell)| {
+                    (
+                        count + cell.clone(),
+                        weighted + Expression::Constant(F::from(j as u64)) * cell.clone(),
+                    )
+                },
+            );
+            constraints.push(q_board.clone() * (count - one));
+            constraints.push(q_board * (position - weighted));
+            constraints
+        });
+
+        meta.create_gate("attacks", |meta| {
+            let q_board = meta.query_selector(q_board);
+            let q_first = meta.query_selector(q_first);
+            let q_next = meta.query_selector(q_next);
+            let q_last = meta.query_selector(q_last);
+            let zero = Expression::Constant(F::zero());
+            let one = Expression::Constant(F::one());
+
+            let mut constraints = vec![];
+            for (j, cell) in cell.iter().enumerate() {
+                let cell = meta.query_advice(*cell, Rotation::cur());
+                let col_cur = meta.query_advice(col[j], Rotation::cur());
+                let diag_cur = meta.query_advice(diag[j], Rotation::cur());
+                let anti_cur = meta.query_advice(anti[j], Rotation::cur());
+                let col_prev = meta.query_advice(col[j], Rotation::prev());
+                let diag_prev = match j.checked_sub(1) {
+                    Some(left) => meta.query_advice(diag[left], Rotation::prev()),
+                    None => zero.clone(),
+                };
+                let anti_prev = match anti.get(j + 1) {
+                    Some(right) => meta.query_advice(*right, Rotation::prev()),
+                    None => zero.clone(),
+                };
+
+                for count in [&col_cur, &diag_cur, &anti_cur] {
+                    constraints
+                        .push(q_board.clone() * count.clone() * (one.clone() - count.clone()));
+                    constraints.push(q_first.clone() * (count.clone() - cell.clone()));
+                }
+                for (count, prev) in [
+                    (&col_cur, col_prev),
+                    (&diag_cur, diag_prev),
+                    (&anti_cur, anti_prev),
+                ] {
+                    constraints.push(q_next.clone() * (count.clone() - prev - cell.clone()));
+                }
+                constraints.push(q_last.clone() * (col_cur - one.clone()));
+            }
+            constraints
+        });
+
+        QueensConfig {
+            cell,
+            col,
+            diag,
+            anti,
+            position,
+            q_board,
+            q_first,
+            q_next,
+            q_last,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: QueensConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let positions = layouter.assign_region(
+            || "board",
+            |mut region| {
+                let zero = Value::known(F::zero());
+                let (mut col, mut diag, mut anti) = ([zero; N], [zero; N], [zero; N]);
+                let mut positions = vec![];
+                for (offset, row) in self.board.iter().enumerate() {
+                    config.q_board.enable(&mut region, offset)?;
+                    if offset == 0 {
+                        config.q_first.enable(&mut region, offset)?;
+                    } else {
+                        config.q_next.enable(&mut region, offset)?;
+                    }
+                    if offset == N - 1 {
+                        config.q_last.enable(&mut region, offset)?;
+                    }
+
+                    let (diag_prev, anti_prev) = (diag, anti);
+                    let mut position = zero;
+                    for (j, cell) in row.iter().enumerate() {
+                        col[j] = col[j] + *cell;
+                        diag[j] = j.checked_sub(1).map_or(zero, |left| diag_prev[left]) + *cell;
+                        anti[j] = anti_prev.get(j + 1).copied().unwrap_or(zero) + *cell;
+                        position = position + cell.map(|cell| cell * F::from(j as u64));
+
+                        region.assign_advice(|| "cell", config.cell[j], offset, || *cell)?;
+                        region.assign_advice(|| "col", config.col[j], offset, || col[j])?;
+                        region.assign_advice(|| "diag", config.diag[j], offset, || diag[j])?;
+                        region.assign_advice(|| "anti", config.anti[j], offset, || anti[j])?;
+                    }
+                    positions.push(region.assign_advice(
+                        || "position",
+                        config.position,
+                        offset,
+                        || position,
+                    )?);
+                }
+                Ok(positions)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(position)"), &positions)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+/// the columns of the queens on each row, one per row for a real placement
+fn prover(queens: [&[usize]; N], position: [u64; N]) -> MockProver<Fp> {
+    let circuit = QueensCircuit {
+        board: queens.map(|row| {
+            let mut cells = [Value::known(Fp::zero()); N];
+            for j in row {
+                cells[*j] = Value::known(Fp::one());
+            }
+            cells
+        }),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = vec![poseidon::hash(&position.map(Fp::from))];
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, QueensCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new().with_advice(config.position, "position");
+                for (j, cell) in config.cell.iter().enumerate() {
+                    names = names
+                        .with_advice(*cell, format!("cell[{}]", j))
+                        .with_advice(config.col[j], format!("col[{}]", j))
+                        .with_advice(config.diag[j], format!("diag[{}]", j))
+                        .with_advice(config.anti[j], format!("anti[{}]", j));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_board, "q_board")
+                    .with_selector(config.q_first, "q_first")
+                    .with_selector(config.q_next, "q_next")
+                    .with_selector(config.q_last, "q_last")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let position = [0, 4, 7, 5, 2, 6, 1, 3];
+    let queens = [&[0][..], &[4], &[7], &[5], &[2], &[6], &[1], &[3]];
+    prover(queens, position).assert_satisfied();
+
+    // another placement than the committed one
+    let mirrored = [&[7][..], &[3], &[0], &[2], &[5], &[1], &[6], &[4]];
+    prover(mirrored, position.map(|j| 7 - j)).assert_satisfied();
+    testing::assert_fails_permutation(&prover(mirrored, position));
+
+    // the main diagonal, every row and column has one queen, diag[1] is 2 on row 1
+    let diagonal = [&[0][..], &[1], &[2], &[3], &[4], &[5], &[6], &[7]];
+    testing::assert_fails_gate(&prover(diagonal, [0, 1, 2, 3, 4, 5, 6, 7]), "attacks", 1);
+
+    // the last queen in the column of the first, col[0] is 2 on the last row
+    let column = [&[0][..], &[4], &[7], &[5], &[2], &[6], &[1], &[0]];
+    testing::assert_fails_gate(&prover(column, [0, 4, 7, 5, 2, 6, 1, 0]), "attacks", N - 1);
+
+    // two queens on row 3 and none on row 7
+    let crowded = [&[0][..], &[4], &[7], &[5, 3], &[2], &[6], &[1], &[]];
+    let prover = prover(crowded, [0, 4, 7, 8, 2, 6, 1, 0]);
+    testing::assert_fails_gate(&prover, "board", 3);
+    testing::assert_fails_gate(&prover, "board", 7);
+}
@@ -0,0 +1,122 @@
+//! linear recurrence circuit
+//!
+//! we are going to prove the n-th term of fibonacci, lucas and pell numbers with the
+//! same chip, parameterized by its coefficients and initial values:
+//!
+//! - fibonacci: `F(i) = F(i-1) + F(i-2)`, `F(0) = 0, F(1) = 1`
+//! - lucas: `L(i) = L(i-1) + L(i-2)`, `L(0) = 2, L(1) = 1`
+//! - pell: `P(i) = 2 * P(i-1) + P(i-2)`, `P(0) = 0, P(1) = 1`
+//!
+//! the instance column is laid out as `[x(0), x(1), x(n)]`.
+//! `lucas` and `pell` pin the initial values instead, each with an instance layout of its own.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::recurrence::{LinearRecurrenceChip, LinearRecurrenceConfig},
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct RecurrenceConfig {
+    recurrence: LinearRecurrenceConfig<2>,
+    instance: Column<Instance>,
+}
+
+/// `x(N)` of the second order recurrence with coefficients `coeffs`
+struct RecurrenceCircuit<F, const N: usize> {
+    coeffs: [F; 2],
+    initial: [Value<F>; 2],
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for RecurrenceCircuit<F, N> {
+    type Config = RecurrenceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        // the coefficients are fixed columns, so they stay
+        Self {
+            coeffs: self.coeffs,
+            initial: [Value::unknown(); 2],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RecurrenceConfig {
+        let x = meta.advice_column();
+        let coeffs = [meta.fixed_column(), meta.fixed_column()];
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        RecurrenceConfig {
+            recurrence: LinearRecurrenceChip::configure(meta, x, coeffs),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: RecurrenceConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = LinearRecurrenceChip::<F, 2>::construct(config.recurrence);
+        let terms = chip.assign(
+            layouter.namespace(|| "terms"),
+            self.coeffs,
+            self.initial,
+            N + 1,
+        )?;
+        layouter.constrain_instance(terms[0].cell(), config.instance, 0)?;
+        layouter.constrain_instance(terms[1].cell(), config.instance, 1)?;
+        layouter.constrain_instance(terms[N].cell(), config.instance, 2)
+    }
+}
+
+/// check `x(10) = expected` with the MockProver
+fn check(name: &str, coeffs: [u64; 2], initial: [u64; 2], expected: u64) {
+    let circuit = RecurrenceCircuit::<Fp, 10> {
+        coeffs: coeffs.map(Fp::from),
+        initial: initial.map(|x| Value::known(Fp::from(x))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instances = |result: u64| {
+        vec![vec![
+            Fp::from(initial[0]),
+            Fp::from(initial[1]),
+            Fp::from(result),
+        ]]
+    };
+
+    MockProver::run(k, &circuit, instances(expected))
+        .unwrap()
+        .assert_satisfied();
+    let prover_failure = MockProver::run(k, &circuit, instances(expected + 1)).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+    println!("{}(10) = {}", name, expected);
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, RecurrenceCircuit<Fp, 10>>(|config| {
+                let [c1, c2] = config.recurrence.coeffs;
+                ColumnNames::new()
+                    .with_advice(config.recurrence.x, "x")
+                    .with_fixed(c1, "c1")
+                    .with_fixed(c2, "c2")
+                    .with_selector(config.recurrence.q_step, "q_step")
+            })
+        );
+    }
+
+    check("fib", [1, 1], [0, 1], 55);
+    check("lucas", [1, 1], [2, 1], 123);
+    check("pell", [2, 1], [0, 1], 2378);
+}
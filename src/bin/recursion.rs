@@ -0,0 +1,92 @@
+//! recursive accumulation of fibonacci proofs
+//!
+//! level 0 proves fib(1), every following level i proves "the level i - 1 proof
+//! (and its accumulator) is valid AND fib(i + 1) holds" by aggregating the previous
+//! proof together with one more fibonacci proof. every level verifies exactly two
+//! proofs, only the instances passed on grow, by one fibonacci statement a level.
+//!
+//! the instances of a level are its accumulator, then the ones carried by the previous
+//! level and the `n_0, n_1, n, fib(n)` of its step, so the last proof states every fib(n)
+//! along the way.
+//!
+//! this accumulates, but is not full IVC: the previous level's verifying key is baked
+//! into each level's circuit instead of being an input, so the final verifier has to
+//! know the depth.
+//!
+//! usage: `recursion [depth]` (default 2), needs the `aggregation` feature
+
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr},
+    poly::kzg::commitment::ParamsKZG,
+};
+use learn_halo2::{
+    aggregation::{self, Snark},
+    fib::PublicInputs,
+    params,
+};
+
+/// `depth` levels of accumulation on top of the proof of fib(1)
+fn accumulate(params: &ParamsKZG<Bn256>, depth: u64) -> Snark {
+    let fib_params = aggregation::inner_params(params);
+    let mut accumulator = aggregation::prove_fib(&fib_params, 1).unwrap();
+    for level in 1..=depth {
+        let step = aggregation::prove_fib(&fib_params, level + 1).unwrap();
+        accumulator = aggregation::aggregate(params, &[accumulator, step]).unwrap();
+        println!(
+            "level {}: proof size {} bytes",
+            level,
+            accumulator.proof.len()
+        );
+    }
+    accumulator
+}
+
+/// the instances carried after `depth` levels, the fibonacci statements of every step
+fn statements(depth: u64) -> Vec<Fr> {
+    let (mut l, mut r) = (Fr::zero(), Fr::one());
+    let mut statements = vec![];
+    for n in 1..=depth + 1 {
+        (l, r) = (r, l + r);
+        let inputs = PublicInputs::new().with_n(Fr::from(n)).with_result(l);
+        statements.extend(inputs.to_instance_columns().remove(0));
+    }
+    statements
+}
+
+fn main() {
+    let depth: u64 = std::env::args()
+        .nth(1)
+        .map(|depth| depth.parse().expect("depth should be a number"))
+        .unwrap_or(2);
+
+    let params = params::load_or_setup(aggregation::AGG_K).unwrap();
+    let accumulator = accumulate(&params, depth);
+
+    assert!(aggregation::verify(&params, &accumulator));
+    assert_eq!(accumulator.carried_instances(), statements(depth));
+    println!("verified {} levels of accumulation", depth);
+}
+
+#[test]
+fn carried_statements() {
+    let statement = |n: u64, result: u64| [Fr::zero(), Fr::one(), Fr::from(n), Fr::from(result)];
+    assert_eq!(statements(0), statement(1, 1));
+    assert_eq!(
+        statements(2),
+        [statement(1, 1), statement(2, 1), statement(3, 2)].concat()
+    );
+}
+
+#[test]
+#[ignore = "needs k = 22 params, takes minutes"]
+fn two_steps() {
+    let params = params::load_or_setup(aggregation::AGG_K).unwrap();
+    let accumulator = accumulate(&params, 2);
+    assert!(aggregation::verify(&params, &accumulator));
+    assert_eq!(accumulator.carried_instances(), statements(2));
+
+    // the last level claimed for another fib(3)
+    let mut wrong = accumulator;
+    *wrong.instances[0].last_mut().unwrap() += Fr::one();
+    assert!(!aggregation::verify(&params, &wrong));
+}
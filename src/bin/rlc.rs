@@ -0,0 +1,186 @@
+//! random linear combination circuit
+//!
+//! we are going to prove that a public string of `LEN` bytes is a palindrome, by comparing
+//! random linear combinations of its bytes in both directions
+//!
+//! | instance     |
+//! |--------------|
+//! | byte[0]      |
+//! | ...          |
+//! | byte[LEN-1]  |
+//!
+//! the rlc of `b[0], ..., b[n-1]` is `b[0] * r^(n-1) + ... + b[n-1]`, a polynomial in `r`
+//! with the bytes as coefficients. two different strings give two different polynomials,
+//! which agree on at most `n - 1` points, so for a random `r` the rlcs are equal when the
+//! strings are, and different otherwise with high probability. zkevm circuits use them to
+//! compare byte strings, or whole rows of tables, as single field elements.
+//!
+//! `r` must not be known when the bytes are committed to, or the prover could pick bytes
+//! for it. halo2 does this with phases: the `byte` column is in the first phase, `r` is a
+//! `Challenge` drawn after the first phase is committed, and the rlcs are in second phase
+//! columns, which can only be assigned once `r` is known:
+//!
+//! | row | byte        | fwd              | bwd                  | q_rlc |
+//! |-----|-------------|------------------|----------------------|-------|
+//! | 0   | byte[0]     | 0                | rlc(byte[LEN-1..=0]) | 1     |
+//! | i   | byte[i]     | rlc(byte[..i])   | rlc(byte[LEN-1..=i]) | 1     |
+//! | LEN |             | rlc(byte)        | 0                    | 0     |
+//!
+//! - `q_rlc * (fwd' - fwd * r - byte) = 0`
+//! - `q_rlc * (bwd - bwd' * r - byte) = 0`
+//!
+//! `fwd` reads the string from the top, `bwd` from the bottom, and the string is a
+//! palindrome when `fwd[LEN] = bwd[0]`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    plonk::{
+        Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, Fixed, Instance,
+        SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+use std::marker::PhantomData;
+
+const LEN: usize = 9;
+
+#[derive(Debug, Clone)]
+struct RlcConfig {
+    byte: Column<Advice>,
+    fwd: Column<Advice>,
+    bwd: Column<Advice>,
+    q_rlc: Selector,
+    r: Challenge,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct PalindromeCircuit<F>(PhantomData<F>);
+
+impl<F: FieldExt> Circuit<F> for PalindromeCircuit<F> {
+    type Config = RlcConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RlcConfig {
+        let byte = meta.advice_column_in(FirstPhase);
+        let r = meta.challenge_usable_after(FirstPhase);
+        let fwd = meta.advice_column_in(SecondPhase);
+        let bwd = meta.advice_column_in(SecondPhase);
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_rlc = meta.selector();
+        meta.enable_equality(byte);
+        meta.enable_equality(fwd);
+        meta.enable_equality(bwd);
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("rlc", |meta| {
+            let q_rlc = meta.query_selector(q_rlc);
+            let r = meta.query_challenge(r);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let fwd = meta.query_advice(fwd, Rotation::cur());
+            let fwd_next = meta.query_advice(fwd, Rotation::next());
+            let bwd = meta.query_advice(bwd, Rotation::cur());
+            let bwd_next = meta.query_advice(bwd, Rotation::next());
+            vec![
+                q_rlc.clone() * (fwd_next - fwd * r.clone() - byte.clone()),
+                q_rlc * (bwd - bwd_next * r - byte),
+            ]
+        });
+
+        RlcConfig {
+            byte,
+            fwd,
+            bwd,
+            q_rlc,
+            r,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: RlcConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        // unknown until the first phase is committed
+        let r = layouter.get_challenge(config.r);
+
+        layouter.assign_region(
+            || "palindrome",
+            |mut region| {
+                let mut bytes = Vec::with_capacity(LEN);
+                for row in 0..LEN {
+                    config.q_rlc.enable(&mut region, row)?;
+                    let byte = region.assign_advice_from_instance(
+                        || "byte",
+                        config.instance,
+                        row,
+                        config.byte,
+                        row,
+                    )?;
+                    bytes.push(byte.value().copied());
+                }
+
+                let mut fwd =
+                    region.assign_advice_from_constant(|| "fwd", config.fwd, 0, F::zero())?;
+                for (row, byte) in bytes.iter().enumerate() {
+                    let value = fwd.value().copied() * r + *byte;
+                    fwd = region.assign_advice(|| "fwd", config.fwd, row + 1, || value)?;
+                }
+                let mut bwd =
+                    region.assign_advice_from_constant(|| "bwd", config.bwd, LEN, F::zero())?;
+                for (row, byte) in bytes.iter().enumerate().rev() {
+                    let value = bwd.value().copied() * r + *byte;
+                    bwd = region.assign_advice(|| "bwd", config.bwd, row, || value)?;
+                }
+
+                region.constrain_equal(fwd.cell(), bwd.cell())
+            },
+        )
+    }
+}
+
+fn prover(message: &[u8; LEN]) -> MockProver<Fp> {
+    let circuit = PalindromeCircuit::default();
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = message
+        .iter()
+        .map(|byte| Fp::from(u64::from(*byte)))
+        .collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, PalindromeCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.byte, "byte")
+                    .with_advice(config.fwd, "fwd")
+                    .with_advice(config.bwd, "bwd")
+                    .with_selector(config.q_rlc, "q_rlc")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    prover(b"rotavator").assert_satisfied();
+
+    // not a palindrome, the rlcs differ
+    testing::assert_fails_permutation(&prover(b"rotavated"));
+
+    // the same bytes, two of them swapped
+    testing::assert_fails_permutation(&prover(b"rotavatro"));
+}
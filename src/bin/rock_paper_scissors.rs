@@ -0,0 +1,248 @@
+//! rock paper scissors circuit
+//!
+//! we are going to prove the winner of a game of rock paper scissors between two players,
+//! who committed to their moves with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip) before revealing them
+//!
+//! | instance              |
+//! |-----------------------|
+//! | H(salt[0], move[0])   |
+//! | H(salt[1], move[1])   |
+//! | winner                |
+//!
+//! the moves are opened to the prover, say a referee, and stay private. they are `ROCK`,
+//! `PAPER` and `SCISSORS`, each beating the one before it, so the winner is
+//! `move[0] - move[1] mod 3`, `DRAW`, `FIRST` or `SECOND`. the 9 games are in fixed table
+//! columns, with a tag and a first row of 0s:
+//!
+//! | row | salt[0] | salt[1] | move[0] | move[1] | winner | q_game |
+//! |-----|---------|---------|---------|---------|--------|--------|
+//! | 0   | salt[0] | salt[1] | move[0] | move[1] | winner | 1      |
+//!
+//! - `(q_game, q_game * move[0], q_game * move[1], q_game * winner)` in
+//!   `(tag, first, second, winner)`
+//!
+//! the lookup also keeps the moves in `0..3`, the salts hide them in the commitments.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector, TableColumn},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::poseidon::{self, PoseidonChip, PoseidonConfig},
+    testing, util,
+};
+
+const ROCK: u64 = 0;
+const PAPER: u64 = 1;
+const SCISSORS: u64 = 2;
+
+const DRAW: u64 = 0;
+const FIRST: u64 = 1;
+const SECOND: u64 = 2;
+
+/// the winner of a game
+fn winner(first: u64, second: u64) -> u64 {
+    (3 + first - second) % 3
+}
+
+#[derive(Debug, Clone)]
+struct GameConfig {
+    salt: [Column<Advice>; 2],
+    moves: [Column<Advice>; 2],
+    winner: Column<Advice>,
+    q_game: Selector,
+    /// tag, first move, second move and winner of the games
+    table: [TableColumn; 4],
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct GameCircuit<F> {
+    salt: [Value<F>; 2],
+    moves: [Value<F>; 2],
+}
+
+impl<F> Default for GameCircuit<F> {
+    fn default() -> Self {
+        Self {
+            salt: [(); 2].map(|_| Value::unknown()),
+            moves: [(); 2].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for GameCircuit<F> {
+    type Config = GameConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> GameConfig {
+        let salt = [(); 2].map(|_| meta.advice_column());
+        let moves = [(); 2].map(|_| meta.advice_column());
+        let winner = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_game = meta.complex_selector();
+        let table = [(); 4].map(|_| meta.lookup_table_column());
+        for column in salt.into_iter().chain(moves).chain([winner]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.lookup(|meta| {
+            let q_game = meta.query_selector(q_game);
+            let [first, second] = moves.map(|column| meta.query_advice(column, Rotation::cur()));
+            let winner = meta.query_advice(winner, Rotation::cur());
+            let [tag, first_table, second_table, winner_table] = table;
+            vec![
+                (q_game.clone(), tag),
+                (q_game.clone() * first, first_table),
+                (q_game.clone() * second, second_table),
+                (q_game * winner, winner_table),
+            ]
+        });
+
+        GameConfig {
+            salt,
+            moves,
+            winner,
+            q_game,
+            table,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: GameConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "games",
+            |mut table| {
+                let games = [ROCK, PAPER, SCISSORS].into_iter().flat_map(|first| {
+                    [ROCK, PAPER, SCISSORS].map(|second| [1, first, second, winner(first, second)])
+                });
+                for (row, game) in [[0; 4]].into_iter().chain(games).enumerate() {
+                    for (column, value) in config.table.iter().zip(game) {
+                        table.assign_cell(
+                            || "game",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let openings = layouter.assign_region(
+            || "game",
+            |mut region| {
+                config.q_game.enable(&mut region, 0)?;
+                region.assign_advice_from_instance(
+                    || "winner",
+                    config.instance,
+                    2,
+                    config.winner,
+                    0,
+                )?;
+                let mut openings = vec![];
+                for ((salt_column, move_column), (salt, play)) in config
+                    .salt
+                    .iter()
+                    .zip(&config.moves)
+                    .zip(self.salt.iter().zip(&self.moves))
+                {
+                    let salt = region.assign_advice(|| "salt", *salt_column, 0, || *salt)?;
+                    let play = region.assign_advice(|| "move", *move_column, 0, || *play)?;
+                    openings.push([salt, play]);
+                }
+                Ok(openings)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        for (player, opening) in openings.iter().enumerate() {
+            let commitment = poseidon.hash(layouter.namespace(|| "H(salt, move)"), opening)?;
+            layouter.constrain_instance(commitment.cell(), config.instance, player)?;
+        }
+        Ok(())
+    }
+}
+
+fn commit(salt: u64, play: u64) -> Fp {
+    poseidon::hash(&[Fp::from(salt), Fp::from(play)])
+}
+
+fn prover(salt: [u64; 2], moves: [u64; 2], commitments: [Fp; 2], winner: u64) -> MockProver<Fp> {
+    let circuit = GameCircuit {
+        salt: salt.map(|salt| Value::known(Fp::from(salt))),
+        moves: moves.map(|play| Value::known(Fp::from(play))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = commitments.into_iter().chain([Fp::from(winner)]).collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, GameCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new().with_advice(config.winner, "winner");
+                for (i, (salt, play)) in config.salt.iter().zip(&config.moves).enumerate() {
+                    names = names
+                        .with_advice(*salt, format!("salt[{}]", i))
+                        .with_advice(*play, format!("move[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_game, "q_game")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let salt = [0x5eed, 0xfeed];
+    let moves = [PAPER, ROCK];
+    let commitments = [commit(salt[0], moves[0]), commit(salt[1], moves[1])];
+    assert_eq!(winner(PAPER, ROCK), FIRST);
+    prover(salt, moves, commitments, FIRST).assert_satisfied();
+    // the other way around
+    let swapped = [commitments[1], commitments[0]];
+    prover([salt[1], salt[0]], [ROCK, PAPER], swapped, SECOND).assert_satisfied();
+    // scissors against scissors
+    let scissors = [commit(salt[0], SCISSORS), commit(salt[1], SCISSORS)];
+    prover(salt, [SCISSORS; 2], scissors, DRAW).assert_satisfied();
+
+    // the second player claimed to win
+    testing::assert_fails_lookup(&prover(salt, moves, commitments, SECOND), 0);
+
+    // the second player changes their mind after seeing paper, scissors wasn't committed
+    testing::assert_fails_permutation(&prover(salt, [PAPER, SCISSORS], commitments, SECOND));
+
+    // a fourth move, which would beat paper
+    let lizard = [commitments[0], commit(salt[1], 3)];
+    testing::assert_fails_lookup(&prover(salt, [PAPER, 3], lizard, SECOND), 0);
+}
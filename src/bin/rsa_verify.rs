@@ -0,0 +1,186 @@
+//! rsa signature circuit
+//!
+//! we are going to prove that we know an rsa signature `sig` of a message under a public
+//! key `(N, e)`, that is `sig^e = H(m) mod N` for `e = 65537`, without showing the signature
+//!
+//! | instance  |
+//! |-----------|
+//! | H(m)[0]   |
+//! | ...       |
+//! | H(m)[3]   |
+//! | N[0]      |
+//! | ...       |
+//! | N[3]      |
+//!
+//! both numbers are 4 little endian 64 bit limbs, and every product goes through `mul_mod`
+//! of the [`BigIntChip`](learn_halo2::gadgets::bigint::BigIntChip). `e` is fixed, so its
+//! bits decide the layout of square and multiply instead of being witnessed: 16 squarings
+//! for `e = 2^16 + 1`, then one multiplication by `sig`.
+//!
+//! the chip stops at 256 bits, so the key is a toy, the product of two 128 bit primes. the
+//! signature was made outside the circuit with the private exponent, and `H(m)` is the
+//! sha256 of the message, which is already below `N`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::bigint::{BigIntChip, BigIntConfig, LIMBS},
+    testing, util,
+};
+use std::marker::PhantomData;
+
+/// the public exponent
+const E: u64 = 65537;
+
+/// the modulus, `p * q` for two 128 bit primes
+const N: [u64; LIMBS] = [
+    0xc5cd_5efa_6c24_1ced,
+    0x792e_e684_846e_66d8,
+    0x2a28_90e1_d84a_c84c,
+    0xb541_62a0_dbde_ecc5,
+];
+
+/// `sha256("hello halo2")`
+const DIGEST: [u64; LIMBS] = [
+    0xa94a_efb8_0e44_3a34,
+    0x47d7_1c49_38e0_9990,
+    0xecef_4230_e015_6693,
+    0xa156_4cac_bc82_24d5,
+];
+
+/// `DIGEST^d mod N`
+const SIGNATURE: [u64; LIMBS] = [
+    0x5074_a90e_1822_e248,
+    0x3251_efd2_c92f_41d4,
+    0xf182_b19a_2b87_ab9e,
+    0x4c62_108e_5e3c_8b3b,
+];
+
+#[derive(Debug, Clone)]
+struct RsaConfig {
+    bigint: BigIntConfig,
+    instance: Column<Instance>,
+}
+
+struct RsaCircuit<F> {
+    signature: Value<[u64; LIMBS]>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for RsaCircuit<F> {
+    type Config = RsaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            signature: Value::unknown(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> RsaConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        RsaConfig {
+            bigint: BigIntChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: RsaConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = BigIntChip::construct(config.bigint);
+        chip.load_table(layouter.namespace(|| "u8"))?;
+
+        let signature = chip.assign(layouter.namespace(|| "sig"), self.signature)?;
+        let modulus = chip.assign(layouter.namespace(|| "N"), Value::known(N))?;
+        for (row, limb) in modulus.limbs.iter().enumerate() {
+            layouter.constrain_instance(limb.cell(), config.instance, LIMBS + row)?;
+        }
+
+        // square and multiply over the bits of e below the top one
+        let mut acc = signature.clone();
+        for bit in (0..63 - E.leading_zeros() as usize).rev() {
+            acc = chip.mul_mod(
+                layouter.namespace(|| format!("square {}", bit)),
+                &acc,
+                &acc,
+                &modulus,
+            )?;
+            if (E >> bit) & 1 == 1 {
+                acc = chip.mul_mod(
+                    layouter.namespace(|| format!("multiply {}", bit)),
+                    &acc,
+                    &signature,
+                    &modulus,
+                )?;
+            }
+        }
+
+        for (row, limb) in acc.limbs.iter().enumerate() {
+            layouter.constrain_instance(limb.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn prover(signature: [u64; LIMBS], digest: [u64; LIMBS]) -> MockProver<Fp> {
+    let circuit = RsaCircuit {
+        signature: Value::known(signature),
+        _marker: PhantomData,
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = digest
+        .iter()
+        .chain(&N)
+        .map(|limb| Fp::from(*limb))
+        .collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, RsaCircuit<Fp>>(|config| {
+                let bigint = config.bigint;
+                let mut names = ColumnNames::new()
+                    .with_advice(bigint.uint.value, "value")
+                    .with_advice(bigint.uint.aux, "aux");
+                for (i, column) in bigint.limbs.iter().enumerate() {
+                    names = names.with_advice(*column, format!("limb[{}]", i));
+                }
+                for (i, column) in bigint.uint.limbs.iter().enumerate() {
+                    names = names.with_advice(*column, format!("byte[{}]", i));
+                }
+                names
+                    .with_selector(bigint.q_add, "q_add")
+                    .with_selector(bigint.q_add_one, "q_add_one")
+                    .with_selector(bigint.q_mul_add, "q_mul_add")
+                    .with_selector(bigint.uint.q_decompose, "q_decompose")
+                    .with_selector(bigint.uint.q_add, "q_uint_add")
+                    .with_selector(bigint.uint.q_mul, "q_uint_mul")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    prover(SIGNATURE, DIGEST).assert_satisfied();
+
+    // the signature of another message
+    let mut other = DIGEST;
+    other[0] += 1;
+    testing::assert_fails_permutation(&prover(SIGNATURE, other));
+
+    // a forged signature
+    let mut forged = SIGNATURE;
+    forged[0] += 1;
+    testing::assert_fails_permutation(&prover(forged, DIGEST));
+}
@@ -0,0 +1,240 @@
+//! elementary cellular automaton circuit
+//!
+//! we are going to prove that `T` steps of the rule 110 cellular automaton take a private
+//! tape of `N` cells to a public one, with the cells outside of the tape 0
+//!
+//! | instance      |
+//! |---------------|
+//! | final[0]      |
+//! | ...           |
+//! | final[N-1]    |
+//!
+//! a cell is updated from itself and its two neighbors, the bit `4 * left + 2 * cell +
+//! right` of the rule number is the next cell. the 8 patterns of the rule are in fixed
+//! table columns, with a tag and a first row of 0s, and every generation is a row:
+//!
+//! | row | cell[0]     | ...  | cell[N-1]     | q_step |
+//! |-----|-------------|------|---------------|--------|
+//! | 0   | tape[0]     | ...  | tape[N-1]     | 1      |
+//! | t   | ...         | ...  | ...           | 1      |
+//! | T   | final[0]    | ...  | final[N-1]    | 0      |
+//!
+//! - `(q_step, q_step * cell[j-1], q_step * cell[j], q_step * cell[j+1], q_step * cell[j]')`
+//!   in `(tag, left, center, right, next)` for every `j`
+//!
+//! the table only has bits, so the lookups also keep the cells boolean.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+
+const N: usize = 16;
+const T: usize = 8;
+const RULE: u8 = 110;
+
+/// the next generation of `tape` under `rule`
+fn step(rule: u8, tape: &[u64; N]) -> [u64; N] {
+    std::array::from_fn(|j| {
+        let left = if j == 0 { 0 } else { tape[j - 1] };
+        let right = tape.get(j + 1).copied().unwrap_or(0);
+        let pattern = 4 * left + 2 * tape[j] + right;
+        u64::from(rule >> pattern & 1)
+    })
+}
+
+#[derive(Debug, Clone)]
+struct AutomatonConfig {
+    cell: [Column<Advice>; N],
+    q_step: Selector,
+    /// tag, left, center, right and next of the patterns
+    table: [TableColumn; 5],
+    instance: Column<Instance>,
+}
+
+struct AutomatonCircuit<F> {
+    tape: [Value<F>; N],
+}
+
+impl<F> Default for AutomatonCircuit<F> {
+    fn default() -> Self {
+        Self {
+            tape: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for AutomatonCircuit<F> {
+    type Config = AutomatonConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> AutomatonConfig {
+        let cell = [(); N].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let q_step = meta.complex_selector();
+        let table = [(); 5].map(|_| meta.lookup_table_column());
+        for column in cell {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        for j in 0..N {
+            meta.lookup(|meta| {
+                let q_step = meta.query_selector(q_step);
+                let zero = Expression::Constant(F::zero());
+                let left = match j.checked_sub(1) {
+                    Some(left) => meta.query_advice(cell[left], Rotation::cur()),
+                    None => zero.clone(),
+                };
+                let right = match cell.get(j + 1) {
+                    Some(right) => meta.query_advice(*right, Rotation::cur()),
+                    None => zero,
+                };
+                let center = meta.query_advice(cell[j], Rotation::cur());
+                let next = meta.query_advice(cell[j], Rotation::next());
+                let [tag, left_table, center_table, right_table, next_table] = table;
+                vec![
+                    (q_step.clone(), tag),
+                    (q_step.clone() * left, left_table),
+                    (q_step.clone() * center, center_table),
+                    (q_step.clone() * right, right_table),
+                    (q_step * next, next_table),
+                ]
+            });
+        }
+
+        AutomatonConfig {
+            cell,
+            q_step,
+            table,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: AutomatonConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("rule {}", RULE),
+            |mut table| {
+                let patterns = (0..8).map(|pattern| {
+                    let bit = |i: u64| pattern >> i & 1;
+                    [1, bit(2), bit(1), bit(0), u64::from(RULE >> pattern & 1)]
+                });
+                for (row, pattern) in [[0; 5]].into_iter().chain(patterns).enumerate() {
+                    for (column, value) in config.table.iter().zip(pattern) {
+                        table.assign_cell(
+                            || "pattern",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let tapes = self
+            .tape
+            .iter()
+            .copied()
+            .collect::<Value<Vec<_>>>()
+            .map(|tape| {
+                let mut tape: [u64; N] = std::array::from_fn(|j| tape[j].get_lower_128() as u64);
+                let mut tapes = vec![tape];
+                for _ in 0..T {
+                    tape = step(RULE, &tape);
+                    tapes.push(tape);
+                }
+                tapes
+            });
+
+        layouter.assign_region(
+            || "evolution",
+            |mut region| {
+                for (j, (column, cell)) in config.cell.iter().zip(&self.tape).enumerate() {
+                    region.assign_advice(|| "tape", *column, 0, || *cell)?;
+                    region.assign_advice_from_instance(
+                        || "final",
+                        config.instance,
+                        j,
+                        *column,
+                        T,
+                    )?;
+                }
+                for t in 0..T {
+                    config.q_step.enable(&mut region, t)?;
+                }
+                for t in 1..T {
+                    for (j, column) in config.cell.iter().enumerate() {
+                        let cell = tapes.as_ref().map(|tapes| F::from(tapes[t][j]));
+                        region.assign_advice(|| "cell", *column, t, || cell)?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prover(tape: [u64; N], last: [u64; N]) -> MockProver<Fp> {
+    let circuit = AutomatonCircuit {
+        tape: tape.map(|cell| Value::known(Fp::from(cell))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![last.map(Fp::from).to_vec()]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, AutomatonCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new();
+                for (j, column) in config.cell.iter().enumerate() {
+                    names = names.with_advice(*column, format!("cell[{}]", j));
+                }
+                names
+                    .with_selector(config.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let run = |rule, tape| (0..T).fold(tape, |tape, _| step(rule, &tape));
+
+    // one live cell on the right, rule 110 grows to the left
+    let mut tape = [0; N];
+    tape[N - 1] = 1;
+    let last = run(RULE, tape);
+    assert_eq!(last, [0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 1]);
+    prover(tape, last).assert_satisfied();
+
+    // the last generation of rule 30, the witness follows rule 110 up to the last step
+    testing::assert_fails_lookup(&prover(tape, run(30, tape)), T - 1);
+
+    // a cell of 2 isn't a bit
+    let mut two = tape;
+    two[0] = 2;
+    testing::assert_fails_lookup(&prover(two, last), 0);
+}
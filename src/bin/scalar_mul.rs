@@ -0,0 +1,171 @@
+//! secp256k1 scalar multiplication circuit
+//!
+//! we are going to prove that we know the discrete log `k` of a public point `P = k * G`,
+//! the way a private key is tied to its public key, with the
+//! [`EccChip`](learn_halo2::gadgets::ecc::EccChip)
+//!
+//! | instance |
+//! |----------|
+//! | P.x      |
+//! | P.y      |
+//!
+//! the scalar is private and split into `SCALAR_BITS` bits by the
+//! [`DecomposeChip`](learn_halo2::gadgets::bits::DecomposeChip), which drive the double
+//! and add of `scalar_mul`. `G` is the secp256k1 generator, fixed in the circuit.
+//!
+//! the coordinates of secp256k1 points are in its base field, so this circuit is over the
+//! secp256k1 `Fp` whatever the `curve-*` feature, and is only run by the `MockProver`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::{
+        group::{prime::PrimeCurveAffine, Curve},
+        secp256k1::{Fp, Fq, Secp256k1Affine},
+    },
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    gadgets::{
+        bits::{DecomposeChip, DecomposeConfig},
+        ecc::{EccChip, EccConfig, EccPoint},
+    },
+    testing, util,
+};
+
+const SCALAR_BITS: usize = 128;
+
+#[derive(Debug, Clone)]
+struct ScalarMulConfig {
+    ecc: EccConfig,
+    decompose: DecomposeConfig,
+    scalar: Column<Advice>,
+    constant: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+struct ScalarMulCircuit<F> {
+    g: (F, F),
+    k: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for ScalarMulCircuit<F> {
+    type Config = ScalarMulConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            g: self.g,
+            k: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ScalarMulConfig {
+        let scalar = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(scalar);
+        meta.enable_constant(constant);
+        meta.enable_equality(instance);
+
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        ScalarMulConfig {
+            ecc: EccChip::configure(meta),
+            decompose: DecomposeChip::configure(meta, bit, acc),
+            scalar,
+            constant,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: ScalarMulConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = EccChip::construct(config.ecc);
+        let g = layouter.assign_region(
+            || "G",
+            |mut region| {
+                Ok(EccPoint {
+                    x: region.assign_advice_from_constant(|| "x", config.ecc.x, 0, self.g.0)?,
+                    y: region.assign_advice_from_constant(|| "y", config.ecc.y, 0, self.g.1)?,
+                })
+            },
+        )?;
+        let k = layouter.assign_region(
+            || "k",
+            |mut region| region.assign_advice(|| "k", config.scalar, 0, || self.k),
+        )?;
+        let bits = DecomposeChip::construct(config.decompose).decompose(
+            layouter.namespace(|| "bits of k"),
+            &k,
+            SCALAR_BITS,
+        )?;
+
+        let p = chip.scalar_mul(layouter.namespace(|| "k G"), &bits, &g)?;
+        layouter.constrain_instance(p.x.cell(), config.instance, 0)?;
+        layouter.constrain_instance(p.y.cell(), config.instance, 1)
+    }
+}
+
+/// the affine coordinates of `k * G`
+fn multiple(k: u128) -> (Fp, Fp) {
+    let point = (Secp256k1Affine::generator() * Fq::from_u128(k)).to_affine();
+    let coordinates = point.coordinates().unwrap();
+    (*coordinates.x(), *coordinates.y())
+}
+
+fn prover(k: u128, p: (Fp, Fp)) -> MockProver<Fp> {
+    let circuit = ScalarMulCircuit {
+        g: multiple(1),
+        k: Value::known(Fp::from_u128(k)),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![p.0, p.1]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, ScalarMulCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.ecc.x, "x")
+                    .with_advice(config.ecc.y, "y")
+                    .with_advice(config.ecc.lambda, "lambda")
+                    .with_advice(config.ecc.inv, "inv")
+                    .with_advice(config.scalar, "scalar")
+                    .with_advice(config.decompose.bit, "bit")
+                    .with_advice(config.decompose.acc, "acc")
+                    .with_fixed(config.constant, "constant")
+                    .with_selector(config.ecc.q_on_curve, "q_on_curve")
+                    .with_selector(config.ecc.q_add, "q_add")
+                    .with_selector(config.ecc.q_double, "q_double")
+                    .with_selector(config.ecc.q_select, "q_select")
+                    .with_selector(config.ecc.q_neg, "q_neg")
+                    .with_selector(config.decompose.q_bit, "q_bit")
+                    .with_selector(config.decompose.q_first, "q_first")
+                    .with_selector(config.decompose.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let k = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    let p = multiple(k);
+    println!(
+        "P = ({}, {})",
+        util::display_field(p.0),
+        util::display_field(p.1)
+    );
+    prover(k, p).assert_satisfied();
+
+    // the discrete log of another point
+    testing::assert_fails_permutation(&prover(k + 1, p));
+}
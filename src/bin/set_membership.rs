@@ -0,0 +1,155 @@
+//! set membership circuit
+//!
+//! we are going to prove that a private value is a member of a public set, with a lookup
+//! into a table filled at synthesis time
+//!
+//! | instance   |
+//! |------------|
+//! | set[0]     |
+//! | ...        |
+//! | set[N - 1] |
+//!
+//! a fixed table would put the set in the circuit, a new set would need a new key. here the
+//! table is an advice column `set`, copied from the instance, and `lookup_any` looks up
+//! expressions of advice columns instead of a `TableColumn`:
+//!
+//! | row | set    | value | q_set | q_member |
+//! |-----|--------|-------|-------|----------|
+//! | 0   | set[0] | value | 1     | 1        |
+//! | i   | set[i] |       | 1     | 0        |
+//!
+//! `(q_member, q_member * value)` is looked up in `(q_set, q_set * set)`. the rows without
+//! a selector look up `(0, 0)`, which the rows of the table without `q_set` hold, while a
+//! member has to match a row of the set with `q_set = 1`, even when it is 0.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+
+const N: usize = 8;
+
+#[derive(Debug, Clone)]
+struct MembershipConfig {
+    set: Column<Advice>,
+    value: Column<Advice>,
+    q_set: Selector,
+    q_member: Selector,
+    instance: Column<Instance>,
+}
+
+struct MembershipCircuit<F> {
+    value: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MembershipCircuit<F> {
+    type Config = MembershipConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            value: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> MembershipConfig {
+        let set = meta.advice_column();
+        let value = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_set = meta.complex_selector();
+        let q_member = meta.complex_selector();
+        meta.enable_equality(set);
+        meta.enable_equality(instance);
+
+        meta.lookup_any(|meta| {
+            let q_member = meta.query_selector(q_member);
+            let q_set = meta.query_selector(q_set);
+            let value = meta.query_advice(value, Rotation::cur());
+            let set = meta.query_advice(set, Rotation::cur());
+            vec![
+                (q_member.clone(), q_set.clone()),
+                (q_member * value, q_set * set),
+            ]
+        });
+
+        MembershipConfig {
+            set,
+            value,
+            q_set,
+            q_member,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: MembershipConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "set",
+            |mut region| {
+                for row in 0..N {
+                    config.q_set.enable(&mut region, row)?;
+                    region.assign_advice_from_instance(
+                        || "set",
+                        config.instance,
+                        row,
+                        config.set,
+                        row,
+                    )?;
+                }
+                config.q_member.enable(&mut region, 0)?;
+                region.assign_advice(|| "value", config.value, 0, || self.value)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn prover(value: u64, set: [u64; N]) -> MockProver<Fp> {
+    let circuit = MembershipCircuit {
+        value: Value::known(Fp::from(value)),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = set.iter().map(|x| Fp::from(*x)).collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, MembershipCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.set, "set")
+                    .with_advice(config.value, "value")
+                    .with_selector(config.q_set, "q_set")
+                    .with_selector(config.q_member, "q_member")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // the ids of an allow list
+    let set = [3, 17, 42, 101, 256, 1024, 4099, 65537];
+    for value in set {
+        prover(value, set).assert_satisfied();
+    }
+
+    // a value out of the set
+    testing::assert_fails_lookup(&prover(5, set), 0);
+
+    // 0 is not a member, although the rows out of the table look it up
+    testing::assert_fails_lookup(&prover(0, set), 0);
+}
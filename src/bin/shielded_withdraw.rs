@@ -0,0 +1,237 @@
+//! shielded withdraw circuit
+//!
+//! we are going to prove that we own a note in a public merkle tree of deposits without
+//! saying which one, the way a tornado-style mixer withdraws. a note is a private
+//! `(nullifier, secret)` pair, the tree holds its commitment
+//!
+//! - `commitment = H(nullifier, secret)`, the leaf at a private index
+//! - `nullifier_hash = H(nullifier)`, public
+//!
+//! | instance       |
+//! |----------------|
+//! | root           |
+//! | nullifier_hash |
+//! | recipient      |
+//!
+//! the contract records every `nullifier_hash` it has seen, so a note can only be withdrawn
+//! once, while nothing links it to its commitment. the hashes are computed by the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), the path from the
+//! commitment up to the root by the [`SmtChip`](learn_halo2::gadgets::smt::SmtChip).
+//!
+//! the recipient is not in any constraint. the instance is committed to in the transcript,
+//! so a proof for one recipient still does not verify for another, and nobody watching the
+//! proof can redirect the withdrawal.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        poseidon::{self, PoseidonChip},
+        smt::{SmtChip, SmtConfig, SparseMerkleTree},
+    },
+    testing, util,
+};
+
+const DEPTH: usize = 8;
+
+#[derive(Debug, Clone)]
+struct WithdrawConfig {
+    smt: SmtConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Note<F> {
+    nullifier: F,
+    secret: F,
+}
+
+impl<F: FieldExt> Note<F> {
+    fn commitment(&self) -> F {
+        poseidon::hash(&[self.nullifier, self.secret])
+    }
+
+    fn nullifier_hash(&self) -> F {
+        poseidon::hash(&[self.nullifier])
+    }
+}
+
+struct WithdrawCircuit<F> {
+    note: Value<Note<F>>,
+    index: Value<F>,
+    siblings: [Value<F>; DEPTH],
+}
+
+impl<F> Default for WithdrawCircuit<F> {
+    fn default() -> Self {
+        Self {
+            note: Value::unknown(),
+            index: Value::unknown(),
+            siblings: [(); DEPTH].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for WithdrawCircuit<F> {
+    type Config = WithdrawConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WithdrawConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        WithdrawConfig {
+            smt: SmtChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: WithdrawConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let poseidon = PoseidonChip::construct(config.smt.poseidon);
+        let smt = SmtChip::construct(config.smt);
+
+        let (nullifier, secret, index) = layouter.assign_region(
+            || "note",
+            |mut region| {
+                let column = config.smt.sibling;
+                let nullifier = region.assign_advice(
+                    || "nullifier",
+                    column,
+                    0,
+                    || self.note.map(|note| note.nullifier),
+                )?;
+                let secret = region.assign_advice(
+                    || "secret",
+                    column,
+                    1,
+                    || self.note.map(|note| note.secret),
+                )?;
+                let index = region.assign_advice(|| "index", column, 2, || self.index)?;
+                Ok((nullifier, secret, index))
+            },
+        )?;
+
+        let nullifier_hash = poseidon.hash(
+            layouter.namespace(|| "nullifier hash"),
+            &[nullifier.clone()],
+        )?;
+        let commitment =
+            poseidon.hash(layouter.namespace(|| "commitment"), &[nullifier, secret])?;
+        let root = smt.root(
+            layouter.namespace(|| "path"),
+            &index,
+            commitment,
+            &self.siblings,
+        )?;
+
+        layouter.constrain_instance(root.cell(), config.instance, 0)?;
+        layouter.constrain_instance(nullifier_hash.cell(), config.instance, 1)
+    }
+}
+
+fn prover(
+    tree: &SparseMerkleTree<Fp>,
+    note: Note<Fp>,
+    index: u64,
+    nullifier_hash: Fp,
+    recipient: Fp,
+) -> MockProver<Fp> {
+    let siblings: Vec<_> = tree.siblings(index).into_iter().map(Value::known).collect();
+    let circuit = WithdrawCircuit {
+        note: Value::known(note),
+        index: Value::known(Fp::from(index)),
+        siblings: siblings.try_into().unwrap(),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = vec![tree.root(), nullifier_hash, recipient];
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, WithdrawCircuit<Fp>>(|config| {
+                let smt = config.smt;
+                let mut names = ColumnNames::new().with_advice(smt.sibling, "sibling");
+                for (i, column) in smt.poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in smt.poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("input[{}]", i));
+                }
+                for (i, column) in smt.poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                for (name, column) in ["cond", "a", "b", "out"].iter().zip(smt.select.advice) {
+                    names = names.with_advice(column, *name);
+                }
+                names
+                    .with_advice(smt.decompose.bit, "bit")
+                    .with_advice(smt.decompose.acc, "acc")
+                    .with_selector(smt.poseidon.q_absorb, "q_absorb")
+                    .with_selector(smt.poseidon.q_full, "q_full")
+                    .with_selector(smt.poseidon.q_partial, "q_partial")
+                    .with_selector(smt.select.q_select, "q_select")
+                    .with_selector(smt.decompose.q_bit, "q_bit")
+                    .with_selector(smt.decompose.q_first, "q_first")
+                    .with_selector(smt.decompose.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // the deposits so far
+    let notes: Vec<_> = (0..5u64)
+        .map(|i| Note {
+            nullifier: Fp::from(1000 + i),
+            secret: Fp::from(0xdead_beef * (i + 1)),
+        })
+        .collect();
+    let mut tree = SparseMerkleTree::new(DEPTH);
+    for (index, note) in notes.iter().enumerate() {
+        tree.set_leaf(index as u64, note.commitment());
+    }
+    println!("root = {}", util::display_field(tree.root()));
+
+    let note = notes[3];
+    let recipient = Fp::from(0xcafe);
+    prover(&tree, note, 3, note.nullifier_hash(), recipient).assert_satisfied();
+
+    // a fresh nullifier hash to spend the note twice
+    testing::assert_fails_permutation(&prover(
+        &tree,
+        note,
+        3,
+        note.nullifier_hash() + Fp::one(),
+        recipient,
+    ));
+
+    // a note which was never deposited
+    let forged = Note {
+        nullifier: Fp::from(1),
+        secret: Fp::from(2),
+    };
+    testing::assert_fails_permutation(&prover(
+        &tree,
+        forged,
+        5,
+        forged.nullifier_hash(),
+        recipient,
+    ));
+}
@@ -0,0 +1,145 @@
+//! sparse merkle tree non-membership circuit
+//!
+//! we are going to prove that a public key holds nothing in a sparse merkle tree of
+//! depth DEPTH with a public root, e.g. that a nullifier has not been spent yet, with the
+//! [`SmtChip`](learn_halo2::gadgets::smt::SmtChip)
+//!
+//! | instance |
+//! |----------|
+//! | root     |
+//! | key      |
+//!
+//! the siblings on the path of the key are private, most of them are the roots of empty
+//! subtrees which the prover gets from
+//! [`empty_roots`](learn_halo2::gadgets::smt::empty_roots).
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::smt::{SmtChip, SmtConfig, SparseMerkleTree},
+    testing, util,
+};
+
+const DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+struct NonMembershipConfig {
+    smt: SmtConfig,
+    instance: Column<Instance>,
+}
+
+struct NonMembershipCircuit<F> {
+    siblings: [Value<F>; DEPTH],
+}
+
+impl<F> Default for NonMembershipCircuit<F> {
+    fn default() -> Self {
+        Self {
+            siblings: [(); DEPTH].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for NonMembershipCircuit<F> {
+    type Config = NonMembershipConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> NonMembershipConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        NonMembershipConfig {
+            smt: SmtChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: NonMembershipConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = SmtChip::construct(config.smt);
+        let key = layouter.assign_region(
+            || "key",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "key",
+                    config.instance,
+                    1,
+                    config.smt.sibling,
+                    0,
+                )
+            },
+        )?;
+        let root = chip.non_membership(layouter.namespace(|| "smt"), &key, &self.siblings)?;
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+fn prover(tree: &SparseMerkleTree<Fp>, key: u64) -> MockProver<Fp> {
+    let siblings: Vec<_> = tree.siblings(key).into_iter().map(Value::known).collect();
+    let circuit = NonMembershipCircuit {
+        siblings: siblings.try_into().unwrap(),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![tree.root(), Fp::from(key)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, NonMembershipCircuit<Fp>>(|config| {
+                let smt = config.smt;
+                let mut names = ColumnNames::new().with_advice(smt.sibling, "sibling");
+                for (i, column) in smt.poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in smt.poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("input[{}]", i));
+                }
+                for (i, column) in smt.poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                for (name, column) in ["cond", "a", "b", "out"].iter().zip(smt.select.advice) {
+                    names = names.with_advice(column, *name);
+                }
+                names
+                    .with_advice(smt.decompose.bit, "bit")
+                    .with_advice(smt.decompose.acc, "acc")
+                    .with_selector(smt.poseidon.q_absorb, "q_absorb")
+                    .with_selector(smt.poseidon.q_full, "q_full")
+                    .with_selector(smt.poseidon.q_partial, "q_partial")
+                    .with_selector(smt.select.q_select, "q_select")
+                    .with_selector(smt.decompose.q_bit, "q_bit")
+                    .with_selector(smt.decompose.q_first, "q_first")
+                    .with_selector(smt.decompose.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // the spent nullifiers
+    let mut tree = SparseMerkleTree::new(DEPTH);
+    for nullifier in [7, 42, 1000, 65535] {
+        tree.insert(nullifier, Fp::one());
+    }
+    println!("root = {}", util::display_field(tree.root()));
+    prover(&tree, 43).assert_satisfied();
+    prover(&tree, 0).assert_satisfied();
+
+    // 42 has been spent
+    testing::assert_fails_permutation(&prover(&tree, 42));
+}
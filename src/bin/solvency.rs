@@ -0,0 +1,192 @@
+//! solvency circuit
+//!
+//! we are going to prove that a private balance, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is at least a public
+//! threshold, without revealing the balance
+//!
+//! | instance          |
+//! |-------------------|
+//! | H(salt, balance)  |
+//! | threshold         |
+//!
+//! the balance is compared with the threshold by the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) of 8 bytes:
+//!
+//! | row | salt | balance | threshold | lt | q_compare |
+//! |-----|------|---------|-----------|----|-----------|
+//! | 0   | salt | balance | threshold | 0  | 1         |
+//!
+//! `lt` is constrained to 0, so `balance - threshold` fits in 8 bytes, which needs no other
+//! range check of the balance. the threshold has to be below `2^64`, the balance and the
+//! salt are copied into the commitment.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+const N_BYTES: usize = 8;
+
+#[derive(Debug, Clone)]
+struct SolvencyConfig {
+    salt: Column<Advice>,
+    balance: Column<Advice>,
+    threshold: Column<Advice>,
+    q_compare: Selector,
+    lt: LtConfig<N_BYTES>,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct SolvencyCircuit<F> {
+    salt: Value<F>,
+    balance: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SolvencyCircuit<F> {
+    type Config = SolvencyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SolvencyConfig {
+        let salt = meta.advice_column();
+        let balance = meta.advice_column();
+        let threshold = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_compare = meta.complex_selector();
+        for column in [salt, balance, threshold] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_compare),
+            |meta| meta.query_advice(balance, Rotation::cur()),
+            |meta| meta.query_advice(threshold, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        SolvencyConfig {
+            salt,
+            balance,
+            threshold,
+            q_compare,
+            lt,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SolvencyConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let lt = LtChip::<F, N_BYTES>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "u8"))?;
+
+        let opening = layouter.assign_region(
+            || "balance >= threshold",
+            |mut region| {
+                config.q_compare.enable(&mut region, 0)?;
+                let salt = region.assign_advice(|| "salt", config.salt, 0, || self.salt)?;
+                let balance =
+                    region.assign_advice(|| "balance", config.balance, 0, || self.balance)?;
+                let threshold = region.assign_advice_from_instance(
+                    || "threshold",
+                    config.instance,
+                    1,
+                    config.threshold,
+                    0,
+                )?;
+                let lt = lt.assign(&mut region, 0, self.balance, threshold.value().copied())?;
+                region.constrain_constant(lt.cell(), F::zero())?;
+                Ok([salt, balance])
+            },
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(salt, balance)"), &opening)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+fn commit(salt: u64, balance: u64) -> Fp {
+    poseidon::hash(&[Fp::from(salt), Fp::from(balance)])
+}
+
+fn prover(salt: u64, balance: u64, commitment: Fp, threshold: u64) -> MockProver<Fp> {
+    let circuit = SolvencyCircuit {
+        salt: Value::known(Fp::from(salt)),
+        balance: Value::known(Fp::from(balance)),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![commitment, Fp::from(threshold)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SolvencyCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.salt, "salt")
+                    .with_advice(config.balance, "balance")
+                    .with_advice(config.threshold, "threshold")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_compare, "q_compare")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let (salt, balance) = (0x5eed, 1_500_000);
+    let commitment = commit(salt, balance);
+    prover(salt, balance, commitment, 1_000_000).assert_satisfied();
+    // just enough
+    prover(salt, balance, commitment, balance).assert_satisfied();
+
+    // one short, the flag is 1, which does not match the constant 0
+    testing::assert_fails_permutation(&prover(salt, balance, commitment, balance + 1));
+
+    // a larger balance than the committed one
+    testing::assert_fails_permutation(&prover(salt, 2_000_000, commitment, 1_800_000));
+}
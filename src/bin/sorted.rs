@@ -0,0 +1,314 @@
+//! sorting circuit
+//!
+//! we are going to prove that a private list is the sorted version of another private list,
+//! both committed to with the [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip)
+//!
+//! | instance      |
+//! |---------------|
+//! | H(input)      |
+//! | H(output)     |
+//!
+//! copy constraints can only say that two fixed cells are equal, they can't say that
+//! `output` is some reordering of `input`. the trick of the permutation argument of halo2
+//! itself is that it is, for a random `gamma`, with high probability when
+//!
+//! `(input[0] + gamma) * ... * (input[N-1] + gamma) = (output[0] + gamma) * ... * (output[N-1] + gamma)`
+//!
+//! both sides are polynomials in `gamma` with the elements of the lists as roots, they are
+//! equal everywhere only when the lists are the same multiset, and two different ones agree
+//! on at most `N` points. `gamma` has to be drawn after the lists are fixed, so it is
+//! `H(H(input), H(output))`, the fiat-shamir way. both products are running products of the
+//! [`RunningProductChip`](learn_halo2::gadgets::product::RunningProductChip):
+//!
+//! | row | input    | output    | gamma | x_in             | acc_in | x_out             | acc_out | q_shuffle |
+//! |-----|----------|-----------|-------|------------------|--------|-------------------|---------|-----------|
+//! | i   | input[i] | output[i] | gamma | input[i] + gamma | ...    | output[i] + gamma | ...     | 1         |
+//! | N   |          |           |       |                  | prod   |                   | prod    | 0         |
+//!
+//! - `q_shuffle * (x_in - input - gamma) = 0`
+//! - `q_shuffle * (x_out - output - gamma) = 0`
+//!
+//! and the two products are copy constrained to be equal. the output is sorted when no
+//! element is smaller than the one before it, checked with the
+//! [`LtChip`](learn_halo2::gadgets::compare::LtChip) on every pair:
+//!
+//! | row | output        | lt | q_sorted |
+//! |-----|---------------|----|----------|
+//! | 0   | 0             | 0  | 1        |
+//! | i   | output[i - 1] | 0  | 1        |
+//! | N   | output[N - 1] |    | 0        |
+//!
+//! `lt(output', output)` is constrained to 0, so every step `output' - output` is below
+//! `2^32`. starting from a constant 0, all the elements are smaller than `N * 2^32`, the
+//! steps can't wrap around the field, and the order is the order of the integers.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        compare::{LtChip, LtConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        product::{RunningProductChip, RunningProductConfig},
+    },
+    testing, util,
+};
+
+const N: usize = 8;
+const N_BYTES: usize = 4;
+
+#[derive(Debug, Clone)]
+struct SortedConfig {
+    input: Column<Advice>,
+    output: Column<Advice>,
+    gamma: Column<Advice>,
+    q_shuffle: Selector,
+    q_sorted: Selector,
+    product_in: RunningProductConfig,
+    product_out: RunningProductConfig,
+    lt: LtConfig<N_BYTES>,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct SortedCircuit<F> {
+    input: [Value<F>; N],
+    output: [Value<F>; N],
+}
+
+impl<F> Default for SortedCircuit<F> {
+    fn default() -> Self {
+        Self {
+            input: [(); N].map(|_| Value::unknown()),
+            output: [(); N].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for SortedCircuit<F> {
+    type Config = SortedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SortedConfig {
+        let input = meta.advice_column();
+        let output = meta.advice_column();
+        let gamma = meta.advice_column();
+        let x_in = meta.advice_column();
+        let acc_in = meta.advice_column();
+        let x_out = meta.advice_column();
+        let acc_out = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_shuffle = meta.selector();
+        let q_sorted = meta.complex_selector();
+        meta.enable_equality(input);
+        meta.enable_equality(output);
+        meta.enable_equality(gamma);
+        meta.enable_equality(instance);
+
+        let product_in = RunningProductChip::configure(meta, x_in, acc_in);
+        let product_out = RunningProductChip::configure(meta, x_out, acc_out);
+        meta.create_gate("shuffle", |meta| {
+            let q_shuffle = meta.query_selector(q_shuffle);
+            let gamma = meta.query_advice(gamma, Rotation::cur());
+            let input = meta.query_advice(input, Rotation::cur());
+            let output = meta.query_advice(output, Rotation::cur());
+            let x_in = meta.query_advice(x_in, Rotation::cur());
+            let x_out = meta.query_advice(x_out, Rotation::cur());
+            vec![
+                q_shuffle.clone() * (x_in - input - gamma.clone()),
+                q_shuffle * (x_out - output - gamma),
+            ]
+        });
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_sorted),
+            |meta| meta.query_advice(output, Rotation::next()),
+            |meta| meta.query_advice(output, Rotation::cur()),
+        );
+        meta.enable_equality(lt.lt);
+
+        SortedConfig {
+            input,
+            output,
+            gamma,
+            q_shuffle,
+            q_sorted,
+            product_in,
+            product_out,
+            lt,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SortedConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let lt = LtChip::<F, N_BYTES>::construct(config.lt);
+        lt.load_table(layouter.namespace(|| "u8"))?;
+
+        let (input, output) = layouter.assign_region(
+            || "lists",
+            |mut region| {
+                let mut assign = |name: &'static str, column, values: &[Value<F>]| {
+                    values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, value)| {
+                            region.assign_advice(|| name, column, offset, || *value)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                let input = assign("input", config.input, &self.input)?;
+                let output = assign("output", config.output, &self.output)?;
+                Ok((input, output))
+            },
+        )?;
+        let commitment_in = poseidon.hash(layouter.namespace(|| "H(input)"), &input)?;
+        let commitment_out = poseidon.hash(layouter.namespace(|| "H(output)"), &output)?;
+        let gamma = poseidon.hash(
+            layouter.namespace(|| "gamma"),
+            &[commitment_in.clone(), commitment_out.clone()],
+        )?;
+
+        let shifted = |list: &[Value<F>; N]| -> Vec<_> {
+            list.iter()
+                .map(|value| *value + gamma.value().copied())
+                .collect()
+        };
+        let (x_in, x_out) = (shifted(&self.input), shifted(&self.output));
+        let product_in = RunningProductChip::construct(config.product_in);
+        let product_out = RunningProductChip::construct(config.product_out);
+        layouter.assign_region(
+            || "shuffle",
+            |mut region| {
+                for (offset, (a, b)) in input.iter().zip(&output).enumerate() {
+                    config.q_shuffle.enable(&mut region, offset)?;
+                    a.copy_advice(|| "input", &mut region, config.input, offset)?;
+                    b.copy_advice(|| "output", &mut region, config.output, offset)?;
+                    gamma.copy_advice(|| "gamma", &mut region, config.gamma, offset)?;
+                }
+                let total_in = product_in.assign(&mut region, &x_in)?;
+                let total_out = product_out.assign(&mut region, &x_out)?;
+                region.constrain_equal(total_in.cell(), total_out.cell())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "sorted",
+            |mut region| {
+                let mut previous =
+                    region.assign_advice_from_constant(|| "zero", config.output, 0, F::zero())?;
+                for (offset, cell) in output.iter().enumerate() {
+                    config.q_sorted.enable(&mut region, offset)?;
+                    let next =
+                        cell.copy_advice(|| "output", &mut region, config.output, offset + 1)?;
+                    let lt = lt.assign(
+                        &mut region,
+                        offset,
+                        next.value().copied(),
+                        previous.value().copied(),
+                    )?;
+                    region.constrain_constant(lt.cell(), F::zero())?;
+                    previous = next;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.constrain_instance(commitment_in.cell(), config.instance, 0)?;
+        layouter.constrain_instance(commitment_out.cell(), config.instance, 1)
+    }
+}
+
+fn prover(input: [u64; N], output: [u64; N]) -> MockProver<Fp> {
+    let (input, output) = (input.map(Fp::from), output.map(Fp::from));
+    let circuit = SortedCircuit {
+        input: input.map(Value::known),
+        output: output.map(Value::known),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = vec![poseidon::hash(&input), poseidon::hash(&output)];
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SortedCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.input, "input")
+                    .with_advice(config.output, "output")
+                    .with_advice(config.gamma, "gamma")
+                    .with_advice(config.product_in.x, "x_in")
+                    .with_advice(config.product_in.acc, "acc_in")
+                    .with_advice(config.product_out.x, "x_out")
+                    .with_advice(config.product_out.acc, "acc_out")
+                    .with_advice(config.lt.lt, "lt");
+                for (i, column) in config.lt.diff.iter().enumerate() {
+                    names = names.with_advice(*column, format!("diff[{}]", i));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_shuffle, "q_shuffle")
+                    .with_selector(config.q_sorted, "q_sorted")
+                    .with_selector(config.product_in.q_first, "q_first_in")
+                    .with_selector(config.product_in.q_product, "q_product_in")
+                    .with_selector(config.product_out.q_first, "q_first_out")
+                    .with_selector(config.product_out.q_product, "q_product_out")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let input = [42, 7, 1999, 7, 0, 65536, 3, 128];
+    let mut output = input;
+    output.sort_unstable();
+    prover(input, output).assert_satisfied();
+
+    // sorted, but a 7 became an 8, the products differ
+    let mut other = output;
+    other[3] = 8;
+    testing::assert_fails_permutation(&prover(input, other));
+
+    // the same elements, out of order, one of the flags is 1
+    let mut unsorted = output;
+    unsorted.swap(0, 7);
+    testing::assert_fails_permutation(&prover(input, unsorted));
+
+    // an element past `2^32`, the last step doesn't fit in the bytes of the difference
+    let mut large = input;
+    large[0] = 1 << 40;
+    let mut output = large;
+    output.sort_unstable();
+    testing::assert_fails_gate(&prover(large, output), "lt", N - 1);
+}
@@ -0,0 +1,129 @@
+//! square root circuit
+//!
+//! we are going to prove that we know a square root of a public field element `x`, the
+//! one with the public sign, i.e. the lowest bit of its canonical representation
+//!
+//! | instance |
+//! |----------|
+//! | x        |
+//! | sign     |
+//!
+//! both are copied into the [`SqrtChip`](learn_halo2::gadgets::sqrt::SqrtChip), the root
+//! is the only witness. without the sign `p - y` would be accepted as well as `y`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::sqrt::{parity, SqrtChip, SqrtConfig},
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct SqrtCircuitConfig {
+    sqrt: SqrtConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct SqrtCircuit<F> {
+    root: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SqrtCircuit<F> {
+    type Config = SqrtCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SqrtCircuitConfig {
+        let advice = [(); 3].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        SqrtCircuitConfig {
+            sqrt: SqrtChip::configure(meta, advice),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SqrtCircuitConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = SqrtChip::construct(config.sqrt);
+        let (x, sign) = layouter.assign_region(
+            || "public",
+            |mut region| {
+                let x = region.assign_advice_from_instance(
+                    || "x",
+                    config.instance,
+                    0,
+                    config.sqrt.advice[0],
+                    0,
+                )?;
+                let sign = region.assign_advice_from_instance(
+                    || "sign",
+                    config.instance,
+                    1,
+                    config.sqrt.advice[1],
+                    0,
+                )?;
+                Ok((x, sign))
+            },
+        )?;
+
+        let y = chip.assign(layouter.namespace(|| "root"), &x, self.root)?;
+        chip.canonical(layouter.namespace(|| "sign"), &y, &sign)
+    }
+}
+
+fn prover(root: Fp, x: Fp, sign: u64) -> MockProver<Fp> {
+    let circuit = SqrtCircuit {
+        root: Value::known(root),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![x, Fp::from(sign)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SqrtCircuit<Fp>>(|config| {
+                let [a, b, c] = config.sqrt.advice;
+                ColumnNames::new()
+                    .with_advice(a, "a")
+                    .with_advice(b, "b")
+                    .with_advice(c, "c")
+                    .with_fixed(config.sqrt.canonical.p_bit, "p_bit")
+                    .with_selector(config.sqrt.q_sqrt, "q_sqrt")
+                    .with_selector(config.sqrt.canonical.q_first, "q_first")
+                    .with_selector(config.sqrt.canonical.q_step, "q_step")
+                    .with_selector(config.sqrt.canonical.q_last, "q_last")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let root = Fp::from(0x1234_5678_9abc_def0);
+    let x = root * root;
+    let sign = parity(&root);
+    prover(root, x, sign).assert_satisfied();
+    prover(-root, x, 1 - sign).assert_satisfied();
+
+    // the other root has the other sign
+    testing::assert_fails_permutation(&prover(-root, x, sign));
+
+    // not a root at all
+    testing::assert_fails_gate(&prover(root + Fp::one(), x, sign), "sqrt", 0);
+}
@@ -0,0 +1,272 @@
+//! substring search circuit
+//!
+//! we are going to prove that a public pattern of `P` bytes appears in a private text of `N`
+//! bytes, committed to with the [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip),
+//! at an offset only the prover knows
+//!
+//! | instance     |
+//! |--------------|
+//! | H(text)      |
+//! | pattern[0]   |
+//! | ...          |
+//! | pattern[P-1] |
+//!
+//! the `W = N - P + 1` windows of the text start at the rows `0..W`, the window of the row
+//! `i` is `text[i..i + P]`, queried at `Rotation(k)`. `hit` masks all of them but the one at
+//! the offset, and the `win` columns carry that window up to the first row, where it is
+//! copied to the pattern:
+//!
+//! | row | text      | hit         | seen         | win[k]        | q_window |
+//! |-----|-----------|-------------|--------------|---------------|----------|
+//! | 0   | text[0]   | offset == 0 | 1            | text[off + k] | 1        |
+//! | i   | text[i]   | offset == i | offset >= i  | ...           | 1        |
+//! | W   | text[W]   |             | 0            | 0             | 0        |
+//! | ... | ...       |             |              |               | 0        |
+//!
+//! - `q_window * hit * (1 - hit) = 0`
+//! - `q_window * (seen - seen' - hit) = 0`
+//! - `q_window * (win[k] - (hit ? text[i + k] : win[k]')) = 0` for every `k`
+//!
+//! with [`select::expr`](learn_halo2::gadgets::select::expr), where `'` is the next row.
+//! `seen` counts the hits from the bottom, and with the constants at its ends there is
+//! exactly one.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+        select,
+    },
+    testing, util,
+};
+
+const N: usize = 12;
+const P: usize = 4;
+/// the number of windows
+const W: usize = N - P + 1;
+
+#[derive(Debug, Clone)]
+struct SubstringConfig {
+    text: Column<Advice>,
+    hit: Column<Advice>,
+    seen: Column<Advice>,
+    win: [Column<Advice>; P],
+    q_window: Selector,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct SubstringCircuit<F> {
+    text: [Value<F>; N],
+    offset: Value<usize>,
+}
+
+impl<F> Default for SubstringCircuit<F> {
+    fn default() -> Self {
+        Self {
+            text: [(); N].map(|_| Value::unknown()),
+            offset: Value::unknown(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for SubstringCircuit<F> {
+    type Config = SubstringConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SubstringConfig {
+        let text = meta.advice_column();
+        let hit = meta.advice_column();
+        let seen = meta.advice_column();
+        let win = [(); P].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_window = meta.selector();
+        for column in [text, seen].into_iter().chain(win) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        meta.create_gate("window", |meta| {
+            let q_window = meta.query_selector(q_window);
+            let one = Expression::Constant(F::one());
+            let hit = meta.query_advice(hit, Rotation::cur());
+            let seen_next = meta.query_advice(seen, Rotation::next());
+            let seen = meta.query_advice(seen, Rotation::cur());
+            let mut constraints = vec![
+                q_window.clone() * hit.clone() * (one - hit.clone()),
+                q_window.clone() * (seen - seen_next - hit.clone()),
+            ];
+            for (k, column) in win.iter().enumerate() {
+                let text = meta.query_advice(text, Rotation(k as i32));
+                let win = meta.query_advice(*column, Rotation::cur());
+                let win_next = meta.query_advice(*column, Rotation::next());
+                let masked = select::expr(hit.clone(), text, win_next);
+                constraints.push(q_window.clone() * (win - masked));
+            }
+            constraints
+        });
+
+        SubstringConfig {
+            text,
+            hit,
+            seen,
+            win,
+            q_window,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: SubstringConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (text, window) = layouter.assign_region(
+            || "windows",
+            |mut region| {
+                let mut text = vec![];
+                for (i, c) in self.text.iter().enumerate() {
+                    text.push(region.assign_advice(|| "text", config.text, i, || *c)?);
+                }
+
+                // the bottom row, below the last window
+                region.assign_advice_from_constant(|| "seen", config.seen, W, F::zero())?;
+                let mut window = vec![];
+                for column in config.win {
+                    window.push(region.assign_advice_from_constant(
+                        || "win",
+                        column,
+                        W,
+                        F::zero(),
+                    )?);
+                }
+
+                for i in (0..W).rev() {
+                    config.q_window.enable(&mut region, i)?;
+                    let hit = self.offset.map(|offset| offset == i);
+                    region.assign_advice(
+                        || "hit",
+                        config.hit,
+                        i,
+                        || hit.map(|hit| F::from(hit as u64)),
+                    )?;
+                    let seen = self.offset.map(|offset| F::from((offset >= i) as u64));
+                    if i == 0 {
+                        region.assign_advice_from_constant(|| "seen", config.seen, i, F::one())?;
+                    } else {
+                        region.assign_advice(|| "seen", config.seen, i, || seen)?;
+                    }
+                    for (k, (column, cell)) in config.win.iter().zip(&mut window).enumerate() {
+                        let win = hit
+                            .zip(text[i + k].value().copied())
+                            .zip(cell.value().copied())
+                            .map(|((hit, text), below)| if hit { text } else { below });
+                        *cell = region.assign_advice(|| "win", *column, i, || win)?;
+                    }
+                }
+                Ok((text, window))
+            },
+        )?;
+        for (k, cell) in window.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, 1 + k)?;
+        }
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(text)"), &text)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+/// the first offset of `pattern` in `text` outside of the circuit
+fn find(text: &[u8; N], pattern: &[u8; P]) -> Option<usize> {
+    text.windows(P).position(|window| window == pattern)
+}
+
+fn commit(text: &[u8; N]) -> Fp {
+    poseidon::hash(&text.map(|c| Fp::from(u64::from(c))))
+}
+
+fn prover(text: &[u8; N], offset: usize, commitment: Fp, pattern: &[u8; P]) -> MockProver<Fp> {
+    let circuit = SubstringCircuit {
+        text: text.map(|c| Value::known(Fp::from(u64::from(c)))),
+        offset: Value::known(offset),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let pattern = pattern.map(|c| Fp::from(u64::from(c)));
+    let instance = [commitment].into_iter().chain(pattern).collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SubstringCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.text, "text")
+                    .with_advice(config.hit, "hit")
+                    .with_advice(config.seen, "seen");
+                for (k, column) in config.win.iter().enumerate() {
+                    names = names.with_advice(*column, format!("win[{}]", k));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_window, "q_window")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let text = b"learn halo2!";
+    let commitment = commit(text);
+    assert_eq!(find(text, b"halo"), Some(6));
+    prover(text, 6, commitment, b"halo").assert_satisfied();
+    // the first and the last window
+    prover(text, 0, commitment, b"lear").assert_satisfied();
+    prover(text, W - 1, commitment, b"lo2!").assert_satisfied();
+
+    // the pattern one byte off
+    testing::assert_fails_permutation(&prover(text, 5, commitment, b"halo"));
+
+    // a pattern that is not in the text
+    assert_eq!(find(text, b"halp"), None);
+    testing::assert_fails_permutation(&prover(text, 6, commitment, b"halp"));
+
+    // another text with the pattern
+    let other = b"hello, halo!";
+    testing::assert_fails_permutation(&prover(other, 7, commitment, b"halo"));
+
+    // no window at all, the pattern left as the zeros below the last one
+    testing::assert_fails_gate(&prover(text, W, commitment, &[0; P]), "window", W - 1);
+}
@@ -0,0 +1,84 @@
+//! sum of a private vector
+//!
+//! we are going to prove that the private values add up to a public total
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::accumulator::{RunningSumChip, RunningSumConfig},
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct SumConfig {
+    sum: RunningSumConfig,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct SumCircuit<F> {
+    values: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> Circuit<F> for SumCircuit<F> {
+    type Config = SumConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            values: vec![Value::unknown(); self.values.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SumConfig {
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        SumConfig {
+            sum: RunningSumChip::configure(meta, x, acc),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: SumConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = RunningSumChip::construct(config.sum);
+        let total = chip.assign(layouter.namespace(|| "sum"), &self.values)?;
+        layouter.constrain_instance(total.cell(), config.instance, 0)
+    }
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, SumCircuit<Fp>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+            })
+        );
+    }
+
+    let values = [3, 1, 4, 1, 5, 9, 2, 6];
+    let circuit = SumCircuit {
+        values: values.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+
+    let prover_success = MockProver::run(k, &circuit, vec![vec![Fp::from(31)]]).unwrap();
+    prover_success.assert_satisfied();
+
+    let prover_failure = MockProver::run(k, &circuit, vec![vec![Fp::from(30)]]).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+}
@@ -0,0 +1,218 @@
+//! tally circuit
+//!
+//! we are going to prove that a public tally counts the private ballots of `V` voters
+//! among `C` candidates
+//!
+//! | instance    |
+//! |-------------|
+//! | tally[0]    |
+//! | ...         |
+//! | tally[C-1]  |
+//!
+//! a ballot is one flag per candidate, one-hot, and its choice is range checked to `0..C`
+//! by the [`RangeCheckChip`](learn_halo2::gadgets::range::RangeCheckChip) of `BITS` bits,
+//! `C = 2^BITS`:
+//!
+//! | row | choice    | flag[c]       | q_ballot |
+//! |-----|-----------|---------------|----------|
+//! | v   | choice[v] | ballot[v][c]  | 1        |
+//!
+//! - `q_ballot * flag * (1 - flag) = 0` for every candidate
+//! - `q_ballot * (flag[0] + ... + flag[C-1] - 1) = 0`
+//! - `q_ballot * (0 * flag[0] + ... + (C-1) * flag[C-1] - choice) = 0`
+//!
+//! every flag column is added up by a running sum of the
+//! [`RunningSumChip`](learn_halo2::gadgets::accumulator::RunningSumChip), copy constrained
+//! to the ballots, and the totals are the tally.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        accumulator::{RunningSumChip, RunningSumConfig},
+        range::{RangeCheckChip, RangeCheckConfig},
+    },
+    testing, util,
+};
+
+const V: usize = 8;
+const BITS: usize = 2;
+const C: usize = 1 << BITS;
+
+#[derive(Debug, Clone)]
+struct TallyConfig {
+    range: RangeCheckConfig,
+    flag: [Column<Advice>; C],
+    q_ballot: Selector,
+    sum: RunningSumConfig,
+    instance: Column<Instance>,
+}
+
+struct TallyCircuit<F> {
+    ballots: [[Value<F>; C]; V],
+}
+
+impl<F> Default for TallyCircuit<F> {
+    fn default() -> Self {
+        Self {
+            ballots: [(); V].map(|_| [(); C].map(|_| Value::unknown())),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for TallyCircuit<F> {
+    type Config = TallyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> TallyConfig {
+        let choice = meta.advice_column();
+        let flag = [(); C].map(|_| meta.advice_column());
+        let x = meta.advice_column();
+        let acc = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_ballot = meta.selector();
+        for column in flag.into_iter().chain([x]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        let range = RangeCheckChip::<F, BITS>::configure(meta, choice);
+
+        meta.create_gate("ballot", |meta| {
+            let q_ballot = meta.query_selector(q_ballot);
+            let one = Expression::Constant(F::one());
+            let choice = meta.query_advice(choice, Rotation::cur());
+            let mut constraints = vec![];
+            let mut count = Expression::Constant(F::zero());
+            let mut encoded = Expression::Constant(F::zero());
+            for (c, column) in flag.iter().enumerate() {
+                let flag = meta.query_advice(*column, Rotation::cur());
+                constraints.push(q_ballot.clone() * flag.clone() * (one.clone() - flag.clone()));
+                count = count + flag.clone();
+                encoded = encoded + Expression::Constant(F::from(c as u64)) * flag;
+            }
+            constraints.push(q_ballot.clone() * (count - one));
+            constraints.push(q_ballot * (encoded - choice));
+            constraints
+        });
+
+        TallyConfig {
+            range,
+            flag,
+            q_ballot,
+            sum: RunningSumChip::configure(meta, x, acc),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: TallyConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let range = RangeCheckChip::<F, BITS>::construct(config.range);
+        range.load_table(layouter.namespace(|| "candidates"))?;
+
+        let flags = layouter.assign_region(
+            || "ballots",
+            |mut region| {
+                let mut flags = vec![vec![]; C];
+                for (v, ballot) in self.ballots.iter().enumerate() {
+                    config.q_ballot.enable(&mut region, v)?;
+                    let mut choice = Value::known(F::zero());
+                    for (c, ((column, flag), cells)) in
+                        config.flag.iter().zip(ballot).zip(&mut flags).enumerate()
+                    {
+                        cells.push(region.assign_advice(|| "flag", *column, v, || *flag)?);
+                        choice = choice + *flag * Value::known(F::from(c as u64));
+                    }
+                    range.assign_checked(&mut region, v, choice)?;
+                }
+                Ok(flags)
+            },
+        )?;
+
+        let sum = RunningSumChip::construct(config.sum);
+        for (c, cells) in flags.iter().enumerate() {
+            let xs: Vec<_> = cells.iter().map(|cell| cell.value().copied()).collect();
+            let (x_cells, accs) = sum.assign_cells(layouter.namespace(|| "tally"), &xs)?;
+            layouter.assign_region(
+                || "votes",
+                |mut region| {
+                    for (x, cell) in x_cells.iter().zip(cells) {
+                        region.constrain_equal(x.cell(), cell.cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.constrain_instance(accs.last().unwrap().cell(), config.instance, c)?;
+        }
+        Ok(())
+    }
+}
+
+/// the one-hot ballot of a vote for `choice`
+fn ballot(choice: usize) -> [u64; C] {
+    std::array::from_fn(|c| u64::from(c == choice))
+}
+
+fn prover(ballots: [[u64; C]; V], tally: [u64; C]) -> MockProver<Fp> {
+    let circuit = TallyCircuit {
+        ballots: ballots.map(|ballot| ballot.map(|flag| Value::known(Fp::from(flag)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![tally.map(Fp::from).to_vec()]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, TallyCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new().with_advice(config.range.value, "choice");
+                for (c, column) in config.flag.iter().enumerate() {
+                    names = names.with_advice(*column, format!("flag[{}]", c));
+                }
+                names
+                    .with_advice(config.sum.x, "x")
+                    .with_advice(config.sum.acc, "acc")
+                    .with_selector(config.range.q_lookup, "q_lookup")
+                    .with_selector(config.q_ballot, "q_ballot")
+                    .with_selector(config.sum.q_first, "q_first")
+                    .with_selector(config.sum.q_sum, "q_sum")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let ballots = [2, 0, 2, 3, 2, 0, 1, 2].map(ballot);
+    prover(ballots, [2, 1, 4, 1]).assert_satisfied();
+    // unanimous
+    prover([ballot(3); V], [0, 0, 0, V as u64]).assert_satisfied();
+
+    // a vote moved from the winner to the runner up
+    testing::assert_fails_permutation(&prover(ballots, [3, 1, 3, 1]));
+
+    // a ballot for two candidates, each flag is still boolean
+    let mut double = ballots;
+    double[3] = [0, 1, 0, 1];
+    testing::assert_fails_gate(&prover(double, [2, 2, 4, 1]), "ballot", 3);
+
+    // a blank ballot
+    let mut blank = ballots;
+    blank[3] = [0; C];
+    testing::assert_fails_gate(&prover(blank, [2, 1, 4, 0]), "ballot", 3);
+
+    // two votes for one candidate in a single ballot, which also takes the choice out of range
+    let mut stuffed = ballots;
+    stuffed[3] = [0, 0, 0, 2];
+    testing::assert_fails_lookup(&prover(stuffed, [2, 1, 4, 2]), 3);
+}
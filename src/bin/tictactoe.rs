@@ -0,0 +1,378 @@
+//! tic-tac-toe circuit
+//!
+//! we are going to prove that we know a private sequence of legal moves of tic-tac-toe, X
+//! first, that ends the game with a public outcome
+//!
+//! | instance |
+//! |----------|
+//! | outcome  |
+//!
+//! the turns are kept by the [`FsmChip`](learn_halo2::gadgets::fsm::FsmChip), which reads
+//! the kind of every move:
+//!
+//! | state  | PASS   | MOVE   | WIN    |
+//! |--------|--------|--------|--------|
+//! | X_TURN |        | O_TURN | X_WON  |
+//! | O_TURN |        | X_TURN | O_WON  |
+//! | X_WON  | X_WON  |        |        |
+//! | O_WON  | O_WON  |        |        |
+//!
+//! the outcome is the last state, a game that ends early is padded with `PASS` after the
+//! winning move, and a draw fills the board and ends in `O_TURN`. the board is a boolean
+//! per square and player, one row per move, with the move as a boolean per square:
+//!
+//! | row | x[s]  | o[s]  | hit[s]  | kind  | state    | input | lines_inv | no_line | q_move |
+//! |-----|-------|-------|---------|-------|----------|-------|-----------|---------|--------|
+//! | 0   | 0     | 0     | ...     | ...   | X_TURN   | kind  | ...       | ...     | 1      |
+//! | t   | ...   | ...   | ...     | ...   | ...      | kind  | ...       | ...     | 1      |
+//! | M   | ...   | ...   |         |       | outcome  |       |           |         | 0      |
+//!
+//! - `q_move * hit * (1 - hit) = 0` and `q_move * hit * (x + o) = 0` for every square
+//! - `q_move * (x' - x - hit * (2 - state)) = 0`
+//! - `q_move * (o' - o - hit * (state - 1)) = 0`
+//! - `q_move * moved * (1 - moved) = 0`
+//! - `q_move * (kind - moved * (2 - no_line)) = 0`
+//!
+//! where `moved` is the sum of the hits, the player is the state, X in `X_TURN` and O in
+//! `O_TURN`, and `no_line` is an [`IsZeroChip`](learn_halo2::gadgets::is_zero::IsZeroChip)
+//! on the number of complete lines of the board after the move. no line is complete before
+//! the winning move, so a move that completes one has to be a `WIN`, and the kinds are
+//! copied into the input of the automaton.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        fsm::{self, FsmChip, FsmConfig, Transition},
+        is_zero::{IsZeroChip, IsZeroConfig},
+    },
+    testing, util,
+};
+
+const SQUARES: usize = 9;
+const M: usize = 9;
+/// the move after the end of the game
+const NONE: u64 = SQUARES as u64;
+
+const PASS: u64 = 0;
+const MOVE: u64 = 1;
+const WIN: u64 = 2;
+
+const X_TURN: u64 = 1;
+const O_TURN: u64 = 2;
+const X_WON: u64 = 3;
+const O_WON: u64 = 4;
+
+const TRANSITIONS: [Transition; 6] = [
+    (X_TURN, MOVE, O_TURN),
+    (O_TURN, MOVE, X_TURN),
+    (X_TURN, WIN, X_WON),
+    (O_TURN, WIN, O_WON),
+    (X_WON, PASS, X_WON),
+    (O_WON, PASS, O_WON),
+];
+
+/// the rows, the columns and the diagonals
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+type Board = [u64; SQUARES];
+
+/// the number of complete lines of a player
+fn lines(board: &Board) -> u64 {
+    LINES
+        .iter()
+        .map(|line| line.iter().map(|s| board[*s]).product::<u64>())
+        .sum()
+}
+
+/// the boards of X and O before every move and after the last one, the kinds of the moves
+/// and the outcome
+fn play(moves: &[u64]) -> (Vec<(Board, Board)>, Vec<u64>, u64) {
+    let (mut x, mut o, mut state) = ([0; SQUARES], [0; SQUARES], X_TURN);
+    let mut boards = vec![(x, o)];
+    let mut kinds = vec![];
+    for square in moves.iter().map(|square| *square as usize) {
+        let kind = match (x.get_mut(square), o.get_mut(square)) {
+            (Some(x), Some(o)) => {
+                *x += u64::from(state == X_TURN);
+                *o += u64::from(state == O_TURN);
+                MOVE
+            }
+            _ => PASS,
+        };
+        let kind = if kind == MOVE && lines(&x) + lines(&o) > 0 {
+            WIN
+        } else {
+            kind
+        };
+        state = fsm::step(&TRANSITIONS, state, kind).unwrap_or(0);
+        boards.push((x, o));
+        kinds.push(kind);
+    }
+    (boards, kinds, state)
+}
+
+#[derive(Debug, Clone)]
+struct TicTacToeConfig {
+    x: [Column<Advice>; SQUARES],
+    o: [Column<Advice>; SQUARES],
+    hit: [Column<Advice>; SQUARES],
+    kind: Column<Advice>,
+    no_line: IsZeroConfig,
+    q_move: Selector,
+    fsm: FsmConfig,
+    instance: Column<Instance>,
+}
+
+struct TicTacToeCircuit<F> {
+    moves: [Value<F>; M],
+}
+
+impl<F> Default for TicTacToeCircuit<F> {
+    fn default() -> Self {
+        Self {
+            moves: [(); M].map(|_| Value::unknown()),
+        }
+    }
+}
+
+/// the number of complete lines of both players on the next row
+fn line_count<F: FieldExt>(
+    meta: &mut VirtualCells<'_, F>,
+    x: &[Column<Advice>; SQUARES],
+    o: &[Column<Advice>; SQUARES],
+) -> Expression<F> {
+    let mut count = Expression::Constant(F::zero());
+    for board in [x, o] {
+        for line in LINES {
+            let [a, b, c] = line.map(|s| meta.query_advice(board[s], Rotation::next()));
+            count = count + a * b * c;
+        }
+    }
+    count
+}
+
+impl<F: FieldExt> Circuit<F> for TicTacToeCircuit<F> {
+    type Config = TicTacToeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> TicTacToeConfig {
+        let x = [(); SQUARES].map(|_| meta.advice_column());
+        let o = [(); SQUARES].map(|_| meta.advice_column());
+        let hit = [(); SQUARES].map(|_| meta.advice_column());
+        let kind = meta.advice_column();
+        let state = meta.advice_column();
+        let input = meta.advice_column();
+        let lines_inv = meta.advice_column();
+        let no_line = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        let q_move = meta.selector();
+        for column in x.into_iter().chain(o).chain([kind]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let fsm = FsmChip::configure(meta, state, input);
+        let no_line = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_move),
+            |meta| line_count(meta, &x, &o),
+            lines_inv,
+            no_line,
+        );
+
+        meta.create_gate("move", |meta| {
+            let q_move = meta.query_selector(q_move);
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+            let state = meta.query_advice(state, Rotation::cur());
+            let kind = meta.query_advice(kind, Rotation::cur());
+            let no_line = no_line.expr(meta, Rotation::cur());
+            let mut constraints = vec![];
+            let mut moved = Expression::Constant(F::zero());
+            for ((x, o), hit) in x.iter().zip(&o).zip(&hit) {
+                let hit = meta.query_advice(*hit, Rotation::cur());
+                let x_next = meta.query_advice(*x, Rotation::next());
+                let x = meta.query_advice(*x, Rotation::cur());
+                let o_next = meta.query_advice(*o, Rotation::next());
+                let o = meta.query_advice(*o, Rotation::cur());
+                constraints.push(q_move.clone() * hit.clone() * (one.clone() - hit.clone()));
+                constraints.push(q_move.clone() * hit.clone() * (x.clone() + o.clone()));
+                constraints.push(
+                    q_move.clone() * (x_next - x - hit.clone() * (two.clone() - state.clone())),
+                );
+                constraints.push(
+                    q_move.clone() * (o_next - o - hit.clone() * (state.clone() - one.clone())),
+                );
+                moved = moved + hit;
+            }
+            constraints.push(q_move.clone() * moved.clone() * (one - moved.clone()));
+            constraints.push(q_move * (kind - moved * (two - no_line)));
+            constraints
+        });
+
+        TicTacToeConfig {
+            x,
+            o,
+            hit,
+            kind,
+            no_line,
+            q_move,
+            fsm,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: TicTacToeConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FsmChip::construct(config.fsm, &TRANSITIONS);
+        chip.load_table(layouter.namespace(|| "transitions"))?;
+
+        let trace = self
+            .moves
+            .iter()
+            .copied()
+            .collect::<Value<Vec<_>>>()
+            .map(|moves| {
+                let moves: Vec<_> = moves.iter().map(|m| m.get_lower_128() as u64).collect();
+                play(&moves)
+            });
+
+        let start = layouter.assign_region(
+            || "start",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "x to move",
+                    config.fsm.state,
+                    0,
+                    F::from(X_TURN),
+                )
+            },
+        )?;
+
+        let outcome = layouter.assign_region(
+            || "game",
+            |mut region| {
+                for column in config.x.into_iter().chain(config.o) {
+                    region.assign_advice_from_constant(|| "empty", column, 0, F::zero())?;
+                }
+                let no_line = IsZeroChip::construct(config.no_line);
+                let mut kinds = vec![];
+                for (t, square) in self.moves.iter().enumerate() {
+                    config.q_move.enable(&mut region, t)?;
+                    for (s, column) in config.hit.iter().enumerate() {
+                        let hit =
+                            square.map(|square| F::from(u64::from(square == F::from(s as u64))));
+                        region.assign_advice(|| "hit", *column, t, || hit)?;
+                    }
+                    let after = trace.as_ref().map(|(boards, _, _)| boards[t + 1]);
+                    for (s, (x, o)) in config.x.iter().zip(&config.o).enumerate() {
+                        let x_value = after.map(|(x, _)| F::from(x[s]));
+                        let o_value = after.map(|(_, o)| F::from(o[s]));
+                        region.assign_advice(|| "x", *x, t + 1, || x_value)?;
+                        region.assign_advice(|| "o", *o, t + 1, || o_value)?;
+                    }
+                    let count = after.map(|(x, o)| F::from(lines(&x) + lines(&o)));
+                    no_line.assign(&mut region, t, count)?;
+                    let kind = trace.as_ref().map(|(_, kinds, _)| F::from(kinds[t]));
+                    kinds.push(region.assign_advice(|| "kind", config.kind, t, || kind)?);
+                }
+                chip.assign(&mut region, 0, &start, &kinds)
+            },
+        )?;
+        layouter.constrain_instance(outcome.cell(), config.instance, 0)
+    }
+}
+
+fn prover(moves: [u64; M], outcome: u64) -> MockProver<Fp> {
+    let circuit = TicTacToeCircuit {
+        moves: moves.map(|square| Value::known(Fp::from(square))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(outcome)]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, TicTacToeCircuit<Fp>>(|config| {
+                let mut names = ColumnNames::new();
+                let squares = config.x.iter().zip(&config.o).zip(&config.hit);
+                for (s, ((x, o), hit)) in squares.enumerate() {
+                    names = names
+                        .with_advice(*x, format!("x[{}]", s))
+                        .with_advice(*o, format!("o[{}]", s))
+                        .with_advice(*hit, format!("hit[{}]", s));
+                }
+                names
+                    .with_advice(config.kind, "kind")
+                    .with_advice(config.no_line.value_inv, "lines_inv")
+                    .with_advice(config.no_line.is_zero, "no_line")
+                    .with_advice(config.fsm.state, "state")
+                    .with_advice(config.fsm.input, "input")
+                    .with_selector(config.q_move, "q_move")
+                    .with_selector(config.fsm.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    // X takes the diagonal on the fifth move
+    let x_wins = [4, 1, 0, 2, 8, NONE, NONE, NONE, NONE];
+    assert_eq!(play(&x_wins).2, X_WON);
+    prover(x_wins, X_WON).assert_satisfied();
+    // O takes the middle column
+    let o_wins = [0, 4, 2, 1, 8, 7, NONE, NONE, NONE];
+    assert_eq!(play(&o_wins).2, O_WON);
+    prover(o_wins, O_WON).assert_satisfied();
+    // a full board without a line
+    let draw = [4, 0, 2, 6, 3, 5, 1, 7, 8];
+    assert_eq!(play(&draw).2, O_TURN);
+    prover(draw, O_TURN).assert_satisfied();
+
+    // O claimed to win the game of X
+    testing::assert_fails_permutation(&prover(x_wins, O_WON));
+
+    // O plays on the square X took first
+    let taken = [4, 4, 0, 2, 8, NONE, NONE, NONE, NONE];
+    testing::assert_fails_gate(&prover(taken, X_WON), "move", 1);
+
+    // the game goes on after X won
+    let on = [4, 1, 0, 2, 8, 3, NONE, NONE, NONE];
+    testing::assert_fails_lookup(&prover(on, X_WON), 5);
+
+    // the game stops before anyone won
+    let early = [4, 1, 0, NONE, NONE, NONE, NONE, NONE, NONE];
+    testing::assert_fails_lookup(&prover(early, X_WON), 3);
+}
@@ -0,0 +1,159 @@
+//! tribonacci circuit
+//!
+//! we are going to prove that t(10) = 81 when t(0) = 0, t(1) = 0, t(2) = 1,
+//! where `t(i + 2) = t(i - 1) + t(i) + t(i + 1)`
+//!
+//! the gate looks two rows ahead with `Rotation(2)`, the last term is carried in `pair`:
+//!
+//! | row |   t   |        pair         | q_step |
+//! |:---:|:-----:|:-------------------:|:------:|
+//! |  0  | t(0)  |    t(2) - t(1)      |   1    |
+//! |  1  | t(1)  |    t(0) + t(1)      |   1    |
+//! |  2  | t(2)  |    t(1) + t(2)      |   1    |
+//! | ... |  ...  |        ...          |  ...   |
+//! | N-2 | t(N-2)| t(N-3) + t(N-2)     |   1    |
+//! | N-1 | t(N-1)| t(N-2) + t(N-1)     |   0    |
+//! |  N  | t(N)  |                     |   0    |
+//!
+//! - `pair[next] = t[cur] + t[next]`
+//! - `t[2] = pair[cur] + t[next]`
+//!
+//! `pair(0)` is forced to `t(2) - t(1)` by the second constraint, so the instance
+//! `[t(0), t(1), t(2), t(N)]` pins down every cell.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    testing, util,
+};
+
+#[derive(Debug, Clone)]
+struct TribConfig {
+    t: Column<Advice>,
+    pair: Column<Advice>,
+    q_step: Selector,
+    instance: Column<Instance>,
+}
+
+/// `t(N)` starting from the three initial terms
+#[derive(Default)]
+struct TribCircuit<F, const N: usize> {
+    initial: [Value<F>; 3],
+}
+
+impl<F: FieldExt, const N: usize> Circuit<F> for TribCircuit<F, N> {
+    type Config = TribConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            initial: [Value::unknown(); 3],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> TribConfig {
+        let t = meta.advice_column();
+        let pair = meta.advice_column();
+        let q_step = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(t);
+        meta.enable_equality(instance);
+
+        meta.create_gate("tribonacci", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let t_cur = meta.query_advice(t, Rotation::cur());
+            let t_next = meta.query_advice(t, Rotation::next());
+            let t_next_next = meta.query_advice(t, Rotation(2));
+            let pair_cur = meta.query_advice(pair, Rotation::cur());
+            let pair_next = meta.query_advice(pair, Rotation::next());
+            vec![
+                q_step.clone() * (pair_next - (t_cur + t_next.clone())),
+                q_step * (t_next_next - (pair_cur + t_next)),
+            ]
+        });
+
+        TribConfig {
+            t,
+            pair,
+            q_step,
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: TribConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (first, last) = layouter.assign_region(
+            || "terms",
+            |mut region| {
+                let mut terms = self.initial.to_vec();
+                for i in 3..=N {
+                    let next = terms[i - 3] + terms[i - 2] + terms[i - 1];
+                    terms.push(next);
+                }
+
+                let mut cells = Vec::with_capacity(N + 1);
+                for (offset, term) in terms.iter().enumerate() {
+                    cells.push(region.assign_advice(|| "t", config.t, offset, || *term)?);
+                }
+                region.assign_advice(|| "pair", config.pair, 0, || terms[2] - terms[1])?;
+                for offset in 1..N {
+                    region.assign_advice(
+                        || "pair",
+                        config.pair,
+                        offset,
+                        || terms[offset - 1] + terms[offset],
+                    )?;
+                }
+                for offset in 0..N - 1 {
+                    config.q_step.enable(&mut region, offset)?;
+                }
+                Ok((cells[..3].to_vec(), cells[N].clone()))
+            },
+        )?;
+        for (row, cell) in first.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, row)?;
+        }
+        layouter.constrain_instance(last.cell(), config.instance, 3)
+    }
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, TribCircuit<Fp, 10>>(|config| {
+                ColumnNames::new()
+                    .with_advice(config.t, "t")
+                    .with_advice(config.pair, "pair")
+                    .with_selector(config.q_step, "q_step")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let circuit = TribCircuit::<Fp, 10> {
+        initial: [0, 0, 1].map(|x| Value::known(Fp::from(x))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instances = |result: u64| {
+        vec![vec![
+            Fp::from(0),
+            Fp::from(0),
+            Fp::from(1),
+            Fp::from(result),
+        ]]
+    };
+
+    let prover_success = MockProver::run(k, &circuit, instances(81)).unwrap();
+    prover_success.assert_satisfied();
+    let prover_failure = MockProver::run(k, &circuit, instances(80)).unwrap();
+    testing::assert_fails_permutation(&prover_failure);
+}
@@ -0,0 +1,264 @@
+//! utf-8 validity circuit
+//!
+//! we are going to prove that a private string of `LEN` bytes, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), is valid utf-8, by
+//! running the utf-8 acceptance automaton over it with the
+//! [`FsmChip`](learn_halo2::gadgets::fsm::FsmChip)
+//!
+//! | instance  |
+//! |-----------|
+//! | H(bytes)  |
+//!
+//! the automaton tracks the continuation bytes left in a character, with a state for each
+//! range the next one has to be in, to rule out overlong encodings, surrogates and code
+//! points above `U+10FFFF`:
+//!
+//! | state  | next byte            | to     |
+//! |--------|----------------------|--------|
+//! | ACCEPT | `00..=7F`            | ACCEPT |
+//! | ACCEPT | `C2..=DF`            | TAIL_1 |
+//! | ACCEPT | `E0`                 | E0     |
+//! | ACCEPT | `E1..=EC`, `EE..=EF` | TAIL_2 |
+//! | ACCEPT | `ED`                 | ED     |
+//! | ACCEPT | `F0`                 | F0     |
+//! | ACCEPT | `F1..=F3`            | TAIL_3 |
+//! | ACCEPT | `F4`                 | F4     |
+//! | TAIL_1 | `80..=BF`            | ACCEPT |
+//! | TAIL_2 | `80..=BF`            | TAIL_1 |
+//! | TAIL_3 | `80..=BF`            | TAIL_2 |
+//! | E0     | `A0..=BF`            | TAIL_1 |
+//! | ED     | `80..=9F`            | TAIL_1 |
+//! | F0     | `90..=BF`            | TAIL_2 |
+//! | F4     | `80..=8F`            | TAIL_2 |
+//!
+//! every one of the 499 transitions is a row of the table, which is also the range check
+//! of the bytes. the run starts and ends in `ACCEPT`, so the last character is complete,
+//! and a shorter string is padded with `00`.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Fixed, Instance},
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        fsm::{self, FsmChip, FsmConfig, Transition},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+const LEN: usize = 16;
+
+const ACCEPT: u64 = 1;
+const TAIL_1: u64 = 2;
+const TAIL_2: u64 = 3;
+const TAIL_3: u64 = 4;
+const E0: u64 = 5;
+const ED: u64 = 6;
+const F0: u64 = 7;
+const F4: u64 = 8;
+
+/// `(state, lo, hi, next)`, a transition for every byte in `lo..=hi`
+const RULES: [(u64, u64, u64, u64); 16] = [
+    (ACCEPT, 0x00, 0x7f, ACCEPT),
+    (ACCEPT, 0xc2, 0xdf, TAIL_1),
+    (ACCEPT, 0xe0, 0xe0, E0),
+    (ACCEPT, 0xe1, 0xec, TAIL_2),
+    (ACCEPT, 0xed, 0xed, ED),
+    (ACCEPT, 0xee, 0xef, TAIL_2),
+    (ACCEPT, 0xf0, 0xf0, F0),
+    (ACCEPT, 0xf1, 0xf3, TAIL_3),
+    (ACCEPT, 0xf4, 0xf4, F4),
+    (TAIL_1, 0x80, 0xbf, ACCEPT),
+    (TAIL_2, 0x80, 0xbf, TAIL_1),
+    (TAIL_3, 0x80, 0xbf, TAIL_2),
+    (E0, 0xa0, 0xbf, TAIL_1),
+    (ED, 0x80, 0x9f, TAIL_1),
+    (F0, 0x90, 0xbf, TAIL_2),
+    (F4, 0x80, 0x8f, TAIL_2),
+];
+
+fn transitions() -> Vec<Transition> {
+    RULES
+        .iter()
+        .flat_map(|(state, lo, hi, next)| (*lo..=*hi).map(move |byte| (*state, byte, *next)))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Utf8Config {
+    fsm: FsmConfig,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct Utf8Circuit<F> {
+    bytes: [Value<F>; LEN],
+}
+
+impl<F> Default for Utf8Circuit<F> {
+    fn default() -> Self {
+        Self {
+            bytes: [(); LEN].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for Utf8Circuit<F> {
+    type Config = Utf8Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Utf8Config {
+        let state = meta.advice_column();
+        let input = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        Utf8Config {
+            fsm: FsmChip::configure(meta, state, input),
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: Utf8Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FsmChip::construct(config.fsm, &transitions());
+        chip.load_table(layouter.namespace(|| "transitions"))?;
+
+        let (start, bytes) = layouter.assign_region(
+            || "bytes",
+            |mut region| {
+                let start = region.assign_advice_from_constant(
+                    || "start",
+                    config.fsm.state,
+                    0,
+                    F::from(ACCEPT),
+                )?;
+                let bytes = self
+                    .bytes
+                    .iter()
+                    .enumerate()
+                    .map(|(row, byte)| {
+                        region.assign_advice(|| "byte", config.fsm.input, row, || *byte)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((start, bytes))
+            },
+        )?;
+        let last = chip.run(layouter.namespace(|| "utf-8"), &start, &bytes)?;
+        layouter.assign_region(
+            || "accept",
+            |mut region| region.constrain_constant(last.cell(), F::from(ACCEPT)),
+        )?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let commitment = poseidon.hash(layouter.namespace(|| "H(bytes)"), &bytes)?;
+        layouter.constrain_instance(commitment.cell(), config.instance, 0)
+    }
+}
+
+/// whether the automaton accepts `bytes`, outside of the circuit
+fn accepts(bytes: &[u8]) -> bool {
+    let transitions = transitions();
+    let last = bytes.iter().try_fold(ACCEPT, |state, byte| {
+        fsm::step(&transitions, state, u64::from(*byte))
+    });
+    last == Some(ACCEPT)
+}
+
+fn padded(bytes: &[u8]) -> [u8; LEN] {
+    let mut padded = [0; LEN];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    padded
+}
+
+fn commit(bytes: &[u8; LEN]) -> Fp {
+    poseidon::hash(&bytes.map(|byte| Fp::from(u64::from(byte))))
+}
+
+fn prover(bytes: &[u8; LEN], commitment: Fp) -> MockProver<Fp> {
+    let circuit = Utf8Circuit {
+        bytes: bytes.map(|byte| Value::known(Fp::from(u64::from(byte)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, Utf8Circuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.fsm.state, "state")
+                    .with_advice(config.fsm.input, "byte");
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.fsm.q_step, "q_step")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    assert_eq!(transitions().len(), 499);
+    // the automaton agrees with the standard library on every string of up to 2 bytes
+    for a in 0..=255 {
+        assert_eq!(accepts(&[a]), std::str::from_utf8(&[a]).is_ok());
+        for b in 0..=255 {
+            assert_eq!(accepts(&[a, b]), std::str::from_utf8(&[a, b]).is_ok());
+        }
+    }
+
+    // 1, 2, 3 and 4 byte characters
+    for text in ["zk proofs ✓", "π≈3 🦀!", "hello, world", ""] {
+        let bytes = padded(text.as_bytes());
+        assert!(accepts(&bytes));
+        prover(&bytes, commit(&bytes)).assert_satisfied();
+    }
+
+    // another string
+    let bytes = padded("zk proofs ✓".as_bytes());
+    let other = padded("zk proofs ✗".as_bytes());
+    testing::assert_fails_permutation(&prover(&other, commit(&bytes)));
+
+    // an overlong encoding of '/'
+    let overlong = padded(b"a/\xc0\xaf");
+    testing::assert_fails_lookup(&prover(&overlong, commit(&overlong)), 2);
+
+    // a surrogate, U+D800
+    let surrogate = padded(b"ab\xed\xa0\x80");
+    testing::assert_fails_lookup(&prover(&surrogate, commit(&surrogate)), 3);
+
+    // a continuation byte on its own
+    let stray = padded(b"abc\x80");
+    testing::assert_fails_lookup(&prover(&stray, commit(&stray)), 3);
+
+    // the last character cut short, every step is valid but the run ends in TAIL_1
+    let mut truncated = padded(b"zk proofs ");
+    truncated[LEN - 2..].copy_from_slice(b"\xe2\x9c");
+    assert!(std::str::from_utf8(&truncated).is_err());
+    testing::assert_fails_permutation(&prover(&truncated, commit(&truncated)));
+}
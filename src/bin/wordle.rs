@@ -0,0 +1,315 @@
+//! wordle circuit
+//!
+//! we are going to prove that a secret word, committed to with the
+//! [`PoseidonChip`](learn_halo2::gadgets::poseidon::PoseidonChip), gives a public feedback
+//! to a public guess
+//!
+//! | instance             |
+//! |----------------------|
+//! | guess[0]             |
+//! | ...                  |
+//! | guess[LEN-1]         |
+//! | feedback[0]          |
+//! | ...                  |
+//! | feedback[LEN-1]      |
+//! | H(salt, secret)      |
+//!
+//! letters are their ascii codes, and the feedback of a letter is 2 (green) when it is the
+//! letter of the secret at the same position, 1 (yellow) when it is elsewhere in the secret
+//! and 0 (gray) otherwise. unlike the game, a repeated letter of the guess is yellow as many
+//! times as it is repeated. the secret has few enough possible values to be found by
+//! hashing them all, the random `salt` hides it.
+//!
+//! the game is one row per letter of the guess, with the whole secret copied on every row,
+//! and two flags of the [`IsZeroChip`](learn_halo2::gadgets::is_zero::IsZeroChip):
+//!
+//! | row | guess    | secret[j] | secret_at | green                        | present                                 | feedback    |
+//! |-----|----------|-----------|-----------|------------------------------|-----------------------------------------|-------------|
+//! | i   | guess[i] | secret[j] | secret[i] | `guess[i] - secret[i] == 0`  | `prod (guess[i] - secret[j]) == 0`      | feedback[i] |
+//!
+//! - `feedback = green + present`
+//!
+//! a green letter is present too, so it counts twice.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use learn_halo2::{
+    constraints::{self, ColumnNames},
+    curve::Fp,
+    gadgets::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        poseidon::{self, PoseidonChip, PoseidonConfig},
+    },
+    testing, util,
+};
+
+const LEN: usize = 5;
+
+#[derive(Debug, Clone)]
+struct WordleConfig {
+    guess: Column<Advice>,
+    secret: [Column<Advice>; LEN],
+    secret_at: Column<Advice>,
+    feedback: Column<Advice>,
+    green: IsZeroConfig,
+    present: IsZeroConfig,
+    q_letter: Selector,
+    poseidon: PoseidonConfig,
+    instance: Column<Instance>,
+}
+
+struct WordleCircuit<F> {
+    salt: Value<F>,
+    secret: [Value<F>; LEN],
+}
+
+impl<F> Default for WordleCircuit<F> {
+    fn default() -> Self {
+        Self {
+            salt: Value::unknown(),
+            secret: [(); LEN].map(|_| Value::unknown()),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for WordleCircuit<F> {
+    type Config = WordleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> WordleConfig {
+        let guess = meta.advice_column();
+        let secret = [(); LEN].map(|_| meta.advice_column());
+        let secret_at = meta.advice_column();
+        let feedback = meta.advice_column();
+        let flags = [(); 4].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        let q_letter = meta.selector();
+        for column in secret.into_iter().chain([guess, secret_at, feedback]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        let green = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_letter),
+            |meta| {
+                meta.query_advice(guess, Rotation::cur())
+                    - meta.query_advice(secret_at, Rotation::cur())
+            },
+            flags[0],
+            flags[1],
+        );
+        let present = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_letter),
+            |meta| {
+                let guess = meta.query_advice(guess, Rotation::cur());
+                secret
+                    .iter()
+                    .fold(Expression::Constant(F::one()), |product, column| {
+                        product * (guess.clone() - meta.query_advice(*column, Rotation::cur()))
+                    })
+            },
+            flags[2],
+            flags[3],
+        );
+
+        meta.create_gate("feedback", |meta| {
+            let q_letter = meta.query_selector(q_letter);
+            let feedback = meta.query_advice(feedback, Rotation::cur());
+            let green = green.expr(meta, Rotation::cur());
+            let present = present.expr(meta, Rotation::cur());
+            vec![q_letter * (feedback - green - present)]
+        });
+
+        WordleConfig {
+            guess,
+            secret,
+            secret_at,
+            feedback,
+            green,
+            present,
+            q_letter,
+            poseidon: PoseidonChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: WordleConfig,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (salt, secret) = layouter.assign_region(
+            || "secret",
+            |mut region| {
+                let salt = region.assign_advice(|| "salt", config.guess, 0, || self.salt)?;
+                let secret = config
+                    .secret
+                    .iter()
+                    .zip(self.secret)
+                    .map(|(column, letter)| {
+                        region.assign_advice(|| "secret", *column, 0, || letter)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((salt, secret))
+            },
+        )?;
+        let poseidon = PoseidonChip::construct(config.poseidon);
+        let inputs: Vec<_> = [salt].into_iter().chain(secret.iter().cloned()).collect();
+        let commitment = poseidon.hash(layouter.namespace(|| "H(salt, secret)"), &inputs)?;
+
+        layouter.assign_region(
+            || "letters",
+            |mut region| {
+                let green = IsZeroChip::construct(config.green);
+                let present = IsZeroChip::construct(config.present);
+                for (offset, at) in secret.iter().enumerate() {
+                    config.q_letter.enable(&mut region, offset)?;
+                    let guess = region.assign_advice_from_instance(
+                        || "guess",
+                        config.instance,
+                        offset,
+                        config.guess,
+                        offset,
+                    )?;
+                    region.assign_advice_from_instance(
+                        || "feedback",
+                        config.instance,
+                        LEN + offset,
+                        config.feedback,
+                        offset,
+                    )?;
+                    let guess = guess.value().copied();
+                    let mut product = Value::known(F::one());
+                    for (cell, column) in secret.iter().zip(config.secret) {
+                        let letter = cell.copy_advice(|| "secret", &mut region, column, offset)?;
+                        product = product * (guess - letter.value().copied());
+                    }
+                    let secret_at =
+                        at.copy_advice(|| "secret at", &mut region, config.secret_at, offset)?;
+                    green.assign(&mut region, offset, guess - secret_at.value().copied())?;
+                    present.assign(&mut region, offset, product)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.constrain_instance(commitment.cell(), config.instance, 2 * LEN)
+    }
+}
+
+/// the feedback of the game, 2 for green, 1 for yellow and 0 for gray
+fn feedback(secret: &[u8; LEN], guess: &[u8; LEN]) -> [u64; LEN] {
+    let mut feedback = [0; LEN];
+    for ((feedback, letter), at) in feedback.iter_mut().zip(guess).zip(secret) {
+        *feedback = u64::from(letter == at) + u64::from(secret.contains(letter));
+    }
+    feedback
+}
+
+fn prover(
+    salt: u64,
+    secret: &[u8; LEN],
+    guess: &[u8; LEN],
+    feedback: [u64; LEN],
+    commitment: Fp,
+) -> MockProver<Fp> {
+    let circuit = WordleCircuit {
+        salt: Value::known(Fp::from(salt)),
+        secret: secret.map(|letter| Value::known(Fp::from(u64::from(letter)))),
+    };
+    let k = util::min_k_for(&circuit).unwrap();
+    let instance = guess
+        .iter()
+        .map(|letter| Fp::from(u64::from(*letter)))
+        .chain(feedback.map(Fp::from))
+        .chain([commitment])
+        .collect();
+    MockProver::run(k, &circuit, vec![instance]).unwrap()
+}
+
+fn commit(salt: u64, secret: &[u8; LEN]) -> Fp {
+    let inputs: Vec<_> = [salt]
+        .into_iter()
+        .chain(secret.iter().map(|letter| u64::from(*letter)))
+        .map(Fp::from)
+        .collect();
+    poseidon::hash(&inputs)
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, WordleCircuit<Fp>>(|config| {
+                let poseidon = config.poseidon;
+                let mut names = ColumnNames::new()
+                    .with_advice(config.guess, "guess")
+                    .with_advice(config.secret_at, "secret_at")
+                    .with_advice(config.feedback, "feedback")
+                    .with_advice(config.green.value_inv, "green_inv")
+                    .with_advice(config.green.is_zero, "green")
+                    .with_advice(config.present.value_inv, "present_inv")
+                    .with_advice(config.present.is_zero, "present");
+                for (j, column) in config.secret.iter().enumerate() {
+                    names = names.with_advice(*column, format!("secret[{}]", j));
+                }
+                for (i, column) in poseidon.state.iter().enumerate() {
+                    names = names.with_advice(*column, format!("state[{}]", i));
+                }
+                for (i, column) in poseidon.input.iter().enumerate() {
+                    names = names.with_advice(*column, format!("poseidon input[{}]", i));
+                }
+                for (i, column) in poseidon.rc.iter().enumerate() {
+                    names = names.with_fixed(*column, format!("rc[{}]", i));
+                }
+                names
+                    .with_selector(config.q_letter, "q_letter")
+                    .with_selector(poseidon.q_absorb, "q_absorb")
+                    .with_selector(poseidon.q_full, "q_full")
+                    .with_selector(poseidon.q_partial, "q_partial")
+                    .with_instance(config.instance, "instance")
+            })
+        );
+    }
+
+    let salt = 0x5eed;
+    let secret = b"crane";
+    let commitment = commit(salt, secret);
+
+    let guess = b"cargo";
+    assert_eq!(feedback(secret, guess), [2, 1, 1, 0, 0]);
+    prover(salt, secret, guess, feedback(secret, guess), commitment).assert_satisfied();
+    let guess = b"crane";
+    prover(salt, secret, guess, [2; LEN], commitment).assert_satisfied();
+
+    // the `o` of cargo claimed yellow
+    testing::assert_fails_gate(
+        &prover(salt, secret, b"cargo", [2, 1, 1, 0, 1], commitment),
+        "feedback",
+        4,
+    );
+
+    // crate gives the same feedback to cargo, but isn't the committed word
+    let guess = b"cargo";
+    assert_eq!(feedback(b"crate", guess), feedback(secret, guess));
+    testing::assert_fails_permutation(&prover(
+        salt,
+        b"crate",
+        guess,
+        feedback(secret, guess),
+        commitment,
+    ));
+}
@@ -0,0 +1,69 @@
+//! stack machine circuit
+//!
+//! we are going to prove that a program run for `n` steps on the stack machine of
+//! [`learn_halo2::zkvm`] leaves `top` on the top of its stack
+//!
+//! | instance |
+//! |----------|
+//! | n        |
+//! | top      |
+//!
+//! the program is in the ROM of the circuit, here `x = 3x + 1` in a loop starting from 1.
+//!
+//! pass `--dump-gates` to print the constraints of the circuit
+
+use halo2_proofs::dev::MockProver;
+use learn_halo2::{
+    constraints,
+    curve::Fp,
+    testing,
+    zkvm::{self, Instruction, ZkvmCircuit},
+};
+
+/// steps the circuit has room for
+const T: usize = 64;
+
+const PROGRAM: [Instruction; 6] = [
+    Instruction::Push(1),
+    Instruction::Push(3),
+    Instruction::Mul,
+    Instruction::Push(1),
+    Instruction::Add,
+    Instruction::Jump(1),
+];
+
+fn prover(steps: u64, top: Fp) -> MockProver<Fp> {
+    let circuit = ZkvmCircuit::<Fp, T>::new(PROGRAM.to_vec(), steps);
+    let k = ZkvmCircuit::<Fp, T>::k();
+    MockProver::run(k, &circuit, vec![vec![Fp::from(steps), top]]).unwrap()
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--dump-gates") {
+        print!(
+            "{}",
+            constraints::dump_circuit::<Fp, ZkvmCircuit<Fp, T>>(|config| config.column_names())
+        );
+    }
+
+    // the first step pushes 1, each loop is 5 more
+    let steps = 1 + 5 * 5;
+    let top = zkvm::run::<Fp>(&PROGRAM, steps)
+        .unwrap()
+        .last()
+        .unwrap()
+        .stack[0];
+    assert_eq!(top, Fp::from(364));
+    prover(steps, top).assert_satisfied();
+
+    // another top
+    testing::assert_fails_permutation(&prover(steps, Fp::from(365)));
+
+    // more steps than rows, n is not 0 on the last row
+    let top = zkvm::run::<Fp>(&PROGRAM, T as u64)
+        .unwrap()
+        .last()
+        .unwrap()
+        .stack[0];
+    testing::assert_fails_permutation(&prover(T as u64 + 1, top));
+}
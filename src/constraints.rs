@@ -0,0 +1,213 @@
+//! constraint system pretty-printer
+//!
+//! [`dump_constraints`] prints every gate as polynomial expressions, e.g.
+//! `s * (1 - is_zero) * (l + r - r[+1])`, followed by the lookups and the columns in the
+//! permutation. columns are printed as `advice[0]`, `fixed[1]`... unless given a
+//! name in [`ColumnNames`].
+
+use crate::util;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{
+        Advice, Any, Circuit, Column, ConstraintSystem, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use std::{collections::HashMap, fmt::Write};
+
+#[derive(Debug, Clone, Default)]
+pub struct ColumnNames {
+    advice: HashMap<usize, String>,
+    fixed: HashMap<usize, String>,
+    instance: HashMap<usize, String>,
+    selectors: HashMap<usize, String>,
+}
+
+impl ColumnNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_advice(mut self, column: Column<Advice>, name: impl Into<String>) -> Self {
+        self.advice.insert(column.index(), name.into());
+        self
+    }
+
+    pub fn with_fixed(mut self, column: Column<Fixed>, name: impl Into<String>) -> Self {
+        self.fixed.insert(column.index(), name.into());
+        self
+    }
+
+    pub fn with_instance(mut self, column: Column<Instance>, name: impl Into<String>) -> Self {
+        self.instance.insert(column.index(), name.into());
+        self
+    }
+
+    pub fn with_selector(mut self, selector: Selector, name: impl Into<String>) -> Self {
+        self.selectors.insert(selector.index(), name.into());
+        self
+    }
+
+    fn name(names: &HashMap<usize, String>, kind: &str, index: usize) -> String {
+        names
+            .get(&index)
+            .cloned()
+            .unwrap_or_else(|| format!("{}[{}]", kind, index))
+    }
+
+    fn advice(&self, index: usize) -> String {
+        Self::name(&self.advice, "advice", index)
+    }
+
+    fn fixed(&self, index: usize) -> String {
+        Self::name(&self.fixed, "fixed", index)
+    }
+
+    fn instance(&self, index: usize) -> String {
+        Self::name(&self.instance, "instance", index)
+    }
+
+    fn selector(&self, index: usize) -> String {
+        Self::name(&self.selectors, "selector", index)
+    }
+
+    fn column(&self, column: &Column<Any>) -> String {
+        match column.column_type() {
+            Any::Advice(_) => self.advice(column.index()),
+            Any::Fixed => self.fixed(column.index()),
+            Any::Instance => self.instance(column.index()),
+        }
+    }
+}
+
+/// gates, lookups and permutation of `cs` with the default column names
+pub fn dump_constraints<F: FieldExt>(cs: &ConstraintSystem<F>) -> String {
+    dump_constraints_named(cs, &ColumnNames::default())
+}
+
+pub fn dump_constraints_named<F: FieldExt>(
+    cs: &ConstraintSystem<F>,
+    names: &ColumnNames,
+) -> String {
+    let mut out = String::new();
+    for gate in cs.gates() {
+        writeln!(out, "gate {:?}:", gate.name()).unwrap();
+        for (i, polynomial) in gate.polynomials().iter().enumerate() {
+            match gate.constraint_name(i) {
+                "" => writeln!(out, "  {}", expression(polynomial, names)),
+                name => writeln!(out, "  {}: {}", name, expression(polynomial, names)),
+            }
+            .unwrap();
+        }
+    }
+    for (i, lookup) in cs.lookups().iter().enumerate() {
+        let list = |expressions: &[Expression<F>]| {
+            expressions
+                .iter()
+                .map(|e| expression(e, names))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        writeln!(
+            out,
+            "lookup {}: ({}) in ({})",
+            i,
+            list(lookup.input_expressions()),
+            list(lookup.table_expressions())
+        )
+        .unwrap();
+    }
+    let columns: Vec<_> = cs
+        .permutation()
+        .get_columns()
+        .iter()
+        .map(|column| names.column(column))
+        .collect();
+    writeln!(out, "permutation: {}", columns.join(", ")).unwrap();
+    out
+}
+
+/// configure `C` and dump its constraints, with the names `names` gives its config
+pub fn dump_circuit<F: FieldExt, C: Circuit<F>>(
+    names: impl FnOnce(&C::Config) -> ColumnNames,
+) -> String {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    dump_constraints_named(&cs, &names(&config))
+}
+
+// binding strength of the printed expression, to know when it needs parentheses
+const SUM: u8 = 0;
+const PRODUCT: u8 = 1;
+const ATOM: u8 = 2;
+
+/// print `expression`, e.g. `s * (l + r - r[+1])`
+pub fn expression<F: FieldExt>(expression: &Expression<F>, names: &ColumnNames) -> String {
+    let query = |name: String, rotation: Rotation| match rotation.0 {
+        0 => (name, ATOM),
+        r => (format!("{}[{:+}]", name, r), ATOM),
+    };
+    let constant = |value: F| {
+        let value = util::display_field(value);
+        let strength = if value.starts_with('-') {
+            PRODUCT
+        } else {
+            ATOM
+        };
+        (value, strength)
+    };
+    let wrap = |(s, strength): (String, u8), min: u8| {
+        if strength < min {
+            format!("({})", s)
+        } else {
+            s
+        }
+    };
+
+    let (s, _) = expression.evaluate(
+        &constant,
+        &|selector| (names.selector(selector.index()), ATOM),
+        &|q| query(names.fixed(q.column_index()), q.rotation()),
+        &|q| query(names.advice(q.column_index()), q.rotation()),
+        &|q| query(names.instance(q.column_index()), q.rotation()),
+        &|challenge| (format!("challenge[{}]", challenge.index()), ATOM),
+        &|e| (format!("-{}", wrap(e, PRODUCT)), PRODUCT),
+        &|a, b| match b.0.strip_prefix('-') {
+            Some(b) => (format!("{} - {}", a.0, b), SUM),
+            None => (format!("{} + {}", a.0, b.0), SUM),
+        },
+        &|a, b| {
+            (
+                format!("{} * {}", wrap(a, PRODUCT), wrap(b, PRODUCT)),
+                PRODUCT,
+            )
+        },
+        &|e, c| {
+            (
+                format!("{} * {}", wrap(constant(c), PRODUCT), wrap(e, PRODUCT)),
+                PRODUCT,
+            )
+        },
+    );
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::FibCircuit;
+    use halo2_proofs::{halo2curves::bn256::Fr, plonk::Circuit};
+
+    #[test]
+    fn fib_gates() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let config = FibCircuit::<Fr>::configure(&mut cs);
+        let dump = dump_constraints_named(&cs, &config.column_names());
+
+        assert!(dump
+            .contains("gate \"is zero\":\n  s * (1 - n * n_inv - is_zero)\n  s * n * is_zero\n"));
+        assert!(dump.contains("  s * (r[+1] - (is_zero * r + (1 - is_zero) * (l + r)))\n"));
+        assert!(dump.contains("lookup 0: (s_range * n) in (fixed[0])\n"));
+        assert!(dump.ends_with("permutation: n, l, r, instance\n"));
+    }
+}
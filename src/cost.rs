@@ -0,0 +1,180 @@
+//! circuit cost report
+//!
+//! summarizes the constraint system of a circuit and estimates its proof size with
+//! `halo2_proofs::dev::CircuitCost`, so layout changes can be compared.
+//!
+//! [`MultiOpenCost`] measures real KZG proofs instead, to compare the GWC and SHPLONK
+//! multi-open arguments on the same circuit and keys.
+
+use crate::{
+    prover::{self, Kzg, KzgShplonk, ProvingBackend},
+    util,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    dev::CircuitCost,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{Circuit, ConstraintSystem, Error, ProvingKey},
+    poly::kzg::commitment::ParamsKZG,
+};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct CostReport {
+    pub name: String,
+    pub k: u32,
+    /// rows reserved for blinding at the end of every column
+    pub reserved_rows: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub gates: usize,
+    pub degree: usize,
+    pub permutation_columns: usize,
+    pub lookups: usize,
+    pub proof_size: usize,
+}
+
+impl CostReport {
+    /// measure `circuit` for proofs over `C` with `instances` public inputs
+    pub fn measure<C: CurveAffine, Circ: Circuit<C::ScalarExt>>(
+        name: impl Into<String>,
+        circuit: &Circ,
+        instances: usize,
+    ) -> Result<Self, Error>
+    where
+        C::ScalarExt: FieldExt,
+    {
+        let k = util::min_k_for(circuit)?;
+        let mut cs = ConstraintSystem::default();
+        Circ::configure(&mut cs);
+        let cost = CircuitCost::<C::CurveExt, Circ>::measure(k as usize, circuit);
+
+        Ok(Self {
+            name: name.into(),
+            k,
+            reserved_rows: cs.minimum_rows(),
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            gates: cs.gates().len(),
+            degree: cs.degree(),
+            permutation_columns: cs.permutation().get_columns().len(),
+            lookups: cs.lookups().len(),
+            proof_size: cost.proof_size(instances).into(),
+        })
+    }
+}
+
+impl fmt::Display for CostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        writeln!(
+            f,
+            "  rows:        2^{} = {} ({} reserved)",
+            self.k,
+            1usize << self.k,
+            self.reserved_rows
+        )?;
+        writeln!(
+            f,
+            "  columns:     {} advice, {} fixed, {} instance, {} selectors",
+            self.advice_columns, self.fixed_columns, self.instance_columns, self.selectors
+        )?;
+        writeln!(f, "  gates:       {} (degree {})", self.gates, self.degree)?;
+        writeln!(f, "  permutation: {} columns", self.permutation_columns)?;
+        writeln!(f, "  lookups:     {}", self.lookups)?;
+        write!(f, "  proof size:  {} bytes", self.proof_size)
+    }
+}
+
+/// size and verify time of a real KZG proof with one multi-open argument
+#[derive(Debug, Clone)]
+pub struct MultiOpenCost {
+    pub name: &'static str,
+    pub proof_size: usize,
+    /// mean over the verifications
+    pub verify_time: Duration,
+}
+
+impl MultiOpenCost {
+    /// prove `circuit()` with GWC and with SHPLONK from the same proving key, and time
+    /// `runs` verifications of each proof
+    pub fn compare<C: Circuit<Fr>>(
+        params: &ParamsKZG<Bn256>,
+        circuit: impl Fn() -> C,
+        instances: &[&[Fr]],
+        runs: u32,
+    ) -> Result<[Self; 2], Error> {
+        let pk = prover::keygen(params, &circuit())?;
+        Ok([
+            Self::measure::<Kzg, _>(params, &pk, circuit(), instances, runs)?,
+            Self::measure::<KzgShplonk, _>(params, &pk, circuit(), instances, runs)?,
+        ])
+    }
+
+    fn measure<B, C>(
+        params: &ParamsKZG<Bn256>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[Fr]],
+        runs: u32,
+    ) -> Result<Self, Error>
+    where
+        B: ProvingBackend<Scalar = Fr, Curve = G1Affine, Params = ParamsKZG<Bn256>>,
+        C: Circuit<Fr>,
+    {
+        let proof = B::prove(params, pk, circuit, instances)?;
+        let start = Instant::now();
+        for _ in 0..runs {
+            B::verify(params, pk.get_vk(), &proof, instances)?;
+        }
+        Ok(Self {
+            name: B::NAME,
+            proof_size: proof.len(),
+            verify_time: start.elapsed() / runs.max(1),
+        })
+    }
+}
+
+impl fmt::Display for MultiOpenCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} proof size: {} bytes, verify: {:.3} ms",
+            self.name,
+            self.proof_size,
+            self.verify_time.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::{self, FibCircuit, PublicInputs};
+
+    #[test]
+    fn compares_gwc_and_shplonk() {
+        let circuit = || FibCircuit::<Fr, 100> {
+            n: Fr::from(42),
+            n_0: Fr::zero(),
+            n_1: Fr::one(),
+        };
+        let instances = PublicInputs::new()
+            .with_n(Fr::from(42))
+            .with_result(fib::expected(Fr::zero(), Fr::one(), 42))
+            .to_instance_columns();
+        let params = prover::setup(FibCircuit::<Fr, 100>::k());
+        let costs =
+            MultiOpenCost::compare(&params, circuit, &[instances[0].as_slice()], 2).unwrap();
+
+        assert!(costs.iter().all(|cost| cost.proof_size > 0));
+        assert_eq!(costs.map(|cost| cost.name), ["kzg", "kzg-shplonk"]);
+    }
+}
@@ -0,0 +1,22 @@
+//! field and commitment scheme selected by the `curve-*` features
+//!
+//! - `curve-bn256` (default): bn256 `Fr` proven with [`crate::prover::Kzg`]
+//! - `curve-pasta`: pasta `Fp` proven with [`crate::prover_ipa::Ipa`]
+//! - `curve-secp256k1`: secp256k1 `Fp`, which has no commitment scheme here, so `MockProver` only
+//!
+//! the features are additive: `curve-secp256k1` wins over `curve-pasta`, which wins over
+//! bn256, so `--features curve-pasta` works without `--no-default-features` and
+//! `--all-features` builds the secp256k1 examples.
+
+#[cfg(not(any(feature = "curve-pasta", feature = "curve-secp256k1")))]
+pub use halo2_proofs::halo2curves::bn256::Fr as Fp;
+#[cfg(not(any(feature = "curve-pasta", feature = "curve-secp256k1")))]
+pub type Backend = crate::prover::Kzg;
+
+#[cfg(all(feature = "curve-pasta", not(feature = "curve-secp256k1")))]
+pub use halo2_proofs::halo2curves::pasta::Fp;
+#[cfg(all(feature = "curve-pasta", not(feature = "curve-secp256k1")))]
+pub type Backend = crate::prover_ipa::Ipa;
+
+#[cfg(feature = "curve-secp256k1")]
+pub use halo2_proofs::halo2curves::secp256k1::Fp;
@@ -0,0 +1,829 @@
+//! fibonacci chip and circuit
+//!
+//! proves fib(n) for 0 <= n < MAX_N, see README.md for the layout.
+//!
+//! MAX_N is a const generic, so circuits of different capacity can be instantiated,
+//! [`FibCircuit::k`] gives the `k` needed for it. [`FibBatchCircuit`] proves several
+//! fib(n) at once.
+//!
+//! [`simple`] is the textbook three column layout, one `a + b = c` row per step.
+
+pub mod simple;
+
+use crate::constraints::ColumnNames;
+use crate::gadgets::{
+    is_zero::{IsZeroChip, IsZeroConfig},
+    select,
+};
+use crate::step::{self, StepChip};
+use halo2_proofs::circuit::{AssignedCell, Cell, Region};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        TableColumn,
+    },
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct FibConfig {
+    // [n, l, r]
+    pub advice: [Column<Advice>; 3],
+    // n == 0, with its n_inv column
+    pub n_is_zero: IsZeroConfig,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+    // enabled on the first row only, `n` is looked up in `range_table` to prove `n < MAX_N`
+    pub range_selector: Selector,
+    pub range_table: TableColumn,
+}
+
+impl FibConfig {
+    /// names for [`crate::constraints::dump_constraints_named`]
+    pub fn column_names(&self) -> ColumnNames {
+        let [n, l, r] = self.advice;
+        ColumnNames::new()
+            .with_advice(n, "n")
+            .with_advice(l, "l")
+            .with_advice(r, "r")
+            .with_advice(self.n_is_zero.value_inv, "n_inv")
+            .with_advice(self.n_is_zero.is_zero, "is_zero")
+            .with_instance(self.instance, "instance")
+            .with_selector(self.selector, "s")
+            .with_selector(self.range_selector, "s_range")
+    }
+}
+
+/// capacity of the circuits used by the examples
+pub const DEFAULT_MAX_N: usize = 370;
+
+pub struct FibChip<F: FieldExt, const MAX_N: usize = DEFAULT_MAX_N> {
+    config: FibConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const MAX_N: usize> FibChip<F, MAX_N> {
+    pub fn construct(config: FibConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [col_n, col_l, col_r, col_n_inv, col_is_zero]: [Column<Advice>; 5],
+        selector: Selector,
+        instance: Column<Instance>,
+        range_selector: Selector,
+        range_table: TableColumn,
+    ) -> FibConfig {
+        meta.enable_equality(col_n);
+        meta.enable_equality(col_l);
+        meta.enable_equality(col_r);
+        meta.enable_equality(instance);
+
+        meta.lookup(|meta| {
+            // n < MAX_N, otherwise the rows run out before n reaches 0
+            let n = meta.query_advice(col_n, Rotation::cur());
+            let s = meta.query_selector(range_selector);
+
+            vec![(s * n, range_table)]
+        });
+
+        let n_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(selector),
+            |meta| meta.query_advice(col_n, Rotation::cur()),
+            col_n_inv,
+            col_is_zero,
+        );
+
+        meta.create_gate("fib", |meta| {
+            let n = meta.query_advice(col_n, Rotation::cur());
+            let n_next = meta.query_advice(col_n, Rotation::next());
+            let is_n_zero = n_is_zero.expr(meta, Rotation::cur());
+
+            let l = meta.query_advice(col_l, Rotation::cur());
+            let l_next = meta.query_advice(col_l, Rotation::next());
+            let r = meta.query_advice(col_r, Rotation::cur());
+            let r_next = meta.query_advice(col_r, Rotation::next());
+
+            let s = meta.query_selector(selector);
+
+            // n == 0 holds the row, n != 0 steps. l has to be held as well, stepping it to r
+            // once n reached 0 would leave fib(n + 1) in l[MAX] for every n < MAX
+            vec![
+                // l' = n == 0 ? l : r
+                s.clone() * (l_next - select::expr(is_n_zero.clone(), l.clone(), r.clone())),
+                // r' = n == 0 ? r : l + r
+                s.clone() * (r_next - select::expr(is_n_zero.clone(), r.clone(), l + r)),
+                // n' = n == 0 ? 0 : n - 1
+                s * (n_next
+                    - select::expr(
+                        is_n_zero,
+                        Expression::Constant(F::zero()),
+                        n - Expression::Constant(F::one()),
+                    )),
+            ]
+        });
+
+        FibConfig {
+            advice: [col_n, col_l, col_r],
+            n_is_zero,
+            selector,
+            instance,
+            range_selector,
+            range_table,
+        }
+    }
+
+    pub fn assign_next_row(
+        &self,
+        region: &mut Region<'_, F>,
+        current_row_offset: usize,
+        n: Value<F>,
+        l: Value<F>,
+        r: Value<F>,
+        is_n_zero: Value<F>,
+    ) -> Result<
+        (
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+        ),
+        Error,
+    > {
+        let [col_n, col_l, col_r] = self.config.advice;
+
+        let next_n = n
+            .zip(is_n_zero)
+            .map(|(n, is_n_zero)| is_n_zero * n + (F::one() - is_n_zero) * (n - F::one()));
+        let next_l = l.zip(r).and_then(|(l, r)| {
+            is_n_zero.map(|is_n_zero| is_n_zero * l + (F::one() - is_n_zero) * r)
+        });
+        let next_r = l.zip(r).and_then(|(l, r)| {
+            is_n_zero.map(|is_n_zero| is_n_zero * r + (F::one() - is_n_zero) * (l + r))
+        });
+
+        // we are done here
+        if current_row_offset != MAX_N - 2 {
+            self.config
+                .selector
+                .enable(region, current_row_offset + 1)?;
+        }
+
+        let next_n = region.assign_advice(|| "n", col_n, current_row_offset + 1, || next_n)?;
+        let next_l = region.assign_advice(|| "l", col_l, current_row_offset + 1, || next_l)?;
+        let next_r = region.assign_advice(|| "r", col_r, current_row_offset + 1, || next_r)?;
+        let next_is_zero = IsZeroChip::construct(self.config.n_is_zero).assign(
+            region,
+            current_row_offset + 1,
+            next_n.value().copied(),
+        )?;
+
+        Ok((next_n, next_l, next_r, next_is_zero))
+    }
+
+    pub fn assign_setup(
+        &self,
+        region: &mut Region<'_, F>,
+        n_0: F,
+        n_1: F,
+        n: F,
+    ) -> Result<
+        (
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+            AssignedCell<F, F>,
+        ),
+        Error,
+    > {
+        let [col_n, col_l, col_r] = self.config.advice;
+
+        self.config.selector.enable(region, 0)?;
+        self.config.range_selector.enable(region, 0)?;
+
+        let n = region.assign_advice(|| "initial n", col_n, 0, || Value::known(n))?;
+        let l = region.assign_advice(|| "initial l0", col_l, 0, || Value::known(n_0))?;
+        let r = region.assign_advice(|| "initial l1/r0", col_r, 0, || Value::known(n_1))?;
+        let is_zero =
+            IsZeroChip::construct(self.config.n_is_zero).assign(region, 0, n.value().copied())?;
+        Ok((n, l, r, is_zero))
+    }
+
+    pub fn load_range_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "n range",
+            |mut table| {
+                for n in 0..MAX_N {
+                    table.assign_cell(
+                        || "n",
+                        self.config.range_table,
+                        n,
+                        || Value::known(F::from(n as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// expose the cells of the `index`-th computation, at instance rows `4 * index..4 * index + 4`
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        index: usize,
+        n_cell: Cell,
+        l0_cell: Cell,
+        r0_cell: Cell,
+        l_last_cell: Cell,
+    ) -> Result<(), Error> {
+        // - `l[0] = instance[0]`
+        // - `r[0] = instance[1]`, not `l[1]`, which holds `l[0]` when n = 0
+        // - `l[MAX] = instance[3]` => to minimize rows that are equality enabled
+        // - `n[0] = instance[2]`
+        let base = PublicInputs::<F>::LEN * index;
+        layouter.constrain_instance(l0_cell, self.config.instance, base)?;
+        layouter.constrain_instance(r0_cell, self.config.instance, base + 1)?;
+        layouter.constrain_instance(n_cell, self.config.instance, base + 2)?;
+        layouter.constrain_instance(l_last_cell, self.config.instance, base + 3)?;
+        Ok(())
+    }
+}
+
+/// the cells assigned in one row of [`FibChip`]
+#[derive(Debug, Clone)]
+pub struct FibRow<F: FieldExt> {
+    pub n: AssignedCell<F, F>,
+    pub l: AssignedCell<F, F>,
+    pub r: AssignedCell<F, F>,
+    pub is_zero: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt, const MAX_N: usize> StepChip<F> for FibChip<F, MAX_N> {
+    type Config = FibConfig;
+    // (fib(0), fib(1), n)
+    type Input = (F, F, F);
+    type Row = FibRow<F>;
+    // [n, l0, r0, l_last]
+    type Public = [Cell; 4];
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> FibConfig {
+        let col_n = meta.advice_column();
+        let col_l = meta.advice_column();
+        let col_r = meta.advice_column();
+        let col_n_inv = meta.advice_column();
+        let col_is_zero = meta.advice_column();
+        let selector = meta.selector();
+        let instance = meta.instance_column();
+        let range_selector = meta.complex_selector();
+        let range_table = meta.lookup_table_column();
+
+        Self::configure(
+            meta,
+            [col_n, col_l, col_r, col_n_inv, col_is_zero],
+            selector,
+            instance,
+            range_selector,
+            range_table,
+        )
+    }
+
+    fn construct(config: FibConfig) -> Self {
+        Self::construct(config)
+    }
+
+    fn assign_first_row(
+        &self,
+        region: &mut Region<'_, F>,
+        &(n_0, n_1, n): &(F, F, F),
+    ) -> Result<FibRow<F>, Error> {
+        let (n, l, r, is_zero) = self.assign_setup(region, n_0, n_1, n)?;
+        Ok(FibRow { n, l, r, is_zero })
+    }
+
+    fn assign_step(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &FibRow<F>,
+    ) -> Result<FibRow<F>, Error> {
+        let (n, l, r, is_zero) = self.assign_next_row(
+            region,
+            offset,
+            row.n.value().copied(),
+            row.l.value().copied(),
+            row.r.value().copied(),
+            row.is_zero.value().copied(),
+        )?;
+        Ok(FibRow { n, l, r, is_zero })
+    }
+
+    fn finalize(&self, rows: &[FibRow<F>]) -> [Cell; 4] {
+        let last = rows.last().expect("at least one row");
+        [
+            rows[0].n.cell(),
+            rows[0].l.cell(),
+            rows[0].r.cell(),
+            last.l.cell(),
+        ]
+    }
+
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        [n, l0, r0, l_last]: [Cell; 4],
+    ) -> Result<(), Error> {
+        self.expose_public(layouter, 0, n, l0, r0, l_last)
+    }
+}
+
+/// the witness can be loaded from json with [`crate::json::read`]
+#[derive(Default, Serialize, Deserialize)]
+#[serde(bound = "F: FieldExt", deny_unknown_fields)]
+pub struct FibCircuit<F, const MAX_N: usize = DEFAULT_MAX_N> {
+    #[serde(with = "crate::json::field")]
+    pub n_0: F,
+    #[serde(with = "crate::json::field")]
+    pub n_1: F,
+    #[serde(with = "crate::json::field")]
+    pub n: F,
+}
+
+impl<F: FieldExt, const MAX_N: usize> FibCircuit<F, MAX_N> {
+    /// the smallest `k` fitting MAX_N rows plus the rows reserved for blinding
+    pub fn k() -> u32 {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        let rows = MAX_N + cs.minimum_rows();
+        usize::BITS - (rows - 1).leading_zeros()
+    }
+}
+
+impl<F: FieldExt, const MAX_N: usize> Circuit<F> for FibCircuit<F, MAX_N> {
+    type Config = FibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        <FibChip<F, MAX_N> as StepChip<F>>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibChip::<F, MAX_N>::construct(config);
+        chip.load_range_table(layouter.namespace(|| "n range"))?;
+        step::synthesize(
+            &chip,
+            layouter.namespace(|| "fib"),
+            &(self.n_0, self.n_1, self.n),
+            MAX_N,
+        )
+    }
+}
+
+/// [`FibCircuit`] repeated `M` times in one proof, every computation in a region of its own
+///
+/// the instance column holds the [`PublicInputs`] of each computation one after another,
+/// see [`PublicInputs::to_batch_instance_columns`].
+pub struct FibBatchCircuit<F, const M: usize, const MAX_N: usize = DEFAULT_MAX_N> {
+    /// `(fib(0), fib(1), n)` of each computation
+    pub inputs: [(F, F, F); M],
+}
+
+impl<F: FieldExt, const M: usize, const MAX_N: usize> Default for FibBatchCircuit<F, M, MAX_N> {
+    fn default() -> Self {
+        Self {
+            inputs: [(F::zero(), F::one(), F::zero()); M],
+        }
+    }
+}
+
+impl<F: FieldExt, const M: usize, const MAX_N: usize> Circuit<F> for FibBatchCircuit<F, M, MAX_N> {
+    type Config = FibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        <FibChip<F, MAX_N> as StepChip<F>>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibChip::<F, MAX_N>::construct(config);
+        chip.load_range_table(layouter.namespace(|| "n range"))?;
+        for (index, input) in self.inputs.iter().enumerate() {
+            let [n, l0, r0, l_last] = step::assign_rows(
+                &chip,
+                layouter.namespace(|| format!("fib {}", index)),
+                input,
+                MAX_N,
+            )?;
+            chip.expose_public(
+                layouter.namespace(|| format!("expose public {}", index)),
+                index,
+                n,
+                l0,
+                r0,
+                l_last,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// fib(n) computed natively, for `fib(0) = n_0, fib(1) = n_1`
+pub fn expected<F: FieldExt>(n_0: F, n_1: F, n: u64) -> F {
+    let (mut l, mut r) = (n_0, n_1);
+    for _ in 0..n {
+        (l, r) = (r, l + r);
+    }
+    l
+}
+
+/// public inputs of [`FibCircuit`]
+///
+/// the single instance column is laid out as `[fib(0), fib(1), n, fib(n)]`.
+#[derive(Debug, Clone)]
+pub struct PublicInputs<F> {
+    pub n_0: F,
+    pub n_1: F,
+    pub n: F,
+    pub result: F,
+}
+
+impl<F: FieldExt> Default for PublicInputs<F> {
+    fn default() -> Self {
+        Self {
+            n_0: F::zero(),
+            n_1: F::one(),
+            n: F::zero(),
+            result: F::zero(),
+        }
+    }
+}
+
+impl<F: FieldExt> PublicInputs<F> {
+    /// instance rows taken by one computation
+    pub const LEN: usize = 4;
+
+    /// `fib(0) = 0, fib(1) = 1`, `n` and `fib(n)` still have to be set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_initials(mut self, n_0: F, n_1: F) -> Self {
+        self.n_0 = n_0;
+        self.n_1 = n_1;
+        self
+    }
+
+    pub fn with_n(mut self, n: F) -> Self {
+        self.n = n;
+        self
+    }
+
+    pub fn with_result(mut self, result: F) -> Self {
+        self.result = result;
+        self
+    }
+
+    /// the inverse of [`PublicInputs::to_instance_columns`], `None` if the layout does not match
+    pub fn from_instance_columns(columns: &[Vec<F>]) -> Option<Self> {
+        match columns {
+            [column] => match column[..] {
+                [n_0, n_1, n, result] => Some(Self {
+                    n_0,
+                    n_1,
+                    n,
+                    result,
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_instance_columns(&self) -> Vec<Vec<F>> {
+        vec![vec![self.n_0, self.n_1, self.n, self.result]]
+    }
+
+    /// the instance column of [`FibBatchCircuit`], the layout of every entry one after another
+    pub fn to_batch_instance_columns(batch: &[Self]) -> Vec<Vec<F>> {
+        vec![batch
+            .iter()
+            .flat_map(|inputs| inputs.to_instance_columns().remove(0))
+            .collect()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256::Fr,
+    };
+    use proptest::prelude::*;
+
+    /// run the circuit with the instances an honest prover would compute for `n`
+    fn run(n: u64) -> Result<(), Vec<VerifyFailure>> {
+        // the last row holds fib(min(n, MAX_N - 1))
+        let (mut l, mut r) = (Fr::zero(), Fr::one());
+        for _ in 0..n.min(DEFAULT_MAX_N as u64 - 1) {
+            (l, r) = (r, l + r);
+        }
+        let circuit: FibCircuit<Fr> = FibCircuit {
+            n: Fr::from(n),
+            n_0: Fr::zero(),
+            n_1: Fr::one(),
+        };
+        let instances = PublicInputs::new()
+            .with_n(Fr::from(n))
+            .with_result(l)
+            .to_instance_columns();
+        MockProver::run(FibCircuit::<Fr>::k(), &circuit, instances)
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn n_in_range() {
+        assert_eq!(run(0), Ok(()));
+        assert_eq!(run(DEFAULT_MAX_N as u64 - 1), Ok(()));
+    }
+
+    #[test]
+    fn k_from_max_n() {
+        assert_eq!(FibCircuit::<Fr>::k(), 9);
+        assert_eq!(FibCircuit::<Fr, 100>::k(), 7);
+        assert_eq!(FibCircuit::<Fr, 1000>::k(), 10);
+    }
+
+    #[test]
+    fn n_out_of_range() {
+        for n in [DEFAULT_MAX_N as u64, 1000] {
+            let failures = run(n).unwrap_err();
+            assert!(failures
+                .iter()
+                .any(|failure| matches!(failure, VerifyFailure::Lookup { .. })));
+        }
+    }
+
+    /// assert the circuit of capacity `MAX_N` proves the natively computed fib(n)
+    fn assert_complete<const MAX_N: usize>(n_0: u64, n_1: u64, n: u64) {
+        let (n_0, n_1) = (Fr::from(n_0), Fr::from(n_1));
+        let circuit: FibCircuit<Fr, MAX_N> = FibCircuit {
+            n: Fr::from(n),
+            n_0,
+            n_1,
+        };
+        let instances = PublicInputs::new()
+            .with_initials(n_0, n_1)
+            .with_n(Fr::from(n))
+            .with_result(expected(n_0, n_1, n))
+            .to_instance_columns();
+        MockProver::run(FibCircuit::<Fr, MAX_N>::k(), &circuit, instances)
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn complete_for_every_n() {
+        for n in 0..100 {
+            assert_complete::<100>(0, 1, n);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn complete_max_n_100(n_0: u64, n_1: u64, n in 0..100u64) {
+            assert_complete::<100>(n_0, n_1, n);
+        }
+
+        #[test]
+        fn complete_default_max_n(n_0: u64, n_1: u64, n in 0..DEFAULT_MAX_N as u64) {
+            assert_complete::<DEFAULT_MAX_N>(n_0, n_1, n);
+        }
+
+        #[test]
+        fn complete_max_n_1000(n_0: u64, n_1: u64, n in 0..1000u64) {
+            assert_complete::<1000>(n_0, n_1, n);
+        }
+    }
+
+    /// three computations in one proof, with the result of `wrong` off by one
+    fn batch(wrong: Option<usize>) -> MockProver<Fr> {
+        let inputs = [(0, 1, 5), (2, 1, 10), (0, 1, 99)]
+            .map(|(n_0, n_1, n)| (Fr::from(n_0), Fr::from(n_1), n));
+        let circuit = FibBatchCircuit::<Fr, 3, 100> {
+            inputs: inputs.map(|(n_0, n_1, n)| (n_0, n_1, Fr::from(n))),
+        };
+        let public: Vec<_> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, &(n_0, n_1, n))| {
+                let mut result = expected(n_0, n_1, n);
+                if wrong == Some(index) {
+                    result += Fr::one();
+                }
+                PublicInputs::new()
+                    .with_initials(n_0, n_1)
+                    .with_n(Fr::from(n))
+                    .with_result(result)
+            })
+            .collect();
+        let k = crate::util::min_k_for(&circuit).unwrap();
+        MockProver::run(
+            k,
+            &circuit,
+            PublicInputs::to_batch_instance_columns(&public),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn batch_of_three() {
+        batch(None).assert_satisfied();
+        for wrong in 0..3 {
+            testing::assert_fails_permutation(&batch(Some(wrong)));
+        }
+    }
+
+    /// the honest circuit for `n`, with the cell of `column` at `row` overwritten by `value`
+    #[derive(Clone, Copy)]
+    struct CheatCircuit {
+        n: u64,
+        column: usize,
+        row: usize,
+        value: u64,
+    }
+
+    impl Circuit<Fr> for CheatCircuit {
+        type Config = FibConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            *self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> FibConfig {
+            FibCircuit::<Fr>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: FibConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = FibChip::<Fr>::construct(config.clone());
+            chip.load_range_table(layouter.namespace(|| "n range"))?;
+            let public = layouter.assign_region(
+                || "rows",
+                |mut region| {
+                    let mut rows = vec![chip.assign_first_row(
+                        &mut region,
+                        &(Fr::zero(), Fr::one(), Fr::from(self.n)),
+                    )?];
+                    for offset in 0..DEFAULT_MAX_N - 1 {
+                        let row = chip.assign_step(&mut region, offset, &rows[offset])?;
+                        rows.push(row);
+                    }
+                    let [n, l, r] = config.advice;
+                    let columns = [
+                        n,
+                        l,
+                        r,
+                        config.n_is_zero.value_inv,
+                        config.n_is_zero.is_zero,
+                    ];
+                    region.assign_advice(
+                        || "cheat",
+                        columns[self.column],
+                        self.row,
+                        || Value::known(Fr::from(self.value)),
+                    )?;
+                    Ok(chip.finalize(&rows))
+                },
+            )?;
+            StepChip::expose_public(&chip, layouter.namespace(|| "expose public"), public)
+        }
+    }
+
+    fn cheat(n: u64, column: usize, row: usize, value: u64) -> MockProver<Fr> {
+        let instances = PublicInputs::new()
+            .with_n(Fr::from(n))
+            .with_result(expected(Fr::zero(), Fr::one(), n))
+            .to_instance_columns();
+        let circuit = CheatCircuit {
+            n,
+            column,
+            row,
+            value,
+        };
+        MockProver::run(FibCircuit::<Fr>::k(), &circuit, instances).unwrap()
+    }
+
+    const N: usize = 0;
+    const L: usize = 1;
+    const R: usize = 2;
+    const N_INV: usize = 3;
+    const IS_ZERO: usize = 4;
+
+    #[test]
+    fn cheat_is_zero() {
+        // claiming n = 5 is zero
+        testing::assert_fails_gate(&cheat(5, N_INV, 0, 0), "is zero", 0);
+        testing::assert_fails_gate(&cheat(5, IS_ZERO, 0, 1), "is zero", 0);
+        // claiming n = 0 is not
+        testing::assert_fails_gate(&cheat(0, IS_ZERO, 0, 0), "is zero", 0);
+    }
+
+    #[test]
+    fn cheat_n() {
+        // skipping a step, n' = n - 1
+        testing::assert_fails_gate(&cheat(5, N, 2, 2), "fib", 1);
+        // restarting after reaching zero, n == 0 => n' = 0
+        testing::assert_fails_gate(&cheat(2, N, 4, 1), "fib", 3);
+    }
+
+    #[test]
+    fn cheat_l() {
+        // n != 0 => l' = r
+        testing::assert_fails_gate(&cheat(5, L, 2, 7), "fib", 1);
+        // n == 0 => l' = l
+        testing::assert_fails_gate(&cheat(2, L, 4, 7), "fib", 3);
+    }
+
+    #[test]
+    fn cheat_r() {
+        // n != 0 => r' = l + r
+        testing::assert_fails_gate(&cheat(5, R, 3, 7), "fib", 2);
+        // n == 0 => r' = r
+        testing::assert_fails_gate(&cheat(2, R, 4, 7), "fib", 3);
+    }
+
+    #[test]
+    fn cheat_range() {
+        // the range table is filled with 0..MAX_N
+        let mut prover = cheat(5, N, 0, DEFAULT_MAX_N as u64);
+        testing::assert_fails_lookup(&prover, 0);
+        prover = cheat(5, N, 0, 6);
+        testing::assert_fails_permutation(&prover);
+    }
+
+    #[test]
+    fn cheat_result() {
+        // the last l is copied to the instance
+        testing::assert_fails_permutation(&cheat(5, L, DEFAULT_MAX_N - 1, 6));
+    }
+
+    #[test]
+    fn holds_from_the_first_row() {
+        // n = 0 selects the hold branch on row 0 already, with fib(1) still public
+        assert_complete::<100>(3, 7, 0);
+        // stepping l' = r anyway
+        testing::assert_fails_gate(&cheat(0, L, 1, 1), "fib", 0);
+    }
+
+    #[test]
+    fn holds_fib_n() {
+        // l[MAX] is fib(n), not the fib(n + 1) of r on the rows held after n reached 0
+        for n in [0, 5, 98, 99] {
+            assert_complete::<100>(0, 1, n);
+            let circuit: FibCircuit<Fr, 100> = FibCircuit {
+                n: Fr::from(n),
+                n_0: Fr::zero(),
+                n_1: Fr::one(),
+            };
+            let instances = PublicInputs::new()
+                .with_n(Fr::from(n))
+                .with_result(expected(Fr::zero(), Fr::one(), n + 1))
+                .to_instance_columns();
+            let prover = MockProver::run(FibCircuit::<Fr, 100>::k(), &circuit, instances).unwrap();
+            testing::assert_fails_permutation(&prover);
+        }
+    }
+}
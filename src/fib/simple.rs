@@ -1,45 +1,70 @@
-//! simple fibonacci circuit
+//! the textbook fibonacci circuit
 //!
-//! we are going to prove that fib(5) = 8 when fib(0) = 0, fib(1) = 1
+//! a row `a + b = c` per step, each row copying `b` and `c` of the one above into `a` and
+//! `b`:
+//!
+//! | a          | b      | c          | s |
+//! |------------|--------|------------|---|
+//! | fib(0)     | fib(1) | fib(2)     | 1 |
+//! | fib(1)     | fib(2) | fib(3)     | 1 |
+//! | ...        | ...    | ...        | 1 |
+//! | fib(n - 1) | fib(n) | fib(n + 1) | 1 |
+//!
+//! the instance column is `[fib(0), fib(1), fib(n + 1)]`, so `n = 5` proves fib(6) = 8.
+//!
+//! it keeps a layout of its own, because the number of rows follows `n`: `n` is not an
+//! input, so a proof does not say which fib(n) it is about, and every `n` needs its own
+//! keys. it is the simplest circuit to start reading from, [`super::FibChip`] is the one
+//! to embed, it takes `n` as a public input and checks it against the rows.
 
-use halo2_proofs::circuit::Cell;
+use crate::constraints::ColumnNames;
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
-    dev::MockProver,
-    halo2curves::secp256k1::Fp,
+    circuit::{AssignedCell, Cell, Layouter, Region, SimpleFloorPlanner, Value},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
     poly::Rotation,
 };
 use std::marker::PhantomData;
 
 #[derive(Debug, Clone)]
-struct FibConfig {
+pub struct SimpleFibConfig {
     // [a, b, c]
-    advice: [Column<Advice>; 3],
-    selector: Selector,
-    instance: Column<Instance>,
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
 }
 
-struct FibChip<F: FieldExt> {
-    config: FibConfig,
+impl SimpleFibConfig {
+    pub fn column_names(&self) -> ColumnNames {
+        let [a, b, c] = self.advice;
+        ColumnNames::new()
+            .with_advice(a, "a")
+            .with_advice(b, "b")
+            .with_advice(c, "c")
+            .with_instance(self.instance, "instance")
+            .with_selector(self.selector, "s")
+    }
+}
+
+pub struct SimpleFibChip<F: FieldExt> {
+    config: SimpleFibConfig,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> FibChip<F> {
-    fn construct(config: FibConfig) -> Self {
+impl<F: FieldExt> SimpleFibChip<F> {
+    pub fn construct(config: SimpleFibConfig) -> Self {
         Self {
             config,
             _marker: PhantomData,
         }
     }
 
-    fn configure(
+    pub fn configure(
         meta: &mut ConstraintSystem<F>,
         [col_a, col_b, col_c]: [Column<Advice>; 3],
         selector: Selector,
         instance: Column<Instance>,
-    ) -> FibConfig {
+    ) -> SimpleFibConfig {
         meta.enable_equality(col_a);
         meta.enable_equality(col_b);
         meta.enable_equality(col_c);
@@ -55,14 +80,15 @@ impl<F: FieldExt> FibChip<F> {
             vec![s * (a + b - c)]
         });
 
-        FibConfig {
+        SimpleFibConfig {
             advice: [col_a, col_b, col_c],
             selector,
             instance,
         }
     }
 
-    fn assign_setup(
+    /// assign the first row `n_0, n_1, n_0 + n_1`
+    pub fn assign_setup(
         &self,
         region: &mut Region<'_, F>,
         n_0: F,
@@ -79,7 +105,8 @@ impl<F: FieldExt> FibChip<F> {
         Ok((a, b, c))
     }
 
-    fn assign_row(
+    /// assign the row at `offset` from `b` and `c` of the row above, returning its own
+    pub fn assign_row(
         &self,
         region: &mut Region<'_, F>,
         offset: usize,
@@ -102,7 +129,7 @@ impl<F: FieldExt> FibChip<F> {
         Ok((b, c))
     }
 
-    fn expose_public(
+    pub fn expose_public(
         &self,
         mut layouter: impl Layouter<F>,
         initial_a: Cell,
@@ -116,19 +143,23 @@ impl<F: FieldExt> FibChip<F> {
     }
 }
 
+/// fib(n + 1) from `n_0` and `n_1` in `n` rows, `n >= 1`
 #[derive(Default)]
-struct FibCircuit<F> {
+pub struct SimpleFibCircuit<F> {
     pub n_0: F,
     pub n_1: F,
     pub n: F,
 }
 
-impl<F: FieldExt> Circuit<F> for FibCircuit<F> {
-    type Config = FibConfig;
+impl<F: FieldExt> Circuit<F> for SimpleFibCircuit<F> {
+    type Config = SimpleFibConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self {
+            n: self.n,
+            ..Self::default()
+        }
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -138,7 +169,7 @@ impl<F: FieldExt> Circuit<F> for FibCircuit<F> {
         let instance = meta.instance_column();
         let selector = meta.selector();
 
-        FibChip::configure(meta, [col_a, col_b, col_c], selector, instance)
+        SimpleFibChip::configure(meta, [col_a, col_b, col_c], selector, instance)
     }
 
     fn synthesize(
@@ -146,7 +177,7 @@ impl<F: FieldExt> Circuit<F> for FibCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        let chip = FibChip::construct(config);
+        let chip = SimpleFibChip::construct(config);
         let (initial_a, initial_b, result) = layouter.assign_region(
             || "rows",
             |mut region| {
@@ -169,25 +200,26 @@ impl<F: FieldExt> Circuit<F> for FibCircuit<F> {
     }
 }
 
-fn main() {
-    let circuit = FibCircuit {
-        n: Fp::from(5),
-        n_0: Fp::from(0),
-        n_1: Fp::from(1),
-    };
-
-    let prover_success = MockProver::run(
-        4,
-        &circuit,
-        vec![vec![Fp::from(0), Fp::from(1), Fp::from(8)]],
-    )
-    .unwrap();
-    prover_success.assert_satisfied();
-    let prover_failure = MockProver::run(
-        4,
-        &circuit,
-        vec![vec![Fp::from(1), Fp::from(1), Fp::from(8)]],
-    )
-    .unwrap();
-    prover_failure.verify().unwrap_err();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fib, testing, util};
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    #[test]
+    fn fib_6_in_5_rows() {
+        let circuit = SimpleFibCircuit {
+            n: Fr::from(5),
+            n_0: Fr::from(0),
+            n_1: Fr::from(1),
+        };
+        let k = util::min_k_for(&circuit).unwrap();
+        let result = fib::expected(Fr::zero(), Fr::one(), 6);
+        assert_eq!(result, Fr::from(8));
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fr::zero(), Fr::one(), result]]);
+        prover.unwrap().assert_satisfied();
+        let prover = MockProver::run(k, &circuit, vec![vec![Fr::one(), Fr::one(), result]]);
+        testing::assert_fails_permutation(&prover.unwrap());
+    }
 }
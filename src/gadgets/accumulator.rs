@@ -0,0 +1,172 @@
+//! running sum gadget
+//!
+//! | row | x    | acc                     | q_first | q_sum |
+//! |-----|------|-------------------------|---------|-------|
+//! | 0   | x[0] | 0                       | 1       | 1     |
+//! | i   | x[i] | x[0] + ... + x[i-1]     | 0       | 1     |
+//! | len |      | x[0] + ... + x[len-1]   | 0       | 0     |
+//!
+//! - `q_first * acc = 0`
+//! - `q_sum * (acc' - acc - x) = 0`
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RunningSumConfig {
+    pub x: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_first: Selector,
+    pub q_sum: Selector,
+}
+
+pub struct RunningSumChip<F: FieldExt> {
+    config: RunningSumConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RunningSumChip<F> {
+    pub fn construct(config: RunningSumConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> RunningSumConfig {
+        meta.enable_equality(acc);
+        let q_first = meta.selector();
+        let q_sum = meta.selector();
+
+        meta.create_gate("running sum", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_sum = meta.query_selector(q_sum);
+            let x = meta.query_advice(x, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![q_first * acc.clone(), q_sum * (acc_next - acc - x)]
+        });
+
+        RunningSumConfig {
+            x,
+            acc,
+            q_first,
+            q_sum,
+        }
+    }
+
+    /// assign `xs` and their running sum, the cell of the total is returned
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        xs: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (_, mut accs) = self.assign_cells(layouter, xs)?;
+        Ok(accs.pop().unwrap())
+    }
+
+    /// assign `xs` and their running sum, the cells of `xs` and of the `len + 1` partial
+    /// sums are returned. the cells of `xs` can only be copied if `x` has equality enabled
+    pub fn assign_cells(
+        &self,
+        mut layouter: impl Layouter<F>,
+        xs: &[Value<F>],
+    ) -> Result<(Vec<AssignedCell<F, F>>, Vec<AssignedCell<F, F>>), Error> {
+        layouter.assign_region(
+            || "running sum",
+            |mut region| {
+                self.config.q_first.enable(&mut region, 0)?;
+                let mut acc = Value::known(F::zero());
+                let mut x_cells = Vec::with_capacity(xs.len());
+                let mut acc_cells = Vec::with_capacity(xs.len() + 1);
+                for (offset, x) in xs.iter().enumerate() {
+                    self.config.q_sum.enable(&mut region, offset)?;
+                    x_cells.push(region.assign_advice(|| "x", self.config.x, offset, || *x)?);
+                    acc_cells.push(region.assign_advice(
+                        || "acc",
+                        self.config.acc,
+                        offset,
+                        || acc,
+                    )?);
+                    acc = acc + *x;
+                }
+                acc_cells.push(region.assign_advice(
+                    || "sum",
+                    self.config.acc,
+                    xs.len(),
+                    || acc,
+                )?);
+                Ok((x_cells, acc_cells))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        xs: Vec<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (RunningSumConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                xs: vec![0; self.xs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let x = meta.advice_column();
+            let acc = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (RunningSumChip::configure(meta, x, acc), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let xs: Vec<_> = self.xs.iter().map(|x| Value::known(Fr::from(*x))).collect();
+            let sum =
+                RunningSumChip::construct(config).assign(layouter.namespace(|| "sum"), &xs)?;
+            layouter.constrain_instance(sum.cell(), instance, 0)
+        }
+    }
+
+    fn run(xs: &[u64], sum: u64) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = TestCircuit { xs: xs.to_vec() };
+        MockProver::run(5, &circuit, vec![vec![Fr::from(sum)]])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn sums() {
+        assert_eq!(run(&[], 0), Ok(()));
+        assert_eq!(run(&[1, 2, 3, 4], 10), Ok(()));
+        assert!(run(&[1, 2, 3, 4], 11).is_err());
+    }
+}
@@ -0,0 +1,610 @@
+//! 256 bit integer gadget
+//!
+//! a [`BigUint`] is 4 little endian 64 bit limbs, range checked by the [`U64Chip`]. the
+//! operations take rows of 4 limbs each in columns of their own:
+//!
+//! - `add`: rows `a, b, s, c` with `a[k] + b[k] + c[k-1] = s[k] + c[k] * 2^64` and boolean
+//!   carries, `c[-1]` is 0, or 1 for `a + b + 1`
+//! - `mul_add`: rows `a, b, e, z[0..4], z[4..8], c_lo[0..4], c_lo[4..7], c_hi[0..4], c_hi[4..7]`
+//!   with `sum(a[i] * b[j], i + j = k) + e[k] + c[k-1] = z[k] + c[k] * 2^64`, where
+//!   `c[k] = c_lo[k] + c_hi[k] * 2^64` and there is no carry out of `z[7]`. every term is
+//!   below `2^193`, so none of the equations wraps around the field
+//! - `reduce`: `x = q * m + r` by `mul_add(q, m, r)`, and `r < m` by `r + s + 1 = m`
+//!   without a carry
+
+use crate::gadgets::uint::{U64Chip, UintConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+pub const LIMBS: usize = 4;
+
+/// a range checked 256 bit integer
+#[derive(Debug, Clone)]
+pub struct BigUint<F: FieldExt> {
+    pub limbs: [AssignedCell<F, F>; LIMBS],
+}
+
+impl<F: FieldExt> BigUint<F> {
+    pub fn value(&self) -> Value<[u64; LIMBS]> {
+        let mut value = Value::known([0; LIMBS]);
+        for (i, limb) in self.limbs.iter().enumerate() {
+            value = value.zip(limb.value()).map(|(mut value, limb)| {
+                value[i] = limb.get_lower_128() as u64;
+                value
+            });
+        }
+        value
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BigIntConfig {
+    pub limbs: [Column<Advice>; LIMBS],
+    pub q_add: Selector,
+    pub q_add_one: Selector,
+    pub q_mul_add: Selector,
+    pub uint: UintConfig<8>,
+}
+
+pub struct BigIntChip<F: FieldExt> {
+    config: BigIntConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BigIntChip<F> {
+    pub fn construct(config: BigIntConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BigIntConfig {
+        let limbs = [(); LIMBS].map(|_| meta.advice_column());
+        for limb in limbs {
+            meta.enable_equality(limb);
+        }
+        let q_add = meta.selector();
+        let q_add_one = meta.selector();
+        let q_mul_add = meta.selector();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let uint = U64Chip::<F>::configure(meta);
+
+        let two_64 = Expression::Constant(F::from_u128(1 << 64));
+        // limb `k` of the numbers starting at `row`, 4 limbs per row
+        let limb = |meta: &mut VirtualCells<'_, F>, row: usize, k: usize| {
+            meta.query_advice(limbs[k % LIMBS], Rotation((row + k / LIMBS) as i32))
+        };
+
+        for (name, q_add, carry_in) in [("bigint add", q_add, 0), ("bigint add one", q_add_one, 1)]
+        {
+            meta.create_gate(name, |meta| {
+                let q_add = meta.query_selector(q_add);
+                let mut carry = Expression::Constant(F::from(carry_in));
+                let mut constraints = Vec::with_capacity(2 * LIMBS);
+                for k in 0..LIMBS {
+                    let (a, b, s, c) = (
+                        limb(meta, 0, k),
+                        limb(meta, 1, k),
+                        limb(meta, 2, k),
+                        limb(meta, 3, k),
+                    );
+                    constraints
+                        .push(q_add.clone() * (a + b + carry - s - c.clone() * two_64.clone()));
+                    constraints.push(
+                        q_add.clone() * c.clone() * (Expression::Constant(F::one()) - c.clone()),
+                    );
+                    carry = c;
+                }
+                constraints
+            });
+        }
+
+        meta.create_gate("bigint mul add", |meta| {
+            let q_mul_add = meta.query_selector(q_mul_add);
+            let mut carry = Expression::Constant(F::zero());
+            let mut constraints = Vec::with_capacity(2 * LIMBS);
+            for k in 0..2 * LIMBS {
+                let mut sum = carry.clone();
+                for i in 0..LIMBS {
+                    if k >= i && k - i < LIMBS {
+                        sum = sum + limb(meta, 0, i) * limb(meta, 1, k - i);
+                    }
+                }
+                if k < LIMBS {
+                    sum = sum + limb(meta, 2, k);
+                }
+                let z = limb(meta, 3, k);
+                if k + 1 < 2 * LIMBS {
+                    let c = limb(meta, 5, k) + limb(meta, 7, k) * two_64.clone();
+                    constraints.push(q_mul_add.clone() * (sum - z - c.clone() * two_64.clone()));
+                    carry = c;
+                } else {
+                    constraints.push(q_mul_add.clone() * (sum - z));
+                }
+            }
+            constraints
+        });
+
+        BigIntConfig {
+            limbs,
+            q_add,
+            q_add_one,
+            q_mul_add,
+            uint,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        U64Chip::construct(self.config.uint).load_table(layouter)
+    }
+
+    /// assign a new integer, its limbs are range checked
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<[u64; LIMBS]>,
+    ) -> Result<BigUint<F>, Error> {
+        let uint = U64Chip::construct(self.config.uint);
+        layouter.assign_region(
+            || "bigint",
+            |mut region| {
+                let mut limbs = Vec::with_capacity(LIMBS);
+                for i in 0..LIMBS {
+                    limbs.push(uint.assign(
+                        &mut region,
+                        i,
+                        value.map(|value| F::from(value[i])),
+                    )?);
+                }
+                Ok(BigUint {
+                    limbs: limbs.try_into().unwrap(),
+                })
+            },
+        )
+    }
+
+    /// `(a + b mod 2^256, carry)`
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &BigUint<F>,
+        b: &BigUint<F>,
+    ) -> Result<(BigUint<F>, AssignedCell<F, F>), Error> {
+        let (sum, carries) = self.add_rows(layouter, self.config.q_add, a, b, 0)?;
+        Ok((sum, carries[LIMBS - 1].clone()))
+    }
+
+    /// `(a * b mod 2^256, a * b >> 256)`
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigUint<F>,
+        b: &BigUint<F>,
+    ) -> Result<(BigUint<F>, BigUint<F>), Error> {
+        let zero = self.assign(layouter.namespace(|| "zero"), Value::known([0; LIMBS]))?;
+        layouter.assign_region(
+            || "zero",
+            |mut region| {
+                for limb in &zero.limbs {
+                    region.constrain_constant(limb.cell(), F::zero())?;
+                }
+                Ok(())
+            },
+        )?;
+        self.mul_add(layouter, a, b, &zero)
+    }
+
+    /// `a * b mod m`, `a * b` has to be smaller than `2^256 * m`, e.g. with `a, b < m`
+    pub fn mul_mod(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigUint<F>,
+        b: &BigUint<F>,
+        m: &BigUint<F>,
+    ) -> Result<BigUint<F>, Error> {
+        let (lo, hi) = self.mul(layouter.namespace(|| "a * b"), a, b)?;
+        self.reduce(layouter.namespace(|| "mod m"), &lo, &hi, m)
+    }
+
+    /// `(lo + hi * 2^256) mod m`, the quotient has to fit in 256 bits
+    pub fn reduce(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lo: &BigUint<F>,
+        hi: &BigUint<F>,
+        m: &BigUint<F>,
+    ) -> Result<BigUint<F>, Error> {
+        let qr = lo
+            .value()
+            .zip(hi.value())
+            .zip(m.value())
+            .map(|((lo, hi), m)| {
+                let mut x = [0; 2 * LIMBS];
+                x[..LIMBS].copy_from_slice(&lo);
+                x[LIMBS..].copy_from_slice(&hi);
+                div_rem(x, m)
+            });
+        let q = self.assign(layouter.namespace(|| "q"), qr.map(|(q, _)| q))?;
+        let r = self.assign(layouter.namespace(|| "r"), qr.map(|(_, r)| r))?;
+
+        // x = q * m + r
+        let (x_lo, x_hi) = self.mul_add(layouter.namespace(|| "q * m + r"), &q, m, &r)?;
+        layouter.assign_region(
+            || "x",
+            |mut region| {
+                for (x, y) in x_lo
+                    .limbs
+                    .iter()
+                    .chain(&x_hi.limbs)
+                    .zip(lo.limbs.iter().chain(&hi.limbs))
+                {
+                    region.constrain_equal(x.cell(), y.cell())?;
+                }
+                Ok(())
+            },
+        )?;
+
+        // r + s + 1 = m without a carry, so r < m
+        let s = self.assign(
+            layouter.namespace(|| "m - r - 1"),
+            m.value().zip(r.value()).map(|(m, r)| {
+                let (difference, _) = sub(m, r);
+                sub(difference, [1, 0, 0, 0]).0
+            }),
+        )?;
+        let (sum, carries) = self.add_rows(
+            layouter.namespace(|| "r < m"),
+            self.config.q_add_one,
+            &r,
+            &s,
+            1,
+        )?;
+        layouter.assign_region(
+            || "r + s + 1 = m",
+            |mut region| {
+                for (x, y) in sum.limbs.iter().zip(&m.limbs) {
+                    region.constrain_equal(x.cell(), y.cell())?;
+                }
+                region.constrain_constant(carries[LIMBS - 1].cell(), F::zero())
+            },
+        )?;
+        Ok(r)
+    }
+
+    /// `a * b + e` as `(lo, hi)`
+    pub fn mul_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &BigUint<F>,
+        b: &BigUint<F>,
+        e: &BigUint<F>,
+    ) -> Result<(BigUint<F>, BigUint<F>), Error> {
+        let product = a
+            .value()
+            .zip(b.value())
+            .zip(e.value())
+            .map(|((a, b), e)| mul_add(a, b, e));
+
+        let (z, carries) = layouter.assign_region(
+            || "bigint mul add",
+            |mut region| {
+                self.config.q_mul_add.enable(&mut region, 0)?;
+                self.copy_row(&mut region, 0, a)?;
+                self.copy_row(&mut region, 1, b)?;
+                self.copy_row(&mut region, 2, e)?;
+
+                let mut z = Vec::with_capacity(2 * LIMBS);
+                for k in 0..2 * LIMBS {
+                    z.push(self.assign_limb(
+                        &mut region,
+                        3,
+                        k,
+                        product.map(|(z, _)| F::from(z[k])),
+                    )?);
+                }
+                let mut carries = Vec::with_capacity(2 * (2 * LIMBS - 1));
+                for k in 0..2 * LIMBS - 1 {
+                    let carry = product.map(|(_, carries)| carries[k]);
+                    carries.push(self.assign_limb(
+                        &mut region,
+                        5,
+                        k,
+                        carry.map(|carry| F::from(carry as u64)),
+                    )?);
+                    carries.push(self.assign_limb(
+                        &mut region,
+                        7,
+                        k,
+                        carry.map(|carry| F::from((carry >> 64) as u64)),
+                    )?);
+                }
+                Ok((z, carries))
+            },
+        )?;
+
+        let uint = U64Chip::construct(self.config.uint);
+        uint.range_check(layouter.namespace(|| "z"), &z)?;
+        uint.range_check(layouter.namespace(|| "carries"), &carries)?;
+        let hi = z.split_off(LIMBS);
+        Ok((
+            BigUint {
+                limbs: z.try_into().unwrap(),
+            },
+            BigUint {
+                limbs: hi.try_into().unwrap(),
+            },
+        ))
+    }
+
+    /// rows `a, b, s, c` of `add` with the carries
+    fn add_rows(
+        &self,
+        mut layouter: impl Layouter<F>,
+        q_add: Selector,
+        a: &BigUint<F>,
+        b: &BigUint<F>,
+        carry_in: u64,
+    ) -> Result<(BigUint<F>, Vec<AssignedCell<F, F>>), Error> {
+        let sum = a.value().zip(b.value()).map(|(a, b)| add(a, b, carry_in));
+
+        let (s, carries) = layouter.assign_region(
+            || "bigint add",
+            |mut region| {
+                q_add.enable(&mut region, 0)?;
+                self.copy_row(&mut region, 0, a)?;
+                self.copy_row(&mut region, 1, b)?;
+                let mut s = Vec::with_capacity(LIMBS);
+                let mut carries = Vec::with_capacity(LIMBS);
+                for k in 0..LIMBS {
+                    s.push(self.assign_limb(&mut region, 2, k, sum.map(|(s, _)| F::from(s[k])))?);
+                    carries.push(self.assign_limb(
+                        &mut region,
+                        3,
+                        k,
+                        sum.map(|(_, c)| F::from(c[k])),
+                    )?);
+                }
+                Ok((s, carries))
+            },
+        )?;
+
+        // the carries are boolean by the gate
+        U64Chip::construct(self.config.uint).range_check(layouter.namespace(|| "s"), &s)?;
+        Ok((
+            BigUint {
+                limbs: s.try_into().unwrap(),
+            },
+            carries,
+        ))
+    }
+
+    fn copy_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &BigUint<F>,
+    ) -> Result<(), Error> {
+        for (limb, column) in value.limbs.iter().zip(self.config.limbs) {
+            limb.copy_advice(|| "limb", region, column, offset)?;
+        }
+        Ok(())
+    }
+
+    /// limb `k` of the numbers starting at `offset`
+    fn assign_limb(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        k: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        region.assign_advice(
+            || format!("limb {}", k),
+            self.config.limbs[k % LIMBS],
+            offset + k / LIMBS,
+            || value,
+        )
+    }
+}
+
+/// `a + b + carry_in` with the carry out of every limb
+fn add(a: [u64; LIMBS], b: [u64; LIMBS], carry_in: u64) -> ([u64; LIMBS], [u64; LIMBS]) {
+    let (mut s, mut c) = ([0; LIMBS], [0; LIMBS]);
+    let mut carry = carry_in as u128;
+    for k in 0..LIMBS {
+        let sum = a[k] as u128 + b[k] as u128 + carry;
+        s[k] = sum as u64;
+        carry = sum >> 64;
+        c[k] = carry as u64;
+    }
+    (s, c)
+}
+
+/// `a - b` with the borrow out
+fn sub(a: [u64; LIMBS], b: [u64; LIMBS]) -> ([u64; LIMBS], bool) {
+    let mut d = [0; LIMBS];
+    let mut borrow = false;
+    for k in 0..LIMBS {
+        let (x, b1) = a[k].overflowing_sub(b[k]);
+        let (x, b2) = x.overflowing_sub(borrow as u64);
+        d[k] = x;
+        borrow = b1 || b2;
+    }
+    (d, borrow)
+}
+
+/// `a * b + e` with the carry out of every limb but the last
+fn mul_add(
+    a: [u64; LIMBS],
+    b: [u64; LIMBS],
+    e: [u64; LIMBS],
+) -> ([u64; 2 * LIMBS], [u128; 2 * LIMBS - 1]) {
+    let (mut z, mut carries) = ([0; 2 * LIMBS], [0; 2 * LIMBS - 1]);
+    let mut carry = 0u128;
+    for k in 0..2 * LIMBS {
+        // up to 4 products of 128 bits, a limb and the carry, in 192 bits
+        let (mut lo, mut hi) = (carry, 0u128);
+        let mut accumulate = |x: u128| {
+            let (sum, overflow) = lo.overflowing_add(x);
+            lo = sum;
+            hi += overflow as u128;
+        };
+        for i in 0..LIMBS {
+            if k >= i && k - i < LIMBS {
+                accumulate(a[i] as u128 * b[k - i] as u128);
+            }
+        }
+        if k < LIMBS {
+            accumulate(e[k] as u128);
+        }
+        z[k] = lo as u64;
+        carry = (lo >> 64) | (hi << 64);
+        if k + 1 < 2 * LIMBS {
+            carries[k] = carry;
+        }
+    }
+    (z, carries)
+}
+
+/// `(x / m, x % m)`, the quotient truncated to 256 bits
+fn div_rem(x: [u64; 2 * LIMBS], m: [u64; LIMBS]) -> ([u64; LIMBS], [u64; LIMBS]) {
+    let (mut q, mut r) = ([0u64; LIMBS], [0u64; LIMBS]);
+    for bit in (0..128 * LIMBS).rev() {
+        // r = 2r + bit, which may take 257 bits
+        let top = r[LIMBS - 1] >> 63;
+        for k in (1..LIMBS).rev() {
+            r[k] = (r[k] << 1) | (r[k - 1] >> 63);
+        }
+        r[0] = (r[0] << 1) | ((x[bit / 64] >> (bit % 64)) & 1);
+
+        let (difference, borrow) = sub(r, m);
+        if top == 1 || !borrow {
+            r = difference;
+            if bit < 64 * LIMBS {
+                q[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+    }
+    (q, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// 2^255 - 19
+    const P25519: [u64; LIMBS] = [u64::MAX - 18, u64::MAX, u64::MAX, u64::MAX >> 1];
+
+    #[derive(Default)]
+    struct TestCircuit {
+        a: [u64; LIMBS],
+        b: [u64; LIMBS],
+        m: [u64; LIMBS],
+        // a * b mod m, or a cheat
+        expected: [u64; LIMBS],
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = BigIntConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> BigIntConfig {
+            BigIntChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: BigIntConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = BigIntChip::construct(config);
+            chip.load_table(layouter.namespace(|| "u8"))?;
+            let a = chip.assign(layouter.namespace(|| "a"), Value::known(self.a))?;
+            let b = chip.assign(layouter.namespace(|| "b"), Value::known(self.b))?;
+            let m = chip.assign(layouter.namespace(|| "m"), Value::known(self.m))?;
+            let expected = chip.assign(
+                layouter.namespace(|| "expected"),
+                Value::known(self.expected),
+            )?;
+
+            let (sum, carry) = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let (expected_sum, expected_carry) = add(self.a, self.b, 0);
+            sum.value().assert_if_known(|sum| *sum == expected_sum);
+            carry
+                .value()
+                .assert_if_known(|carry| **carry == Fr::from(expected_carry[LIMBS - 1]));
+
+            let r = chip.mul_mod(layouter.namespace(|| "a * b mod m"), &a, &b, &m)?;
+            layouter.assign_region(
+                || "r = expected",
+                |mut region| {
+                    for (x, y) in r.limbs.iter().zip(&expected.limbs) {
+                        region.constrain_equal(x.cell(), y.cell())?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(
+        a: [u64; LIMBS],
+        b: [u64; LIMBS],
+        m: [u64; LIMBS],
+        expected: [u64; LIMBS],
+    ) -> MockProver<Fr> {
+        let circuit = TestCircuit { a, b, m, expected };
+        MockProver::run(10, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        // (2^256 - 1)^2 + (2^256 - 1) = 2^512 - 2^256
+        let max = [u64::MAX; LIMBS];
+        let (z, _) = mul_add(max, max, max);
+        assert_eq!(z, [0, 0, 0, 0, u64::MAX, u64::MAX, u64::MAX, u64::MAX]);
+
+        // 2^256 mod (2^255 - 19) = 38
+        let (q, r) = div_rem([0, 0, 0, 0, 1, 0, 0, 0], P25519);
+        assert_eq!((q, r), ([2, 0, 0, 0], [38, 0, 0, 0]));
+    }
+
+    #[test]
+    fn small() {
+        prover([6, 0, 0, 0], [7, 0, 0, 0], [10, 0, 0, 0], [2, 0, 0, 0]).assert_satisfied();
+    }
+
+    #[test]
+    fn mod_25519() {
+        // (p - 1)^2 = 1 mod p
+        let p_minus_one = sub(P25519, [1, 0, 0, 0]).0;
+        prover(p_minus_one, p_minus_one, P25519, [1, 0, 0, 0]).assert_satisfied();
+        // 2^128 * 2^128 = 38 mod p
+        prover([0, 0, 1, 0], [0, 0, 1, 0], P25519, [38, 0, 0, 0]).assert_satisfied();
+    }
+
+    #[test]
+    fn wrong_remainder() {
+        testing::assert_fails_permutation(&prover(
+            [6, 0, 0, 0],
+            [7, 0, 0, 0],
+            [10, 0, 0, 0],
+            [12, 0, 0, 0],
+        ));
+    }
+}
@@ -0,0 +1,453 @@
+//! bit decomposition gadget
+//!
+//! decomposes a cell into `n` bits, one per row, most significant first:
+//!
+//! | row   | bit       | acc                      |
+//! |-------|-----------|--------------------------|
+//! | 0     | b[n-1]    | b[n-1]                   |
+//! | i     | b[n-1-i]  | 2 * acc[i-1] + b[n-1-i]  |
+//! | n - 1 | b[0]      | value                    |
+//!
+//! every bit is boolean and the last `acc` is copied from the value, so the value has
+//! to be smaller than `2^n`. `n` has to be smaller than `F::NUM_BITS`, then `2^n < p` and
+//! `value + p` never fits, so the bits are unique.
+//!
+//! [`CanonicalChip`] decomposes over the full `F::NUM_BITS` instead, where the bits of
+//! `value + p` fit too when it is below `2^NUM_BITS`, so they are compared to the bits of
+//! `p - 1`, most significant first:
+//!
+//! | row   | bit       | eq  | lt | p_bit        |
+//! |-------|-----------|-----|----|--------------|
+//! | 0     | b[n-1]    | eq  | lt | (p-1)[n-1]   |
+//! | i     | b[n-1-i]  | eq  | lt | (p-1)[n-1-i] |
+//! | n - 1 | b[0]      | eq  | lt | (p-1)[0]     |
+//!
+//! - `eq`: the bits so far are equal to the ones of `p - 1`
+//! - `lt`: the bits so far are smaller than the ones of `p - 1`
+//! - `eq + lt = 1` on the last row, so the bits are at most `p - 1`
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DecomposeConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    // every row
+    pub q_bit: Selector,
+    // the first row
+    pub q_first: Selector,
+    // the rows after the first
+    pub q_step: Selector,
+}
+
+pub struct DecomposeChip<F: FieldExt> {
+    config: DecomposeConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DecomposeChip<F> {
+    pub fn construct(config: DecomposeConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        bit: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> DecomposeConfig {
+        let q_bit = meta.selector();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+
+        meta.create_gate("bit", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            vec![q_bit * bit.clone() * (Expression::Constant(F::one()) - bit)]
+        });
+
+        meta.create_gate("recompose", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_step = meta.query_selector(q_step);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            vec![
+                q_first * (acc.clone() - bit.clone()),
+                q_step * (acc - acc_prev * Expression::Constant(F::from(2)) - bit),
+            ]
+        });
+
+        DecomposeConfig {
+            bit,
+            acc,
+            q_bit,
+            q_first,
+            q_step,
+        }
+    }
+
+    /// decompose `value` into `n < F::NUM_BITS` bits, returned least significant first,
+    /// see [`CanonicalChip`] for all the bits of the field
+    pub fn decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        n: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(
+            n > 0 && n < F::NUM_BITS as usize,
+            "{} bits would also fit value + p",
+            n
+        );
+        self.assign_bits(layouter, value, n)
+    }
+
+    fn assign_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        n: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let bits: Value<Vec<F>> = value.value().map(|value| {
+            let repr = value.to_repr();
+            let bytes = repr.as_ref();
+            (0..n)
+                .map(|i| F::from(((bytes[i / 8] >> (i % 8)) & 1) as u64))
+                .collect()
+        });
+
+        layouter.assign_region(
+            || format!("decompose {} bits", n),
+            |mut region| {
+                let mut assigned = Vec::with_capacity(n);
+                let mut acc = Value::known(F::zero());
+                let mut acc_cell = None;
+                for offset in 0..n {
+                    let bit = bits.as_ref().map(|bits| bits[n - 1 - offset]);
+                    acc = acc.zip(bit).map(|(acc, bit)| acc.double() + bit);
+
+                    self.config.q_bit.enable(&mut region, offset)?;
+                    if offset == 0 {
+                        self.config.q_first.enable(&mut region, offset)?;
+                    } else {
+                        self.config.q_step.enable(&mut region, offset)?;
+                    }
+                    assigned.push(region.assign_advice(
+                        || "bit",
+                        self.config.bit,
+                        offset,
+                        || bit,
+                    )?);
+                    acc_cell =
+                        Some(region.assign_advice(|| "acc", self.config.acc, offset, || acc)?);
+                }
+                region.constrain_equal(acc_cell.unwrap().cell(), value.cell())?;
+
+                assigned.reverse();
+                Ok(assigned)
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CanonicalConfig {
+    // [bit, eq, lt], the decomposition uses bit and eq as its bit and acc
+    pub advice: [Column<Advice>; 3],
+    pub p_bit: Column<Fixed>,
+    // the first row of the comparison
+    pub q_first: Selector,
+    // the rows after the first
+    pub q_step: Selector,
+    // the last row
+    pub q_last: Selector,
+    pub decompose: DecomposeConfig,
+}
+
+pub struct CanonicalChip<F: FieldExt> {
+    config: CanonicalConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CanonicalChip<F> {
+    pub fn construct(config: CanonicalConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 3],
+    ) -> CanonicalConfig {
+        let [col_bit, col_eq, col_lt] = advice;
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let p_bit = meta.fixed_column();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        let q_last = meta.selector();
+        let decompose = DecomposeChip::configure(meta, col_bit, col_eq);
+
+        let one = || Expression::Constant(F::one());
+
+        meta.create_gate("canonical", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_step = meta.query_selector(q_step);
+            let q_last = meta.query_selector(q_last);
+            let bit = meta.query_advice(col_bit, Rotation::cur());
+            let eq = meta.query_advice(col_eq, Rotation::cur());
+            let lt = meta.query_advice(col_lt, Rotation::cur());
+            let eq_prev = meta.query_advice(col_eq, Rotation::prev());
+            let lt_prev = meta.query_advice(col_lt, Rotation::prev());
+            let p_bit = meta.query_fixed(p_bit, Rotation::cur());
+
+            let same = one() - bit.clone() - p_bit.clone()
+                + Expression::Constant(F::from(2)) * bit.clone() * p_bit.clone();
+            let below = (one() - bit) * p_bit;
+            vec![
+                q_first.clone() * (eq.clone() - same.clone()),
+                q_first * (lt.clone() - below.clone()),
+                q_step.clone() * (eq.clone() - eq_prev.clone() * same),
+                q_step * (lt.clone() - lt_prev - eq_prev * below),
+                q_last * (eq + lt - one()),
+            ]
+        });
+
+        CanonicalConfig {
+            advice,
+            p_bit,
+            q_first,
+            q_step,
+            q_last,
+            decompose,
+        }
+    }
+
+    /// decompose `value` into its `F::NUM_BITS` bits, returned least significant first
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let n = F::NUM_BITS as usize;
+        let bits = DecomposeChip::construct(self.config.decompose).assign_bits(
+            layouter.namespace(|| "decompose"),
+            value,
+            n,
+        )?;
+        let p_minus_one = (-F::one()).to_repr();
+        let p_bits: Vec<bool> = (0..n)
+            .map(|i| (p_minus_one.as_ref()[i / 8] >> (i % 8)) & 1 == 1)
+            .collect();
+
+        layouter.assign_region(
+            || "compare to p - 1",
+            |mut region| {
+                let mut eq = Value::known(true);
+                let mut lt = Value::known(false);
+                for offset in 0..n {
+                    let i = n - 1 - offset;
+                    if offset == 0 {
+                        self.config.q_first.enable(&mut region, offset)?;
+                    } else {
+                        self.config.q_step.enable(&mut region, offset)?;
+                    }
+                    if offset == n - 1 {
+                        self.config.q_last.enable(&mut region, offset)?;
+                    }
+
+                    let bit = bits[i].copy_advice(
+                        || "bit",
+                        &mut region,
+                        self.config.advice[0],
+                        offset,
+                    )?;
+                    let p_bit = p_bits[i];
+                    region.assign_fixed(
+                        || "p bit",
+                        self.config.p_bit,
+                        offset,
+                        || Value::known(F::from(u64::from(p_bit))),
+                    )?;
+                    let bit = bit.value().map(|bit| *bit == F::one());
+                    lt = lt
+                        .zip(eq)
+                        .zip(bit)
+                        .map(|((lt, eq), bit)| lt || (eq && !bit && p_bit));
+                    eq = eq.zip(bit).map(|(eq, bit)| eq && bit == p_bit);
+                    region.assign_advice(
+                        || "eq",
+                        self.config.advice[1],
+                        offset,
+                        || eq.map(|eq| F::from(u64::from(eq))),
+                    )?;
+                    region.assign_advice(
+                        || "lt",
+                        self.config.advice[2],
+                        offset,
+                        || lt.map(|lt| F::from(u64::from(lt))),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        value: u64,
+        n: usize,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (Column<Advice>, DecomposeConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: 0,
+                n: self.n,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let value = meta.advice_column();
+            meta.enable_equality(value);
+            let bit = meta.advice_column();
+            let acc = meta.advice_column();
+            (value, DecomposeChip::configure(meta, bit, acc))
+        }
+
+        fn synthesize(
+            &self,
+            (column, config): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| {
+                    region.assign_advice(
+                        || "value",
+                        column,
+                        0,
+                        || Value::known(Fr::from(self.value)),
+                    )
+                },
+            )?;
+            let bits = DecomposeChip::construct(config).decompose(
+                layouter.namespace(|| "decompose"),
+                &value,
+                self.n,
+            )?;
+            for (i, bit) in bits.iter().enumerate() {
+                let expected = Fr::from((self.value.checked_shr(i as u32).unwrap_or(0)) & 1);
+                bit.value().assert_if_known(|bit| **bit == expected);
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(value: u64, n: usize) -> MockProver<Fr> {
+        MockProver::run(8, &TestCircuit { value, n }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn widths() {
+        for n in [8, 32, 64] {
+            prover(0, n).assert_satisfied();
+            prover(0b1011, n).assert_satisfied();
+            prover(u64::MAX >> (64 - n), n).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn too_wide() {
+        testing::assert_fails_permutation(&prover(256, 8));
+        testing::assert_fails_permutation(&prover(1 << 32, 32));
+    }
+
+    #[test]
+    #[should_panic(expected = "value + p")]
+    fn full_width() {
+        // all 254 bits of Fr need the CanonicalChip
+        prover(0, 254);
+    }
+
+    #[derive(Default)]
+    struct CanonicalCircuit {
+        value: Fr,
+    }
+
+    impl Circuit<Fr> for CanonicalCircuit {
+        type Config = (Column<Advice>, CanonicalConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let value = meta.advice_column();
+            meta.enable_equality(value);
+            let advice = [(); 3].map(|_| meta.advice_column());
+            (value, CanonicalChip::configure(meta, advice))
+        }
+
+        fn synthesize(
+            &self,
+            (column, config): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| {
+                    region.assign_advice(|| "value", column, 0, || Value::known(self.value))
+                },
+            )?;
+            let bits = CanonicalChip::construct(config)
+                .decompose(layouter.namespace(|| "canonical"), &value)?;
+            assert_eq!(bits.len(), 254);
+            let mut recomposed = Value::known(Fr::zero());
+            for bit in bits.iter().rev() {
+                recomposed = recomposed
+                    .zip(bit.value())
+                    .map(|(acc, bit)| acc + acc + bit);
+            }
+            recomposed.assert_if_known(|value| *value == self.value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        for value in [Fr::zero(), Fr::from(0b1011), Fr::from(u64::MAX), -Fr::one()] {
+            MockProver::run(10, &CanonicalCircuit { value }, vec![])
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! blake2b gadget
+//!
+//! the compression function of blake2b on 64 bit words, built from the other gadgets:
+//! additions mod `2^64` are [`U64Chip`] adds, XORs go byte by byte through the table of
+//! the [`ByteOpsChip`]. in between, words are taken apart into bytes and put back
+//! together on rows of their own:
+//!
+//! | word | limb[0]  | ... | limb[7]   |
+//! |------|----------|-----|-----------|
+//! | w    | w & 255  | ... | w >> 56   |
+//! | x    | x & 255  | ... | x >> 56   |
+//! | y    |          |     |           |
+//!
+//! - `word = sum limb[i] * 2^(8i)` on every row with `q_word`. the limbs are not range
+//!   checked here, they are always inputs or outputs of byte lookups
+//! - the rotations by 32, 24 and 16 bits of `G` move whole bytes, the XORed bytes are
+//!   copied to the limbs of the rotated word in their new order
+//! - the rotation by 63 bits is a rotation left by one, with `m = x[7] & 0x80` looked
+//!   up, `128 y = 256 x - m (2^64 - 1)` on the rows `x, y` with `q_rotl1`, `m` in
+//!   `limb[0]` of `x`
+//!
+//! the IV and the counter and final flag mixed into it are constants of the circuit,
+//! only messages of whole words fitting in a single block are hashed, as blake2b-512.
+//! the byte table takes `2^16` rows, so `k` has to be at least 17.
+
+use crate::gadgets::{
+    byte_ops::{ByteOpsChip, ByteOpsConfig},
+    uint::{U64Chip, UintConfig},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+pub const ROUNDS: usize = 12;
+/// words in a message block
+pub const BLOCK_WORDS: usize = 16;
+
+pub const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// message word schedule, round `i` uses `SIGMA[i % 10]`
+pub const SIGMA: [[usize; BLOCK_WORDS]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// the words of `v` mixed by the 8 `G` of a round, columns then diagonals
+const MIX: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/// bytes of the digest
+pub const OUT_BYTES: u64 = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Blake2bConfig {
+    pub word: Column<Advice>,
+    // little endian bytes of word
+    pub limbs: [Column<Advice>; 8],
+    pub q_word: Selector,
+    pub q_rotl1: Selector,
+    pub uint: UintConfig<8>,
+    pub byte_ops: ByteOpsConfig,
+}
+
+pub struct Blake2bChip<F: FieldExt> {
+    config: Blake2bConfig,
+    uint: U64Chip<F>,
+    byte_ops: ByteOpsChip<F>,
+}
+
+impl<F: FieldExt> Blake2bChip<F> {
+    pub fn construct(config: Blake2bConfig) -> Self {
+        Self {
+            config,
+            uint: U64Chip::construct(config.uint),
+            byte_ops: ByteOpsChip::construct(config.byte_ops),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Blake2bConfig {
+        let word = meta.advice_column();
+        let limbs = [(); 8].map(|_| meta.advice_column());
+        let constant = meta.fixed_column();
+        let q_word = meta.selector();
+        let q_rotl1 = meta.selector();
+        meta.enable_equality(word);
+        for column in limbs {
+            meta.enable_equality(column);
+        }
+        meta.enable_constant(constant);
+
+        meta.create_gate("blake2b word", |meta| {
+            let q_word = meta.query_selector(q_word);
+            let word = meta.query_advice(word, Rotation::cur());
+            let sum = limbs
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, column| {
+                    acc * Expression::Constant(F::from(256))
+                        + meta.query_advice(*column, Rotation::cur())
+                });
+            vec![q_word * (word - sum)]
+        });
+
+        meta.create_gate("blake2b rotl1", |meta| {
+            let q_rotl1 = meta.query_selector(q_rotl1);
+            let x = meta.query_advice(word, Rotation::cur());
+            let m = meta.query_advice(limbs[0], Rotation::cur());
+            let y = meta.query_advice(word, Rotation::next());
+            let max = Expression::Constant(F::from(u64::MAX));
+            vec![
+                q_rotl1
+                    * (y * Expression::Constant(F::from(128))
+                        - (x * Expression::Constant(F::from(256)) - m * max)),
+            ]
+        });
+
+        let byte_ops = [(); 3].map(|_| meta.advice_column());
+        Blake2bConfig {
+            word,
+            limbs,
+            q_word,
+            q_rotl1,
+            uint: U64Chip::configure(meta),
+            byte_ops: ByteOpsChip::configure(meta, byte_ops),
+        }
+    }
+
+    pub fn load_tables(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.uint.load_table(layouter.namespace(|| "u8"))?;
+        self.byte_ops.load_table(layouter.namespace(|| "byte ops"))
+    }
+
+    /// blake2b-512 of `message`, one block of up to 16 words read as little endian bytes.
+    /// the digest is returned as 8 words, the bytes of the digest in little endian order
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<[AssignedCell<F, F>; 8], Error> {
+        assert!(message.len() <= BLOCK_WORDS, "a single block");
+        let mut h = IV;
+        // no key, a digest of OUT_BYTES
+        h[0] ^= 0x0101_0000 ^ OUT_BYTES;
+        let h = self.constants(layouter.namespace(|| "h"), &h)?;
+        let padding = self.constants(
+            layouter.namespace(|| "padding"),
+            &[0; BLOCK_WORDS][message.len()..],
+        )?;
+        let block: Vec<_> = message.iter().cloned().chain(padding).collect();
+        self.compress(
+            layouter.namespace(|| "compress"),
+            &h,
+            &block,
+            8 * message.len() as u128,
+            true,
+        )
+    }
+
+    /// the compression function `F(h, m, t, f)`, the counter `t` and the final flag
+    /// are part of the circuit
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: &[AssignedCell<F, F>],
+        m: &[AssignedCell<F, F>],
+        t: u128,
+        last: bool,
+    ) -> Result<[AssignedCell<F, F>; 8], Error> {
+        assert_eq!(h.len(), 8);
+        assert_eq!(m.len(), BLOCK_WORDS);
+        let mut iv = IV;
+        iv[4] ^= t as u64;
+        iv[5] ^= (t >> 64) as u64;
+        if last {
+            iv[6] = !iv[6];
+        }
+        let iv = self.constants(layouter.namespace(|| "iv"), &iv)?;
+        let mut v: Vec<_> = h.iter().cloned().chain(iv).collect();
+
+        let high_bit = self.constants(layouter.namespace(|| "0x80"), &[0x80])?;
+        for round in 0..ROUNDS {
+            let s = SIGMA[round % 10];
+            for (i, indices) in MIX.iter().enumerate() {
+                self.g(
+                    layouter.namespace(|| format!("round {} g {}", round, i)),
+                    &mut v,
+                    *indices,
+                    (&m[s[2 * i]], &m[s[2 * i + 1]]),
+                    &high_bit[0],
+                )?;
+            }
+        }
+
+        let mut out = Vec::with_capacity(8);
+        for (i, h) in h.iter().enumerate() {
+            let x = self.xor_rotr(layouter.namespace(|| "h ^ v"), h, &v[i], 0, &high_bit[0])?;
+            out.push(self.xor_rotr(
+                layouter.namespace(|| "h ^ v ^ v"),
+                &x,
+                &v[i + 8],
+                0,
+                &high_bit[0],
+            )?);
+        }
+        Ok(out.try_into().unwrap())
+    }
+
+    /// the mixing function `G` on `v[a], v[b], v[c], v[d]`
+    fn g(
+        &self,
+        mut layouter: impl Layouter<F>,
+        v: &mut [AssignedCell<F, F>],
+        [a, b, c, d]: [usize; 4],
+        (x, y): (&AssignedCell<F, F>, &AssignedCell<F, F>),
+        high_bit: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        for (m, (r_d, r_b)) in [(x, (32, 24)), (y, (16, 63))] {
+            v[a] = self.add(layouter.namespace(|| "a + b"), &v[a], &v[b])?;
+            v[a] = self.add(layouter.namespace(|| "a + m"), &v[a], m)?;
+            v[d] = self.xor_rotr(layouter.namespace(|| "d"), &v[d], &v[a], r_d, high_bit)?;
+            v[c] = self.add(layouter.namespace(|| "c + d"), &v[c], &v[d])?;
+            v[b] = self.xor_rotr(layouter.namespace(|| "b"), &v[b], &v[c], r_b, high_bit)?;
+        }
+        Ok(())
+    }
+
+    /// `a + b mod 2^64`
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (sum, _) = self.uint.add(layouter, a, b)?;
+        Ok(sum)
+    }
+
+    /// `(a ^ b) >>> r` for a rotation by whole bytes or by 63 bits
+    fn xor_rotr(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        r: usize,
+        high_bit: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(r % 8 == 0 || r == 63, "a rotation of G");
+        let a = self.bytes(layouter.namespace(|| "a"), a)?;
+        let b = self.bytes(layouter.namespace(|| "b"), b)?;
+        let x = layouter.assign_region(
+            || "blake2b xor",
+            |mut region| {
+                a.iter()
+                    .zip(&b)
+                    .enumerate()
+                    .map(|(offset, (a, b))| self.byte_ops.xor(&mut region, offset, a, b))
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+        if r % 8 == 0 {
+            // byte i of the rotated word is byte i + r / 8 of x
+            let rotated: Vec<_> = (0..8).map(|i| x[(i + r / 8) % 8].clone()).collect();
+            return self.word(layouter.namespace(|| "rotr"), &rotated);
+        }
+
+        let m = layouter.assign_region(
+            || "blake2b msb",
+            |mut region| self.byte_ops.and(&mut region, 0, &x[7], high_bit),
+        )?;
+        let word = self.word(layouter.namespace(|| "x"), &x)?;
+        layouter.assign_region(
+            || "blake2b rotl1",
+            |mut region| {
+                self.config.q_rotl1.enable(&mut region, 0)?;
+                let x = word.copy_advice(|| "x", &mut region, self.config.word, 0)?;
+                m.copy_advice(|| "m", &mut region, self.config.limbs[0], 0)?;
+                let y = word_value(&x).map(|x| F::from(x.rotate_left(1)));
+                region.assign_advice(|| "y", self.config.word, 1, || y)
+            },
+        )
+    }
+
+    /// the little endian bytes of `word`
+    fn bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        word: &AssignedCell<F, F>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let bytes = word_value(word).map(u64::to_le_bytes);
+        layouter.assign_region(
+            || "blake2b bytes",
+            |mut region| {
+                self.config.q_word.enable(&mut region, 0)?;
+                word.copy_advice(|| "word", &mut region, self.config.word, 0)?;
+                self.config
+                    .limbs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        region.assign_advice(
+                            || format!("limb {}", i),
+                            *column,
+                            0,
+                            || bytes.map(|bytes| F::from(u64::from(bytes[i]))),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// the word of the little endian `bytes`
+    fn word(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value = bytes.iter().rev().fold(Value::known(0u64), |acc, byte| {
+            acc.zip(word_value(byte))
+                .map(|(acc, byte)| (acc << 8) | byte)
+        });
+        layouter.assign_region(
+            || "blake2b word",
+            |mut region| {
+                self.config.q_word.enable(&mut region, 0)?;
+                for (byte, column) in bytes.iter().zip(&self.config.limbs) {
+                    byte.copy_advice(|| "limb", &mut region, *column, 0)?;
+                }
+                region.assign_advice(|| "word", self.config.word, 0, || value.map(F::from))
+            },
+        )
+    }
+
+    fn constants(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[u64],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "blake2b constants",
+            |mut region| {
+                words
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, word)| {
+                        region.assign_advice_from_constant(
+                            || "constant",
+                            self.config.word,
+                            offset,
+                            F::from(*word),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+}
+
+/// out of range words are truncated, the lookups and gates reject them
+fn word_value<F: FieldExt>(cell: &AssignedCell<F, F>) -> Value<u64> {
+    cell.value().map(|value| value.get_lower_128() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use blake2::{Blake2b512, Digest};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// hash `message`, with the first word replaced by `first_word` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        message: Vec<u8>,
+        first_word: Option<u128>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = Blake2bConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![0; self.message.len()],
+                first_word: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Blake2bConfig {
+            Blake2bChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Blake2bConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = Blake2bChip::construct(config);
+            chip.load_tables(layouter.namespace(|| "tables"))?;
+            debug_assert!(
+                self.message.len() % 8 == 0,
+                "only messages of whole words are hashed"
+            );
+            let message = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    self.message
+                        .chunks(8)
+                        .enumerate()
+                        .map(|(offset, word)| {
+                            let word = match (offset, self.first_word) {
+                                (0, Some(word)) => word,
+                                _ => u64::from_le_bytes(word.try_into().unwrap()).into(),
+                            };
+                            region.assign_advice(
+                                || "message",
+                                config.word,
+                                offset,
+                                || Value::known(Fr::from_u128(word)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let digest = chip.hash(layouter.namespace(|| "blake2b"), &message)?;
+            let expected = Blake2b512::digest(&self.message);
+            for (word, expected) in digest.iter().zip(expected.chunks(8)) {
+                let expected = u64::from_le_bytes(expected.try_into().unwrap());
+                word.value().assert_if_known(|word| {
+                    self.first_word.is_some() || **word == Fr::from(expected)
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(message: &[u8], first_word: Option<u128>) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            message: message.to_vec(),
+            first_word,
+        };
+        MockProver::run(17, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn hash() {
+        prover(b"", None).assert_satisfied();
+        prover(b"hello halo2 blake2b 2b!!", None).assert_satisfied();
+        prover(b"blake2b!", None).assert_satisfied();
+        prover(&[0xa5; 8 * BLOCK_WORDS], None).assert_satisfied();
+    }
+
+    #[test]
+    fn out_of_range_word() {
+        // the second add of the first G copies the message word to its row 1
+        testing::assert_fails_gate(&prover(b"blake2b!", Some(1 << 64)), "uint recompose", 1);
+    }
+}
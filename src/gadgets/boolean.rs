@@ -0,0 +1,297 @@
+//! boolean logic gadget
+//!
+//! for boolean `a` and `b`:
+//!
+//! - `a AND b = a * b`
+//! - `a OR b = a + b - a * b`
+//! - `a XOR b = a + b - 2 * a * b`
+//! - `NOT a = 1 - a`
+//!
+//! each op copies its inputs into a row `[a, b, out]` and enables the selector of its
+//! gate. the inputs are not checked to be boolean, they have to come from
+//! [`BooleanChip::witness`] or from another op.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BooleanConfig {
+    // [a, b, out]
+    pub advice: [Column<Advice>; 3],
+    pub q_bool: Selector,
+    pub q_and: Selector,
+    pub q_or: Selector,
+    pub q_xor: Selector,
+    pub q_not: Selector,
+}
+
+pub struct BooleanChip<F: FieldExt> {
+    config: BooleanConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> BooleanChip<F> {
+    pub fn construct(config: BooleanConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> BooleanConfig {
+        let [col_a, col_b, col_out] = advice;
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let q_bool = meta.selector();
+        let q_and = meta.selector();
+        let q_or = meta.selector();
+        let q_xor = meta.selector();
+        let q_not = meta.selector();
+
+        let one = || Expression::Constant(F::one());
+
+        meta.create_gate("bool", |meta| {
+            let q = meta.query_selector(q_bool);
+            let out = meta.query_advice(col_out, Rotation::cur());
+            vec![q * out.clone() * (one() - out)]
+        });
+
+        let ops: [(
+            &'static str,
+            Selector,
+            fn(Expression<F>, Expression<F>) -> Expression<F>,
+        ); 3] = [
+            ("and", q_and, |a, b| a * b),
+            ("or", q_or, |a, b| a.clone() + b.clone() - a * b),
+            ("xor", q_xor, |a, b| {
+                a.clone() + b.clone() - Expression::Constant(F::from(2)) * a * b
+            }),
+        ];
+        for (name, selector, op) in ops {
+            meta.create_gate(name, |meta| {
+                let q = meta.query_selector(selector);
+                let a = meta.query_advice(col_a, Rotation::cur());
+                let b = meta.query_advice(col_b, Rotation::cur());
+                let out = meta.query_advice(col_out, Rotation::cur());
+                vec![q * (op(a, b) - out)]
+            });
+        }
+
+        meta.create_gate("not", |meta| {
+            let q = meta.query_selector(q_not);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let out = meta.query_advice(col_out, Rotation::cur());
+            vec![q * (one() - a - out)]
+        });
+
+        BooleanConfig {
+            advice,
+            q_bool,
+            q_and,
+            q_or,
+            q_xor,
+            q_not,
+        }
+    }
+
+    /// assign `value` and constrain it to be boolean
+    pub fn witness(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "witness bool",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                region.assign_advice(
+                    || "bool",
+                    self.config.advice[2],
+                    0,
+                    || value.map(|value| F::from(value as u64)),
+                )
+            },
+        )
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.binary(layouter, Op::And, a, b)
+    }
+
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.binary(layouter, Op::Or, a, b)
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.binary(layouter, Op::Xor, a, b)
+    }
+
+    pub fn not(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [col_a, _, col_out] = self.config.advice;
+        layouter.assign_region(
+            || "not",
+            |mut region| {
+                self.config.q_not.enable(&mut region, 0)?;
+                let a = a.copy_advice(|| "a", &mut region, col_a, 0)?;
+                let out = a.value().map(|a| F::one() - a);
+                region.assign_advice(|| "out", col_out, 0, || out)
+            },
+        )
+    }
+
+    pub fn binary(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: Op,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [col_a, col_b, col_out] = self.config.advice;
+        let selector = match op {
+            Op::And => self.config.q_and,
+            Op::Or => self.config.q_or,
+            Op::Xor => self.config.q_xor,
+        };
+        layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                selector.enable(&mut region, 0)?;
+                let a = a.copy_advice(|| "a", &mut region, col_a, 0)?;
+                let b = b.copy_advice(|| "b", &mut region, col_b, 0)?;
+                let out = a.value().zip(b.value()).map(|(&a, &b)| match op {
+                    Op::And => a * b,
+                    Op::Or => a + b - a * b,
+                    Op::Xor => a + b - a * b * F::from(2),
+                });
+                region.assign_advice(|| "out", col_out, 0, || out)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// `op(a, b)`, `NOT a` for `op = None`
+    struct TestCircuit {
+        a: u64,
+        b: u64,
+        op: Option<Op>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = BooleanConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: 0,
+                b: 0,
+                op: self.op,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> BooleanConfig {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            BooleanChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: BooleanConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = BooleanChip::construct(config);
+            // a non boolean value can only be assigned by skipping `witness`
+            let a = layouter.assign_region(
+                || "a",
+                |mut region| {
+                    config.q_bool.enable(&mut region, 0)?;
+                    region.assign_advice(
+                        || "a",
+                        config.advice[2],
+                        0,
+                        || Value::known(Fr::from(self.a)),
+                    )
+                },
+            )?;
+            let b = chip.witness(layouter.namespace(|| "b"), Value::known(self.b != 0))?;
+            let out = match self.op {
+                Some(op) => chip.binary(layouter.namespace(|| "op"), op, &a, &b)?,
+                None => chip.not(layouter.namespace(|| "not"), &a)?,
+            };
+            let expected = match self.op {
+                Some(Op::And) => self.a & self.b,
+                Some(Op::Or) => self.a | self.b,
+                Some(Op::Xor) => self.a ^ self.b,
+                None => 1 - self.a,
+            };
+            out.value()
+                .assert_if_known(|out| **out == Fr::from(expected));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn truth_tables() {
+        for op in [Some(Op::And), Some(Op::Or), Some(Op::Xor), None] {
+            for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                MockProver::run(5, &TestCircuit { a, b, op }, vec![])
+                    .unwrap()
+                    .assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_non_bool() {
+        let prover = MockProver::run(
+            5,
+            &TestCircuit {
+                a: 2,
+                b: 1,
+                op: Some(Op::And),
+            },
+            vec![],
+        )
+        .unwrap();
+        testing::assert_fails_gate(&prover, "bool", 0);
+    }
+}
@@ -0,0 +1,226 @@
+//! byte-wise XOR and AND gadget
+//!
+//! a table with the columns `[a, b, a ^ b, a & b]` over all pairs of bytes, `xor` and
+//! `and` look up `(a, b, out)` in 3 of its columns. the table takes `2^16` rows, so `k`
+//! has to be at least 17.
+//!
+//! the lookups also prove that `a`, `b` and `out` are bytes.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ByteOpsConfig {
+    // [a, b, out]
+    pub advice: [Column<Advice>; 3],
+    pub q_xor: Selector,
+    pub q_and: Selector,
+    // [a, b, a ^ b, a & b]
+    pub table: [TableColumn; 4],
+}
+
+pub struct ByteOpsChip<F: FieldExt> {
+    config: ByteOpsConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ByteOpsChip<F> {
+    pub fn construct(config: ByteOpsConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> ByteOpsConfig {
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let q_xor = meta.complex_selector();
+        let q_and = meta.complex_selector();
+        let table = [(); 4].map(|_| meta.lookup_table_column());
+        let [table_a, table_b, table_xor, table_and] = table;
+
+        for (selector, table_out) in [(q_xor, table_xor), (q_and, table_and)] {
+            meta.lookup(|meta| {
+                // (0, 0, 0) is in the table, so the rows not enabled pass
+                let q = meta.query_selector(selector);
+                let [a, b, out] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+                vec![
+                    (q.clone() * a, table_a),
+                    (q.clone() * b, table_b),
+                    (q * out, table_out),
+                ]
+            });
+        }
+
+        ByteOpsConfig {
+            advice,
+            q_xor,
+            q_and,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "byte ops",
+            |mut table| {
+                for (row, (a, b)) in (0..256u64)
+                    .flat_map(|a| (0..256u64).map(move |b| (a, b)))
+                    .enumerate()
+                {
+                    for (column, value) in self.config.table.iter().zip([a, b, a ^ b, a & b]) {
+                        table.assign_cell(
+                            || "byte op",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// copy `a` and `b` to `offset` and assign `a ^ b`
+    pub fn xor(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_xor.enable(region, offset)?;
+        self.assign(region, offset, a, b, |a, b| a ^ b)
+    }
+
+    /// copy `a` and `b` to `offset` and assign `a & b`
+    pub fn and(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_and.enable(region, offset)?;
+        self.assign(region, offset, a, b, |a, b| a & b)
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        op: fn(u64, u64) -> u64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [col_a, col_b, col_out] = self.config.advice;
+        let a = a.copy_advice(|| "a", region, col_a, offset)?;
+        let b = b.copy_advice(|| "b", region, col_b, offset)?;
+        // out of range inputs are left for the lookup to reject
+        let out = a
+            .value()
+            .zip(b.value())
+            .map(|(a, b)| F::from(op(a.get_lower_128() as u64, b.get_lower_128() as u64)));
+        region.assign_advice(|| "out", col_out, offset, || out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        a: u64,
+        b: u64,
+        cheat: Option<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = ByteOpsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> ByteOpsConfig {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            ByteOpsChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: ByteOpsConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = ByteOpsChip::construct(config);
+            chip.load_table(layouter.namespace(|| "byte ops"))?;
+            layouter.assign_region(
+                || "xor and",
+                |mut region| {
+                    let [col_a, col_b, col_out] = config.advice;
+                    let a = region.assign_advice(
+                        || "a",
+                        col_a,
+                        0,
+                        || Value::known(Fr::from(self.a)),
+                    )?;
+                    let b = region.assign_advice(
+                        || "b",
+                        col_b,
+                        0,
+                        || Value::known(Fr::from(self.b)),
+                    )?;
+                    let xor = chip.xor(&mut region, 1, &a, &b)?;
+                    let and = chip.and(&mut region, 2, &a, &b)?;
+                    xor.value()
+                        .assert_if_known(|xor| **xor == Fr::from(self.a ^ self.b));
+                    and.value()
+                        .assert_if_known(|and| **and == Fr::from(self.a & self.b));
+                    if let Some(cheat) = self.cheat {
+                        region.assign_advice(
+                            || "cheat",
+                            col_out,
+                            1,
+                            || Value::known(Fr::from(cheat)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(a: u64, b: u64, cheat: Option<u64>) -> MockProver<Fr> {
+        MockProver::run(17, &TestCircuit { a, b, cheat }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn xor_and() {
+        prover(0b1100_1010, 0b1010_0110, None).assert_satisfied();
+        prover(255, 0, None).assert_satisfied();
+    }
+
+    #[test]
+    fn rejects_wrong_output() {
+        testing::assert_fails_lookup(&prover(0b1100, 0b1010, Some(0b1110)), 1);
+    }
+
+    #[test]
+    fn rejects_non_bytes() {
+        testing::assert_fails_lookup(&prover(256, 1, None), 1);
+    }
+}
@@ -0,0 +1,276 @@
+//! less than gadget
+//!
+//! witnesses `lt = a < b` for `a, b < 2^(8 * N_BYTES)`:
+//!
+//! - `a - b + lt * 2^(8 * N_BYTES) = diff`
+//! - `lt` is boolean
+//! - `diff` is decomposed into `N_BYTES` bytes, each looked up in a table of `0..256`
+//!
+//! `diff` fitting in `N_BYTES` bytes leaves only one choice of `lt`: with `lt = 0` the
+//! difference wraps around when `a < b`, with `lt = 1` it is too large when `a >= b`.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, TableColumn, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LtConfig<const N_BYTES: usize> {
+    pub lt: Column<Advice>,
+    // little endian bytes of diff
+    pub diff: [Column<Advice>; N_BYTES],
+    pub u8_table: TableColumn,
+}
+
+pub struct LtChip<F: FieldExt, const N_BYTES: usize> {
+    config: LtConfig<N_BYTES>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const N_BYTES: usize> LtChip<F, N_BYTES> {
+    pub fn construct(config: LtConfig<N_BYTES>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// constrain `lt` on the rows where `q_enable` is 1, `q_enable` is used in lookups,
+    /// so it has to be built from a complex selector
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        a: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        b: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+    ) -> LtConfig<N_BYTES> {
+        assert!(
+            N_BYTES * 8 < F::CAPACITY as usize,
+            "a - b would wrap around the field"
+        );
+
+        let lt = meta.advice_column();
+        let diff = [(); N_BYTES].map(|_| meta.advice_column());
+        let u8_table = meta.lookup_table_column();
+
+        meta.create_gate("lt", |meta| {
+            let q_enable = q_enable(meta);
+            let a = a(meta);
+            let b = b(meta);
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let diff = diff
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, byte| {
+                    acc * Expression::Constant(F::from(256))
+                        + meta.query_advice(*byte, Rotation::cur())
+                });
+
+            vec![
+                q_enable.clone()
+                    * (a - b + lt.clone() * Expression::Constant(range::<F>(N_BYTES)) - diff),
+                q_enable * lt.clone() * (Expression::Constant(F::one()) - lt),
+            ]
+        });
+
+        for byte in diff {
+            meta.lookup(|meta| {
+                let q_enable = q_enable(meta);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(q_enable * byte, u8_table)]
+            });
+        }
+
+        LtConfig { lt, diff, u8_table }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "u8",
+            |mut table| {
+                for byte in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        self.config.u8_table,
+                        byte,
+                        || Value::known(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// assign `lt` and the bytes of `diff` at `offset`, `lt` is returned
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: Value<F>,
+        b: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // the reprs are little endian, compared from the most significant byte
+        let lt = a.zip(b).map(|(a, b)| {
+            let (a, b) = (a.to_repr(), b.to_repr());
+            a.as_ref().iter().rev().lt(b.as_ref().iter().rev())
+        });
+        let diff = a.zip(b).zip(lt).map(|((a, b), lt)| {
+            let diff = a - b + if lt { range::<F>(N_BYTES) } else { F::zero() };
+            diff.to_repr()
+        });
+
+        for (i, column) in self.config.diff.iter().enumerate() {
+            region.assign_advice(
+                || format!("diff byte {}", i),
+                *column,
+                offset,
+                || diff.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64)),
+            )?;
+        }
+        region.assign_advice(
+            || "lt",
+            self.config.lt,
+            offset,
+            || lt.map(|lt| F::from(lt as u64)),
+        )
+    }
+}
+
+/// `2^(8 * n_bytes)`
+fn range<F: FieldExt>(n_bytes: usize) -> F {
+    (0..8 * n_bytes).fold(F::one(), |acc, _| acc.double())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Selector},
+    };
+
+    /// `a < b` in `N` bytes, with `lt` overwritten by `cheat` if given
+    #[derive(Default)]
+    struct TestCircuit<const N: usize> {
+        a: Fr,
+        b: Fr,
+        lt: bool,
+        cheat: Option<u64>,
+    }
+
+    #[derive(Clone)]
+    struct TestConfig<const N: usize> {
+        q_enable: Selector,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        lt: LtConfig<N>,
+    }
+
+    impl<const N: usize> Circuit<Fr> for TestCircuit<N> {
+        type Config = TestConfig<N>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> TestConfig<N> {
+            let q_enable = meta.complex_selector();
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let lt = LtChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(a, Rotation::cur()),
+                |meta| meta.query_advice(b, Rotation::cur()),
+            );
+            TestConfig { q_enable, a, b, lt }
+        }
+
+        fn synthesize(
+            &self,
+            config: TestConfig<N>,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = LtChip::<Fr, N>::construct(config.lt);
+            chip.load_table(layouter.namespace(|| "u8"))?;
+            layouter.assign_region(
+                || "a < b",
+                |mut region| {
+                    let (a, b) = (Value::known(self.a), Value::known(self.b));
+                    config.q_enable.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", config.a, 0, || a)?;
+                    region.assign_advice(|| "b", config.b, 0, || b)?;
+                    let lt = chip.assign(&mut region, 0, a, b)?;
+                    lt.value()
+                        .assert_if_known(|lt| **lt == Fr::from(self.lt as u64));
+                    if let Some(cheat) = self.cheat {
+                        region.assign_advice(
+                            || "cheat",
+                            config.lt.lt,
+                            0,
+                            || Value::known(Fr::from(cheat)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(a: u64, b: u64, cheat: Option<u64>) -> MockProver<Fr> {
+        let circuit = TestCircuit::<4> {
+            a: Fr::from(a),
+            b: Fr::from(b),
+            lt: a < b,
+            cheat,
+        };
+        MockProver::run(9, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn compares() {
+        for (a, b) in [
+            (0, 0),
+            (1, 2),
+            (2, 1),
+            (0, u32::MAX as u64),
+            (u32::MAX as u64, 0),
+        ] {
+            prover(a, b, None).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_flag() {
+        // the diff bytes are left for the honest flag, so the gate fails first
+        testing::assert_fails_gate(&prover(1, 2, Some(0)), "lt", 0);
+        testing::assert_fails_gate(&prover(2, 1, Some(1)), "lt", 0);
+        testing::assert_fails_gate(&prover(1, 2, Some(2)), "lt", 0);
+    }
+
+    #[test]
+    fn compares_past_128_bits() {
+        let big = Fr::from_u128(1 << 127) * Fr::from(2);
+        for (a, b, lt) in [
+            (big, Fr::one(), false),
+            (Fr::one(), big, true),
+            (big, big + Fr::one(), true),
+        ] {
+            let circuit = TestCircuit::<20> {
+                a,
+                b,
+                lt,
+                cheat: None,
+            };
+            MockProver::run(9, &circuit, vec![])
+                .unwrap()
+                .assert_satisfied();
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! integer division gadget
+//!
+//! proves `a = q * d + r` with `r < d`, where `q`, `r` and `d - 1 - r` are range checked
+//! to `BITS` bits in a single column, so they share one table:
+//!
+//! | row | a | d | checked       |
+//! |-----|---|---|---------------|
+//! | 0   | a | d | q             |
+//! | 1   |   |   | r             |
+//! | 2   |   |   | d - 1 - r     |
+//!
+//! `q * d + r < 2^(2 * BITS)` has to be smaller than the modulus, otherwise a quotient
+//! could wrap around the field.
+
+use crate::gadgets::range::{RangeCheckChip, RangeCheckConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DivModConfig {
+    pub a: Column<Advice>,
+    pub d: Column<Advice>,
+    pub q_divmod: Selector,
+    // q, r and d - 1 - r
+    pub range: RangeCheckConfig,
+}
+
+pub struct DivModChip<F: FieldExt, const BITS: usize = 8> {
+    config: DivModConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const BITS: usize> DivModChip<F, BITS> {
+    pub fn construct(config: DivModConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        d: Column<Advice>,
+        checked: Column<Advice>,
+    ) -> DivModConfig {
+        assert!(2 * BITS < F::CAPACITY as usize);
+        meta.enable_equality(a);
+        meta.enable_equality(d);
+        meta.enable_equality(checked);
+        let q_divmod = meta.selector();
+        let range = RangeCheckChip::<F, BITS>::configure(meta, checked);
+
+        meta.create_gate("divmod", |meta| {
+            let q_divmod = meta.query_selector(q_divmod);
+            let a = meta.query_advice(a, Rotation::cur());
+            let d = meta.query_advice(d, Rotation::cur());
+            let q = meta.query_advice(checked, Rotation::cur());
+            let r = meta.query_advice(checked, Rotation::next());
+            let slack = meta.query_advice(checked, Rotation(2));
+            vec![
+                q_divmod.clone() * (a - (q * d.clone() + r.clone())),
+                q_divmod * (slack - (d - Expression::Constant(F::one()) - r)),
+            ]
+        });
+
+        DivModConfig {
+            a,
+            d,
+            q_divmod,
+            range,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        RangeCheckChip::<F, BITS>::construct(self.config.range).load_table(layouter)
+    }
+
+    /// divide `a` by `d`, returning the `(quotient, remainder)` cells
+    pub fn divmod(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        d: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let range = RangeCheckChip::<F, BITS>::construct(self.config.range);
+        // an honest prover has both below 2^128, d = 0 leaves q = r = 0 and fails the range check
+        let qr: Value<(u128, u128)> = a.value().zip(d.value()).map(|(a, d)| {
+            let (a, d) = (a.get_lower_128(), d.get_lower_128());
+            a.checked_div(d).zip(a.checked_rem(d)).unwrap_or((0, 0))
+        });
+
+        layouter.assign_region(
+            || "divmod",
+            |mut region| {
+                self.config.q_divmod.enable(&mut region, 0)?;
+                a.copy_advice(|| "a", &mut region, self.config.a, 0)?;
+                let d = d.copy_advice(|| "d", &mut region, self.config.d, 0)?;
+                let q = range.assign_checked(&mut region, 0, qr.map(|(q, _)| F::from_u128(q)))?;
+                let r = range.assign_checked(&mut region, 1, qr.map(|(_, r)| F::from_u128(r)))?;
+                range.assign_checked(
+                    &mut region,
+                    2,
+                    d.value().zip(r.value()).map(|(d, r)| *d - F::one() - r),
+                )?;
+                Ok((q, r))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        a: u64,
+        d: u64,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = DivModConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> DivModConfig {
+            let a = meta.advice_column();
+            let d = meta.advice_column();
+            let checked = meta.advice_column();
+            DivModChip::<Fr>::configure(meta, a, d, checked)
+        }
+
+        fn synthesize(
+            &self,
+            config: DivModConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = DivModChip::<Fr>::construct(config);
+            chip.load_table(layouter.namespace(|| "range"))?;
+            let (a, d) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(
+                        || "a",
+                        config.a,
+                        0,
+                        || Value::known(Fr::from(self.a)),
+                    )?;
+                    let d = region.assign_advice(
+                        || "d",
+                        config.d,
+                        0,
+                        || Value::known(Fr::from(self.d)),
+                    )?;
+                    Ok((a, d))
+                },
+            )?;
+            let (q, r) = chip.divmod(layouter.namespace(|| "divmod"), &a, &d)?;
+            if self.d != 0 {
+                q.value()
+                    .assert_if_known(|q| **q == Fr::from(self.a / self.d));
+                r.value()
+                    .assert_if_known(|r| **r == Fr::from(self.a % self.d));
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(a: u64, d: u64) -> MockProver<Fr> {
+        MockProver::run(9, &TestCircuit { a, d }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn divides() {
+        prover(0, 7).assert_satisfied();
+        prover(42, 7).assert_satisfied();
+        prover(43, 7).assert_satisfied();
+        prover(255, 1).assert_satisfied();
+        prover(255 * 255 + 254, 255).assert_satisfied();
+    }
+
+    #[test]
+    fn by_zero() {
+        // d - 1 - r = -1
+        testing::assert_fails_lookup(&prover(5, 0), 2);
+    }
+
+    #[test]
+    fn quotient_too_large() {
+        testing::assert_fails_lookup(&prover(256 * 3, 3), 0);
+    }
+}
@@ -0,0 +1,510 @@
+//! elliptic curve gadget
+//!
+//! affine points of `y^2 = x^3 + 7`, secp256k1 when the circuit is over its base field,
+//! so the coordinates are native field elements. every point takes a row, with the slope
+//! of the operation on the row of its first input:
+//!
+//! | row | x  | y  | lambda | inv             | selector    |
+//! |-----|----|----|--------|-----------------|-------------|
+//! | 0   | x1 | y1 | lambda | 1 / (x2 - x1)   | q_add       |
+//! | 1   | x2 | y2 |        |                 |             |
+//! | 2   | x3 | y3 |        |                 |             |
+//!
+//! - on curve: `y^2 = x^3 + 7`
+//! - add: `lambda (x2 - x1) = y2 - y1` with `x2 - x1` invertible, then
+//!   `x3 = lambda^2 - x1 - x2` and `y3 = lambda (x1 - x3) - y1`
+//! - double, on the rows `p, r`: `2 y lambda = 3 x^2`, `x3 = lambda^2 - 2 x` and
+//!   `y3 = lambda (x - x3) - y`
+//! - select, on the rows `p, q, r` with a boolean `bit` in `lambda` on the first one:
+//!   `r = bit ? p : q`
+//! - neg, on the rows `p, r`: `x3 = x1` and `y3 = -y1`
+//!
+//! the formulas are incomplete: there is no point at infinity, `add` rejects `p = q` and
+//! `p = -q`, and `double` expects a point on the curve, where `y` is never 0 on a curve of
+//! odd order like secp256k1.
+//!
+//! `scalar_mul` is double and add over the bits of the scalar, most significant first.
+//! the accumulator starts from `p` instead of the point at infinity, as if the scalar had
+//! an extra top bit, so every step adds `p` to a different multiple of it and only the
+//! final subtraction of `2^n p` could hit `p = -q`, for a zero scalar.
+
+use crate::gadgets::select;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// `b` of `y^2 = x^3 + b`
+pub const B: u64 = 7;
+
+#[derive(Debug, Clone)]
+pub struct EccPoint<F: FieldExt> {
+    pub x: AssignedCell<F, F>,
+    pub y: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EccConfig {
+    pub x: Column<Advice>,
+    pub y: Column<Advice>,
+    pub lambda: Column<Advice>,
+    pub inv: Column<Advice>,
+    pub q_on_curve: Selector,
+    pub q_add: Selector,
+    pub q_double: Selector,
+    pub q_select: Selector,
+    pub q_neg: Selector,
+}
+
+pub struct EccChip<F: FieldExt> {
+    config: EccConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> EccChip<F> {
+    pub fn construct(config: EccConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> EccConfig {
+        let x = meta.advice_column();
+        let y = meta.advice_column();
+        let lambda = meta.advice_column();
+        let inv = meta.advice_column();
+        let q_on_curve = meta.selector();
+        let q_add = meta.selector();
+        let q_double = meta.selector();
+        let q_select = meta.selector();
+        let q_neg = meta.selector();
+        meta.enable_equality(x);
+        meta.enable_equality(y);
+        meta.enable_equality(lambda);
+        let constant = |value: u64| Expression::Constant(F::from(value));
+
+        meta.create_gate("ecc on curve", |meta| {
+            let q = meta.query_selector(q_on_curve);
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            vec![q * (y.clone() * y - x.clone() * x.clone() * x - constant(B))]
+        });
+
+        meta.create_gate("ecc add", |meta| {
+            let q = meta.query_selector(q_add);
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let [x1, x2, x3] = [0, 1, 2].map(|i| meta.query_advice(x, Rotation(i)));
+            let [y1, y2, y3] = [0, 1, 2].map(|i| meta.query_advice(y, Rotation(i)));
+            vec![
+                q.clone() * ((x2.clone() - x1.clone()) * inv - constant(1)),
+                q.clone() * (lambda.clone() * (x2.clone() - x1.clone()) - (y2 - y1.clone())),
+                q.clone() * (x3.clone() - (lambda.clone() * lambda.clone() - x1.clone() - x2)),
+                q * (y3 - (lambda * (x1 - x3) - y1)),
+            ]
+        });
+
+        meta.create_gate("ecc double", |meta| {
+            let q = meta.query_selector(q_double);
+            let lambda = meta.query_advice(lambda, Rotation::cur());
+            let [x1, x3] = [0, 1].map(|i| meta.query_advice(x, Rotation(i)));
+            let [y1, y3] = [0, 1].map(|i| meta.query_advice(y, Rotation(i)));
+            vec![
+                q.clone()
+                    * (constant(2) * y1.clone() * lambda.clone()
+                        - constant(3) * x1.clone() * x1.clone()),
+                q.clone()
+                    * (x3.clone() - (lambda.clone() * lambda.clone() - constant(2) * x1.clone())),
+                q * (y3 - (lambda * (x1 - x3) - y1)),
+            ]
+        });
+
+        meta.create_gate("ecc select", |meta| {
+            let q = meta.query_selector(q_select);
+            let bit = meta.query_advice(lambda, Rotation::cur());
+            let [x1, x2, x3] = [0, 1, 2].map(|i| meta.query_advice(x, Rotation(i)));
+            let [y1, y2, y3] = [0, 1, 2].map(|i| meta.query_advice(y, Rotation(i)));
+            vec![
+                q.clone() * bit.clone() * (constant(1) - bit.clone()),
+                q.clone() * (x3 - select::expr(bit.clone(), x1, x2)),
+                q * (y3 - select::expr(bit, y1, y2)),
+            ]
+        });
+
+        meta.create_gate("ecc neg", |meta| {
+            let q = meta.query_selector(q_neg);
+            let [x1, x3] = [0, 1].map(|i| meta.query_advice(x, Rotation(i)));
+            let [y1, y3] = [0, 1].map(|i| meta.query_advice(y, Rotation(i)));
+            vec![q.clone() * (x3 - x1), q * (y3 + y1)]
+        });
+
+        EccConfig {
+            x,
+            y,
+            lambda,
+            inv,
+            q_on_curve,
+            q_add,
+            q_double,
+            q_select,
+            q_neg,
+        }
+    }
+
+    /// assign a new point and check it is on the curve
+    pub fn witness_point(
+        &self,
+        mut layouter: impl Layouter<F>,
+        point: Value<(F, F)>,
+    ) -> Result<EccPoint<F>, Error> {
+        layouter.assign_region(
+            || "ecc witness",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                self.assign(&mut region, 0, point)
+            },
+        )
+    }
+
+    /// check an assigned point is on the curve
+    pub fn assert_on_curve(
+        &self,
+        mut layouter: impl Layouter<F>,
+        point: &EccPoint<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "ecc on curve",
+            |mut region| {
+                self.config.q_on_curve.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, point)?;
+                Ok(())
+            },
+        )
+    }
+
+    /// `p + q` for `p != q` and `p != -q`
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EccPoint<F>,
+        q: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        let p_value = coordinates(p);
+        let q_value = coordinates(q);
+        // a zero inverse for p = q, left for the gate to reject
+        let inv = p_value
+            .zip(q_value)
+            .map(|((x1, _), (x2, _))| (x2 - x1).invert().unwrap_or(F::zero()));
+        let lambda = p_value
+            .zip(q_value)
+            .zip(inv)
+            .map(|(((_, y1), (_, y2)), inv)| (y2 - y1) * inv);
+        let r = p_value
+            .zip(q_value)
+            .zip(lambda)
+            .map(|(((x1, y1), (x2, _)), lambda)| {
+                let x3 = lambda * lambda - x1 - x2;
+                (x3, lambda * (x1 - x3) - y1)
+            });
+
+        layouter.assign_region(
+            || "ecc add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, p)?;
+                self.copy(&mut region, 1, q)?;
+                region.assign_advice(|| "lambda", self.config.lambda, 0, || lambda)?;
+                region.assign_advice(|| "inv", self.config.inv, 0, || inv)?;
+                self.assign(&mut region, 2, r)
+            },
+        )
+    }
+
+    /// `2 p` for `p` on the curve
+    pub fn double(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        let p_value = coordinates(p);
+        let lambda = p_value.map(|(x, y)| {
+            let inv = y.double().invert().unwrap_or(F::zero());
+            F::from(3) * x * x * inv
+        });
+        let r = p_value.zip(lambda).map(|((x, y), lambda)| {
+            let x3 = lambda * lambda - x.double();
+            (x3, lambda * (x - x3) - y)
+        });
+
+        layouter.assign_region(
+            || "ecc double",
+            |mut region| {
+                self.config.q_double.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, p)?;
+                region.assign_advice(|| "lambda", self.config.lambda, 0, || lambda)?;
+                self.assign(&mut region, 1, r)
+            },
+        )
+    }
+
+    /// `p` if `bit` is 1, `q` if it is 0
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bit: &AssignedCell<F, F>,
+        p: &EccPoint<F>,
+        q: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        let r = bit
+            .value()
+            .zip(coordinates(p))
+            .zip(coordinates(q))
+            .map(|((bit, p), q)| if *bit == F::one() { p } else { q });
+
+        layouter.assign_region(
+            || "ecc select",
+            |mut region| {
+                self.config.q_select.enable(&mut region, 0)?;
+                bit.copy_advice(|| "bit", &mut region, self.config.lambda, 0)?;
+                self.copy(&mut region, 0, p)?;
+                self.copy(&mut region, 1, q)?;
+                self.assign(&mut region, 2, r)
+            },
+        )
+    }
+
+    /// `-p`
+    pub fn neg(
+        &self,
+        mut layouter: impl Layouter<F>,
+        p: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        let r = coordinates(p).map(|(x, y)| (x, -y));
+
+        layouter.assign_region(
+            || "ecc neg",
+            |mut region| {
+                self.config.q_neg.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, p)?;
+                self.assign(&mut region, 1, r)
+            },
+        )
+    }
+
+    /// `k p` from the `n` bits of `k`, least significant first as returned by the
+    /// [`DecomposeChip`](crate::gadgets::bits::DecomposeChip), for `0 < k < 2^n` and `n` well below
+    /// the bit length of the curve order
+    pub fn scalar_mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+        p: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        let mut acc = p.clone();
+        let mut offset = p.clone();
+        for (i, bit) in bits.iter().enumerate().rev() {
+            let mut layouter = layouter.namespace(|| format!("bit {}", i));
+            let double = self.double(layouter.namespace(|| "2 acc"), &acc)?;
+            let sum = self.add(layouter.namespace(|| "2 acc + p"), &double, p)?;
+            acc = self.select(layouter.namespace(|| "select"), bit, &sum, &double)?;
+            offset = self.double(layouter.namespace(|| "offset"), &offset)?;
+        }
+
+        // acc is (2^n + k) p
+        let offset = self.neg(layouter.namespace(|| "-2^n p"), &offset)?;
+        self.add(layouter.namespace(|| "acc - 2^n p"), &acc, &offset)
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        point: Value<(F, F)>,
+    ) -> Result<EccPoint<F>, Error> {
+        Ok(EccPoint {
+            x: region.assign_advice(|| "x", self.config.x, offset, || point.map(|p| p.0))?,
+            y: region.assign_advice(|| "y", self.config.y, offset, || point.map(|p| p.1))?,
+        })
+    }
+
+    fn copy(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        point: &EccPoint<F>,
+    ) -> Result<EccPoint<F>, Error> {
+        Ok(EccPoint {
+            x: point.x.copy_advice(|| "x", region, self.config.x, offset)?,
+            y: point.y.copy_advice(|| "y", region, self.config.y, offset)?,
+        })
+    }
+}
+
+fn coordinates<F: FieldExt>(point: &EccPoint<F>) -> Value<(F, F)> {
+    point.x.value().copied().zip(point.y.value().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gadgets::bits::{DecomposeChip, DecomposeConfig},
+        testing,
+    };
+    use halo2_proofs::{
+        arithmetic::CurveAffine,
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::{
+            group::{prime::PrimeCurveAffine, Curve},
+            secp256k1::{Fp, Fq, Secp256k1Affine},
+        },
+        plonk::Circuit,
+    };
+
+    /// `n * G`
+    fn multiple(n: u64) -> (Fp, Fp) {
+        let point = (Secp256k1Affine::generator() * Fq::from(n)).to_affine();
+        let coordinates = point.coordinates().unwrap();
+        (*coordinates.x(), *coordinates.y())
+    }
+
+    /// `p + q` and `2 p`, checked against `expected` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        p: (Fp, Fp),
+        q: (Fp, Fp),
+        expected: Option<((Fp, Fp), (Fp, Fp))>,
+    }
+
+    impl Circuit<Fp> for TestCircuit {
+        type Config = EccConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> EccConfig {
+            EccChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: EccConfig,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = EccChip::construct(config);
+            let p = chip.witness_point(layouter.namespace(|| "p"), Value::known(self.p))?;
+            let q = chip.witness_point(layouter.namespace(|| "q"), Value::known(self.q))?;
+            let sum = chip.add(layouter.namespace(|| "p + q"), &p, &q)?;
+            let double = chip.double(layouter.namespace(|| "2p"), &p)?;
+            if let Some((expected_sum, expected_double)) = self.expected {
+                coordinates(&sum).assert_if_known(|sum| *sum == expected_sum);
+                coordinates(&double).assert_if_known(|double| *double == expected_double);
+            }
+            chip.assert_on_curve(layouter.namespace(|| "sum on curve"), &sum)
+        }
+    }
+
+    fn prover(p: (Fp, Fp), q: (Fp, Fp), expected: Option<((Fp, Fp), (Fp, Fp))>) -> MockProver<Fp> {
+        MockProver::run(5, &TestCircuit { p, q, expected }, vec![]).unwrap()
+    }
+
+    const SCALAR_BITS: usize = 8;
+
+    /// `k * G`, checked against `expected` if given
+    #[derive(Default)]
+    struct ScalarMulCircuit {
+        k: u64,
+        expected: Option<(Fp, Fp)>,
+    }
+
+    impl Circuit<Fp> for ScalarMulCircuit {
+        type Config = (EccConfig, DecomposeConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let ecc = EccChip::configure(meta);
+            let bit = meta.advice_column();
+            let acc = meta.advice_column();
+            (ecc, DecomposeChip::configure(meta, bit, acc))
+        }
+
+        fn synthesize(
+            &self,
+            (ecc, decompose): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = EccChip::construct(ecc);
+            let g = chip.witness_point(layouter.namespace(|| "G"), Value::known(multiple(1)))?;
+            let k = layouter.assign_region(
+                || "k",
+                |mut region| {
+                    region.assign_advice(
+                        || "k",
+                        decompose.acc,
+                        0,
+                        || Value::known(Fp::from(self.k)),
+                    )
+                },
+            )?;
+            let bits = DecomposeChip::construct(decompose).decompose(
+                layouter.namespace(|| "bits of k"),
+                &k,
+                SCALAR_BITS,
+            )?;
+            let r = chip.scalar_mul(layouter.namespace(|| "k G"), &bits, &g)?;
+            if let Some(expected) = self.expected {
+                coordinates(&r).assert_if_known(|r| *r == expected);
+            }
+            Ok(())
+        }
+    }
+
+    fn scalar_mul_prover(k: u64, expected: Option<(Fp, Fp)>) -> MockProver<Fp> {
+        MockProver::run(8, &ScalarMulCircuit { k, expected }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn add_double() {
+        let expected = Some((multiple(3), multiple(2)));
+        prover(multiple(1), multiple(2), expected).assert_satisfied();
+        let expected = Some((multiple(16), multiple(10)));
+        prover(multiple(5), multiple(11), expected).assert_satisfied();
+    }
+
+    #[test]
+    fn off_curve() {
+        let (x, y) = multiple(1);
+        let prover = prover((x, y + Fp::one()), multiple(2), None);
+        testing::assert_fails_gate(&prover, "ecc on curve", 0);
+    }
+
+    #[test]
+    fn add_same_point() {
+        let prover = prover(multiple(7), multiple(7), None);
+        testing::assert_fails_gate(&prover, "ecc add", 0);
+    }
+
+    #[test]
+    fn scalar_mul() {
+        for k in [1, 2, 5, 0x80, 0xa7, 0xff] {
+            scalar_mul_prover(k, Some(multiple(k))).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn zero_scalar() {
+        // acc ends at 2^n G, the offset to remove at -2^n G
+        testing::assert_fails_gate(&scalar_mul_prover(0, None), "ecc add", 0);
+    }
+}
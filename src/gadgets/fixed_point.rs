@@ -0,0 +1,374 @@
+//! fixed point gadget
+//!
+//! signed Q32.32 numbers: `x` is the integer `X = round(x * 2^32)` with `|X| < 2^63`,
+//! negative ones wrapped around the field. every result takes a row:
+//!
+//! | a | b | z     | rem | slack         | shifted  |
+//! |---|---|-------|-----|---------------|----------|
+//! | a | b | a + b |     |               | z + 2^63 |
+//! | a | b | a * b | rem | 2^32 - 1 - rem| z + 2^63 |
+//! | a | b | a / b | rem | 2b - 1 - rem  | z + 2^63 |
+//!
+//! - `shifted` fits in 64 bits, so `z` is a signed 64 bit integer
+//! - mul: `a * b + 2^31 = z * 2^32 + rem`, `0 <= rem < 2^32`, rounding to the nearest
+//! - div: `2^33 * a + b = 2b * z + rem`, `0 <= rem < 2b`, rounding to the nearest, `b`
+//!   has to be positive
+//!
+//! the bounds are checked by the [`U64Chip`] on `shifted`, `rem` and `slack`, all of the
+//! equations stay below `2^130`, far from wrapping around.
+
+use crate::gadgets::uint::{U64Chip, UintConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// bits after the point
+pub const FRAC_BITS: u32 = 32;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPointConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub z: Column<Advice>,
+    pub rem: Column<Advice>,
+    pub slack: Column<Advice>,
+    pub shifted: Column<Advice>,
+    // every row
+    pub q_range: Selector,
+    pub q_add: Selector,
+    pub q_mul: Selector,
+    pub q_div: Selector,
+    pub uint: UintConfig<8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Assign,
+    Add,
+    Mul,
+    Div,
+}
+
+pub struct FixedPointChip<F: FieldExt> {
+    config: FixedPointConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FixedPointChip<F> {
+    pub fn construct(config: FixedPointConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FixedPointConfig {
+        let [a, b, z, rem, slack, shifted] = [(); 6].map(|_| meta.advice_column());
+        for column in [a, b, z, rem, slack, shifted] {
+            meta.enable_equality(column);
+        }
+        let q_range = meta.selector();
+        let q_add = meta.selector();
+        let q_mul = meta.selector();
+        let q_div = meta.selector();
+        let uint = U64Chip::<F>::configure(meta);
+
+        let constant = |value: u128| Expression::Constant(F::from_u128(value));
+        let cur =
+            |meta: &mut VirtualCells<'_, F>, column| meta.query_advice(column, Rotation::cur());
+
+        meta.create_gate("fixed range", |meta| {
+            let q_range = meta.query_selector(q_range);
+            vec![q_range * (cur(meta, shifted) - cur(meta, z) - constant(1 << 63))]
+        });
+
+        meta.create_gate("fixed add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            vec![q_add * (cur(meta, a) + cur(meta, b) - cur(meta, z))]
+        });
+
+        meta.create_gate("fixed mul", |meta| {
+            let q_mul = meta.query_selector(q_mul);
+            let (a, b, z) = (cur(meta, a), cur(meta, b), cur(meta, z));
+            let (rem, slack) = (cur(meta, rem), cur(meta, slack));
+            vec![
+                q_mul.clone()
+                    * (a * b + constant(1 << (FRAC_BITS - 1))
+                        - z * constant(1 << FRAC_BITS)
+                        - rem.clone()),
+                q_mul * (slack - (constant((1 << FRAC_BITS) - 1) - rem)),
+            ]
+        });
+
+        meta.create_gate("fixed div", |meta| {
+            let q_div = meta.query_selector(q_div);
+            let (a, b, z) = (cur(meta, a), cur(meta, b), cur(meta, z));
+            let (rem, slack) = (cur(meta, rem), cur(meta, slack));
+            vec![
+                q_div.clone()
+                    * (a * constant(1 << (FRAC_BITS + 1)) + b.clone()
+                        - b.clone() * constant(2) * z
+                        - rem.clone()),
+                q_div * (slack - (b * constant(2) - constant(1) - rem)),
+            ]
+        });
+
+        FixedPointConfig {
+            a,
+            b,
+            z,
+            rem,
+            slack,
+            shifted,
+            q_range,
+            q_add,
+            q_mul,
+            q_div,
+            uint,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        U64Chip::construct(self.config.uint).load_table(layouter)
+    }
+
+    /// assign a new number, encoded by [`encode`]
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<i64>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign_op(layouter, Op::Assign, None, value)
+    }
+
+    pub fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let z = decode_cell(a).zip(decode_cell(b)).map(|(a, b)| a + b);
+        self.assign_op(layouter, Op::Add, Some((a, b)), z)
+    }
+
+    /// `a * b`, rounded to the nearest
+    pub fn mul(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let z = decode_cell(a)
+            .zip(decode_cell(b))
+            .map(|(a, b)| mul_rounded(a, b));
+        self.assign_op(layouter, Op::Mul, Some((a, b)), z)
+    }
+
+    /// `a / b` for a positive `b`, rounded to the nearest
+    pub fn div(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let z = decode_cell(a)
+            .zip(decode_cell(b))
+            .map(|(a, b)| div_rounded(a, b));
+        self.assign_op(layouter, Op::Div, Some((a, b)), z)
+    }
+
+    fn assign_op(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: Op,
+        inputs: Option<(&AssignedCell<F, F>, &AssignedCell<F, F>)>,
+        z: Value<i64>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = self.config;
+        let operands = inputs
+            .map(|(a, b)| decode_cell(a).zip(decode_cell(b)))
+            .unwrap_or_else(|| Value::known((0, 0)));
+        // (rem, slack)
+        let bounds = operands.map(|(a, b)| {
+            let (a, b) = (a as i128, b as i128);
+            match op {
+                Op::Assign | Op::Add => None,
+                Op::Mul => {
+                    let rem = (a * b + (1 << (FRAC_BITS - 1))).rem_euclid(1 << FRAC_BITS);
+                    Some((rem, (1 << FRAC_BITS) - 1 - rem))
+                }
+                Op::Div => {
+                    let rem = ((a << (FRAC_BITS + 1)) + b).rem_euclid(2 * b);
+                    Some((rem, 2 * b - 1 - rem))
+                }
+            }
+        });
+
+        let (z, checked) = layouter.assign_region(
+            || format!("fixed {:?}", op),
+            |mut region| {
+                config.q_range.enable(&mut region, 0)?;
+                match op {
+                    Op::Assign => {}
+                    Op::Add => config.q_add.enable(&mut region, 0)?,
+                    Op::Mul => config.q_mul.enable(&mut region, 0)?,
+                    Op::Div => config.q_div.enable(&mut region, 0)?,
+                }
+                if let Some((a, b)) = inputs {
+                    a.copy_advice(|| "a", &mut region, config.a, 0)?;
+                    b.copy_advice(|| "b", &mut region, config.b, 0)?;
+                }
+
+                let z = region.assign_advice(|| "z", config.z, 0, || z.map(encode::<F>))?;
+                let shifted = region.assign_advice(
+                    || "z + 2^63",
+                    config.shifted,
+                    0,
+                    || z.value().map(|z| *z + F::from_u128(1 << 63)),
+                )?;
+                let mut checked = vec![shifted];
+                if !matches!(op, Op::Assign | Op::Add) {
+                    let rem = region.assign_advice(
+                        || "rem",
+                        config.rem,
+                        0,
+                        || bounds.map(|bounds| F::from_u128(bounds.unwrap().0 as u128)),
+                    )?;
+                    let slack = region.assign_advice(
+                        || "slack",
+                        config.slack,
+                        0,
+                        || bounds.map(|bounds| encode_i128(bounds.unwrap().1)),
+                    )?;
+                    checked.extend([rem, slack]);
+                }
+                Ok((z, checked))
+            },
+        )?;
+
+        U64Chip::construct(config.uint).range_check(layouter.namespace(|| "bounds"), &checked)?;
+        Ok(z)
+    }
+}
+
+/// the field element of the Q32.32 integer `x`
+pub fn encode<F: FieldExt>(x: i64) -> F {
+    encode_i128(x as i128)
+}
+
+fn encode_i128<F: FieldExt>(x: i128) -> F {
+    if x < 0 {
+        -F::from_u128(x.unsigned_abs())
+    } else {
+        F::from_u128(x as u128)
+    }
+}
+
+/// the Q32.32 integer of `x`, which has to be in range
+pub fn decode<F: FieldExt>(x: &F) -> i64 {
+    ((*x + F::from_u128(1 << 63)).get_lower_128() as u64 ^ (1 << 63)) as i64
+}
+
+fn decode_cell<F: FieldExt>(cell: &AssignedCell<F, F>) -> Value<i64> {
+    cell.value().map(decode::<F>)
+}
+
+pub fn from_f64(x: f64) -> i64 {
+    (x * (1u64 << FRAC_BITS) as f64).round() as i64
+}
+
+pub fn to_f64(x: i64) -> f64 {
+    x as f64 / (1u64 << FRAC_BITS) as f64
+}
+
+/// `a * b` rounded like the chip does, outside of the circuit
+pub fn mul_rounded(a: i64, b: i64) -> i64 {
+    ((a as i128 * b as i128 + (1 << (FRAC_BITS - 1))) >> FRAC_BITS) as i64
+}
+
+/// `a / b` rounded like the chip does, outside of the circuit
+pub fn div_rounded(a: i64, b: i64) -> i64 {
+    let (a, b) = (a as i128, b as i128);
+    ((a << (FRAC_BITS + 1)) + b).div_euclid(2 * b) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        a: f64,
+        b: f64,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = FixedPointConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> FixedPointConfig {
+            FixedPointChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: FixedPointConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = FixedPointChip::construct(config);
+            chip.load_table(layouter.namespace(|| "u8"))?;
+            let (a, b) = (from_f64(self.a), from_f64(self.b));
+            let a_cell = chip.assign(layouter.namespace(|| "a"), Value::known(a))?;
+            let b_cell = chip.assign(layouter.namespace(|| "b"), Value::known(b))?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a_cell, &b_cell)?;
+            sum.value().assert_if_known(|sum| decode(*sum) == a + b);
+            let product = chip.mul(layouter.namespace(|| "a * b"), &a_cell, &b_cell)?;
+            product
+                .value()
+                .assert_if_known(|product| decode(*product) == mul_rounded(a, b));
+            chip.div(layouter.namespace(|| "a / b"), &a_cell, &b_cell)?;
+            Ok(())
+        }
+    }
+
+    fn prover(a: f64, b: f64) -> MockProver<Fr> {
+        MockProver::run(9, &TestCircuit { a, b }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        assert_eq!(decode::<Fr>(&encode(-5)), -5);
+        assert_eq!(to_f64(mul_rounded(from_f64(1.5), from_f64(-2.25))), -3.375);
+        assert_eq!(to_f64(div_rounded(from_f64(-7.5), from_f64(2.5))), -3.0);
+        // 1 / 3 rounds to the nearest multiple of 2^-32
+        assert_eq!(div_rounded(1 << 32, 3 << 32), 1431655765);
+        assert_eq!(div_rounded(2 << 32, 3 << 32), 2863311531);
+    }
+
+    #[test]
+    fn arithmetic() {
+        prover(1.5, 2.25).assert_satisfied();
+        prover(-1.5, 2.25).assert_satisfied();
+        prover(1000.125, 0.001).assert_satisfied();
+        prover(-0.0, 1.0).assert_satisfied();
+    }
+
+    #[test]
+    fn divide_by_negative() {
+        // the slack 2b - 1 - rem is negative, its bytes do not add up to it
+        testing::assert_fails_gate(&prover(1.0, -2.0), "uint recompose", 2);
+    }
+}
@@ -0,0 +1,275 @@
+//! finite state machine gadget
+//!
+//! runs a deterministic automaton over a stream of input symbols, one step per row. the
+//! transitions `(state, input, next)` of the automaton are loaded in fixed table columns,
+//! and each step is a lookup of `(1, state, input, state')` in them:
+//!
+//! | row | state    | input    | q_step |
+//! |-----|----------|----------|--------|
+//! | 0   | start    | input[0] | 1      |
+//! | i   | state[i] | input[i] | 1      |
+//! | n   | state[n] |          | 0      |
+//!
+//! the rows without `q_step` look up `(0, 0, 0, 0)`, the first row of the table, while a
+//! step has to match a transition with the tag 1, whatever the numbers of its states and
+//! symbols. a step without a transition fails its lookup, accepting is left to the caller,
+//! which gets the cell of the last state.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// `(state, input, next)`
+pub type Transition = (u64, u64, u64);
+
+/// the state after `input` in `state`, if the automaton has such a transition
+pub fn step(transitions: &[Transition], state: u64, input: u64) -> Option<u64> {
+    transitions
+        .iter()
+        .find(|(from, symbol, _)| *from == state && *symbol == input)
+        .map(|(_, _, next)| *next)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsmConfig {
+    pub state: Column<Advice>,
+    pub input: Column<Advice>,
+    pub q_step: Selector,
+    /// tag, state, input and next state of the transitions
+    pub table: [TableColumn; 4],
+}
+
+pub struct FsmChip<F: FieldExt> {
+    config: FsmConfig,
+    transitions: Vec<Transition>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FsmChip<F> {
+    pub fn construct(config: FsmConfig, transitions: &[Transition]) -> Self {
+        Self {
+            config,
+            transitions: transitions.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        state: Column<Advice>,
+        input: Column<Advice>,
+    ) -> FsmConfig {
+        let q_step = meta.complex_selector();
+        let table = [(); 4].map(|_| meta.lookup_table_column());
+        meta.enable_equality(state);
+        meta.enable_equality(input);
+
+        meta.lookup(|meta| {
+            let q_step = meta.query_selector(q_step);
+            let state = meta.query_advice(state, Rotation::cur());
+            let input = meta.query_advice(input, Rotation::cur());
+            let next = meta.query_advice(state, Rotation::next());
+            let [tag, from, symbol, to] = table;
+            vec![
+                (q_step.clone(), tag),
+                (q_step.clone() * state, from),
+                (q_step.clone() * input, symbol),
+                (q_step * next, to),
+            ]
+        });
+
+        FsmConfig {
+            state,
+            input,
+            q_step,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "transitions",
+            |mut table| {
+                let rows = [(0, 0, 0, 0)]
+                    .into_iter()
+                    .chain(self.transitions.iter().map(|(s, i, n)| (1, *s, *i, *n)));
+                for (row, (tag, from, symbol, to)) in rows.enumerate() {
+                    for (column, value) in self.config.table.iter().zip([tag, from, symbol, to]) {
+                        table.assign_cell(
+                            || "transition",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// run the automaton from `start` over `inputs`, the cell of the last state is returned.
+    /// a step without a transition is witnessed as the state 0, and fails its lookup
+    pub fn run(
+        &self,
+        mut layouter: impl Layouter<F>,
+        start: &AssignedCell<F, F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "fsm",
+            |mut region| self.assign(&mut region, 0, start, inputs),
+        )
+    }
+
+    /// the same as [`FsmChip::run`], in the rows `offset..=offset + inputs.len()` of a region
+    /// of the caller, so that many runs can share one
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        start: &AssignedCell<F, F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = self.config;
+        let mut state = start.copy_advice(|| "start", region, config.state, offset)?;
+        for (i, input) in inputs.iter().enumerate() {
+            config.q_step.enable(region, offset + i)?;
+            input.copy_advice(|| "input", region, config.input, offset + i)?;
+            let next = state.value().zip(input.value()).map(|(state, input)| {
+                let next = step(
+                    &self.transitions,
+                    state.get_lower_128() as u64,
+                    input.get_lower_128() as u64,
+                );
+                F::from(next.unwrap_or(0))
+            });
+            state = region.assign_advice(|| "state", config.state, offset + i + 1, || next)?;
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// binary numbers, most significant bit first, the state is the number mod 3
+    const MOD3: [Transition; 6] = [
+        (0, 0, 0),
+        (0, 1, 1),
+        (1, 0, 2),
+        (1, 1, 0),
+        (2, 0, 1),
+        (2, 1, 2),
+    ];
+
+    #[derive(Default)]
+    struct TestCircuit {
+        inputs: Vec<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = FsmConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![0; self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> FsmConfig {
+            let state = meta.advice_column();
+            let input = meta.advice_column();
+            let constant = meta.fixed_column();
+            meta.enable_constant(constant);
+            FsmChip::configure(meta, state, input)
+        }
+
+        fn synthesize(
+            &self,
+            config: FsmConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = FsmChip::construct(config, &MOD3);
+            chip.load_table(layouter.namespace(|| "transitions"))?;
+            let (start, inputs) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let start = region.assign_advice_from_constant(
+                        || "start",
+                        config.state,
+                        0,
+                        Fr::zero(),
+                    )?;
+                    let inputs = self
+                        .inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, input)| {
+                            region.assign_advice(
+                                || "input",
+                                config.input,
+                                offset,
+                                || Value::known(Fr::from(*input)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok((start, inputs))
+                },
+            )?;
+            let last = chip.run(layouter.namespace(|| "mod 3"), &start, &inputs)?;
+            // accept the multiples of 3
+            layouter.assign_region(
+                || "accept",
+                |mut region| region.constrain_constant(last.cell(), Fr::zero()),
+            )
+        }
+    }
+
+    fn prover(inputs: &[u64]) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            inputs: inputs.to_vec(),
+        };
+        MockProver::run(4, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        let run = |bits: &[u64]| {
+            bits.iter()
+                .try_fold(0, |state, bit| step(&MOD3, state, *bit))
+        };
+        assert_eq!(run(&[1, 1, 0]), Some(0));
+        assert_eq!(run(&[1, 0, 1, 1]), Some(2));
+        assert_eq!(run(&[1, 2]), None);
+    }
+
+    #[test]
+    fn accepted() {
+        // 6, 9 and 21
+        prover(&[1, 1, 0]).assert_satisfied();
+        prover(&[1, 0, 0, 1]).assert_satisfied();
+        prover(&[1, 0, 1, 0, 1]).assert_satisfied();
+    }
+
+    #[test]
+    fn rejected() {
+        // 11 ends in the state 2
+        testing::assert_fails_permutation(&prover(&[1, 0, 1, 1]));
+    }
+
+    #[test]
+    fn no_transition() {
+        testing::assert_fails_lookup(&prover(&[1, 2, 0]), 1);
+    }
+}
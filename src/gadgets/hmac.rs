@@ -0,0 +1,187 @@
+//! hmac-sha256 gadget
+//!
+//! `H((K ^ opad) || H((K ^ ipad) || m))` with the [`Sha256Chip`], for a key of at most one
+//! block, zero padded to 16 words. the pads are XORed in with the bitwise regions of the
+//! chip, the padding of the key is a constant of the circuit since `0 ^ pad = pad`, so only
+//! the length of the key is public.
+
+use crate::gadgets::sha256::{self, Sha256Chip, Sha256Config, Word, BLOCK_WORDS};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error},
+};
+
+const IPAD: u32 = 0x3636_3636;
+const OPAD: u32 = 0x5c5c_5c5c;
+
+/// hmac-sha256, outside of the circuit, for a key of at most 64 bytes
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    assert!(key.len() <= 4 * BLOCK_WORDS);
+    let mut block = key.to_vec();
+    block.resize(4 * BLOCK_WORDS, 0);
+    let pad = |pad: u8| block.iter().map(move |byte| byte ^ pad);
+
+    let inner: Vec<_> = pad(0x36).chain(message.iter().copied()).collect();
+    let outer: Vec<_> = pad(0x5c).chain(sha256::sha256(&inner)).collect();
+    sha256::sha256(&outer)
+}
+
+pub struct HmacChip<F: FieldExt> {
+    sha256: Sha256Chip<F>,
+}
+
+impl<F: FieldExt> HmacChip<F> {
+    pub fn construct(config: Sha256Config) -> Self {
+        Self {
+            sha256: Sha256Chip::construct(config),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Sha256Config {
+        Sha256Chip::configure(meta)
+    }
+
+    /// the tag of `message` under `key`, both whole words read as big endian bytes
+    pub fn mac(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &[Word<F>],
+        message: &[Word<F>],
+    ) -> Result<[Word<F>; 8], Error> {
+        assert!(key.len() <= BLOCK_WORDS, "a key of at most one block");
+        let inner = self.pad(layouter.namespace(|| "key ^ ipad"), key, IPAD)?;
+        let outer = self.pad(layouter.namespace(|| "key ^ opad"), key, OPAD)?;
+
+        let inner: Vec<_> = inner.into_iter().chain(message.iter().cloned()).collect();
+        let inner = self
+            .sha256
+            .hash(layouter.namespace(|| "inner hash"), &inner)?;
+        let outer: Vec<_> = outer.into_iter().chain(inner).collect();
+        self.sha256
+            .hash(layouter.namespace(|| "outer hash"), &outer)
+    }
+
+    /// the block of `key ^ pad`
+    fn pad(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &[Word<F>],
+        pad: u32,
+    ) -> Result<Vec<Word<F>>, Error> {
+        let mut block = Vec::with_capacity(BLOCK_WORDS);
+        for word in key {
+            block.push(
+                self.sha256
+                    .xor_constant(layouter.namespace(|| "word ^ pad"), word, pad)?,
+            );
+        }
+        for _ in key.len()..BLOCK_WORDS {
+            block.push(self.sha256.constant(layouter.namespace(|| "pad"), pad)?);
+        }
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    /// the tag of `message` under `key`, checked against `hmac_sha256`
+    #[derive(Default)]
+    struct TestCircuit {
+        key: Vec<u8>,
+        message: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = Sha256Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                key: vec![0; self.key.len()],
+                message: vec![0; self.message.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Sha256Config {
+            HmacChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Sha256Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let sha256 = Sha256Chip::construct(config);
+            let mut words = |name: &'static str, bytes: &[u8]| {
+                let cells = layouter.assign_region(
+                    || name,
+                    |mut region| {
+                        bytes
+                            .chunks(4)
+                            .enumerate()
+                            .map(|(offset, word)| {
+                                let word = u32::from_be_bytes(word.try_into().unwrap());
+                                region.assign_advice(
+                                    || name,
+                                    config.input[0],
+                                    offset,
+                                    || Value::known(Fr::from(u64::from(word))),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    },
+                )?;
+                cells
+                    .iter()
+                    .map(|cell| sha256.word(layouter.namespace(|| name), cell))
+                    .collect::<Result<Vec<_>, Error>>()
+            };
+            let key = words("key", &self.key)?;
+            let message = words("message", &self.message)?;
+
+            let tag =
+                HmacChip::construct(config).mac(layouter.namespace(|| "hmac"), &key, &message)?;
+            let expected = hmac_sha256(&self.key, &self.message);
+            for (word, expected) in tag.iter().zip(expected.chunks(4)) {
+                let expected = u32::from_be_bytes(expected.try_into().unwrap());
+                word.value
+                    .value()
+                    .assert_if_known(|word| **word == Fr::from(u64::from(expected)));
+            }
+            Ok(())
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn native() {
+        // rfc 4231, test case 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn mac() {
+        let circuit = TestCircuit {
+            key: b"Jefe".to_vec(),
+            message: b"what do ya want for nothing?".to_vec(),
+        };
+        MockProver::run(17, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+}
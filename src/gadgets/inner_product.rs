@@ -0,0 +1,222 @@
+//! inner product gadget
+//!
+//! | row | a    | b    | acc                                 | q_first | q_step |
+//! |-----|------|------|-------------------------------------|---------|--------|
+//! | 0   | a[0] | b[0] | 0                                   | 1       | 1      |
+//! | i   | a[i] | b[i] | a[0] * b[0] + ... + a[i-1] * b[i-1] | 0       | 1      |
+//! | len |      |      | out                                 | 0       | 0      |
+//!
+//! - `q_first * acc = 0`
+//! - `q_step * (acc' - acc - a * b) = 0`
+//!
+//! the vectors are copied in, one term per row, so vectors of any length share the gate. a
+//! vector padded with zeros has the same inner product, at the cost of a row per zero.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct InnerProductConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_first: Selector,
+    pub q_step: Selector,
+}
+
+pub struct InnerProductChip<F: FieldExt> {
+    config: InnerProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> InnerProductChip<F> {
+    pub fn construct(config: InnerProductConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> InnerProductConfig {
+        meta.enable_equality(a);
+        meta.enable_equality(b);
+        meta.enable_equality(acc);
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+
+        meta.create_gate("inner product", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_step = meta.query_selector(q_step);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![q_first * acc.clone(), q_step * (acc_next - acc - a * b)]
+        });
+
+        InnerProductConfig {
+            a,
+            b,
+            acc,
+            q_first,
+            q_step,
+        }
+    }
+
+    /// the inner product of `a` and `b` in a region of its own, the cell of `out` is returned
+    pub fn inner_product(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "inner product",
+            |mut region| self.assign(&mut region, 0, a, b),
+        )
+    }
+
+    /// the same as [`InnerProductChip::inner_product`], in the rows `offset..=offset + len`
+    /// of a region of the caller, so that many products can share one
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: &[AssignedCell<F, F>],
+        b: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(a.len(), b.len(), "the vectors have different lengths");
+        let config = self.config;
+        config.q_first.enable(region, offset)?;
+        let mut acc = Value::known(F::zero());
+        for (i, (x, y)) in a.iter().zip(b).enumerate() {
+            config.q_step.enable(region, offset + i)?;
+            let x = x.copy_advice(|| "a", region, config.a, offset + i)?;
+            let y = y.copy_advice(|| "b", region, config.b, offset + i)?;
+            region.assign_advice(|| "acc", config.acc, offset + i, || acc)?;
+            acc = acc + x.value().copied() * y.value().copied();
+        }
+        region.assign_advice(|| "out", config.acc, offset + a.len(), || acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        a: Vec<u64>,
+        b: Vec<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (InnerProductConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: vec![0; self.a.len()],
+                b: vec![0; self.b.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let acc = meta.advice_column();
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (
+                InnerProductChip::configure(meta, a, b, acc),
+                input,
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "vectors",
+                |mut region| {
+                    let mut assign = |xs: &[u64], start: usize| {
+                        xs.iter()
+                            .enumerate()
+                            .map(|(i, x)| {
+                                region.assign_advice(
+                                    || "x",
+                                    input,
+                                    start + i,
+                                    || Value::known(Fr::from(*x)),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    };
+                    let a = assign(&self.a, 0)?;
+                    let b = assign(&self.b, self.a.len())?;
+                    Ok((a, b))
+                },
+            )?;
+            let chip = InnerProductChip::construct(config);
+            let out = chip.inner_product(layouter.namespace(|| "a . b"), &a, &b)?;
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    fn prover(a: &[u64], b: &[u64], out: u64) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            a: a.to_vec(),
+            b: b.to_vec(),
+        };
+        MockProver::run(6, &circuit, vec![vec![Fr::from(out)]]).unwrap()
+    }
+
+    #[test]
+    fn lengths() {
+        prover(&[], &[], 0).assert_satisfied();
+        prover(&[7], &[6], 42).assert_satisfied();
+        prover(&[1, 2, 3], &[4, 5, 6], 32).assert_satisfied();
+        let a: Vec<_> = (1..=8).collect();
+        prover(&a, &a, 204).assert_satisfied();
+    }
+
+    #[test]
+    fn zero_padding() {
+        prover(&[1, 2, 3, 0, 0], &[4, 5, 6, 0, 0], 32).assert_satisfied();
+        // only one side padded with zeros, the extra terms are 0 anyway
+        prover(&[1, 2, 3, 0], &[4, 5, 6, 9], 32).assert_satisfied();
+    }
+
+    #[test]
+    fn wrong_out() {
+        testing::assert_fails_permutation(&prover(&[1, 2, 3], &[4, 5, 6], 33));
+    }
+
+    #[test]
+    #[should_panic(expected = "different lengths")]
+    fn different_lengths() {
+        prover(&[1, 2, 3], &[4, 5], 14);
+    }
+}
@@ -0,0 +1,213 @@
+//! is zero gadget
+//!
+//! witnesses `is_zero = value == 0` with the help of `value_inv`, the inverse of
+//! `value` or 0 if there is none:
+//!
+//! - `1 - value * value_inv = is_zero`
+//! - `value * is_zero = 0`
+//!
+//! when `value != 0` the second one forces `is_zero = 0`, so the first one forces
+//! `value_inv = 1 / value`. when `value == 0` the first one forces `is_zero = 1`.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct IsZeroConfig {
+    pub value_inv: Column<Advice>,
+    pub is_zero: Column<Advice>,
+}
+
+impl IsZeroConfig {
+    /// the flag at `rotation`, for use in the gates of other chips
+    pub fn expr<F: FieldExt>(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+        rotation: Rotation,
+    ) -> Expression<F> {
+        meta.query_advice(self.is_zero, rotation)
+    }
+}
+
+pub struct IsZeroChip<F: FieldExt> {
+    config: IsZeroConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// constrain `is_zero` on the rows where `q_enable` is non zero
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+        is_zero: Column<Advice>,
+    ) -> IsZeroConfig {
+        meta.create_gate("is zero", |meta| {
+            let q_enable = q_enable(meta);
+            let value = value(meta);
+            let value_inv = meta.query_advice(value_inv, Rotation::cur());
+            let is_zero = meta.query_advice(is_zero, Rotation::cur());
+
+            vec![
+                q_enable.clone()
+                    * (Expression::Constant(F::one())
+                        - value.clone() * value_inv
+                        - is_zero.clone()),
+                q_enable * value * is_zero,
+            ]
+        });
+
+        IsZeroConfig { value_inv, is_zero }
+    }
+
+    /// assign `value_inv` and the flag for `value` at `offset`, the flag is returned
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let value_inv = value.map(|value| value.invert().unwrap_or_else(F::zero));
+        region.assign_advice(|| "value inv", self.config.value_inv, offset, || value_inv)?;
+        region.assign_advice(
+            || "is zero",
+            self.config.is_zero,
+            offset,
+            || {
+                value.map(|value| {
+                    if value == F::zero() {
+                        F::one()
+                    } else {
+                        F::zero()
+                    }
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Selector},
+    };
+
+    /// assign `value`, then overwrite the flag with `is_zero` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        value: Fr,
+        is_zero: Option<Fr>,
+    }
+
+    #[derive(Clone)]
+    struct TestConfig {
+        q_enable: Selector,
+        value: Column<Advice>,
+        is_zero: IsZeroConfig,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> TestConfig {
+            let q_enable = meta.selector();
+            let value = meta.advice_column();
+            let value_inv = meta.advice_column();
+            let is_zero = meta.advice_column();
+            let is_zero = IsZeroChip::configure(
+                meta,
+                |meta| meta.query_selector(q_enable),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                value_inv,
+                is_zero,
+            );
+            TestConfig {
+                q_enable,
+                value,
+                is_zero,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: TestConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = IsZeroChip::construct(config.is_zero);
+            layouter.assign_region(
+                || "is zero",
+                |mut region| {
+                    config.q_enable.enable(&mut region, 0)?;
+                    region.assign_advice(
+                        || "value",
+                        config.value,
+                        0,
+                        || Value::known(self.value),
+                    )?;
+                    let is_zero = chip.assign(&mut region, 0, Value::known(self.value))?;
+                    if let Some(cheat) = self.is_zero {
+                        region.assign_advice(
+                            || "cheat",
+                            config.is_zero.is_zero,
+                            0,
+                            || Value::known(cheat),
+                        )?;
+                    } else {
+                        let expected = if self.value == Fr::zero() {
+                            Fr::one()
+                        } else {
+                            Fr::zero()
+                        };
+                        is_zero
+                            .value()
+                            .assert_if_known(|is_zero| **is_zero == expected);
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(value: u64, is_zero: Option<u64>) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            value: Fr::from(value),
+            is_zero: is_zero.map(Fr::from),
+        };
+        MockProver::run(4, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn flags_zero() {
+        prover(0, None).assert_satisfied();
+        prover(5, None).assert_satisfied();
+    }
+
+    #[test]
+    fn rejects_wrong_flag() {
+        testing::assert_fails_gate(&prover(0, Some(0)), "is zero", 0);
+        testing::assert_fails_gate(&prover(5, Some(1)), "is zero", 0);
+    }
+}
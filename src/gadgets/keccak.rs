@@ -0,0 +1,581 @@
+//! keccak gadget
+//!
+//! the keccak-f[1600] permutation on a state of 25 lanes of 64 bits, lane `(x, y)` at
+//! index `x + 5y`. every round is one region of 65 rows, row `z + 1` holding bit `z` of
+//! every lane, so theta and chi only look at one row, while rho and pi move bits between
+//! rows and columns with copy constraints:
+//!
+//! | row   | b                 | a          | c, h            | t          | rc           |
+//! |-------|-------------------|------------|-----------------|------------|--------------|
+//! | 0     |                   |            | c of bit 63     |            |              |
+//! | z + 1 | bit z of b (x, y) | bit z of a | theta sums of a | theta of a | bit z of RC  |
+//!
+//! - `b`: rho and pi of the `t` of the previous round, the copy of bit `z - r` of lane
+//!   `(x, y)` lands in bit `z` of lane `(y, 2x + 3y)`
+//! - `a`: the state, boolean. chi and iota of `b` in every round but the first, where it
+//!   is the input
+//! - `c[x] + 2 h[x] = a[x][0] + ... + a[x][4]`, with a boolean `c` and `h` in `0..=2`, so
+//!   `c` is the parity of the column
+//! - `t[x][y] = a[x][y] ^ c[x - 1] ^ c[x + 1]` with the `c[x + 1]` of the row above, the
+//!   first row holds a copy of the last one as the lane rotates by one bit
+//!
+//! chi works on the 5 lanes of a plane, so each plane looks up
+//! `(sum 2^x b[x][y], sum 2^x a[x][y])` in a table of all 32 inputs. iota flips the bits
+//! of the round constant in lane `(0, 0)` after chi, the lookup flips them back. the
+//! output is the `a` of the last region, one more after the 24 rounds.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, TableColumn,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+pub const ROUNDS: usize = 24;
+pub const LANES: usize = 25;
+pub const LANE_BITS: usize = 64;
+/// bytes absorbed per permutation by keccak-256
+pub const RATE_BYTES: usize = 136;
+
+/// rotation of lane `(x, y)` in rho
+pub const RHO: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+pub const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+fn lane(x: usize, y: usize) -> usize {
+    x + 5 * y
+}
+
+/// the lane of `b` that lane `(x, y)` moves to in pi
+fn pi(x: usize, y: usize) -> usize {
+    lane(y, (2 * x + 3 * y) % 5)
+}
+
+/// the parities of the columns and the state after theta
+fn theta(a: &[u64; LANES]) -> ([u64; 5], [u64; LANES]) {
+    let c: [u64; 5] = std::array::from_fn(|x| (0..5).fold(0, |c, y| c ^ a[lane(x, y)]));
+    let t = std::array::from_fn(|i| a[i] ^ c[(i + 4) % 5] ^ c[(i + 1) % 5].rotate_left(1));
+    (c, t)
+}
+
+fn rho_pi(t: &[u64; LANES]) -> [u64; LANES] {
+    let mut b = [0; LANES];
+    for x in 0..5 {
+        for y in 0..5 {
+            b[pi(x, y)] = t[lane(x, y)].rotate_left(RHO[x][y]);
+        }
+    }
+    b
+}
+
+fn chi_iota(b: &[u64; LANES], rc: u64) -> [u64; LANES] {
+    let mut a: [u64; LANES] = std::array::from_fn(|i| {
+        let (x, y) = (i % 5, i / 5);
+        b[i] ^ (!b[lane((x + 1) % 5, y)] & b[lane((x + 2) % 5, y)])
+    });
+    a[0] ^= rc;
+    a
+}
+
+/// chi on the 5 bits of `i`
+fn chi5(i: u64) -> u64 {
+    let bit = |x: usize| (i >> (x % 5)) & 1;
+    (0..5).fold(0, |out, x| {
+        out | ((bit(x) ^ ((bit(x + 1) ^ 1) & bit(x + 2))) << x)
+    })
+}
+
+pub fn keccak_f(state: &mut [u64; LANES]) {
+    for rc in ROUND_CONSTANTS {
+        let (_, t) = theta(state);
+        *state = chi_iota(&rho_pi(&t), rc);
+    }
+}
+
+/// keccak-256 as used by ethereum, outside of the circuit
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut padded = input.to_vec();
+    padded.push(0x01);
+    padded.resize((padded.len() + RATE_BYTES - 1) / RATE_BYTES * RATE_BYTES, 0);
+    *padded.last_mut().unwrap() |= 0x80;
+
+    let mut state = [0; LANES];
+    for block in padded.chunks(RATE_BYTES) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f(&mut state);
+    }
+    let mut digest = [0; 32];
+    for (bytes, lane) in digest.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+/// `a ^ b ^ c` for bits
+fn xor3<F: FieldExt>(a: Expression<F>, b: Expression<F>, c: Expression<F>) -> Expression<F> {
+    let pairs = a.clone() * b.clone() + b.clone() * c.clone() + c.clone() * a.clone();
+    a.clone() + b.clone() + c.clone() - Expression::Constant(F::from(2)) * pairs
+        + Expression::Constant(F::from(4)) * a * b * c
+}
+
+/// a bit of the first state
+#[derive(Debug)]
+enum Bit<'a, F: FieldExt> {
+    Cell(&'a AssignedCell<F, F>),
+    Constant(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeccakConfig {
+    pub a: [Column<Advice>; LANES],
+    pub b: [Column<Advice>; LANES],
+    pub c: [Column<Advice>; 5],
+    pub h: [Column<Advice>; 5],
+    pub t: [Column<Advice>; LANES],
+    pub rc: Column<Fixed>,
+    pub q_bool: Selector,
+    pub q_theta: Selector,
+    pub q_chi: Selector,
+    // [5 bits, chi of them]
+    pub table: [TableColumn; 2],
+}
+
+pub struct KeccakChip<F: FieldExt> {
+    config: KeccakConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> KeccakChip<F> {
+    pub fn construct(config: KeccakConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> KeccakConfig {
+        let a = [(); LANES].map(|_| meta.advice_column());
+        let b = [(); LANES].map(|_| meta.advice_column());
+        let c = [(); 5].map(|_| meta.advice_column());
+        let h = [(); 5].map(|_| meta.advice_column());
+        let t = [(); LANES].map(|_| meta.advice_column());
+        for column in a.iter().chain(&b).chain(&c).chain(&t) {
+            meta.enable_equality(*column);
+        }
+        let rc = meta.fixed_column();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let q_bool = meta.selector();
+        let q_theta = meta.selector();
+        let q_chi = meta.complex_selector();
+        let table = [(); 2].map(|_| meta.lookup_table_column());
+
+        let one = || Expression::Constant(F::one());
+        let two = || Expression::Constant(F::from(2));
+        let cur =
+            |meta: &mut VirtualCells<'_, F>, column| meta.query_advice(column, Rotation::cur());
+
+        meta.create_gate("keccak bool", |meta| {
+            let q_bool = meta.query_selector(q_bool);
+            a.iter()
+                .map(|column| {
+                    let bit = cur(meta, *column);
+                    q_bool.clone() * bit.clone() * (one() - bit)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("keccak theta", |meta| {
+            let q_theta = meta.query_selector(q_theta);
+            let mut constraints = Vec::with_capacity(5 * 3 + LANES);
+            for x in 0..5 {
+                let sum = (0..5).fold(Expression::Constant(F::zero()), |sum, y| {
+                    sum + cur(meta, a[lane(x, y)])
+                });
+                let (c, h) = (cur(meta, c[x]), cur(meta, h[x]));
+                constraints.push(q_theta.clone() * (sum - c.clone() - two() * h.clone()));
+                constraints.push(q_theta.clone() * c.clone() * (one() - c));
+                constraints.push(q_theta.clone() * h.clone() * (one() - h.clone()) * (two() - h));
+            }
+            for x in 0..5 {
+                let c_left = cur(meta, c[(x + 4) % 5]);
+                let c_right = meta.query_advice(c[(x + 1) % 5], Rotation::prev());
+                for y in 0..5 {
+                    let i = lane(x, y);
+                    let xor = xor3(cur(meta, a[i]), c_left.clone(), c_right.clone());
+                    constraints.push(q_theta.clone() * (cur(meta, t[i]) - xor));
+                }
+            }
+            constraints
+        });
+
+        for y in 0..5 {
+            meta.lookup(|meta| {
+                // chi of 0 is 0, so the rows not enabled pass
+                let q_chi = meta.query_selector(q_chi);
+                let rc = meta.query_fixed(rc, Rotation::cur());
+                let zero = || Expression::Constant(F::zero());
+                let (input, output) = (0..5).fold((zero(), zero()), |(input, output), x| {
+                    let i = lane(x, y);
+                    let mut out = cur(meta, a[i]);
+                    if i == 0 {
+                        out = out.clone() + rc.clone() - two() * out * rc.clone();
+                    }
+                    let weight = Expression::Constant(F::from(1 << x));
+                    (
+                        input + weight.clone() * cur(meta, b[i]),
+                        output + weight * out,
+                    )
+                });
+                vec![
+                    (q_chi.clone() * input, table[0]),
+                    (q_chi * output, table[1]),
+                ]
+            });
+        }
+
+        KeccakConfig {
+            a,
+            b,
+            c,
+            h,
+            t,
+            rc,
+            q_bool,
+            q_theta,
+            q_chi,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "chi",
+            |mut table| {
+                for i in 0..32 {
+                    for (column, value) in self.config.table.iter().zip([i, chi5(i)]) {
+                        table.assign_cell(
+                            || "chi",
+                            *column,
+                            i as usize,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// permute a state of `25 * 64` bits, bit `z` of lane `i` at `64 * i + z`
+    pub fn permute(
+        &self,
+        layouter: impl Layouter<F>,
+        state: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert_eq!(state.len(), LANES * LANE_BITS);
+        self.rounds(layouter, state.iter().map(Bit::Cell).collect())
+    }
+
+    /// the 256 bits of the keccak-256 digest of `message`, given as bits in the order of
+    /// [`KeccakChip::permute`], i.e. the bits of each byte least significant first. the
+    /// message has to fit in a single block.
+    pub fn hash(
+        &self,
+        layouter: impl Layouter<F>,
+        message: &[AssignedCell<F, F>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(message.len() % 8 == 0 && message.len() < RATE_BYTES * 8);
+        // 0x01 after the message, 0x80 in the last byte of the rate
+        let padding = (message.len()..LANES * LANE_BITS)
+            .map(|i| Bit::Constant(i == message.len() || i == RATE_BYTES * 8 - 1));
+        let state = message.iter().map(Bit::Cell).chain(padding).collect();
+        let mut output = self.rounds(layouter, state)?;
+        output.truncate(256);
+        Ok(output)
+    }
+
+    fn rounds(
+        &self,
+        mut layouter: impl Layouter<F>,
+        input: Vec<Bit<'_, F>>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = self.config;
+        let mut a = input
+            .iter()
+            .enumerate()
+            .fold(Value::known([0; LANES]), |state, (i, bit)| {
+                let bit = match bit {
+                    Bit::Cell(cell) => cell.value().map(|bit| *bit != F::zero()),
+                    Bit::Constant(bit) => Value::known(*bit),
+                };
+                state.zip(bit).map(|(mut state, bit)| {
+                    state[i / LANE_BITS] |= u64::from(bit) << (i % LANE_BITS);
+                    state
+                })
+            });
+        let mut t_cells: Option<Vec<Vec<AssignedCell<F, F>>>> = None;
+        let mut output = vec![];
+
+        for round in 0..=ROUNDS {
+            let rc = round.checked_sub(1).map(|i| ROUND_CONSTANTS[i]);
+            if let Some(rc) = rc {
+                a = a.map(|a| chi_iota(&rho_pi(&theta(&a).1), rc));
+            }
+            let theta_out = (round < ROUNDS).then(|| a.map(|a| theta(&a)));
+
+            let (a_cells, next_t_cells) = layouter.assign_region(
+                || format!("keccak round {}", round),
+                |mut region| {
+                    if let Some(t_cells) = &t_cells {
+                        for x in 0..5 {
+                            for y in 0..5 {
+                                let (from, to) = (lane(x, y), pi(x, y));
+                                let r = RHO[x][y] as usize;
+                                for z in 0..LANE_BITS {
+                                    t_cells[from][(z + LANE_BITS - r) % LANE_BITS].copy_advice(
+                                        || "b",
+                                        &mut region,
+                                        config.b[to],
+                                        z + 1,
+                                    )?;
+                                }
+                            }
+                        }
+                    }
+
+                    let mut a_cells = vec![Vec::with_capacity(LANE_BITS); LANES];
+                    let mut t_cells = vec![Vec::with_capacity(LANE_BITS); LANES];
+                    let mut c_cells = vec![];
+                    for z in 0..LANE_BITS {
+                        let offset = z + 1;
+                        let bit = |lane: u64| F::from((lane >> z) & 1);
+                        config.q_bool.enable(&mut region, offset)?;
+                        if let Some(rc) = rc {
+                            config.q_chi.enable(&mut region, offset)?;
+                            region.assign_fixed(
+                                || "rc",
+                                config.rc,
+                                offset,
+                                || Value::known(bit(rc)),
+                            )?;
+                        }
+
+                        for (i, column) in config.a.iter().enumerate() {
+                            let cell = match input.get(LANE_BITS * i + z).filter(|_| round == 0) {
+                                Some(Bit::Cell(cell)) => {
+                                    cell.copy_advice(|| "a", &mut region, *column, offset)?
+                                }
+                                Some(Bit::Constant(value)) => region.assign_advice_from_constant(
+                                    || "a",
+                                    *column,
+                                    offset,
+                                    F::from(u64::from(*value)),
+                                )?,
+                                None => region.assign_advice(
+                                    || "a",
+                                    *column,
+                                    offset,
+                                    || a.map(|a| bit(a[i])),
+                                )?,
+                            };
+                            a_cells[i].push(cell);
+                        }
+
+                        if let Some(theta) = theta_out {
+                            config.q_theta.enable(&mut region, offset)?;
+                            c_cells.clear();
+                            for x in 0..5 {
+                                let sum = a
+                                    .map(|a| (0..5).map(|y| (a[lane(x, y)] >> z) & 1).sum::<u64>());
+                                let c = theta.map(|(c, _)| (c[x] >> z) & 1);
+                                c_cells.push(region.assign_advice(
+                                    || "c",
+                                    config.c[x],
+                                    offset,
+                                    || c.map(F::from),
+                                )?);
+                                region.assign_advice(
+                                    || "h",
+                                    config.h[x],
+                                    offset,
+                                    || sum.zip(c).map(|(sum, c)| F::from((sum - c) / 2)),
+                                )?;
+                            }
+                            for (i, column) in config.t.iter().enumerate() {
+                                t_cells[i].push(region.assign_advice(
+                                    || "t",
+                                    *column,
+                                    offset,
+                                    || theta.map(|(_, t)| bit(t[i])),
+                                )?);
+                            }
+                        }
+                    }
+
+                    // the c of bit 63 above bit 0
+                    for (x, cell) in c_cells.iter().enumerate() {
+                        cell.copy_advice(|| "c", &mut region, config.c[x], 0)?;
+                    }
+                    Ok((a_cells, t_cells))
+                },
+            )?;
+
+            t_cells = Some(next_t_cells);
+            output = a_cells;
+        }
+        Ok(output.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// hash `message`, with the value of the first bit replaced by `first_bit` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        message: Vec<u8>,
+        first_bit: Option<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = KeccakConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![0; self.message.len()],
+                first_bit: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> KeccakConfig {
+            KeccakChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: KeccakConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = KeccakChip::construct(config);
+            chip.load_table(layouter.namespace(|| "chi"))?;
+            let message = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    (0..self.message.len() * 8)
+                        .map(|i| {
+                            let bit = match (i, self.first_bit) {
+                                (0, Some(bit)) => bit,
+                                _ => u64::from((self.message[i / 8] >> (i % 8)) & 1),
+                            };
+                            region.assign_advice(
+                                || "message",
+                                config.a[i % LANES],
+                                i / LANES,
+                                || Value::known(Fr::from(bit)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            let digest = chip.hash(layouter.namespace(|| "keccak"), &message)?;
+            let expected = keccak256(&self.message);
+            for (i, bit) in digest.iter().enumerate() {
+                let expected = Fr::from(u64::from((expected[i / 8] >> (i % 8)) & 1));
+                bit.value()
+                    .assert_if_known(|bit| self.first_bit.is_some() || **bit == expected);
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(message: &[u8], first_bit: Option<u64>) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            message: message.to_vec(),
+            first_bit,
+        };
+        MockProver::run(11, &circuit, vec![]).unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn native() {
+        assert_eq!(
+            hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(&keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        for i in 0..32 {
+            assert_eq!(chi5(chi5_inverse(i)), i);
+        }
+    }
+
+    /// chi on 5 bits is a permutation
+    fn chi5_inverse(i: u64) -> u64 {
+        (0..32).find(|j| chi5(*j) == i).unwrap()
+    }
+
+    #[test]
+    fn hash() {
+        prover(b"", None).assert_satisfied();
+        prover(b"abc", None).assert_satisfied();
+        // 0x01 and 0x80 share the last byte
+        prover(&[0x42; RATE_BYTES - 1], None).assert_satisfied();
+    }
+
+    #[test]
+    fn non_boolean_message() {
+        // "a" is 0x61, its lowest bit is 1
+        testing::assert_fails_gate(&prover(b"a", Some(3)), "keccak bool", 1);
+    }
+}
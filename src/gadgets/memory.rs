@@ -0,0 +1,400 @@
+//! read/write memory consistency gadget
+//!
+//! offline memory checking: a VM emits one `(addr, timestamp, is_write, value)` per memory
+//! access, in the order of execution, and every read has to return the value of the last
+//! write to its address, or 0 if there is none. checking that against the execution order
+//! would need a lookup into a table that changes every step, instead the same accesses are
+//! witnessed a second time, sorted by address then timestamp, where the last write to an
+//! address is simply the row above:
+//!
+//! | row | trace       | acc        | sorted      | acc_s      | same_addr          |
+//! |-----|-------------|------------|-------------|------------|--------------------|
+//! | 0   | op[0]       | 1          | sorted[0]   | 1          | addr[1] == addr[0] |
+//! | i   | op[i]       | prod f(..) | sorted[i]   | prod f(..) | ...                |
+//! | n   |             | prod f(op) |             | prod f(..) |                    |
+//!
+//! - sorted: `addr' - addr - 1` when the address changes, `timestamp' - timestamp - 1`
+//!   when it does not, is looked up in a table of `0..2^BITS`, so the rows are strictly
+//!   increasing and no two accesses share an address and a timestamp
+//! - consistent: a read returns the value of the row above when the address is the same,
+//!   0 when it is the first access to the address
+//! - permutation: both columns are the same multiset, `prod (gamma + f(op))` is the same
+//!   for the trace and the sorted rows, where `f(op) = addr + alpha * timestamp +
+//!   alpha^2 * is_write + alpha^3 * value`. `alpha` and `gamma` are challenges drawn after
+//!   the accesses are committed, the running products are second phase columns, like in
+//!   `src/bin/rlc.rs`
+//!
+//! the timestamps come from the caller, which has to make them follow the execution.
+
+use crate::gadgets::is_zero::{IsZeroChip, IsZeroConfig};
+use crate::gadgets::select;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Error, Expression, FirstPhase, SecondPhase,
+        Selector, TableColumn, VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// the cells of one memory access
+#[derive(Debug, Clone)]
+pub struct MemoryOp<F: FieldExt> {
+    pub addr: AssignedCell<F, F>,
+    pub timestamp: AssignedCell<F, F>,
+    /// 1 for a write, 0 for a read
+    pub is_write: AssignedCell<F, F>,
+    pub value: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt> MemoryOp<F> {
+    fn cells(&self) -> [&AssignedCell<F, F>; 4] {
+        [&self.addr, &self.timestamp, &self.is_write, &self.value]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryConfig {
+    /// addr, timestamp, is_write and value, in the order of execution
+    pub trace: [Column<Advice>; 4],
+    /// the same, sorted by address and timestamp
+    pub sorted: [Column<Advice>; 4],
+    /// running products of the trace and of the sorted rows
+    pub acc: [Column<Advice>; 2],
+    pub same_addr: IsZeroConfig,
+    pub q_first: Selector,
+    pub q_product: Selector,
+    pub q_sorted: Selector,
+    pub alpha: Challenge,
+    pub gamma: Challenge,
+    pub table: TableColumn,
+}
+
+pub struct MemoryChip<F: FieldExt, const BITS: usize> {
+    config: MemoryConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const BITS: usize> MemoryChip<F, BITS> {
+    pub fn construct(config: MemoryConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MemoryConfig {
+        let trace = [(); 4].map(|_| meta.advice_column_in(FirstPhase));
+        let sorted = [(); 4].map(|_| meta.advice_column_in(FirstPhase));
+        let value_inv = meta.advice_column_in(FirstPhase);
+        let is_zero = meta.advice_column_in(FirstPhase);
+        let alpha = meta.challenge_usable_after(FirstPhase);
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let acc = [(); 2].map(|_| meta.advice_column_in(SecondPhase));
+        let q_first = meta.selector();
+        let q_product = meta.selector();
+        let q_sorted = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        for column in trace.into_iter().chain(acc) {
+            meta.enable_equality(column);
+        }
+
+        let [addr, timestamp, is_write, value] = sorted;
+        let same_addr = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_sorted),
+            |meta| {
+                meta.query_advice(addr, Rotation::next()) - meta.query_advice(addr, Rotation::cur())
+            },
+            value_inv,
+            is_zero,
+        );
+
+        meta.lookup(|meta| {
+            let q_sorted = meta.query_selector(q_sorted);
+            let same = same_addr.expr(meta, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let step = |meta: &mut VirtualCells<'_, F>, column| {
+                meta.query_advice(column, Rotation::next())
+                    - meta.query_advice(column, Rotation::cur())
+                    - one.clone()
+            };
+            let gap = select::expr(same, step(meta, timestamp), step(meta, addr));
+            vec![(q_sorted * gap, table)]
+        });
+
+        meta.create_gate("memory consistency", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_sorted = meta.query_selector(q_sorted);
+            let same = same_addr.expr(meta, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let is_write_cur = meta.query_advice(is_write, Rotation::cur());
+            let is_write_next = meta.query_advice(is_write, Rotation::next());
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_next = meta.query_advice(value, Rotation::next());
+            let is_read_next = one.clone() - is_write_next.clone();
+            vec![
+                // the first access reads 0
+                q_first.clone() * (one.clone() - is_write_cur.clone()) * value_cur.clone(),
+                q_first * is_write_cur.clone() * (one.clone() - is_write_cur),
+                q_sorted.clone() * is_write_next.clone() * (one.clone() - is_write_next),
+                // a read returns the value above, or 0 at a new address
+                q_sorted * is_read_next * (value_next - same * value_cur),
+            ]
+        });
+
+        meta.create_gate("memory permutation", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_product = meta.query_selector(q_product);
+            let alpha = meta.query_challenge(alpha);
+            let gamma = meta.query_challenge(gamma);
+            let one = Expression::Constant(F::one());
+
+            let mut constraints = vec![];
+            for (columns, acc) in [(trace, acc[0]), (sorted, acc[1])] {
+                let fingerprint = columns
+                    .iter()
+                    .rev()
+                    .fold(Expression::Constant(F::zero()), |f, column| {
+                        f * alpha.clone() + meta.query_advice(*column, Rotation::cur())
+                    });
+                let acc_cur = meta.query_advice(acc, Rotation::cur());
+                let acc_next = meta.query_advice(acc, Rotation::next());
+                constraints.push(q_first.clone() * (acc_cur.clone() - one.clone()));
+                constraints
+                    .push(q_product.clone() * (acc_next - acc_cur * (gamma.clone() + fingerprint)));
+            }
+            constraints
+        });
+
+        MemoryConfig {
+            trace,
+            sorted,
+            acc,
+            same_addr,
+            q_first,
+            q_product,
+            q_sorted,
+            alpha,
+            gamma,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("{} bits gap", BITS),
+            |mut table| {
+                for gap in 0..1 << BITS {
+                    table.assign_cell(
+                        || "gap",
+                        self.config.table,
+                        gap,
+                        || Value::known(F::from(gap as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// check that every read of `ops`, in the order of execution, returns the last value
+    /// written to its address
+    pub fn check(&self, mut layouter: impl Layouter<F>, ops: &[MemoryOp<F>]) -> Result<(), Error> {
+        let config = self.config;
+        let alpha = layouter.get_challenge(config.alpha);
+        let gamma = layouter.get_challenge(config.gamma);
+
+        let trace: Value<Vec<[F; 4]>> = ops
+            .iter()
+            .map(|op| {
+                let [addr, timestamp, is_write, value] =
+                    op.cells().map(|cell| cell.value().copied());
+                addr.zip(timestamp)
+                    .zip(is_write.zip(value))
+                    .map(|((a, t), (w, v))| [a, t, w, v])
+            })
+            .collect();
+        let sorted = trace.clone().map(|mut ops| {
+            ops.sort_by_key(|[addr, timestamp, _, _]| {
+                (addr.get_lower_128(), timestamp.get_lower_128())
+            });
+            ops
+        });
+        let products = |ops: &Value<Vec<[F; 4]>>| {
+            ops.as_ref()
+                .zip(alpha.zip(gamma))
+                .map(|(ops, (alpha, gamma))| {
+                    let mut acc = vec![F::one()];
+                    for op in ops {
+                        let fingerprint = op.iter().rev().fold(F::zero(), |f, x| f * alpha + x);
+                        acc.push(*acc.last().unwrap() * (gamma + fingerprint));
+                    }
+                    acc
+                })
+        };
+        let (acc_trace, acc_sorted) = (products(&trace), products(&sorted));
+
+        layouter.assign_region(
+            || "memory",
+            |mut region| {
+                config.q_first.enable(&mut region, 0)?;
+                for (offset, op) in ops.iter().enumerate() {
+                    config.q_product.enable(&mut region, offset)?;
+                    if offset + 1 < ops.len() {
+                        config.q_sorted.enable(&mut region, offset)?;
+                    }
+                    for (cell, column) in op.cells().into_iter().zip(config.trace) {
+                        cell.copy_advice(|| "trace", &mut region, column, offset)?;
+                    }
+                    for (i, column) in config.sorted.iter().enumerate() {
+                        region.assign_advice(
+                            || "sorted",
+                            *column,
+                            offset,
+                            || sorted.as_ref().map(|ops| ops[offset][i]),
+                        )?;
+                    }
+                    let gap = sorted.as_ref().map(|ops| match ops.get(offset + 1) {
+                        Some(next) => next[0] - ops[offset][0],
+                        None => F::zero(),
+                    });
+                    IsZeroChip::construct(config.same_addr).assign(&mut region, offset, gap)?;
+                }
+
+                let mut last = vec![];
+                for (column, acc) in config.acc.iter().zip([&acc_trace, &acc_sorted]) {
+                    for offset in 0..=ops.len() {
+                        let cell = region.assign_advice(
+                            || "acc",
+                            *column,
+                            offset,
+                            || acc.as_ref().map(|acc| acc[offset]),
+                        )?;
+                        if offset == ops.len() {
+                            last.push(cell);
+                        }
+                    }
+                }
+                region.constrain_equal(last[0].cell(), last[1].cell())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// `(addr, timestamp, is_write, value)` of every access
+    #[derive(Default)]
+    struct TestCircuit {
+        ops: Vec<(u64, u64, bool, u64)>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = MemoryConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                ops: vec![(0, 0, false, 0); self.ops.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> MemoryConfig {
+            MemoryChip::<Fr, 8>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: MemoryConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = MemoryChip::<Fr, 8>::construct(config);
+            chip.load_table(layouter.namespace(|| "gap"))?;
+            let ops = layouter.assign_region(
+                || "accesses",
+                |mut region| {
+                    let mut ops = vec![];
+                    for (offset, (addr, timestamp, is_write, value)) in self.ops.iter().enumerate()
+                    {
+                        let [addr, timestamp, is_write, value] =
+                            [*addr, *timestamp, u64::from(*is_write), *value]
+                                .map(|x| Value::known(Fr::from(x)));
+                        let [c_addr, c_timestamp, c_is_write, c_value] = config.trace;
+                        ops.push(MemoryOp {
+                            addr: region.assign_advice(|| "addr", c_addr, offset, || addr)?,
+                            timestamp: region.assign_advice(
+                                || "timestamp",
+                                c_timestamp,
+                                offset,
+                                || timestamp,
+                            )?,
+                            is_write: region.assign_advice(
+                                || "is_write",
+                                c_is_write,
+                                offset,
+                                || is_write,
+                            )?,
+                            value: region.assign_advice(|| "value", c_value, offset, || value)?,
+                        });
+                    }
+                    Ok(ops)
+                },
+            )?;
+            chip.check(layouter.namespace(|| "memory"), &ops)
+        }
+    }
+
+    fn prover(ops: &[(u64, u64, bool, u64)]) -> MockProver<Fr> {
+        let circuit = TestCircuit { ops: ops.to_vec() };
+        MockProver::run(9, &circuit, vec![]).unwrap()
+    }
+
+    /// sorted: (1, 2), (1, 4), (2, 5), (3, 0), (3, 1), (3, 3)
+    fn ops(last_read: u64, unwritten: u64) -> Vec<(u64, u64, bool, u64)> {
+        vec![
+            (3, 0, true, 7),
+            (3, 1, false, 7),
+            (1, 2, true, 5),
+            (3, 3, false, last_read),
+            (1, 4, false, 5),
+            (2, 5, false, unwritten),
+        ]
+    }
+
+    #[test]
+    fn consistent() {
+        prover(&ops(7, 0)).assert_satisfied();
+        // overwritten
+        prover(&[(4, 0, true, 1), (4, 1, true, 2), (4, 2, false, 2)]).assert_satisfied();
+    }
+
+    #[test]
+    fn stale_read() {
+        testing::assert_fails_gate(&prover(&ops(8, 0)), "memory consistency", 4);
+        testing::assert_fails_gate(
+            &prover(&[(4, 0, true, 1), (4, 1, true, 2), (4, 2, false, 1)]),
+            "memory consistency",
+            1,
+        );
+    }
+
+    #[test]
+    fn uninitialized_read() {
+        testing::assert_fails_gate(&prover(&ops(7, 1)), "memory consistency", 1);
+        testing::assert_fails_gate(&prover(&[(0, 0, false, 9)]), "memory consistency", 0);
+    }
+
+    #[test]
+    fn same_timestamp() {
+        testing::assert_fails_lookup(&prover(&[(4, 0, true, 1), (4, 0, false, 1)]), 0);
+    }
+}
@@ -0,0 +1,33 @@
+//! reusable chips
+//!
+//! each gadget has a config allocated by its `configure` and a chip assigning it,
+//! the circuits in [`crate::fib`] and `src/bin` are built from them.
+
+pub mod accumulator;
+pub mod bigint;
+pub mod bits;
+pub mod blake2b;
+pub mod boolean;
+pub mod byte_ops;
+pub mod compare;
+pub mod divmod;
+pub mod ecc;
+pub mod fixed_point;
+pub mod fsm;
+pub mod hmac;
+pub mod inner_product;
+pub mod is_zero;
+pub mod keccak;
+pub mod memory;
+pub mod pedersen;
+pub mod poseidon;
+pub mod product;
+pub mod range;
+pub mod recurrence;
+pub mod relu;
+pub mod rlp;
+pub mod select;
+pub mod sha256;
+pub mod smt;
+pub mod sqrt;
+pub mod uint;
@@ -0,0 +1,468 @@
+//! pedersen commitment gadget
+//!
+//! `C = value * G + blinding * H` on a curve embedded in the field of the circuit, the
+//! complete edwards curve `x^2 + y^2 = 1 + d x^2 y^2` with `d` the smallest non-square of
+//! the field. its addition law has no exceptions, the identity is `(0, 1)`:
+//!
+//! ```text
+//! x3 = (x1 y2 + y1 x2) / (1 + d x1 x2 y1 y2)
+//! y3 = (y1 y2 - x1 x2) / (1 - d x1 x2 y1 y2)
+//! ```
+//!
+//! `G` and `H` are the first points with `x = 2, 3, ...`, doubled twice to leave the
+//! points of order 4 every such curve has. the rest of the order of the group is not
+//! computed here, good enough to learn the layout, not to hide real values.
+//!
+//! the scalars are multiplied in windows of 2 bits, most significant first, `value` in 64
+//! bits and `blinding` in 252, which is below the modulus of every supported field, so
+//! its decomposition is unique. window `k` picks one of `T_k[j] = j * 4^k * P` from the
+//! fixed columns of its row:
+//!
+//! | row | b0, b1 | px, py      | ax, ay           | z                | x[j], y[j] |
+//! |-----|--------|-------------|------------------|------------------|------------|
+//! | i   | w bits | `T_k[w]`    | `a_prev + T_k[w]`| `4 z_prev + w`   | `T_k[j]`   |
+//!
+//! - `b0, b1` are boolean, `w = b0 + 2 b1` and `p = sum_j L_j(b0, b1) T_k[j]` with the
+//!   lagrange polynomials of the 4 corners
+//! - `z` starts over as `w` on the first row of each scalar, its last row is copied from
+//!   the scalar
+//! - `a = p` on the very first row, the accumulated points are added with the complete
+//!   law on the rows after it, the last `a` is the commitment
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+pub const WINDOW_BITS: usize = 2;
+pub const VALUE_BITS: usize = 64;
+pub const BLINDING_BITS: usize = 252;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point<F> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: FieldExt> Point<F> {
+    pub fn identity() -> Self {
+        Self {
+            x: F::zero(),
+            y: F::one(),
+        }
+    }
+}
+
+/// the embedded curve and its generators
+#[derive(Debug, Clone)]
+pub struct Curve<F> {
+    pub d: F,
+    pub g: Point<F>,
+    pub h: Point<F>,
+}
+
+impl<F: FieldExt> Default for Curve<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt> Curve<F> {
+    pub fn new() -> Self {
+        let d = (2..)
+            .map(F::from)
+            .find(|d| bool::from(d.sqrt().is_none()))
+            .unwrap();
+        // 1 - d x^2 is never 0 with d a non-square
+        let mut points = (2..).filter_map(|x| {
+            let x = F::from(x);
+            let y2 = (F::one() - x * x) * (F::one() - d * x * x).invert().unwrap();
+            Option::<F>::from(y2.sqrt()).map(|y| Point { x, y })
+        });
+        let mut curve = Self {
+            d,
+            g: points.next().unwrap(),
+            h: points.next().unwrap(),
+        };
+        for _ in 0..2 {
+            curve.g = curve.add(&curve.g, &curve.g);
+            curve.h = curve.add(&curve.h, &curve.h);
+        }
+        curve
+    }
+
+    pub fn is_on_curve(&self, p: &Point<F>) -> bool {
+        let (x2, y2) = (p.x * p.x, p.y * p.y);
+        x2 + y2 == F::one() + self.d * x2 * y2
+    }
+
+    pub fn add(&self, p: &Point<F>, q: &Point<F>) -> Point<F> {
+        let t = self.d * p.x * q.x * p.y * q.y;
+        Point {
+            x: (p.x * q.y + p.y * q.x) * (F::one() + t).invert().unwrap(),
+            y: (p.y * q.y - p.x * q.x) * (F::one() - t).invert().unwrap(),
+        }
+    }
+
+    /// `T_k` of the windows `k` of a `bits` bit scalar times `base`
+    pub fn tables(&self, base: Point<F>, bits: usize) -> Vec<[Point<F>; 4]> {
+        let mut base = base;
+        (0..bits / WINDOW_BITS)
+            .map(|_| {
+                let mut table = [Point::identity(); 4];
+                let mut point = Point::identity();
+                for entry in table.iter_mut().skip(1) {
+                    point = self.add(&point, &base);
+                    *entry = point;
+                }
+                base = self.add(&point, &base);
+                table
+            })
+            .collect()
+    }
+
+    /// `scalar * P` with the `tables` of `P`, the bits of `scalar` above them are dropped
+    pub fn mul(&self, tables: &[[Point<F>; 4]], scalar: F) -> Point<F> {
+        windows(scalar, tables.len() * WINDOW_BITS)
+            .iter()
+            .zip(tables)
+            .fold(Point::identity(), |acc, (w, table)| {
+                self.add(&acc, &table[*w])
+            })
+    }
+
+    /// `value * G + blinding * H`, outside of the circuit
+    pub fn commit(&self, value: u64, blinding: F) -> Point<F> {
+        let value = self.mul(&self.tables(self.g, VALUE_BITS), F::from(value));
+        let blinding = self.mul(&self.tables(self.h, BLINDING_BITS), blinding);
+        self.add(&value, &blinding)
+    }
+}
+
+/// the little endian windows of the low `bits` bits of `scalar`
+fn windows<F: FieldExt>(scalar: F, bits: usize) -> Vec<usize> {
+    let repr = scalar.to_repr();
+    let bytes = repr.as_ref();
+    let bit = |i: usize| usize::from((bytes[i / 8] >> (i % 8)) & 1);
+    (0..bits / WINDOW_BITS)
+        .map(|k| bit(WINDOW_BITS * k) + 2 * bit(WINDOW_BITS * k + 1))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PedersenConfig {
+    pub bits: [Column<Advice>; WINDOW_BITS],
+    // [x, y] of T_k[w]
+    pub p: [Column<Advice>; 2],
+    // [x, y] of the accumulated point
+    pub acc: [Column<Advice>; 2],
+    pub z: Column<Advice>,
+    // [x, y] of T_k[j]
+    pub table: [[Column<Fixed>; 4]; 2],
+    // every row
+    pub q_window: Selector,
+    // the first row of a scalar
+    pub q_z_first: Selector,
+    // the rows after it
+    pub q_z_step: Selector,
+    // the first row
+    pub q_acc_first: Selector,
+    // the rows after it
+    pub q_add: Selector,
+}
+
+pub struct PedersenChip<F: FieldExt> {
+    config: PedersenConfig,
+    curve: Curve<F>,
+    // the tables of G and H
+    tables: [Vec<[Point<F>; 4]>; 2],
+}
+
+impl<F: FieldExt> PedersenChip<F> {
+    pub fn construct(config: PedersenConfig) -> Self {
+        let curve = Curve::new();
+        let tables = [
+            curve.tables(curve.g, VALUE_BITS),
+            curve.tables(curve.h, BLINDING_BITS),
+        ];
+        Self {
+            config,
+            curve,
+            tables,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PedersenConfig {
+        let bits = [(); WINDOW_BITS].map(|_| meta.advice_column());
+        let p = [(); 2].map(|_| meta.advice_column());
+        let acc = [(); 2].map(|_| meta.advice_column());
+        let z = meta.advice_column();
+        let table = [(); 2].map(|_| [(); 4].map(|_| meta.fixed_column()));
+        let q_window = meta.selector();
+        let q_z_first = meta.selector();
+        let q_z_step = meta.selector();
+        let q_acc_first = meta.selector();
+        let q_add = meta.selector();
+        meta.enable_equality(z);
+        for column in acc {
+            meta.enable_equality(column);
+        }
+        let d = Curve::<F>::new().d;
+
+        meta.create_gate("pedersen window", |meta| {
+            let q = meta.query_selector(q_window);
+            let one = Expression::Constant(F::one());
+            let [b0, b1] = bits.map(|column| meta.query_advice(column, Rotation::cur()));
+            let lagrange = [
+                (one.clone() - b0.clone()) * (one.clone() - b1.clone()),
+                b0.clone() * (one.clone() - b1.clone()),
+                (one.clone() - b0.clone()) * b1.clone(),
+                b0.clone() * b1.clone(),
+            ];
+            let mut constraints = vec![
+                q.clone() * b0.clone() * (one.clone() - b0),
+                q.clone() * b1.clone() * (one - b1),
+            ];
+            for (p, table) in p.iter().zip(table) {
+                let p = meta.query_advice(*p, Rotation::cur());
+                let selected = lagrange
+                    .iter()
+                    .zip(table)
+                    .fold(Expression::Constant(F::zero()), |acc, (l, column)| {
+                        acc + l.clone() * meta.query_fixed(column, Rotation::cur())
+                    });
+                constraints.push(q.clone() * (p - selected));
+            }
+            constraints
+        });
+
+        meta.create_gate("pedersen z", |meta| {
+            let q_z_first = meta.query_selector(q_z_first);
+            let q_z_step = meta.query_selector(q_z_step);
+            let [b0, b1] = bits.map(|column| meta.query_advice(column, Rotation::cur()));
+            let w = b0 + b1 * Expression::Constant(F::from(2));
+            let z_prev = meta.query_advice(z, Rotation::prev());
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![
+                q_z_first * (z.clone() - w.clone()),
+                q_z_step * (z - z_prev * Expression::Constant(F::from(4)) - w),
+            ]
+        });
+
+        meta.create_gate("pedersen add", |meta| {
+            let q_acc_first = meta.query_selector(q_acc_first);
+            let q_add = meta.query_selector(q_add);
+            let [x1, y1] = acc.map(|column| meta.query_advice(column, Rotation::prev()));
+            let [x2, y2] = p.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [x3, y3] = acc.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(F::one());
+            let t = Expression::Constant(d) * x1.clone() * x2.clone() * y1.clone() * y2.clone();
+            vec![
+                q_acc_first.clone() * (x3.clone() - x2.clone()),
+                q_acc_first * (y3.clone() - y2.clone()),
+                q_add.clone()
+                    * (x3 * (one.clone() + t.clone())
+                        - (x1.clone() * y2.clone() + y1.clone() * x2.clone())),
+                q_add * (y3 * (one - t) - (y1 * y2 - x1 * x2)),
+            ]
+        });
+
+        PedersenConfig {
+            bits,
+            p,
+            acc,
+            z,
+            table,
+            q_window,
+            q_z_first,
+            q_z_step,
+            q_acc_first,
+            q_add,
+        }
+    }
+
+    pub fn curve(&self) -> &Curve<F> {
+        &self.curve
+    }
+
+    /// the `[x, y]` of `value * G + blinding * H`, `value` has to fit in 64 bits and
+    /// `blinding` in 252
+    pub fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        blinding: &AssignedCell<F, F>,
+    ) -> Result<[AssignedCell<F, F>; 2], Error> {
+        let config = self.config;
+        layouter.assign_region(
+            || "pedersen",
+            |mut region| {
+                let mut offset = 0;
+                let mut acc = Value::known(Point::identity());
+                let mut acc_cells = None;
+                for (scalar, tables) in [(value, &self.tables[0]), (blinding, &self.tables[1])] {
+                    let windows = scalar
+                        .value()
+                        .map(|scalar| windows(*scalar, tables.len() * WINDOW_BITS));
+                    let mut z = Value::known(F::zero());
+                    let mut z_cell = None;
+                    for (i, (k, table)) in tables.iter().enumerate().rev().enumerate() {
+                        config.q_window.enable(&mut region, offset)?;
+                        if i == 0 {
+                            config.q_z_first.enable(&mut region, offset)?;
+                        } else {
+                            config.q_z_step.enable(&mut region, offset)?;
+                        }
+                        if offset == 0 {
+                            config.q_acc_first.enable(&mut region, offset)?;
+                        } else {
+                            config.q_add.enable(&mut region, offset)?;
+                        }
+
+                        let w = windows.as_ref().map(|windows| windows[k]);
+                        for (b, column) in config.bits.iter().enumerate() {
+                            region.assign_advice(
+                                || format!("b{}", b),
+                                *column,
+                                offset,
+                                || w.map(|w| F::from(((w >> b) & 1) as u64)),
+                            )?;
+                        }
+                        for (j, point) in table.iter().enumerate() {
+                            let [x, y] = config.table;
+                            region.assign_fixed(|| "x", x[j], offset, || Value::known(point.x))?;
+                            region.assign_fixed(|| "y", y[j], offset, || Value::known(point.y))?;
+                        }
+
+                        let p = w.map(|w| table[w]);
+                        acc = acc.zip(p).map(|(acc, p)| self.curve.add(&acc, &p));
+                        z = z.zip(w).map(|(z, w)| z * F::from(4) + F::from(w as u64));
+                        let [px, py] = config.p;
+                        region.assign_advice(|| "px", px, offset, || p.map(|p| p.x))?;
+                        region.assign_advice(|| "py", py, offset, || p.map(|p| p.y))?;
+                        let [ax, ay] = config.acc;
+                        acc_cells = Some([
+                            region.assign_advice(|| "ax", ax, offset, || acc.map(|a| a.x))?,
+                            region.assign_advice(|| "ay", ay, offset, || acc.map(|a| a.y))?,
+                        ]);
+                        z_cell = Some(region.assign_advice(|| "z", config.z, offset, || z)?);
+                        offset += 1;
+                    }
+                    region.constrain_equal(z_cell.unwrap().cell(), scalar.cell())?;
+                }
+                Ok(acc_cells.unwrap())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        value: Fr,
+        blinding: Fr,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (PedersenConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (PedersenChip::configure(meta), advice, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = PedersenChip::construct(config);
+            let (value, blinding) = layouter.assign_region(
+                || "opening",
+                |mut region| {
+                    let value =
+                        region.assign_advice(|| "value", advice, 0, || Value::known(self.value))?;
+                    let blinding = region.assign_advice(
+                        || "blinding",
+                        advice,
+                        1,
+                        || Value::known(self.blinding),
+                    )?;
+                    Ok((value, blinding))
+                },
+            )?;
+            let commitment = chip.commit(layouter.namespace(|| "commit"), &value, &blinding)?;
+            for (row, cell) in commitment.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(value: Fr, blinding: Fr, commitment: Point<Fr>) -> MockProver<Fr> {
+        let circuit = TestCircuit { value, blinding };
+        MockProver::run(9, &circuit, vec![vec![commitment.x, commitment.y]]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        let curve = Curve::<Fr>::new();
+        assert!(curve.is_on_curve(&curve.g));
+        assert!(curve.is_on_curve(&curve.h));
+        assert_ne!(curve.g, curve.h);
+        assert_eq!(curve.commit(0, Fr::zero()), Point::identity());
+        assert_eq!(curve.commit(1, Fr::zero()), curve.g);
+
+        // commitments are additive
+        let (a, b) = (curve.commit(3, Fr::from(5)), curve.commit(4, Fr::from(6)));
+        assert_eq!(curve.add(&a, &b), curve.commit(7, Fr::from(11)));
+        assert!(curve.is_on_curve(&a));
+    }
+
+    #[test]
+    fn opening() {
+        let curve = Curve::<Fr>::new();
+        let blinding = Fr::from_u128(0x1234_5678_9abc_def0_1234_5678_9abc_def0);
+        for value in [0, 42, u64::MAX] {
+            let commitment = curve.commit(value, blinding);
+            prover(Fr::from(value), blinding, commitment).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_opening() {
+        let curve = Curve::<Fr>::new();
+        let commitment = curve.commit(42, Fr::from(7));
+        testing::assert_fails_permutation(&prover(Fr::from(43), Fr::from(7), commitment));
+        testing::assert_fails_permutation(&prover(Fr::from(42), Fr::from(8), commitment));
+    }
+
+    #[test]
+    fn rejects_wide_value() {
+        // 2^64 drops out of the windows, as if it were 0
+        let curve = Curve::<Fr>::new();
+        let commitment = curve.commit(0, Fr::from(7));
+        let wide = Fr::from_u128(1 << 64);
+        testing::assert_fails_permutation(&prover(wide, Fr::from(7), commitment));
+    }
+}
@@ -0,0 +1,482 @@
+//! poseidon hash gadget
+//!
+//! the poseidon permutation over a state of `WIDTH = 3` elements, `x^5` s-boxes,
+//! `FULL_ROUNDS = 8` full rounds around `PARTIAL_ROUNDS = 57` partial ones, for whatever
+//! field the circuit is over. a round is
+//!
+//! - add the round constants
+//! - `x^5` on every element in a full round, only on the first one in a partial round
+//! - multiply by the MDS matrix
+//!
+//! the round constants are drawn from the grain LFSR of the poseidon paper, the MDS is the
+//! cauchy matrix `1 / (x_i + y_j)` with `x_i = i` and `y_j = WIDTH + j`. the digests are not
+//! compatible with other instantiations, [`hash`] computes them outside of the circuit.
+//!
+//! hashing is a sponge with `RATE = 2` and the capacity element starting at `len * 2^64`,
+//! so inputs of different lengths don't collide. each permutation takes one row to
+//! absorb and one row per round:
+//!
+//! | row   | state                  | input          | rc             | selector  |
+//! |-------|------------------------|----------------|----------------|-----------|
+//! | 0     | s                      | m[0], m[1]     |                | q_absorb  |
+//! | 1     | s + m                  |                | rc[0]          | q_full    |
+//! | 1 + r | round r - 1 of s + m   |                | rc[r]          | q_partial |
+//! | 66    | permutation of s + m   | m[2], m[3]     |                | q_absorb  |
+//!
+//! the first state is fixed by `constrain_constant`, missing inputs are padded with
+//! constant zeros, and the digest is the first element of the last state.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+pub const WIDTH: usize = 3;
+pub const RATE: usize = WIDTH - 1;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+pub const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+/// the round constants and the MDS matrix
+#[derive(Debug, Clone)]
+pub struct Spec<F: FieldExt> {
+    pub round_constants: Vec<[F; WIDTH]>,
+    pub mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: FieldExt> Default for Spec<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt> Spec<F> {
+    pub fn new() -> Self {
+        let mut grain = Grain::new(F::NUM_BITS as u16);
+        let round_constants = (0..ROUNDS)
+            .map(|_| [(); WIDTH].map(|_| grain.next_field_element::<F>()))
+            .collect();
+        let mds = std::array::from_fn(|i| {
+            std::array::from_fn(|j| F::from((i + j + WIDTH) as u64).invert().unwrap())
+        });
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    pub fn is_full_round(round: usize) -> bool {
+        round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+    }
+
+    /// the state after `round`
+    pub fn round(&self, round: usize, state: [F; WIDTH]) -> [F; WIDTH] {
+        let mut state = state;
+        for (x, c) in state.iter_mut().zip(self.round_constants[round]) {
+            *x += c;
+        }
+        for (i, x) in state.iter_mut().enumerate() {
+            if i == 0 || Self::is_full_round(round) {
+                *x = pow5(*x);
+            }
+        }
+        self.mds.map(|row| {
+            row.iter()
+                .zip(state)
+                .fold(F::zero(), |acc, (m, x)| acc + *m * x)
+        })
+    }
+
+    pub fn permute(&self, state: [F; WIDTH]) -> [F; WIDTH] {
+        (0..ROUNDS).fold(state, |state, round| self.round(round, state))
+    }
+}
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+/// the state before absorbing `len` inputs, the capacity element is `len * 2^64`
+fn initial_state<F: FieldExt>(len: usize) -> [F; WIDTH] {
+    let mut state = [F::zero(); WIDTH];
+    state[RATE] = F::from_u128((len as u128) << 64);
+    state
+}
+
+/// the digest of `inputs`, outside of the circuit
+pub fn hash<F: FieldExt>(inputs: &[F]) -> F {
+    let spec = Spec::new();
+    let mut state = initial_state(inputs.len());
+    for chunk in chunks(inputs.len()) {
+        for (i, index) in chunk.enumerate() {
+            state[i] += inputs.get(index).copied().unwrap_or_else(F::zero);
+        }
+        state = spec.permute(state);
+    }
+    state[0]
+}
+
+/// the input indices absorbed by each permutation, at least one
+fn chunks(len: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let n = ((len + RATE - 1) / RATE).max(1);
+    (0..n).map(|chunk| chunk * RATE..(chunk + 1) * RATE)
+}
+
+/// the grain LFSR generating the round constants
+struct Grain {
+    state: Vec<bool>,
+    field_bits: usize,
+}
+
+impl Grain {
+    fn new(field_bits: u16) -> Self {
+        let mut state = Vec::with_capacity(80);
+        let mut append = |bits: usize, value: u64| {
+            state.extend((0..bits).rev().map(|i| (value >> i) & 1 == 1));
+        };
+        // prime field, x^alpha s-box
+        append(2, 1);
+        append(4, 0);
+        append(12, field_bits as u64);
+        append(12, WIDTH as u64);
+        append(10, FULL_ROUNDS as u64);
+        append(10, PARTIAL_ROUNDS as u64);
+        append(30, (1 << 30) - 1);
+
+        let mut grain = Self {
+            state,
+            field_bits: field_bits as usize,
+        };
+        for _ in 0..160 {
+            grain.next_bit();
+        }
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.remove(0);
+        self.state.push(bit);
+        bit
+    }
+
+    /// a pair of bits `(1, b)` outputs `b`, `(0, b)` is discarded
+    fn next_filtered_bit(&mut self) -> bool {
+        loop {
+            let keep = self.next_bit();
+            let bit = self.next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// `field_bits` bits most significant first, sampled again until they are in the field
+    fn next_field_element<F: FieldExt>(&mut self) -> F {
+        loop {
+            let mut repr = F::Repr::default();
+            for i in (0..self.field_bits).rev() {
+                if self.next_filtered_bit() {
+                    repr.as_mut()[i / 8] |= 1 << (i % 8);
+                }
+            }
+            if let Some(x) = Option::<F>::from(F::from_repr(repr)) {
+                return x;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonConfig {
+    pub state: [Column<Advice>; WIDTH],
+    pub input: [Column<Advice>; RATE],
+    pub rc: [Column<Fixed>; WIDTH],
+    pub q_absorb: Selector,
+    pub q_full: Selector,
+    pub q_partial: Selector,
+}
+
+pub struct PoseidonChip<F: FieldExt> {
+    config: PoseidonConfig,
+    spec: Spec<F>,
+}
+
+impl<F: FieldExt> PoseidonChip<F> {
+    pub fn construct(config: PoseidonConfig) -> Self {
+        Self {
+            config,
+            spec: Spec::new(),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PoseidonConfig {
+        let state = [(); WIDTH].map(|_| meta.advice_column());
+        let input = [(); RATE].map(|_| meta.advice_column());
+        let rc = [(); WIDTH].map(|_| meta.fixed_column());
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        for column in state.iter().chain(&input) {
+            meta.enable_equality(*column);
+        }
+        let q_absorb = meta.selector();
+        let q_full = meta.selector();
+        let q_partial = meta.selector();
+        let mds = Spec::<F>::new().mds;
+
+        meta.create_gate("poseidon absorb", |meta| {
+            let q_absorb = meta.query_selector(q_absorb);
+            (0..WIDTH)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let input = match input.get(i) {
+                        Some(input) => meta.query_advice(*input, Rotation::cur()),
+                        None => Expression::Constant(F::zero()),
+                    };
+                    q_absorb.clone() * (next - cur - input)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for (name, selector, full) in [
+            ("poseidon full round", q_full, true),
+            ("poseidon partial round", q_partial, false),
+        ] {
+            meta.create_gate(name, |meta| {
+                let q = meta.query_selector(selector);
+                let sboxed: Vec<_> = (0..WIDTH)
+                    .map(|j| {
+                        let x = meta.query_advice(state[j], Rotation::cur())
+                            + meta.query_fixed(rc[j], Rotation::cur());
+                        if j == 0 || full {
+                            let x2 = x.clone() * x.clone();
+                            x2.clone() * x2 * x
+                        } else {
+                            x
+                        }
+                    })
+                    .collect();
+                (0..WIDTH)
+                    .map(|i| {
+                        let next = meta.query_advice(state[i], Rotation::next());
+                        let mixed = sboxed
+                            .iter()
+                            .zip(mds[i])
+                            .fold(Expression::Constant(F::zero()), |acc, (x, m)| {
+                                acc + x.clone() * Expression::Constant(m)
+                            });
+                        q.clone() * (next - mixed)
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        PoseidonConfig {
+            state,
+            input,
+            rc,
+            q_absorb,
+            q_full,
+            q_partial,
+        }
+    }
+
+    /// the digest of `inputs`
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = self.config;
+        layouter.assign_region(
+            || format!("poseidon {} inputs", inputs.len()),
+            |mut region| {
+                let initial = initial_state(inputs.len());
+                let mut cells = Vec::with_capacity(WIDTH);
+                for (column, value) in config.state.iter().zip(initial) {
+                    cells.push(region.assign_advice_from_constant(
+                        || "initial state",
+                        *column,
+                        0,
+                        value,
+                    )?);
+                }
+                let mut state = Value::known(initial);
+
+                let mut offset = 0;
+                for chunk in chunks(inputs.len()) {
+                    config.q_absorb.enable(&mut region, offset)?;
+                    for (i, index) in chunk.enumerate() {
+                        let input = match inputs.get(index) {
+                            Some(input) => input.copy_advice(
+                                || "input",
+                                &mut region,
+                                config.input[i],
+                                offset,
+                            )?,
+                            None => region.assign_advice_from_constant(
+                                || "padding",
+                                config.input[i],
+                                offset,
+                                F::zero(),
+                            )?,
+                        };
+                        state = state.zip(input.value()).map(|(mut state, input)| {
+                            state[i] += input;
+                            state
+                        });
+                    }
+                    offset += 1;
+                    self.assign_state(&mut region, offset, state)?;
+
+                    for round in 0..ROUNDS {
+                        if Spec::<F>::is_full_round(round) {
+                            config.q_full.enable(&mut region, offset)?;
+                        } else {
+                            config.q_partial.enable(&mut region, offset)?;
+                        }
+                        for (column, c) in config.rc.iter().zip(self.spec.round_constants[round]) {
+                            region.assign_fixed(|| "rc", *column, offset, || Value::known(c))?;
+                        }
+                        state = state.map(|state| self.spec.round(round, state));
+                        offset += 1;
+                        cells = self.assign_state(&mut region, offset, state)?;
+                    }
+                }
+                Ok(cells[0].clone())
+            },
+        )
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.config
+            .state
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                region.assign_advice(|| "state", *column, offset, || state.map(|state| state[i]))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        inputs: Vec<Fr>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (PoseidonConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![Fr::zero(); self.inputs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (PoseidonChip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let inputs = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    self.inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, input)| {
+                            region.assign_advice(
+                                || "input",
+                                config.input[0],
+                                offset,
+                                || Value::known(*input),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let digest =
+                PoseidonChip::construct(config).hash(layouter.namespace(|| "hash"), &inputs)?;
+            layouter.constrain_instance(digest.cell(), instance, 0)
+        }
+    }
+
+    fn prover(inputs: &[u64], digest: Fr) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            inputs: inputs.iter().map(|x| Fr::from(*x)).collect(),
+        };
+        MockProver::run(8, &circuit, vec![vec![digest]]).unwrap()
+    }
+
+    fn native(inputs: &[u64]) -> Fr {
+        hash(&inputs.iter().map(|x| Fr::from(*x)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn spec() {
+        let spec = Spec::<Fr>::new();
+        assert_eq!(spec.round_constants.len(), ROUNDS);
+        // deterministic, and every constant is different
+        assert_eq!(spec.round_constants, Spec::<Fr>::new().round_constants);
+        let constants: Vec<_> = spec.round_constants.iter().flatten().collect();
+        for (i, c) in constants.iter().enumerate() {
+            assert!(!constants[i + 1..].contains(c));
+        }
+    }
+
+    #[test]
+    fn lengths() {
+        // the padding and the length in the capacity keep these apart
+        let digests = [
+            native(&[]),
+            native(&[0]),
+            native(&[0, 0]),
+            native(&[0, 0, 0]),
+        ];
+        for (i, a) in digests.iter().enumerate() {
+            for b in &digests[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_native() {
+        for inputs in [&[][..], &[1], &[1, 2], &[1, 2, 3]] {
+            prover(inputs, native(inputs)).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn wrong_digest() {
+        testing::assert_fails_permutation(&prover(&[1, 2], native(&[2, 1])));
+    }
+}
@@ -0,0 +1,151 @@
+//! running product gadget
+//!
+//! | row | x    | acc                     | q_first | q_product |
+//! |-----|------|-------------------------|---------|-----------|
+//! | 0   | x[0] | 1                       | 1       | 1         |
+//! | i   | x[i] | x[0] * ... * x[i-1]     | 0       | 1         |
+//! | len |      | x[0] * ... * x[len-1]   | 0       | 0         |
+//!
+//! - `q_first * (acc - 1) = 0`
+//! - `q_product * (acc' - acc * x) = 0`
+//!
+//! the rows are assigned into a region of the caller, so its own gates can constrain `x`.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RunningProductConfig {
+    pub x: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_first: Selector,
+    pub q_product: Selector,
+}
+
+pub struct RunningProductChip<F: FieldExt> {
+    config: RunningProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RunningProductChip<F> {
+    pub fn construct(config: RunningProductConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> RunningProductConfig {
+        meta.enable_equality(acc);
+        let q_first = meta.selector();
+        let q_product = meta.selector();
+
+        meta.create_gate("running product", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_product = meta.query_selector(q_product);
+            let x = meta.query_advice(x, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![
+                q_first * (acc.clone() - Expression::Constant(F::one())),
+                q_product * (acc_next - acc * x),
+            ]
+        });
+
+        RunningProductConfig {
+            x,
+            acc,
+            q_first,
+            q_product,
+        }
+    }
+
+    /// assign `xs` and their running product from offset 0 of `region`, the cell of the
+    /// total is returned
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        xs: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_first.enable(region, 0)?;
+        let mut acc = Value::known(F::one());
+        for (offset, x) in xs.iter().enumerate() {
+            self.config.q_product.enable(region, offset)?;
+            region.assign_advice(|| "x", self.config.x, offset, || *x)?;
+            region.assign_advice(|| "acc", self.config.acc, offset, || acc)?;
+            acc = acc * *x;
+        }
+        region.assign_advice(|| "product", self.config.acc, xs.len(), || acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        xs: Vec<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (RunningProductConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                xs: vec![0; self.xs.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let x = meta.advice_column();
+            let acc = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (RunningProductChip::configure(meta, x, acc), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = RunningProductChip::construct(config);
+            let xs: Vec<_> = self.xs.iter().map(|x| Value::known(Fr::from(*x))).collect();
+            let product =
+                layouter.assign_region(|| "product", |mut region| chip.assign(&mut region, &xs))?;
+            layouter.constrain_instance(product.cell(), instance, 0)
+        }
+    }
+
+    fn run(xs: &[u64], product: u64) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = TestCircuit { xs: xs.to_vec() };
+        MockProver::run(5, &circuit, vec![vec![Fr::from(product)]])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn products() {
+        assert_eq!(run(&[], 1), Ok(()));
+        assert_eq!(run(&[2, 3, 7], 42), Ok(()));
+        assert_eq!(run(&[2, 0, 7], 0), Ok(()));
+        assert!(run(&[2, 3, 7], 41).is_err());
+    }
+}
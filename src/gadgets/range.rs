@@ -0,0 +1,143 @@
+//! range check gadget
+//!
+//! proves `value < 2^BITS` by looking it up in a table of `0..2^BITS`, the table takes
+//! `2^BITS` rows, so `k` has to be larger than `BITS`.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RangeCheckConfig {
+    pub value: Column<Advice>,
+    pub q_lookup: Selector,
+    pub table: TableColumn,
+}
+
+pub struct RangeCheckChip<F: FieldExt, const BITS: usize> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const BITS: usize> RangeCheckChip<F, BITS> {
+    pub fn construct(config: RangeCheckConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> RangeCheckConfig {
+        let q_lookup = meta.complex_selector();
+        let table = meta.lookup_table_column();
+
+        meta.lookup(|meta| {
+            // 0 is in the table, so the rows not enabled pass
+            let q_lookup = meta.query_selector(q_lookup);
+            let value = meta.query_advice(value, Rotation::cur());
+            vec![(q_lookup * value, table)]
+        });
+
+        RangeCheckConfig {
+            value,
+            q_lookup,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("{} bits range", BITS),
+            |mut table| {
+                for value in 0..1 << BITS {
+                    table.assign_cell(
+                        || "value",
+                        self.config.table,
+                        value,
+                        || Value::known(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// assign `value` at `offset` and check it is smaller than `2^BITS`
+    pub fn assign_checked(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.config.q_lookup.enable(region, offset)?;
+        region.assign_advice(|| "value", self.config.value, offset, || value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        values: Vec<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = RangeCheckConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> RangeCheckConfig {
+            let value = meta.advice_column();
+            RangeCheckChip::<Fr, 8>::configure(meta, value)
+        }
+
+        fn synthesize(
+            &self,
+            config: RangeCheckConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = RangeCheckChip::<Fr, 8>::construct(config);
+            chip.load_table(layouter.namespace(|| "range"))?;
+            layouter.assign_region(
+                || "values",
+                |mut region| {
+                    for (offset, value) in self.values.iter().enumerate() {
+                        chip.assign_checked(&mut region, offset, Value::known(Fr::from(*value)))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(values: &[u64]) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            values: values.to_vec(),
+        };
+        MockProver::run(9, &circuit, vec![]).unwrap()
+    }
+
+    #[test]
+    fn in_range() {
+        prover(&[0, 1, 42, 255]).assert_satisfied();
+    }
+
+    #[test]
+    fn out_of_range() {
+        testing::assert_fails_lookup(&prover(&[0, 256]), 1);
+        testing::assert_fails_lookup(&prover(&[1 << 20]), 0);
+    }
+}
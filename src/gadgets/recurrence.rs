@@ -0,0 +1,178 @@
+//! linear recurrence gadget
+//!
+//! proves `x[i] = c[1] * x[i-1] + ... + c[ORDER] * x[i-ORDER]` for `i >= ORDER`, one term
+//! per row. the coefficients are assigned to fixed columns, so they are part of the
+//! circuit and not of the witness, e.g. fibonacci is `ORDER = 2, c = [1, 1]`.
+//!
+//! | row   | x        | c[1] ... c[ORDER] | q_step |
+//! |-------|----------|-------------------|--------|
+//! | 0     | x[0]     |                   | 0      |
+//! | ...   | ...      |                   | 0      |
+//! | ORDER | x[ORDER] | c[1] ... c[ORDER] | 1      |
+//! | i     | x[i]     | c[1] ... c[ORDER] | 1      |
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LinearRecurrenceConfig<const ORDER: usize> {
+    pub x: Column<Advice>,
+    // c[1] ... c[ORDER]
+    pub coeffs: [Column<Fixed>; ORDER],
+    pub q_step: Selector,
+}
+
+pub struct LinearRecurrenceChip<F: FieldExt, const ORDER: usize> {
+    config: LinearRecurrenceConfig<ORDER>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const ORDER: usize> LinearRecurrenceChip<F, ORDER> {
+    pub fn construct(config: LinearRecurrenceConfig<ORDER>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        coeffs: [Column<Fixed>; ORDER],
+    ) -> LinearRecurrenceConfig<ORDER> {
+        meta.enable_equality(x);
+        let q_step = meta.selector();
+
+        meta.create_gate("recurrence", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let combination = coeffs.iter().enumerate().fold(
+                Expression::Constant(F::zero()),
+                |acc, (j, coeff)| {
+                    let coeff = meta.query_fixed(*coeff, Rotation::cur());
+                    acc + coeff * meta.query_advice(x, Rotation(-(j as i32) - 1))
+                },
+            );
+            vec![q_step * (x_cur - combination)]
+        });
+
+        LinearRecurrenceConfig { x, coeffs, q_step }
+    }
+
+    /// assign the first `len` terms starting from `initial`, all of them are returned
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: [F; ORDER],
+        initial: [Value<F>; ORDER],
+        len: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(len >= ORDER);
+        layouter.assign_region(
+            || "recurrence",
+            |mut region| {
+                let mut terms = Vec::with_capacity(len);
+                for (offset, x) in initial.iter().enumerate() {
+                    terms.push(region.assign_advice(
+                        || "initial x",
+                        self.config.x,
+                        offset,
+                        || *x,
+                    )?);
+                }
+                for offset in ORDER..len {
+                    self.config.q_step.enable(&mut region, offset)?;
+                    let mut x = Value::known(F::zero());
+                    for (j, (column, coeff)) in self.config.coeffs.iter().zip(coeffs).enumerate() {
+                        region.assign_fixed(|| "coeff", *column, offset, || Value::known(coeff))?;
+                        x = x + terms[offset - 1 - j].value().map(|term| coeff * term);
+                    }
+                    terms.push(region.assign_advice(|| "x", self.config.x, offset, || x)?);
+                }
+                Ok(terms)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    struct TestCircuit<const ORDER: usize> {
+        coeffs: [u64; ORDER],
+        initial: [u64; ORDER],
+        expected: Vec<u64>,
+    }
+
+    impl<const ORDER: usize> Circuit<Fr> for TestCircuit<ORDER> {
+        type Config = LinearRecurrenceConfig<ORDER>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                coeffs: self.coeffs,
+                initial: [0; ORDER],
+                expected: vec![],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let x = meta.advice_column();
+            let coeffs = [(); ORDER].map(|_| meta.fixed_column());
+            LinearRecurrenceChip::configure(meta, x, coeffs)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = LinearRecurrenceChip::<Fr, ORDER>::construct(config);
+            let terms = chip.assign(
+                layouter,
+                self.coeffs.map(Fr::from),
+                self.initial.map(|x| Value::known(Fr::from(x))),
+                self.expected.len().max(ORDER),
+            )?;
+            for (term, expected) in terms.iter().zip(&self.expected) {
+                term.value()
+                    .assert_if_known(|term| **term == Fr::from(*expected));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fibonacci() {
+        let circuit = TestCircuit {
+            coeffs: [1, 1],
+            initial: [0, 1],
+            expected: vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34],
+        };
+        MockProver::run(5, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn third_order() {
+        // x[i] = 2 * x[i-1] + 3 * x[i-3]
+        let circuit = TestCircuit {
+            coeffs: [2, 0, 3],
+            initial: [1, 0, 0],
+            expected: vec![1, 0, 0, 3, 6, 12, 33, 84, 204],
+        };
+        MockProver::run(5, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+}
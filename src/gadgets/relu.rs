@@ -0,0 +1,304 @@
+//! relu and clamp gadget
+//!
+//! `max(x, bound)` or `min(x, bound)` of a signed 64 bit `x` and a fixed `bound`, negative
+//! ones wrapped around the field, such as a number of the
+//! [`FixedPointChip`](crate::gadgets::fixed_point::FixedPointChip). `sign` is 1 for the
+//! max, -1 for the min, and `d = sign * (x - bound)`:
+//!
+//! | x | out | bound | sign | lt    | diff          | q_relu |
+//! |---|-----|-------|------|-------|---------------|--------|
+//! | x | out | bound | ±1   | d < 0 | d + lt * 2^64 | 1      |
+//!
+//! - `sign * x - sign * bound + lt * 2^64 = diff` by the [`LtChip`] of 8 bytes, `lt` is the
+//!   sign bit of `d` and `diff` is range checked to 64 bits
+//! - `q_relu * (out - (lt ? bound : x)) = 0`
+//!
+//! `relu(x) = max(x, 0)` takes a row, `clamp(x, lo, hi) = min(max(x, lo), hi)` two, the
+//! second copying the `out` of the first.
+//!
+//! the comparison is the one of `sign * x + 2^63` with `sign * bound + 2^63`, which has the
+//! same difference, so only the witness is shifted. a difference of `2^64` or more leaves no
+//! `lt` to satisfy the gate, so `x` has to be range bounded to 64 bits by its chip.
+
+use crate::gadgets::{
+    compare::{LtChip, LtConfig},
+    fixed_point::encode,
+    select,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReluConfig {
+    pub x: Column<Advice>,
+    pub out: Column<Advice>,
+    pub bound: Column<Fixed>,
+    pub sign: Column<Fixed>,
+    pub q_relu: Selector,
+    pub lt: LtConfig<8>,
+}
+
+pub struct ReluChip<F: FieldExt> {
+    config: ReluConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ReluChip<F> {
+    pub fn construct(config: ReluConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        x: Column<Advice>,
+        out: Column<Advice>,
+    ) -> ReluConfig {
+        meta.enable_equality(x);
+        meta.enable_equality(out);
+        let bound = meta.fixed_column();
+        let sign = meta.fixed_column();
+        let q_relu = meta.complex_selector();
+
+        let lt = LtChip::configure(
+            meta,
+            |meta| meta.query_selector(q_relu),
+            |meta| meta.query_fixed(sign, Rotation::cur()) * meta.query_advice(x, Rotation::cur()),
+            |meta| {
+                meta.query_fixed(sign, Rotation::cur()) * meta.query_fixed(bound, Rotation::cur())
+            },
+        );
+
+        meta.create_gate("relu", |meta| {
+            let q_relu = meta.query_selector(q_relu);
+            let x = meta.query_advice(x, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let bound = meta.query_fixed(bound, Rotation::cur());
+            let lt = meta.query_advice(lt.lt, Rotation::cur());
+            vec![q_relu * (out - select::expr(lt, bound, x))]
+        });
+
+        ReluConfig {
+            x,
+            out,
+            bound,
+            sign,
+            q_relu,
+            lt,
+        }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        LtChip::<F, 8>::construct(self.config.lt).load_table(layouter)
+    }
+
+    /// `max(x, 0)` in a region of its own
+    pub fn relu(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "relu", |mut region| self.assign(&mut region, 0, x))
+    }
+
+    /// the same as [`ReluChip::relu`], at `offset` of a region of the caller
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign_bounded(region, offset, x, 0, 1)
+    }
+
+    /// `min(max(x, lo), hi)` in a region of its own
+    pub fn clamp(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        lo: i64,
+        hi: i64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "clamp",
+            |mut region| self.assign_clamp(&mut region, 0, x, lo, hi),
+        )
+    }
+
+    /// the same as [`ReluChip::clamp`], in the rows `offset` and `offset + 1` of a region of
+    /// the caller
+    pub fn assign_clamp(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        x: &AssignedCell<F, F>,
+        lo: i64,
+        hi: i64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(lo <= hi, "the bounds are the wrong way around");
+        let above = self.assign_bounded(region, offset, x, lo, 1)?;
+        self.assign_bounded(region, offset + 1, &above, hi, -1)
+    }
+
+    /// `max(x, bound)` for `sign = 1`, `min(x, bound)` for `sign = -1`
+    fn assign_bounded(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        x: &AssignedCell<F, F>,
+        bound: i64,
+        sign: i64,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = self.config;
+        config.q_relu.enable(region, offset)?;
+        let x = x.copy_advice(|| "x", region, config.x, offset)?;
+        let (bound, sign) = (encode::<F>(bound), encode::<F>(sign));
+        region.assign_fixed(|| "bound", config.bound, offset, || Value::known(bound))?;
+        region.assign_fixed(|| "sign", config.sign, offset, || Value::known(sign))?;
+
+        let shift = F::from_u128(1 << 63);
+        let lt = LtChip::<F, 8>::construct(config.lt).assign(
+            region,
+            offset,
+            x.value().map(|x| sign * x + shift),
+            Value::known(sign * bound + shift),
+        )?;
+        let out = x
+            .value()
+            .zip(lt.value())
+            .map(|(x, lt)| if *lt == F::one() { bound } else { *x });
+        region.assign_advice(|| "out", config.out, offset, || out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    /// `max(x, 0)` of a field element `x`, or its clamp to `bounds` if given, with the last
+    /// `out` overwritten by `cheat` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        x: Fr,
+        bounds: Option<(i64, i64)>,
+        cheat: Option<Fr>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (ReluConfig, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                bounds: self.bounds,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let input = meta.advice_column();
+            let x = meta.advice_column();
+            let out = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (ReluChip::configure(meta, x, out), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = ReluChip::construct(config);
+            chip.load_table(layouter.namespace(|| "u8"))?;
+            let out = layouter.assign_region(
+                || "relu",
+                |mut region| {
+                    let x = region.assign_advice(|| "x", input, 0, || Value::known(self.x))?;
+                    let (out, last) = match self.bounds {
+                        Some((lo, hi)) => (chip.assign_clamp(&mut region, 0, &x, lo, hi)?, 1),
+                        None => (chip.assign(&mut region, 0, &x)?, 0),
+                    };
+                    if let Some(cheat) = self.cheat {
+                        region.assign_advice(
+                            || "cheat",
+                            config.out,
+                            last,
+                            || Value::known(cheat),
+                        )?;
+                    }
+                    Ok(out)
+                },
+            )?;
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    fn prover(x: i64, bounds: Option<(i64, i64)>, cheat: Option<i64>, out: i64) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            x: encode(x),
+            bounds,
+            cheat: cheat.map(encode),
+        };
+        MockProver::run(9, &circuit, vec![vec![encode(out)]]).unwrap()
+    }
+
+    #[test]
+    fn relu() {
+        for x in [0, 1, -1, 42, -42, i64::MAX, i64::MIN] {
+            prover(x, None, None, x.max(0)).assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn clamp() {
+        for x in [0, 1, -1, 3, -2, 42, -42, i64::MAX, i64::MIN] {
+            prover(x, Some((-2, 3)), None, x.clamp(-2, 3)).assert_satisfied();
+        }
+        // a single value
+        prover(42, Some((7, 7)), None, 7).assert_satisfied();
+        prover(i64::MIN, Some((i64::MIN, i64::MAX)), None, i64::MIN).assert_satisfied();
+    }
+
+    #[test]
+    fn wrong_out() {
+        testing::assert_fails_permutation(&prover(-5, None, None, -5));
+        // the negative number let through
+        testing::assert_fails_gate(&prover(-5, None, Some(-5), -5), "relu", 0);
+        testing::assert_fails_gate(&prover(5, None, Some(0), 0), "relu", 0);
+        // above the upper bound
+        testing::assert_fails_gate(&prover(5, Some((-2, 3)), Some(5), 5), "relu", 1);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let circuit = TestCircuit {
+            x: Fr::from_u128(1 << 64),
+            bounds: None,
+            cheat: None,
+        };
+        let prover = MockProver::run(9, &circuit, vec![vec![Fr::from_u128(1 << 64)]]).unwrap();
+        testing::assert_fails_gate(&prover, "lt", 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong way around")]
+    fn empty_bounds() {
+        prover(0, Some((3, -2)), None, 0);
+    }
+}
@@ -0,0 +1,509 @@
+//! rlp decoding gadget
+//!
+//! parses the rlp encoding of a list of `FIELDS` strings, of `n` bytes in all, into the
+//! offset and the length of the data of every field. the list and its strings are in the
+//! short forms, a byte below `80` for itself, `80 + len` and `len` bytes for a string of
+//! up to 55 bytes, and `c0 + n - 1` before the list. a byte per row, the first one the
+//! list prefix:
+//!
+//! | row | byte    | head | rem | single | item | pos | off[k] | len[k] | sel[k] | q_rlp |
+//! |-----|---------|------|-----|--------|------|-----|--------|--------|--------|-------|
+//! | 0   | c0+n-1  |      | 0   |        | 0    | 0   |        |        |        | 0     |
+//! | i   | byte[i] | ...  | ... | ...    | ...  | i   | ...    | ...    | ...    | 1     |
+//!
+//! `head` is whether the byte starts a field, `rem` how many bytes of the field are left
+//! after it, `single` whether the byte is the field, `item` the number of fields so far,
+//! `sel[k]` whether the byte starts the field `k`, and `off[k]`, `len[k]` the field `k`
+//! so far. with `'` the row before, `head` and `sel[k]` boolean:
+//!
+//! - `q_rlp * head * rem' = 0`, a field starts right after the last one ends
+//! - `q_rlp * (1 - head) * (rem' - 1 - rem) = 0`
+//! - `q_rlp * (item - item' - head) = 0` and `q_rlp * (pos - pos' - 1) = 0`
+//! - `q_rlp * (sel[0] + ... + sel[FIELDS-1] - head) = 0`
+//! - `q_rlp * (1 * sel[0] + ... + FIELDS * sel[FIELDS-1] - head * item) = 0`
+//! - `q_rlp * (off[k] - (sel[k] ? pos + 1 - single : off[k]')) = 0`
+//! - `q_rlp * (len[k] - (sel[k] ? rem + single : len[k]')) = 0`
+//!
+//! and a lookup of `(2 - head, byte, head * rem, head * single)` in a table of the prefixes
+//! `(1, b, len, single)` for `b <= b7` and the bytes `(2, b, 0, 0)`, tagged behind a first
+//! row of 0s. a head is a single byte or a string prefix, everything else a byte.
+//!
+//! the last row has `rem = 0` and `item = FIELDS`. `rem` only counts down between heads, a
+//! byte after the end of a field takes it to `-1`, from where it never gets back to 0.
+//! the canonical encoding is left to the caller: `81 xx` with `xx < 80` parses as the
+//! single byte `xx`.
+
+use crate::gadgets::select;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// the first string prefix
+const STRING: u64 = 0x80;
+/// the first list prefix
+const LIST: u64 = 0xc0;
+/// the longest string or list in the short form
+const SHORT: u64 = 55;
+
+/// the data of a field, as cells of the last row
+#[derive(Debug, Clone)]
+pub struct RlpField<F: FieldExt> {
+    pub offset: AssignedCell<F, F>,
+    pub len: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RlpConfig<const FIELDS: usize> {
+    pub byte: Column<Advice>,
+    pub head: Column<Advice>,
+    pub rem: Column<Advice>,
+    pub single: Column<Advice>,
+    pub item: Column<Advice>,
+    pub pos: Column<Advice>,
+    pub off: [Column<Advice>; FIELDS],
+    pub len: [Column<Advice>; FIELDS],
+    pub sel: [Column<Advice>; FIELDS],
+    pub q_rlp: Selector,
+    /// tag, byte, length and single of the prefixes and the bytes
+    pub table: [TableColumn; 4],
+}
+
+pub struct RlpChip<F: FieldExt, const FIELDS: usize> {
+    config: RlpConfig<FIELDS>,
+    _marker: PhantomData<F>,
+}
+
+/// the `(offset, len)` of the data of every field of the short list `bytes`, `None` if it
+/// is not one
+pub fn parse(bytes: &[u8]) -> Option<Vec<(usize, usize)>> {
+    let (prefix, payload) = bytes.split_first()?;
+    if u64::from(*prefix) != LIST + payload.len() as u64 || payload.len() as u64 > SHORT {
+        return None;
+    }
+    let mut fields = vec![];
+    let mut pos = 1;
+    while pos < bytes.len() {
+        let prefix = u64::from(bytes[pos]);
+        let field = match prefix {
+            0..=0x7f => (pos, 1),
+            0x80..=0xb7 => (pos + 1, (prefix - STRING) as usize),
+            _ => return None,
+        };
+        pos = field.0 + field.1;
+        fields.push(field);
+    }
+    (pos == bytes.len()).then_some(fields)
+}
+
+/// the witness of a row after the first
+#[derive(Debug, Clone, Copy)]
+struct Step<const FIELDS: usize> {
+    head: bool,
+    rem: u64,
+    single: bool,
+    item: u64,
+    fields: [(u64, u64); FIELDS],
+}
+
+/// the rows of `payload` as the gates see them, also when it is not a valid list
+fn steps<const FIELDS: usize>(payload: &[u64]) -> Vec<Step<FIELDS>> {
+    let mut steps: Vec<Step<FIELDS>> = vec![];
+    let (mut rem, mut item, mut fields) = (0, 0, [(0, 0); FIELDS]);
+    for (i, byte) in payload.iter().enumerate() {
+        let pos = i as u64 + 1;
+        let head = rem == 0;
+        let single = head && *byte < STRING;
+        if head {
+            // a prefix outside of the table fails its lookup
+            rem = if single || *byte > STRING + SHORT {
+                0
+            } else {
+                *byte - STRING
+            };
+            item += 1;
+            if let Some(field) = fields.get_mut(item as usize - 1) {
+                let single = u64::from(single);
+                *field = (pos + 1 - single, rem + single);
+            }
+        } else {
+            rem -= 1;
+        }
+        steps.push(Step {
+            head,
+            rem,
+            single,
+            item,
+            fields,
+        });
+    }
+    steps
+}
+
+impl<F: FieldExt, const FIELDS: usize> RlpChip<F, FIELDS> {
+    pub fn construct(config: RlpConfig<FIELDS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, byte: Column<Advice>) -> RlpConfig<FIELDS> {
+        let [head, rem, single, item, pos] = [(); 5].map(|_| meta.advice_column());
+        let off = [(); FIELDS].map(|_| meta.advice_column());
+        let len = [(); FIELDS].map(|_| meta.advice_column());
+        let sel = [(); FIELDS].map(|_| meta.advice_column());
+        let q_rlp = meta.complex_selector();
+        let table = [(); 4].map(|_| meta.lookup_table_column());
+        for column in [byte, rem, item, pos].into_iter().chain(off).chain(len) {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            let q_rlp = meta.query_selector(q_rlp);
+            let two = Expression::Constant(F::from(2));
+            let [byte, head, rem, single] =
+                [byte, head, rem, single].map(|column| meta.query_advice(column, Rotation::cur()));
+            let [tag, byte_table, len_table, single_table] = table;
+            vec![
+                (q_rlp.clone() * (two - head.clone()), tag),
+                (q_rlp.clone() * byte, byte_table),
+                (q_rlp.clone() * head.clone() * rem, len_table),
+                (q_rlp * head * single, single_table),
+            ]
+        });
+
+        meta.create_gate("rlp", |meta| {
+            let q_rlp = meta.query_selector(q_rlp);
+            let one = Expression::Constant(F::one());
+            let [rem_prev, item_prev, pos_prev] =
+                [rem, item, pos].map(|column| meta.query_advice(column, Rotation::prev()));
+            let [head, rem, single, item, pos] = [head, rem, single, item, pos]
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+
+            let mut constraints = vec![
+                head.clone() * (one.clone() - head.clone()),
+                head.clone() * rem_prev.clone(),
+                (one.clone() - head.clone()) * (rem_prev - one.clone() - rem.clone()),
+                item.clone() - item_prev - head.clone(),
+                pos.clone() - pos_prev - one.clone(),
+            ];
+            let mut count = Expression::Constant(F::zero());
+            let mut encoded = Expression::Constant(F::zero());
+            for k in 0..FIELDS {
+                let sel = meta.query_advice(sel[k], Rotation::cur());
+                let off_prev = meta.query_advice(off[k], Rotation::prev());
+                let off = meta.query_advice(off[k], Rotation::cur());
+                let len_prev = meta.query_advice(len[k], Rotation::prev());
+                let len = meta.query_advice(len[k], Rotation::cur());
+                constraints.push(sel.clone() * (one.clone() - sel.clone()));
+                let offset = pos.clone() + one.clone() - single.clone();
+                constraints.push(off - select::expr(sel.clone(), offset, off_prev));
+                let length = rem.clone() + single.clone();
+                constraints.push(len - select::expr(sel.clone(), length, len_prev));
+                count = count + sel.clone();
+                encoded = encoded + Expression::Constant(F::from(k as u64 + 1)) * sel;
+            }
+            constraints.push(count - head.clone());
+            constraints.push(encoded - head * item);
+            constraints
+                .into_iter()
+                .map(|constraint| q_rlp.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        RlpConfig {
+            byte,
+            head,
+            rem,
+            single,
+            item,
+            pos,
+            off,
+            len,
+            sel,
+            q_rlp,
+            table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "rlp",
+            |mut table| {
+                let prefixes = (0..=STRING + SHORT).map(|byte| match byte {
+                    0..=0x7f => [1, byte, 0, 1],
+                    _ => [1, byte, byte - STRING, 0],
+                });
+                let bytes = (0..256).map(|byte| [2, byte, 0, 0]);
+                let rows = [[0; 4]].into_iter().chain(prefixes).chain(bytes);
+                for (row, entry) in rows.enumerate() {
+                    for (column, value) in self.config.table.iter().zip(entry) {
+                        table.assign_cell(
+                            || "entry",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// parse the list `bytes` in a region of its own, the fields are returned in order
+    pub fn decode(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<[RlpField<F>; FIELDS], Error> {
+        layouter.assign_region(|| "rlp", |mut region| self.assign(&mut region, 0, bytes))
+    }
+
+    /// the same as [`RlpChip::decode`], in the rows `offset..offset + bytes.len()` of a
+    /// region of the caller
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bytes: &[AssignedCell<F, F>],
+    ) -> Result<[RlpField<F>; FIELDS], Error> {
+        let config = self.config;
+        let n = bytes.len();
+        assert!(
+            (2..=SHORT as usize + 1).contains(&n),
+            "not a short list of at least a field"
+        );
+
+        // the list prefix
+        let prefix = bytes[0].copy_advice(|| "byte", region, config.byte, offset)?;
+        region.constrain_constant(prefix.cell(), F::from(LIST + n as u64 - 1))?;
+        for column in [config.rem, config.item, config.pos] {
+            region.assign_advice_from_constant(|| "start", column, offset, F::zero())?;
+        }
+        for column in config.off.into_iter().chain(config.len) {
+            region.assign_advice(|| "field", column, offset, || Value::known(F::zero()))?;
+        }
+
+        let payload: Value<Vec<u64>> = bytes[1..]
+            .iter()
+            .map(|byte| byte.value().map(|byte| byte.get_lower_128() as u64))
+            .collect();
+        let steps = payload.map(|payload| steps::<FIELDS>(&payload));
+
+        let mut last = None;
+        for (i, byte) in bytes.iter().enumerate().skip(1) {
+            let row = offset + i;
+            config.q_rlp.enable(region, row)?;
+            byte.copy_advice(|| "byte", region, config.byte, row)?;
+            let step = steps.clone().map(|steps| steps[i - 1]);
+            region.assign_advice(
+                || "head",
+                config.head,
+                row,
+                || step.map(|step| F::from(u64::from(step.head))),
+            )?;
+            let rem = region.assign_advice(
+                || "rem",
+                config.rem,
+                row,
+                || step.map(|step| F::from(step.rem)),
+            )?;
+            region.assign_advice(
+                || "single",
+                config.single,
+                row,
+                || step.map(|step| F::from(u64::from(step.single))),
+            )?;
+            let item = region.assign_advice(
+                || "item",
+                config.item,
+                row,
+                || step.map(|step| F::from(step.item)),
+            )?;
+            region.assign_advice(
+                || "pos",
+                config.pos,
+                row,
+                || Value::known(F::from(i as u64)),
+            )?;
+            let mut fields = vec![];
+            for k in 0..FIELDS {
+                let sel =
+                    step.map(|step| F::from(u64::from(step.head && step.item == k as u64 + 1)));
+                region.assign_advice(|| "sel", config.sel[k], row, || sel)?;
+                let field = step.map(|step| step.fields[k]);
+                let off = region.assign_advice(
+                    || "off",
+                    config.off[k],
+                    row,
+                    || field.map(|(offset, _)| F::from(offset)),
+                )?;
+                let len = region.assign_advice(
+                    || "len",
+                    config.len[k],
+                    row,
+                    || field.map(|(_, len)| F::from(len)),
+                )?;
+                fields.push(RlpField { offset: off, len });
+            }
+            last = Some((rem, item, fields));
+        }
+
+        // every field is complete, and there are as many as claimed
+        let (rem, item, fields) = last.unwrap();
+        region.constrain_constant(rem.cell(), F::zero())?;
+        region.constrain_constant(item.cell(), F::from(FIELDS as u64))?;
+        Ok(fields.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    const FIELDS: usize = 3;
+
+    #[derive(Default)]
+    struct TestCircuit {
+        bytes: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (RlpConfig<FIELDS>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                bytes: vec![0; self.bytes.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let input = meta.advice_column();
+            let byte = meta.advice_column();
+            let instance = meta.instance_column();
+            let constant = meta.fixed_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            meta.enable_constant(constant);
+            (RlpChip::configure(meta, byte), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = RlpChip::<Fr, FIELDS>::construct(config);
+            chip.load_table(layouter.namespace(|| "rlp"))?;
+            let bytes = layouter.assign_region(
+                || "bytes",
+                |mut region| {
+                    self.bytes
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, byte)| {
+                            region.assign_advice(
+                                || "byte",
+                                input,
+                                offset,
+                                || Value::known(Fr::from(u64::from(*byte))),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let fields = chip.decode(layouter.namespace(|| "list"), &bytes)?;
+            for (k, field) in fields.iter().enumerate() {
+                layouter.constrain_instance(field.offset.cell(), instance, 2 * k)?;
+                layouter.constrain_instance(field.len.cell(), instance, 2 * k + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(bytes: &[u8], fields: &[(usize, usize)]) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            bytes: bytes.to_vec(),
+        };
+        let instance = fields
+            .iter()
+            .flat_map(|(offset, len)| [*offset, *len])
+            .map(|value| Fr::from(value as u64))
+            .collect();
+        MockProver::run(10, &circuit, vec![instance]).unwrap()
+    }
+
+    /// `["cat", "", 0x05]`
+    const LIST: [u8; 7] = [0xc6, 0x83, b'c', b'a', b't', 0x80, 0x05];
+
+    #[test]
+    fn native() {
+        assert_eq!(parse(&LIST), Some(vec![(2, 3), (6, 0), (6, 1)]));
+        // a long string
+        assert_eq!(parse(&[0xc3, 0xb8, 0x01, 0x00]), None);
+        // the list prefix is off by one
+        assert_eq!(parse(&[0xc5, 0x83, b'c', b'a', b't', 0x80, 0x05]), None);
+        // a string running past the end
+        assert_eq!(parse(&[0xc3, 0x85, b'a', b'b']), None);
+    }
+
+    #[test]
+    fn decode() {
+        prover(&LIST, &parse(&LIST).unwrap()).assert_satisfied();
+        // ["dog", "god", "cat"]
+        let list = b"\xcc\x83dog\x83god\x83cat";
+        assert_eq!(parse(list), Some(vec![(2, 3), (6, 3), (10, 3)]));
+        prover(list, &parse(list).unwrap()).assert_satisfied();
+        // three single bytes
+        prover(&[0xc3, 0x00, 0x7f, 0x2a], &[(1, 1), (2, 1), (3, 1)]).assert_satisfied();
+    }
+
+    #[test]
+    fn wrong_fields() {
+        // the prefix of "cat" counted in
+        testing::assert_fails_permutation(&prover(&LIST, &[(1, 4), (6, 0), (6, 1)]));
+        // "cat" and "" merged
+        testing::assert_fails_permutation(&prover(&LIST, &[(2, 4), (6, 0), (6, 1)]));
+    }
+
+    #[test]
+    fn wrong_list_prefix() {
+        let mut list = LIST;
+        list[0] = 0xc5;
+        testing::assert_fails_permutation(&prover(&list, &[(2, 3), (6, 0), (6, 1)]));
+    }
+
+    #[test]
+    fn wrong_number_of_fields() {
+        // ["cat", "dog"]
+        let list = b"\xc8\x83cat\x83dog";
+        testing::assert_fails_permutation(&prover(list, &[(2, 3), (6, 3), (0, 0)]));
+    }
+
+    #[test]
+    fn truncated() {
+        // a string of 5 bytes with 2 of them, after two single bytes
+        let list = [0xc5, 0x01, 0x02, 0x85, b'a', b'b'];
+        testing::assert_fails_permutation(&prover(&list, &[(1, 1), (2, 1), (4, 5)]));
+    }
+
+    #[test]
+    fn long_string() {
+        // a long string prefix is not a head
+        let list = [0xc4, 0x01, 0x02, 0xb8, 0x00];
+        testing::assert_fails_lookup(&prover(&list, &[(1, 1), (2, 1), (4, 0)]), 3);
+    }
+}
@@ -0,0 +1,155 @@
+//! conditional select gadget
+//!
+//! `out = cond ? a : b`, written as `out = cond * a + (1 - cond) * b` with `cond` boolean.
+//!
+//! [`expr`] builds the same expression for use in the gates of other chips, when
+//! `cond` is already known to be boolean there.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// `cond * a + (1 - cond) * b`
+pub fn expr<F: FieldExt>(cond: Expression<F>, a: Expression<F>, b: Expression<F>) -> Expression<F> {
+    cond.clone() * a + (Expression::Constant(F::one()) - cond) * b
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SelectConfig {
+    // [cond, a, b, out]
+    pub advice: [Column<Advice>; 4],
+    pub q_select: Selector,
+}
+
+pub struct SelectChip<F: FieldExt> {
+    config: SelectConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SelectChip<F> {
+    pub fn construct(config: SelectConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 4]) -> SelectConfig {
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        let q_select = meta.selector();
+
+        meta.create_gate("select", |meta| {
+            let q = meta.query_selector(q_select);
+            let [cond, a, b, out] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![
+                q.clone() * cond.clone() * (Expression::Constant(F::one()) - cond.clone()),
+                q * (expr(cond, a, b) - out),
+            ]
+        });
+
+        SelectConfig { advice, q_select }
+    }
+
+    /// copy `cond`, `a` and `b` to `offset` and assign `cond ? a : b`
+    pub fn select(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        cond: &AssignedCell<F, F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [col_cond, col_a, col_b, col_out] = self.config.advice;
+        self.config.q_select.enable(region, offset)?;
+        let cond = cond.copy_advice(|| "cond", region, col_cond, offset)?;
+        let a = a.copy_advice(|| "a", region, col_a, offset)?;
+        let b = b.copy_advice(|| "b", region, col_b, offset)?;
+        let out = cond
+            .value()
+            .zip(a.value())
+            .zip(b.value())
+            .map(|((&cond, &a), &b)| cond * a + (F::one() - cond) * b);
+        region.assign_advice(|| "out", col_out, offset, || out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        cond: u64,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = SelectConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> SelectConfig {
+            let advice = [(); 4].map(|_| meta.advice_column());
+            SelectChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: SelectConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = SelectChip::construct(config);
+            layouter.assign_region(
+                || "select",
+                |mut region| {
+                    let [col_cond, col_a, col_b, _] = config.advice;
+                    let cond = region.assign_advice(
+                        || "cond",
+                        col_cond,
+                        0,
+                        || Value::known(Fr::from(self.cond)),
+                    )?;
+                    let a = region.assign_advice(|| "a", col_a, 0, || Value::known(Fr::from(3)))?;
+                    let b = region.assign_advice(|| "b", col_b, 0, || Value::known(Fr::from(7)))?;
+                    let out = chip.select(&mut region, 1, &cond, &a, &b)?;
+                    if self.cond < 2 {
+                        let expected = if self.cond == 1 { 3 } else { 7 };
+                        out.value()
+                            .assert_if_known(|out| **out == Fr::from(expected));
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn prover(cond: u64) -> MockProver<Fr> {
+        MockProver::run(4, &TestCircuit { cond }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn selects() {
+        prover(0).assert_satisfied();
+        prover(1).assert_satisfied();
+    }
+
+    #[test]
+    fn rejects_non_bool_cond() {
+        testing::assert_fails_gate(&prover(2), "select", 1);
+    }
+}
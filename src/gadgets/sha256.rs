@@ -0,0 +1,697 @@
+//! sha256 gadget
+//!
+//! the compression function of sha256 on 32 bit words. a [`Word`] is kept both as its
+//! bits and as its value, every operation is a region of 32 rows producing a new word, one
+//! bit per row, most significant first:
+//!
+//! | row    | x            | y            | z            | out          | acc      |
+//! |--------|--------------|--------------|--------------|--------------|----------|
+//! | 31 - i | bit i of x   | bit i of y   | bit i of z   | bit i of out | out >> i |
+//!
+//! - `acc = out` on the first row and `acc = 2 acc' + out` below, so the last `acc` is
+//!   the value of the word
+//! - bitwise, with `q_xor`, `q_ch` or `q_maj`: `out = x ^ y ^ z`, `out = x ? y : z` or
+//!   the majority of `x, y, z`. the inputs are bits of other words, so `out` is a bit
+//! - add, with a boolean `out` on every row: up to 8 terms in `x` on the first rows and
+//!   the carry `c0 + 2 c1 + 4 c2` in `x, y, z` on the last one, with
+//!   `acc + carry * 2^32 = sum of the terms`
+//!
+//! the rotations and shifts of `Σ` and `σ` don't take any row, the bits of the input are
+//! copied to `x, y, z` in their new order, with constant zeros shifted in. the round
+//! constants are terms of the adds, only messages of whole words are hashed.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+pub const ROUNDS: usize = 64;
+/// words in a message block
+pub const BLOCK_WORDS: usize = 16;
+pub const WORD_BITS: usize = 32;
+/// terms of an add, including its constant
+const ADD_TERMS: usize = 8;
+
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const ROUND_CONSTANTS: [u32; ROUNDS] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn compress_block(state: &mut [u32; 8], block: &[u32]) {
+    let mut w = block.to_vec();
+    for t in BLOCK_WORDS..ROUNDS {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w.push(
+            s1.wrapping_add(w[t - 7])
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 16]),
+        );
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in ROUND_CONSTANTS.iter().zip(&w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(*w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (state, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *state = state.wrapping_add(v);
+    }
+}
+
+/// sha256, outside of the circuit
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % (4 * BLOCK_WORDS) != 4 * BLOCK_WORDS - 8 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(8 * message.len() as u64).to_be_bytes());
+
+    let mut state = IV;
+    for block in padded.chunks(4 * BLOCK_WORDS) {
+        let words: Vec<_> = block
+            .chunks(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+            .collect();
+        compress_block(&mut state, &words);
+    }
+    let mut digest = [0; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// `a ^ b ^ c` for bits
+fn xor3<F: FieldExt>(a: Expression<F>, b: Expression<F>, c: Expression<F>) -> Expression<F> {
+    let pairs = a.clone() * b.clone() + b.clone() * c.clone() + c.clone() * a.clone();
+    a.clone() + b.clone() + c.clone() - Expression::Constant(F::from(2)) * pairs
+        + Expression::Constant(F::from(4)) * a * b * c
+}
+
+/// a 32 bit word, its bits least significant first
+#[derive(Debug, Clone)]
+pub struct Word<F: FieldExt> {
+    pub bits: Vec<AssignedCell<F, F>>,
+    pub value: AssignedCell<F, F>,
+}
+
+/// a bit of an input of a bitwise operation
+#[derive(Debug)]
+enum Bit<'a, F: FieldExt> {
+    Cell(&'a AssignedCell<F, F>),
+    Constant(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Xor,
+    Ch,
+    Maj,
+}
+
+impl Op {
+    fn apply(self, x: bool, y: bool, z: bool) -> bool {
+        match self {
+            Op::Xor => x ^ y ^ z,
+            Op::Ch => {
+                if x {
+                    y
+                } else {
+                    z
+                }
+            }
+            Op::Maj => (x & y) | (x & z) | (y & z),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sha256Config {
+    // [x, y, z]
+    pub input: [Column<Advice>; 3],
+    pub out: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub q_first: Selector,
+    pub q_step: Selector,
+    pub q_xor: Selector,
+    pub q_ch: Selector,
+    pub q_maj: Selector,
+    pub q_bool: Selector,
+    pub q_add: Selector,
+}
+
+pub struct Sha256Chip<F: FieldExt> {
+    config: Sha256Config,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn construct(config: Sha256Config) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Sha256Config {
+        let input = [(); 3].map(|_| meta.advice_column());
+        let out = meta.advice_column();
+        let acc = meta.advice_column();
+        let constant = meta.fixed_column();
+        for column in input.iter().chain([&out, &acc]) {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        let q_xor = meta.selector();
+        let q_ch = meta.selector();
+        let q_maj = meta.selector();
+        let q_bool = meta.selector();
+        let q_add = meta.selector();
+        let constant = |value: u64| Expression::Constant(F::from(value));
+
+        meta.create_gate("sha256 pack", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_step = meta.query_selector(q_step);
+            let out = meta.query_advice(out, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![
+                q_first * (acc.clone() - out.clone()),
+                q_step * (acc - acc_prev * constant(2) - out),
+            ]
+        });
+
+        meta.create_gate("sha256 bitwise", |meta| {
+            let q_xor = meta.query_selector(q_xor);
+            let q_ch = meta.query_selector(q_ch);
+            let q_maj = meta.query_selector(q_maj);
+            let [x, y, z] = input.map(|column| meta.query_advice(column, Rotation::cur()));
+            let out = meta.query_advice(out, Rotation::cur());
+            let two = constant(2);
+            let maj = x.clone() * y.clone() + x.clone() * z.clone() + y.clone() * z.clone()
+                - two * x.clone() * y.clone() * z.clone();
+            vec![
+                q_xor * (out.clone() - xor3(x.clone(), y.clone(), z.clone())),
+                q_ch * (out.clone()
+                    - (x.clone() * y.clone() + (constant(1) - x.clone()) * z.clone())),
+                q_maj * (out - maj),
+            ]
+        });
+
+        meta.create_gate("sha256 add", |meta| {
+            let q_bool = meta.query_selector(q_bool);
+            let q_add = meta.query_selector(q_add);
+            let out = meta.query_advice(out, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let carry = input.map(|column| meta.query_advice(column, Rotation::cur()));
+            let last = WORD_BITS as i32 - 1;
+            let terms = (0..ADD_TERMS as i32)
+                .map(|i| meta.query_advice(input[0], Rotation(i - last)))
+                .fold(constant(0), |sum, term| sum + term);
+
+            let boolean = |bit: Expression<F>| bit.clone() * (constant(1) - bit);
+            let mut constraints = vec![q_bool * boolean(out)];
+            let carry_value =
+                carry[0].clone() + carry[1].clone() * constant(2) + carry[2].clone() * constant(4);
+            constraints.extend(carry.map(|bit| q_add.clone() * boolean(bit)));
+            constraints.push(q_add * (acc + carry_value * constant(1 << WORD_BITS) - terms));
+            constraints
+        });
+
+        Sha256Config {
+            input,
+            out,
+            acc,
+            q_first,
+            q_step,
+            q_xor,
+            q_ch,
+            q_maj,
+            q_bool,
+            q_add,
+        }
+    }
+
+    /// the word of `cell`, which has at most 35 bits and is reduced mod `2^32`
+    pub fn word(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<Word<F>, Error> {
+        self.add(layouter, &[cell], 0)
+    }
+
+    pub fn constant(&self, layouter: impl Layouter<F>, value: u32) -> Result<Word<F>, Error> {
+        self.add(layouter, &[], value)
+    }
+
+    /// `word ^ constant`
+    pub fn xor_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        word: &Word<F>,
+        constant: u32,
+    ) -> Result<Word<F>, Error> {
+        self.bitwise(
+            layouter,
+            Op::Xor,
+            [bits(word), constant_bits(constant), constant_bits(0)],
+        )
+    }
+
+    /// sha256 of `message`, whole words read as big endian bytes. the digest is returned
+    /// as 8 words, the bytes of the digest in big endian order
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        message: &[Word<F>],
+    ) -> Result<[Word<F>; 8], Error> {
+        // 0x80 after the message, then zeros and the length in bits in the last 2 words
+        let length = (WORD_BITS * message.len()) as u64;
+        let mut padding = vec![0x8000_0000];
+        while (message.len() + padding.len() + 2) % BLOCK_WORDS != 0 {
+            padding.push(0);
+        }
+        padding.extend([(length >> 32) as u32, length as u32]);
+        let padding = padding
+            .iter()
+            .map(|word| self.constant(layouter.namespace(|| "padding"), *word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let padded: Vec<_> = message.iter().cloned().chain(padding).collect();
+
+        let state = IV
+            .iter()
+            .map(|word| self.constant(layouter.namespace(|| "iv"), *word))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut state: [Word<F>; 8] = state.try_into().unwrap();
+        for (i, block) in padded.chunks(BLOCK_WORDS).enumerate() {
+            state = self.compress(layouter.namespace(|| format!("block {}", i)), &state, block)?;
+        }
+        Ok(state)
+    }
+
+    /// the compression function of `block` into `state`
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &[Word<F>; 8],
+        block: &[Word<F>],
+    ) -> Result<[Word<F>; 8], Error> {
+        assert_eq!(block.len(), BLOCK_WORDS);
+        let mut w = block.to_vec();
+        for t in BLOCK_WORDS..ROUNDS {
+            let mut layouter = layouter.namespace(|| format!("w[{}]", t));
+            let (w2, w15) = (&w[t - 2], &w[t - 15]);
+            let s0 = self.bitwise(
+                layouter.namespace(|| "sigma 0"),
+                Op::Xor,
+                [rotr(w15, 7), rotr(w15, 18), shr(w15, 3)],
+            )?;
+            let s1 = self.bitwise(
+                layouter.namespace(|| "sigma 1"),
+                Op::Xor,
+                [rotr(w2, 17), rotr(w2, 19), shr(w2, 10)],
+            )?;
+            let sum = self.add(
+                layouter.namespace(|| "w"),
+                &[&s1.value, &w[t - 7].value, &s0.value, &w[t - 16].value],
+                0,
+            )?;
+            w.push(sum);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.clone();
+        for (t, (k, w)) in ROUND_CONSTANTS.iter().zip(&w).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("round {}", t));
+            let s1 = self.bitwise(
+                layouter.namespace(|| "Sigma 1"),
+                Op::Xor,
+                [rotr(&e, 6), rotr(&e, 11), rotr(&e, 25)],
+            )?;
+            let ch = self.bitwise(
+                layouter.namespace(|| "ch"),
+                Op::Ch,
+                [bits(&e), bits(&f), bits(&g)],
+            )?;
+            let s0 = self.bitwise(
+                layouter.namespace(|| "Sigma 0"),
+                Op::Xor,
+                [rotr(&a, 2), rotr(&a, 13), rotr(&a, 22)],
+            )?;
+            let maj = self.bitwise(
+                layouter.namespace(|| "maj"),
+                Op::Maj,
+                [bits(&a), bits(&b), bits(&c)],
+            )?;
+            // t1 = h + Σ1 + ch + k + w, t2 = Σ0 + maj
+            let t1 = [&h.value, &s1.value, &ch.value, &w.value];
+            let new_e = self.add(
+                layouter.namespace(|| "d + t1"),
+                &[&d.value, t1[0], t1[1], t1[2], t1[3]],
+                *k,
+            )?;
+            let new_a = self.add(
+                layouter.namespace(|| "t1 + t2"),
+                &[t1[0], t1[1], t1[2], t1[3], &s0.value, &maj.value],
+                *k,
+            )?;
+            h = g;
+            g = f;
+            f = e;
+            e = new_e;
+            d = c;
+            c = b;
+            b = a;
+            a = new_a;
+        }
+
+        let mut out = Vec::with_capacity(8);
+        for (state, v) in state.iter().zip([a, b, c, d, e, f, g, h]) {
+            out.push(self.add(
+                layouter.namespace(|| "state + v"),
+                &[&state.value, &v.value],
+                0,
+            )?);
+        }
+        Ok(out.try_into().unwrap())
+    }
+
+    /// `op(x, y, z)` bit by bit
+    fn bitwise(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: Op,
+        inputs: [Vec<Bit<'_, F>>; 3],
+    ) -> Result<Word<F>, Error> {
+        let values: Vec<Vec<_>> = inputs
+            .iter()
+            .map(|bits| {
+                bits.iter()
+                    .map(|bit| match bit {
+                        Bit::Cell(cell) => cell.value().map(|bit| *bit != F::zero()),
+                        Bit::Constant(bit) => Value::known(*bit),
+                    })
+                    .collect()
+            })
+            .collect();
+        let out: Vec<_> = (0..WORD_BITS)
+            .map(|i| {
+                values[0][i]
+                    .zip(values[1][i])
+                    .zip(values[2][i])
+                    .map(|((x, y), z)| u64::from(op.apply(x, y, z)))
+            })
+            .collect();
+        let selector = match op {
+            Op::Xor => self.config.q_xor,
+            Op::Ch => self.config.q_ch,
+            Op::Maj => self.config.q_maj,
+        };
+
+        layouter.assign_region(
+            || format!("sha256 {:?}", op),
+            |mut region| {
+                for (i, (column, bits)) in self.config.input.iter().zip(&inputs).enumerate() {
+                    for (row, bit) in bits.iter().rev().enumerate() {
+                        match bit {
+                            Bit::Cell(cell) => {
+                                cell.copy_advice(
+                                    || format!("input {}", i),
+                                    &mut region,
+                                    *column,
+                                    row,
+                                )?;
+                            }
+                            Bit::Constant(bit) => {
+                                region.assign_advice_from_constant(
+                                    || format!("input {}", i),
+                                    *column,
+                                    row,
+                                    F::from(u64::from(*bit)),
+                                )?;
+                            }
+                        }
+                    }
+                }
+                for row in 0..WORD_BITS {
+                    selector.enable(&mut region, row)?;
+                }
+                self.pack(&mut region, &out)
+            },
+        )
+    }
+
+    /// `sum of terms + constant mod 2^32`, for at most 7 terms
+    fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        terms: &[&AssignedCell<F, F>],
+        constant: u32,
+    ) -> Result<Word<F>, Error> {
+        assert!(terms.len() < ADD_TERMS);
+        let sum = terms
+            .iter()
+            .fold(Value::known(u64::from(constant)), |sum, term| {
+                sum.zip(term.value())
+                    .map(|(sum, term)| sum.saturating_add(term.get_lower_128() as u64))
+            });
+        let out: Vec<_> = (0..WORD_BITS)
+            .map(|i| sum.map(|sum| (sum >> i) & 1))
+            .collect();
+        let carry = sum.map(|sum| sum >> WORD_BITS);
+
+        layouter.assign_region(
+            || "sha256 add",
+            |mut region| {
+                let column = self.config.input[0];
+                for (row, term) in terms.iter().enumerate() {
+                    term.copy_advice(|| "term", &mut region, column, row)?;
+                }
+                region.assign_advice_from_constant(
+                    || "constant",
+                    column,
+                    terms.len(),
+                    F::from(u64::from(constant)),
+                )?;
+                for row in terms.len() + 1..ADD_TERMS {
+                    region.assign_advice_from_constant(|| "zero", column, row, F::zero())?;
+                }
+
+                let last = WORD_BITS - 1;
+                self.config.q_add.enable(&mut region, last)?;
+                for (i, column) in self.config.input.iter().enumerate() {
+                    region.assign_advice(
+                        || format!("carry {}", i),
+                        *column,
+                        last,
+                        || carry.map(|carry| F::from((carry >> i) & 1)),
+                    )?;
+                }
+                for row in 0..WORD_BITS {
+                    self.config.q_bool.enable(&mut region, row)?;
+                }
+                self.pack(&mut region, &out)
+            },
+        )
+    }
+
+    /// assign the bits `out`, least significant first, and the running sums of `acc`
+    fn pack(&self, region: &mut Region<'_, F>, out: &[Value<u64>]) -> Result<Word<F>, Error> {
+        let mut bits = Vec::with_capacity(WORD_BITS);
+        let mut acc = Value::known(0u64);
+        let mut value = None;
+        for (row, bit) in out.iter().rev().enumerate() {
+            if row == 0 {
+                self.config.q_first.enable(region, row)?;
+            } else {
+                self.config.q_step.enable(region, row)?;
+            }
+            acc = acc.zip(*bit).map(|(acc, bit)| 2 * acc + bit);
+            bits.push(region.assign_advice(|| "out", self.config.out, row, || bit.map(F::from))?);
+            value =
+                Some(region.assign_advice(|| "acc", self.config.acc, row, || acc.map(F::from))?);
+        }
+        bits.reverse();
+        Ok(Word {
+            bits,
+            value: value.unwrap(),
+        })
+    }
+}
+
+fn constant_bits<'a, F: FieldExt>(value: u32) -> Vec<Bit<'a, F>> {
+    (0..WORD_BITS)
+        .map(|i| Bit::Constant((value >> i) & 1 == 1))
+        .collect()
+}
+
+fn bits<F: FieldExt>(word: &Word<F>) -> Vec<Bit<'_, F>> {
+    rotr(word, 0)
+}
+
+/// the bits of `word >>> r`
+fn rotr<F: FieldExt>(word: &Word<F>, r: usize) -> Vec<Bit<'_, F>> {
+    (0..WORD_BITS)
+        .map(|i| Bit::Cell(&word.bits[(i + r) % WORD_BITS]))
+        .collect()
+}
+
+/// the bits of `word >> r`
+fn shr<F: FieldExt>(word: &Word<F>, r: usize) -> Vec<Bit<'_, F>> {
+    (0..WORD_BITS)
+        .map(|i| match word.bits.get(i + r) {
+            Some(bit) => Bit::Cell(bit),
+            None => Bit::Constant(false),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    /// hash `message`, with the first word replaced by `first_word` if given
+    #[derive(Default)]
+    struct TestCircuit {
+        message: Vec<u8>,
+        first_word: Option<u64>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = Sha256Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: vec![0; self.message.len()],
+                first_word: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Sha256Config {
+            Sha256Chip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Sha256Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = Sha256Chip::construct(config);
+            let cells = layouter.assign_region(
+                || "message",
+                |mut region| {
+                    self.message
+                        .chunks(4)
+                        .enumerate()
+                        .map(|(offset, word)| {
+                            let word = match (offset, self.first_word) {
+                                (0, Some(word)) => word,
+                                _ => u32::from_be_bytes(word.try_into().unwrap()).into(),
+                            };
+                            region.assign_advice(
+                                || "message",
+                                config.input[0],
+                                offset,
+                                || Value::known(Fr::from(word)),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let message = cells
+                .iter()
+                .map(|cell| chip.word(layouter.namespace(|| "word"), cell))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let digest = chip.hash(layouter.namespace(|| "sha256"), &message)?;
+            let expected = sha256(&self.message);
+            for (word, expected) in digest.iter().zip(expected.chunks(4)) {
+                let expected = u32::from_be_bytes(expected.try_into().unwrap());
+                word.value.value().assert_if_known(|word| {
+                    self.first_word.is_some() || **word == Fr::from(u64::from(expected))
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn prover(message: &[u8], first_word: Option<u64>) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            message: message.to_vec(),
+            first_word,
+        };
+        MockProver::run(16, &circuit, vec![]).unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn native() {
+        assert_eq!(
+            hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn hash() {
+        prover(b"", None).assert_satisfied();
+        prover(b"halo2 sha256", None).assert_satisfied();
+        // the length no longer fits in the first block
+        prover(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            None,
+        )
+        .assert_satisfied();
+    }
+
+    #[test]
+    fn out_of_range_word() {
+        // a carry of 8 out of the 3 bits on the last row of the add
+        testing::assert_fails_gate(&prover(b"sha2", Some(1 << 35)), "sha256 add", 31);
+    }
+}
@@ -0,0 +1,335 @@
+//! sparse merkle tree gadget
+//!
+//! a merkle tree of depth `d` with a leaf for every key in `0..2^d`, almost all of them
+//! empty. an empty leaf is 0 and the leaf of a key holding `value` is `H(key, value)`,
+//! with the poseidon [`hash`](crate::gadgets::poseidon::hash). a subtree without any leaf
+//! hashes to the default of its level in [`empty_roots`], so [`SparseMerkleTree`] only
+//! stores the leaves which are set.
+//!
+//! the chip walks from the leaf of a key up to the root. the key is decomposed into `d`
+//! bits, least significant first, which pick the side of the sibling at every level:
+//!
+//! | row | cond   | a       | b       | out   | sibling |
+//! |-----|--------|---------|---------|-------|---------|
+//! | 0   | bit[i] | sibling | node    | left  | sibling |
+//! | 1   | bit[i] | node    | sibling | right |         |
+//!
+//! and the node of the next level is `H(left, right)`.
+//!
+//! - `membership`: the leaf is `H(key, value)`
+//! - `non_membership`: the leaf is a constant 0, the key holds nothing
+//!
+//! the decomposition also checks the key is smaller than `2^d`.
+
+use crate::gadgets::{
+    bits::{DecomposeChip, DecomposeConfig},
+    poseidon::{self, PoseidonChip, PoseidonConfig},
+    select::{SelectChip, SelectConfig},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+use std::collections::BTreeMap;
+
+/// the roots of the empty subtrees of depth `0..=depth`, the first is the empty leaf
+pub fn empty_roots<F: FieldExt>(depth: usize) -> Vec<F> {
+    let mut roots = vec![F::zero()];
+    for level in 0..depth {
+        let node = roots[level];
+        roots.push(poseidon::hash(&[node, node]));
+    }
+    roots
+}
+
+/// a sparse merkle tree outside of the circuit
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree<F: FieldExt> {
+    depth: usize,
+    // key -> H(key, value)
+    leaves: BTreeMap<u64, F>,
+    empty: Vec<F>,
+}
+
+impl<F: FieldExt> SparseMerkleTree<F> {
+    pub fn new(depth: usize) -> Self {
+        assert!(depth > 0 && depth < 64, "keys are u64");
+        Self {
+            depth,
+            leaves: BTreeMap::new(),
+            empty: empty_roots(depth),
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, value: F) {
+        self.set_leaf(key, poseidon::hash(&[F::from(key), value]));
+    }
+
+    /// set the leaf of `key` itself, to use the tree as a plain merkle tree
+    pub fn set_leaf(&mut self, key: u64, leaf: F) {
+        assert!(key >> self.depth == 0, "key out of range");
+        self.leaves.insert(key, leaf);
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    /// the siblings on the path of `key`, from the leaf up
+    pub fn siblings(&self, key: u64) -> Vec<F> {
+        (0..self.depth)
+            .map(|level| self.node(level, (key >> level) ^ 1))
+            .collect()
+    }
+
+    /// node `index` of `level`, counting from the leaves
+    fn node(&self, level: usize, index: u64) -> F {
+        let mut leaves = self.leaves.range(index << level..(index + 1) << level);
+        match leaves.next() {
+            None => self.empty[level],
+            Some((_, leaf)) if level == 0 => *leaf,
+            Some(_) => poseidon::hash(&[
+                self.node(level - 1, 2 * index),
+                self.node(level - 1, 2 * index + 1),
+            ]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SmtConfig {
+    pub sibling: Column<Advice>,
+    pub poseidon: PoseidonConfig,
+    pub select: SelectConfig,
+    pub decompose: DecomposeConfig,
+}
+
+pub struct SmtChip<F: FieldExt> {
+    config: SmtConfig,
+    poseidon: PoseidonChip<F>,
+    select: SelectChip<F>,
+    decompose: DecomposeChip<F>,
+}
+
+impl<F: FieldExt> SmtChip<F> {
+    pub fn construct(config: SmtConfig) -> Self {
+        Self {
+            config,
+            poseidon: PoseidonChip::construct(config.poseidon),
+            select: SelectChip::construct(config.select),
+            decompose: DecomposeChip::construct(config.decompose),
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SmtConfig {
+        let sibling = meta.advice_column();
+        meta.enable_equality(sibling);
+        let select = [(); 4].map(|_| meta.advice_column());
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        SmtConfig {
+            sibling,
+            poseidon: PoseidonChip::configure(meta),
+            select: SelectChip::configure(meta, select),
+            decompose: DecomposeChip::configure(meta, bit, acc),
+        }
+    }
+
+    /// the root of a tree where `key` holds `value`
+    pub fn membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        value: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let leaf = self
+            .poseidon
+            .hash(layouter.namespace(|| "leaf"), &[key.clone(), value.clone()])?;
+        self.root(layouter, key, leaf, siblings)
+    }
+
+    /// the root of a tree where `key` holds nothing
+    pub fn non_membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let leaf = layouter.assign_region(
+            || "empty leaf",
+            |mut region| {
+                region.assign_advice_from_constant(|| "empty", self.config.sibling, 0, F::zero())
+            },
+        )?;
+        self.root(layouter, key, leaf, siblings)
+    }
+
+    /// hash `leaf` up to the root along the path of `key`, any leaf as in
+    /// [`SparseMerkleTree::set_leaf`]
+    pub fn root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        key: &AssignedCell<F, F>,
+        leaf: AssignedCell<F, F>,
+        siblings: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let bits = self
+            .decompose
+            .decompose(layouter.namespace(|| "key"), key, siblings.len())?;
+        let mut node = leaf;
+        for (level, (bit, sibling)) in bits.iter().zip(siblings).enumerate() {
+            let (left, right) = layouter.assign_region(
+                || format!("smt level {}", level),
+                |mut region| {
+                    let sibling =
+                        region.assign_advice(|| "sibling", self.config.sibling, 0, || *sibling)?;
+                    let left = self.select.select(&mut region, 0, bit, &sibling, &node)?;
+                    let right = self.select.select(&mut region, 1, bit, &node, &sibling)?;
+                    Ok((left, right))
+                },
+            )?;
+            node = self.poseidon.hash(
+                layouter.namespace(|| format!("node {}", level + 1)),
+                &[left, right],
+            )?;
+        }
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, Instance},
+    };
+
+    const DEPTH: usize = 4;
+
+    /// prove `key` holds `value`, or nothing if `value` is `None`
+    struct TestCircuit {
+        key: u64,
+        value: Option<Fr>,
+        siblings: Vec<Fr>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = (SmtConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                key: 0,
+                value: self.value.map(|_| Fr::zero()),
+                siblings: vec![Fr::zero(); DEPTH],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (SmtChip::configure(meta), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = SmtChip::construct(config);
+            let (key, value) = layouter.assign_region(
+                || "key value",
+                |mut region| {
+                    let key = region.assign_advice(
+                        || "key",
+                        config.sibling,
+                        0,
+                        || Value::known(Fr::from(self.key)),
+                    )?;
+                    let value = self
+                        .value
+                        .map(|value| {
+                            region.assign_advice(
+                                || "value",
+                                config.sibling,
+                                1,
+                                || Value::known(value),
+                            )
+                        })
+                        .transpose()?;
+                    Ok((key, value))
+                },
+            )?;
+
+            let siblings: Vec<_> = self.siblings.iter().copied().map(Value::known).collect();
+            let root = match value {
+                Some(value) => {
+                    chip.membership(layouter.namespace(|| "smt"), &key, &value, &siblings)?
+                }
+                None => chip.non_membership(layouter.namespace(|| "smt"), &key, &siblings)?,
+            };
+            layouter.constrain_instance(root.cell(), instance, 0)
+        }
+    }
+
+    fn tree() -> SparseMerkleTree<Fr> {
+        let mut tree = SparseMerkleTree::new(DEPTH);
+        for key in [3, 5, 12] {
+            tree.insert(key, Fr::from(100 + key));
+        }
+        tree
+    }
+
+    fn prover(tree: &SparseMerkleTree<Fr>, key: u64, value: Option<Fr>) -> MockProver<Fr> {
+        let circuit = TestCircuit {
+            key,
+            value,
+            siblings: tree.siblings(key),
+        };
+        MockProver::run(10, &circuit, vec![vec![tree.root()]]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        let empty = SparseMerkleTree::<Fr>::new(DEPTH);
+        let roots = empty_roots::<Fr>(DEPTH);
+        assert_eq!(empty.root(), roots[DEPTH]);
+        assert_eq!(empty.siblings(7), &roots[..DEPTH]);
+
+        // the same leaves inserted in another order
+        let mut other = SparseMerkleTree::new(DEPTH);
+        for key in [12, 3, 5] {
+            other.insert(key, Fr::from(100 + key));
+        }
+        assert_eq!(other.root(), tree().root());
+        assert_ne!(tree().root(), empty.root());
+    }
+
+    #[test]
+    fn membership() {
+        prover(&tree(), 5, Some(Fr::from(105))).assert_satisfied();
+        prover(&tree(), 12, Some(Fr::from(112))).assert_satisfied();
+    }
+
+    #[test]
+    fn non_membership() {
+        prover(&tree(), 0, None).assert_satisfied();
+        prover(&tree(), 4, None).assert_satisfied();
+        prover(&SparseMerkleTree::new(DEPTH), 15, None).assert_satisfied();
+    }
+
+    #[test]
+    fn rejects_wrong_claims() {
+        // 5 holds 105
+        testing::assert_fails_permutation(&prover(&tree(), 5, None));
+        testing::assert_fails_permutation(&prover(&tree(), 5, Some(Fr::from(106))));
+        // 6 is empty
+        testing::assert_fails_permutation(&prover(&tree(), 6, Some(Fr::zero())));
+    }
+}
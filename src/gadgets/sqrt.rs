@@ -0,0 +1,204 @@
+//! square root gadget
+//!
+//! [`SqrtChip::assign`] constrains a witnessed `y` to be a square root of `x`:
+//!
+//! | a | b | q_sqrt |
+//! |---|---|--------|
+//! | x | y | 1      |
+//!
+//! - `y * y = x`
+//!
+//! every non zero square has two roots `y` and `p - y`, one of them even and the other
+//! odd as `p` is odd. [`SqrtChip::canonical`] picks one by its sign, the lowest bit of
+//! `y`, taken from the [`CanonicalChip`] so that `y + p` can not flip it.
+
+use crate::gadgets::bits::{CanonicalChip, CanonicalConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SqrtConfig {
+    // [a, b, c]
+    pub advice: [Column<Advice>; 3],
+    pub q_sqrt: Selector,
+    pub canonical: CanonicalConfig,
+}
+
+pub struct SqrtChip<F: FieldExt> {
+    config: SqrtConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SqrtChip<F> {
+    pub fn construct(config: SqrtConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, advice: [Column<Advice>; 3]) -> SqrtConfig {
+        let [col_a, col_b, _] = advice;
+        let q_sqrt = meta.selector();
+        let canonical = CanonicalChip::configure(meta, advice);
+
+        meta.create_gate("sqrt", |meta| {
+            let q_sqrt = meta.query_selector(q_sqrt);
+            let x = meta.query_advice(col_a, Rotation::cur());
+            let y = meta.query_advice(col_b, Rotation::cur());
+            vec![q_sqrt * (y.clone() * y - x)]
+        });
+
+        SqrtConfig {
+            advice,
+            q_sqrt,
+            canonical,
+        }
+    }
+
+    /// constrain `y` to be a square root of `x`, the assigned `y` is returned
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "sqrt",
+            |mut region| {
+                self.config.q_sqrt.enable(&mut region, 0)?;
+                x.copy_advice(|| "x", &mut region, self.config.advice[0], 0)?;
+                region.assign_advice(|| "y", self.config.advice[1], 0, || y)
+            },
+        )
+    }
+
+    /// a square root of `x` with the lowest bit `sign`, the root is 0 if there is none
+    pub fn sqrt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        sign: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let y = x.value().zip(sign.value()).map(|(x, sign)| {
+            let y = Option::<F>::from(x.sqrt()).unwrap_or_else(F::zero);
+            if F::from(parity(&y)) == *sign {
+                y
+            } else {
+                -y
+            }
+        });
+        let y = self.assign(layouter.namespace(|| "root"), x, y)?;
+        self.canonical(layouter.namespace(|| "sign"), &y, sign)?;
+        Ok(y)
+    }
+
+    /// constrain the lowest bit of `y` to be `sign`
+    pub fn canonical(
+        &self,
+        mut layouter: impl Layouter<F>,
+        y: &AssignedCell<F, F>,
+        sign: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        let bits = CanonicalChip::construct(self.config.canonical)
+            .decompose(layouter.namespace(|| "bits"), y)?;
+        layouter.assign_region(
+            || "sign",
+            |mut region| region.constrain_equal(bits[0].cell(), sign.cell()),
+        )
+    }
+}
+
+/// the lowest bit of `y`
+pub fn parity<F: FieldExt>(y: &F) -> u64 {
+    u64::from(y.to_repr().as_ref()[0] & 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        arithmetic::Field, circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit {
+        x: Fr,
+        sign: u64,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = SqrtConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> SqrtConfig {
+            let advice = [(); 3].map(|_| meta.advice_column());
+            SqrtChip::configure(meta, advice)
+        }
+
+        fn synthesize(
+            &self,
+            config: SqrtConfig,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let (x, sign) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let x = region.assign_advice(
+                        || "x",
+                        config.advice[0],
+                        0,
+                        || Value::known(self.x),
+                    )?;
+                    let sign = region.assign_advice(
+                        || "sign",
+                        config.advice[1],
+                        0,
+                        || Value::known(Fr::from(self.sign)),
+                    )?;
+                    Ok((x, sign))
+                },
+            )?;
+            let y = SqrtChip::construct(config).sqrt(layouter.namespace(|| "sqrt"), &x, &sign)?;
+            y.value()
+                .assert_if_known(|y| **y * **y == self.x || bool::from(self.x.sqrt().is_none()));
+            Ok(())
+        }
+    }
+
+    fn prover(x: Fr, sign: u64) -> MockProver<Fr> {
+        MockProver::run(10, &TestCircuit { x, sign }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn both_roots() {
+        for x in [1, 9, 12345 * 12345, 1 << 40] {
+            for sign in [0, 1] {
+                prover(Fr::from(x), sign).assert_satisfied();
+            }
+        }
+        prover(Fr::zero(), 0).assert_satisfied();
+    }
+
+    #[test]
+    fn zero_has_no_odd_root() {
+        testing::assert_fails_permutation(&prover(Fr::zero(), 1));
+    }
+
+    #[test]
+    fn non_square() {
+        // the multiplicative generator is not a square
+        testing::assert_fails_gate(&prover(Fr::from(7), 0), "sqrt", 0);
+    }
+}
@@ -0,0 +1,413 @@
+//! machine integer gadget
+//!
+//! `N = 8 * LIMBS` bit unsigned integers, [`U64Chip`] and [`U128Chip`] for the usual
+//! widths. every value the chip touches takes a row of its own, decomposed into little
+//! endian bytes which are looked up in a table of `0..256`:
+//!
+//! | value | limb[0] | ... | limb[LIMBS-1] | aux   |
+//! |-------|---------|-----|---------------|-------|
+//! | a     | a & 255 | ... | a >> (N - 8)  | carry |
+//!
+//! - `add`: `a + b = sum + carry * 2^N` with a boolean carry, on rows `a, b, sum`
+//! - `mul`: `a * b = lo + hi * 2^N` on rows `a, b, lo, hi, c`. the full product of two
+//!   128 bit integers does not fit in the field, so with the halves `a = a0 + a1 * 2^H`,
+//!   `b = b0 + b1 * 2^H`, `H = N / 2`:
+//!   - `a0 * b0 + (a0 * b1 + a1 * b0) * 2^H = lo + c * 2^N`
+//!   - `a1 * b1 + c = hi`
+//!
+//!   where `c < 2^(H + 8)` only has its low `LIMBS / 2 + 1` limbs set, so neither side
+//!   wraps around.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn, VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub struct UintConfig<const LIMBS: usize> {
+    pub value: Column<Advice>,
+    // little endian bytes of value
+    pub limbs: [Column<Advice>; LIMBS],
+    // the carry of `add`
+    pub aux: Column<Advice>,
+    // every row holding a value
+    pub q_decompose: Selector,
+    pub q_add: Selector,
+    pub q_mul: Selector,
+    pub u8_table: TableColumn,
+}
+
+pub struct UintChip<F: FieldExt, const LIMBS: usize> {
+    config: UintConfig<LIMBS>,
+    _marker: PhantomData<F>,
+}
+
+pub type U64Chip<F> = UintChip<F, 8>;
+pub type U128Chip<F> = UintChip<F, 16>;
+
+impl<F: FieldExt, const LIMBS: usize> UintChip<F, LIMBS> {
+    /// bits of the integers
+    pub const BITS: usize = 8 * LIMBS;
+
+    pub fn construct(config: UintConfig<LIMBS>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> UintConfig<LIMBS> {
+        assert!(
+            LIMBS % 2 == 0 && LIMBS <= 16,
+            "up to 128 bits, in an even number of bytes"
+        );
+        let value = meta.advice_column();
+        let limbs = [(); LIMBS].map(|_| meta.advice_column());
+        let aux = meta.advice_column();
+        let q_decompose = meta.complex_selector();
+        let q_add = meta.selector();
+        let q_mul = meta.selector();
+        let u8_table = meta.lookup_table_column();
+        meta.enable_equality(value);
+        meta.enable_equality(aux);
+
+        let shift = |bits: usize| Expression::Constant(pow2::<F>(bits));
+        // sum of `limbs[range]` at `rotation`, shifted down to the first of them
+        let recompose =
+            |meta: &mut VirtualCells<'_, F>, range: std::ops::Range<usize>, rotation: i32| {
+                let first = range.start;
+                range.fold(Expression::Constant(F::zero()), |acc, i| {
+                    acc + meta.query_advice(limbs[i], Rotation(rotation)) * shift(8 * (i - first))
+                })
+            };
+
+        meta.create_gate("uint recompose", |meta| {
+            let q_decompose = meta.query_selector(q_decompose);
+            let value = meta.query_advice(value, Rotation::cur());
+            vec![q_decompose * (value - recompose(meta, 0..LIMBS, 0))]
+        });
+
+        for limb in limbs {
+            meta.lookup(|meta| {
+                let q_decompose = meta.query_selector(q_decompose);
+                let limb = meta.query_advice(limb, Rotation::cur());
+                vec![(q_decompose * limb, u8_table)]
+            });
+        }
+
+        meta.create_gate("uint add", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let a = meta.query_advice(value, Rotation::cur());
+            let b = meta.query_advice(value, Rotation::next());
+            let sum = meta.query_advice(value, Rotation(2));
+            let carry = meta.query_advice(aux, Rotation::cur());
+            vec![
+                q_add.clone() * carry.clone() * (Expression::Constant(F::one()) - carry.clone()),
+                q_add * (a + b - sum - carry * shift(Self::BITS)),
+            ]
+        });
+
+        meta.create_gate("uint mul", |meta| {
+            let q_mul = meta.query_selector(q_mul);
+            let half = LIMBS / 2;
+            let a0 = recompose(meta, 0..half, 0);
+            let a1 = recompose(meta, half..LIMBS, 0);
+            let b0 = recompose(meta, 0..half, 1);
+            let b1 = recompose(meta, half..LIMBS, 1);
+            let lo = meta.query_advice(value, Rotation(2));
+            let hi = meta.query_advice(value, Rotation(3));
+            let c = meta.query_advice(value, Rotation(4));
+
+            let mut constraints = vec![
+                q_mul.clone()
+                    * (a0.clone() * b0.clone()
+                        + (a0 * b1.clone() + a1.clone() * b0) * shift(Self::BITS / 2)
+                        - lo
+                        - c.clone() * shift(Self::BITS)),
+                q_mul.clone() * (a1 * b1 + c - hi),
+            ];
+            // c < 2^(H + 8)
+            for limb in &limbs[half + 1..] {
+                constraints.push(q_mul.clone() * meta.query_advice(*limb, Rotation(4)));
+            }
+            constraints
+        });
+
+        UintConfig {
+            value,
+            limbs,
+            aux,
+            q_decompose,
+            q_add,
+            q_mul,
+            u8_table,
+        }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "u8",
+            |mut table| {
+                for byte in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        self.config.u8_table,
+                        byte,
+                        || Value::known(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// assign a new value at `offset` and decompose it
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign_limbs(region, offset, value)?;
+        region.assign_advice(|| "value", self.config.value, offset, || value)
+    }
+
+    /// `(a + b mod 2^N, carry)`
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let sum = to_u128(a).zip(to_u128(b)).map(|(a, b)| {
+            let (sum, overflow) = a.overflowing_add(b);
+            if Self::BITS == 128 {
+                (sum, overflow)
+            } else {
+                (sum & mask(Self::BITS), sum >> Self::BITS != 0)
+            }
+        });
+
+        layouter.assign_region(
+            || "uint add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, a)?;
+                self.copy(&mut region, 1, b)?;
+                let result = self.assign(&mut region, 2, sum.map(|(sum, _)| F::from_u128(sum)))?;
+                let carry = region.assign_advice(
+                    || "carry",
+                    self.config.aux,
+                    0,
+                    || sum.map(|(_, carry)| F::from(carry as u64)),
+                )?;
+                Ok((result, carry))
+            },
+        )
+    }
+
+    /// `(a * b mod 2^N, a * b >> N)`
+    pub fn mul(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let product = to_u128(a)
+            .zip(to_u128(b))
+            .map(|(a, b)| mul_wide(a, b, Self::BITS));
+
+        layouter.assign_region(
+            || "uint mul",
+            |mut region| {
+                self.config.q_mul.enable(&mut region, 0)?;
+                self.copy(&mut region, 0, a)?;
+                self.copy(&mut region, 1, b)?;
+                let lo = self.assign(&mut region, 2, product.map(|(lo, _, _)| F::from_u128(lo)))?;
+                let hi = self.assign(&mut region, 3, product.map(|(_, hi, _)| F::from_u128(hi)))?;
+                self.assign(&mut region, 4, product.map(|(_, _, c)| F::from_u128(c)))?;
+                Ok((lo, hi))
+            },
+        )
+    }
+
+    /// check every cell in `cells` fits in `N` bits, by copying them to rows of their own
+    pub fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "uint range check",
+            |mut region| {
+                for (offset, cell) in cells.iter().enumerate() {
+                    self.copy(&mut region, offset, cell)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// copy `cell` to `offset` and decompose it, which range checks it
+    fn copy(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.assign_limbs(region, offset, cell.value().copied())?;
+        cell.copy_advice(|| "value", region, self.config.value, offset)
+    }
+
+    fn assign_limbs(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<(), Error> {
+        self.config.q_decompose.enable(region, offset)?;
+        let repr = value.map(|value| value.to_repr());
+        for (i, column) in self.config.limbs.iter().enumerate() {
+            region.assign_advice(
+                || format!("limb {}", i),
+                *column,
+                offset,
+                || repr.as_ref().map(|repr| F::from(repr.as_ref()[i] as u64)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `2^bits`
+fn pow2<F: FieldExt>(bits: usize) -> F {
+    (0..bits).fold(F::one(), |acc, _| acc.double())
+}
+
+/// the low `bits` bits set
+fn mask(bits: usize) -> u128 {
+    u128::MAX >> (128 - bits)
+}
+
+fn to_u128<F: FieldExt>(cell: &AssignedCell<F, F>) -> Value<u128> {
+    cell.value().map(|value| value.get_lower_128())
+}
+
+/// `(lo, hi, c)` of `a * b` for `bits` bit integers, see the module docs for `c`
+fn mul_wide(a: u128, b: u128, bits: usize) -> (u128, u128, u128) {
+    let half = bits / 2;
+    let (a0, a1) = (a & mask(half), a >> half);
+    let (b0, b1) = (b & mask(half), b >> half);
+    let (p0, p1, p2, p3) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (p0 >> half) + (p1 & mask(half)) + (p2 & mask(half));
+    let lo = (p0 & mask(half)) | ((mid & mask(half)) << half);
+    let c = (p1 >> half) + (p2 >> half) + (mid >> half);
+    (lo, p3 + c, c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit<const LIMBS: usize> {
+        a: u128,
+        b: u128,
+    }
+
+    impl<const LIMBS: usize> Circuit<Fr> for TestCircuit<LIMBS> {
+        type Config = UintConfig<LIMBS>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> UintConfig<LIMBS> {
+            UintChip::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: UintConfig<LIMBS>,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = UintChip::<Fr, LIMBS>::construct(config);
+            chip.load_table(layouter.namespace(|| "u8"))?;
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = chip.assign(&mut region, 0, Value::known(Fr::from_u128(self.a)))?;
+                    let b = chip.assign(&mut region, 1, Value::known(Fr::from_u128(self.b)))?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let bits = UintChip::<Fr, LIMBS>::BITS;
+            let (sum, carry) = chip.add(layouter.namespace(|| "add"), &a, &b)?;
+            let (wrapped, overflow) = self.a.overflowing_add(self.b);
+            let (expected_sum, expected_carry) = if bits == 128 {
+                (wrapped, overflow)
+            } else {
+                (wrapped & mask(bits), wrapped >> bits != 0)
+            };
+            sum.value()
+                .assert_if_known(|sum| **sum == Fr::from_u128(expected_sum));
+            carry
+                .value()
+                .assert_if_known(|carry| **carry == Fr::from(expected_carry as u64));
+
+            let (lo, hi) = chip.mul(layouter.namespace(|| "mul"), &a, &b)?;
+            let (expected_lo, expected_hi, _) = mul_wide(self.a, self.b, bits);
+            lo.value()
+                .assert_if_known(|lo| **lo == Fr::from_u128(expected_lo));
+            hi.value()
+                .assert_if_known(|hi| **hi == Fr::from_u128(expected_hi));
+            Ok(())
+        }
+    }
+
+    fn prover<const LIMBS: usize>(a: u128, b: u128) -> MockProver<Fr> {
+        MockProver::run(9, &TestCircuit::<LIMBS> { a, b }, vec![]).unwrap()
+    }
+
+    #[test]
+    fn mul_wide_matches_native() {
+        let (a, b) = (u64::MAX as u128, 0xdead_beef_u128);
+        let (lo, hi, _) = mul_wide(a, b, 64);
+        assert_eq!(lo | (hi << 64), a * b);
+        // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+        let (lo, hi, _) = mul_wide(u128::MAX, u128::MAX, 128);
+        assert_eq!((lo, hi), (1, u128::MAX - 1));
+    }
+
+    #[test]
+    fn u64() {
+        prover::<8>(0, 0).assert_satisfied();
+        prover::<8>(12345, 67890).assert_satisfied();
+        prover::<8>(u64::MAX as u128, 1).assert_satisfied();
+        prover::<8>(u64::MAX as u128, u64::MAX as u128).assert_satisfied();
+    }
+
+    #[test]
+    fn u128() {
+        prover::<16>(1 << 100, 3).assert_satisfied();
+        prover::<16>(u128::MAX, 1).assert_satisfied();
+        prover::<16>(u128::MAX, u128::MAX).assert_satisfied();
+    }
+
+    #[test]
+    fn too_wide() {
+        // the limbs of 2^64 are all zero
+        testing::assert_fails_gate(&prover::<8>(1 << 64, 1), "uint recompose", 0);
+    }
+}
@@ -0,0 +1,179 @@
+//! json encoding of field elements and instances
+//!
+//! field elements are hex strings of their big endian value, e.g. `"0x05"`,
+//! the same as their `Debug` output. instances are an array of instance columns,
+//! witnesses are objects deserialized with [`read`], e.g. for `FibCircuit`:
+//!
+//! ```json
+//! { "n_0": "0x00", "n_1": "0x01", "n": "0x05" }
+//! ```
+
+use halo2_proofs::{arithmetic::FieldExt, halo2curves::group::ff::PrimeField};
+use serde::de::DeserializeOwned;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// encode `value` as a `0x` prefixed big endian hex string
+pub fn encode<F: FieldExt>(value: &F) -> String {
+    let hex: String = value
+        .to_repr()
+        .as_ref()
+        .iter()
+        .rev()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("0x{}", hex)
+}
+
+/// decode a field element encoded by [`encode`], leading zeros may be left out
+pub fn decode<F: FieldExt>(s: &str) -> io::Result<F> {
+    let hex = s
+        .strip_prefix("0x")
+        .ok_or_else(|| invalid_data(format!("{:?} should start with 0x", s)))?;
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid_data(format!("{:?} is not a hex number", s)))?;
+
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+    if digits.is_empty() || digits.len() > bytes.len() * 2 {
+        return Err(invalid_data(format!(
+            "{:?} should have 1 to {} hex digits",
+            s,
+            bytes.len() * 2
+        )));
+    }
+    // the repr is little endian, so fill it from the last digits
+    for (byte, pair) in bytes.iter_mut().zip(digits.rchunks(2)) {
+        *byte = pair.iter().fold(0, |acc, digit| acc * 16 + digit);
+    }
+    Option::from(F::from_repr(repr))
+        .ok_or_else(|| invalid_data(format!("{:?} is not smaller than the modulus", s)))
+}
+
+/// serde support for field elements, use as `#[serde(with = "learn_halo2::json::field")]`
+pub mod field {
+    use super::{decode, encode};
+    use halo2_proofs::arithmetic::FieldExt;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<F: FieldExt, S: Serializer>(
+        value: &F,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(value))
+    }
+
+    pub fn deserialize<'de, F: FieldExt, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<F, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        decode(&s).map_err(D::Error::custom)
+    }
+}
+
+/// read a json value, e.g. a circuit witness, from `path`
+pub fn read<T: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<T> {
+    let path = path.as_ref();
+    serde_json::from_reader(BufReader::new(File::open(path)?))
+        .map_err(|err| invalid_data(format!("{}: {}", path.display(), err)))
+}
+
+/// write `instances` to `path` as json
+pub fn write_instances<F: FieldExt>(
+    path: impl AsRef<Path>,
+    instances: &[Vec<F>],
+) -> io::Result<()> {
+    let encoded: Vec<Vec<String>> = instances
+        .iter()
+        .map(|column| column.iter().map(encode).collect())
+        .collect();
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &encoded)?;
+    writer.flush()
+}
+
+/// read instances written by [`write_instances`]
+pub fn read_instances<F: FieldExt>(path: impl AsRef<Path>) -> io::Result<Vec<Vec<F>>> {
+    let encoded: Vec<Vec<String>> = read(path)?;
+    encoded
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(|(j, value)| {
+                    decode(value)
+                        .map_err(|err| invalid_data(format!("instance[{}][{}]: {}", i, j, err)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    #[test]
+    fn round_trip() {
+        for value in [
+            Fr::zero(),
+            Fr::one(),
+            Fr::from(5),
+            -Fr::one(),
+            Fr::from_u128(u128::MAX),
+        ] {
+            assert_eq!(decode::<Fr>(&encode(&value)).unwrap(), value);
+        }
+        assert_eq!(encode(&Fr::from(5)), format!("0x{:064x}", 5));
+    }
+
+    #[test]
+    fn leading_zeros_left_out() {
+        assert_eq!(decode::<Fr>("0x5").unwrap(), Fr::from(5));
+        assert_eq!(decode::<Fr>("0x05").unwrap(), Fr::from(5));
+        // an odd number of digits leaves the most significant byte with one
+        assert_eq!(decode::<Fr>("0x123").unwrap(), Fr::from(0x123));
+        assert_eq!(decode::<Fr>("0xabcde").unwrap(), Fr::from(0xabcde));
+    }
+
+    fn assert_rejects(s: &str) {
+        let err = decode::<Fr>(s).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{}", s);
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        assert_rejects("5");
+        assert_rejects("0x");
+        assert_rejects("0x5g");
+        assert_rejects("0x-5");
+        assert_rejects(" 0x5");
+        // one digit more than the 32 bytes of the repr, odd and even
+        assert_rejects(&format!("0x1{:064x}", 0));
+        assert_rejects(&format!("0x{:066x}", 1));
+    }
+
+    #[test]
+    fn rejects_modulus() {
+        let max = encode(&-Fr::one());
+        assert_eq!(
+            max,
+            "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000"
+        );
+        assert_rejects("0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+        assert_rejects(&format!("0x{}", "f".repeat(64)));
+    }
+}
@@ -0,0 +1,31 @@
+//! verifying key export/import
+//!
+//! lets a verifier-only consumer skip keygen. the pinned halo2 version can only
+//! serialize verifying keys, proving keys still have to come from `keygen_pk`.
+
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{Circuit, VerifyingKey},
+    poly::kzg::commitment::ParamsKZG,
+};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// write `vk` to `path`
+pub fn export_vk<C: CurveAffine>(path: impl AsRef<Path>, vk: &VerifyingKey<C>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    vk.write(&mut writer)?;
+    writer.flush()
+}
+
+/// read a verifying key for circuit `C` written by [`export_vk`]
+pub fn import_vk<C: Circuit<Fr>>(
+    path: impl AsRef<Path>,
+    params: &ParamsKZG<Bn256>,
+) -> io::Result<VerifyingKey<G1Affine>> {
+    VerifyingKey::read::<_, C>(&mut BufReader::new(File::open(path)?), params)
+}
@@ -0,0 +1,94 @@
+//! circuit layout rendering
+//!
+//! renders `halo2_proofs::dev::CircuitLayout` to a PNG or, for `.svg` paths, an SVG file.
+
+use halo2_proofs::{arithmetic::FieldExt, dev::CircuitLayout, plonk::Circuit};
+use plotters::{coord::Shift, prelude::*};
+use std::{error::Error, path::Path};
+
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    /// label regions with their names
+    pub labels: bool,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            title: "Fib Layout".to_string(),
+            width: 1024,
+            height: 3096,
+            labels: false,
+        }
+    }
+}
+
+/// render the layout of `circuit` with `2^k` rows to `path`
+pub fn render<F: FieldExt, C: Circuit<F>>(
+    path: impl AsRef<Path>,
+    k: u32,
+    circuit: &C,
+    options: &LayoutOptions,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let size = (options.width, options.height);
+    if path.extension().map_or(false, |ext| ext == "svg") {
+        draw(
+            SVGBackend::new(path, size).into_drawing_area(),
+            k,
+            circuit,
+            options,
+        )
+    } else {
+        draw(
+            BitMapBackend::new(path, size).into_drawing_area(),
+            k,
+            circuit,
+            options,
+        )
+    }
+}
+
+fn draw<DB: DrawingBackend, F: FieldExt, C: Circuit<F>>(
+    root: DrawingArea<DB, Shift>,
+    k: u32,
+    circuit: &C,
+    options: &LayoutOptions,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let root = root.titled(&options.title, ("sans-serif", 60))?;
+    CircuitLayout::default()
+        .show_labels(options.labels)
+        .mark_equality_cells(true)
+        .show_equality_constraints(true)
+        .render(k, circuit, &root)?;
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fib::FibCircuit, util};
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    #[test]
+    fn plot_fibo1() {
+        let circuit: FibCircuit<Fr> = FibCircuit {
+            n: Fr::from(10),
+            n_0: Fr::from(0),
+            n_1: Fr::from(1),
+        };
+        let path =
+            std::env::temp_dir().join(format!("learn_halo2-{}-fib-layout.png", std::process::id()));
+        let k = util::min_k_for(&circuit).unwrap();
+        render(&path, k, &circuit, &LayoutOptions::default()).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
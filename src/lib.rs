@@ -0,0 +1,31 @@
+//! learn halo2 by fibonacci
+//!
+//! the circuits and chips used by the example binaries, exposed as a library
+//! so they can be embedded in other circuits.
+
+#[cfg(feature = "aggregation")]
+pub mod aggregation;
+pub mod constraints;
+pub mod cost;
+pub mod curve;
+pub mod fib;
+pub mod gadgets;
+pub mod json;
+pub mod keys;
+#[cfg(feature = "dev-graph")]
+pub mod layout;
+pub mod params;
+pub mod proof;
+pub mod prover;
+#[cfg(feature = "ipa")]
+pub mod prover_ipa;
+pub mod session;
+pub mod step;
+pub mod store;
+pub mod telemetry;
+pub mod testing;
+pub mod trace;
+pub mod util;
+#[cfg(feature = "evm")]
+pub mod verifier_gen;
+pub mod zkvm;
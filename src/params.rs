@@ -0,0 +1,44 @@
+//! KZG parameter generation and caching
+//!
+//! setup dominates runtime, so generated params are written to
+//! `~/.cache/learn_halo2/kzg_k{N}.srs` and reloaded on subsequent runs.
+
+use crate::prover;
+use halo2_proofs::{
+    halo2curves::bn256::Bn256,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+/// directory the params are cached in
+pub fn cache_dir() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_else(|| ".".into());
+    PathBuf::from(home).join(".cache").join("learn_halo2")
+}
+
+/// path of the cached params for `k`
+pub fn cache_path(k: u32) -> PathBuf {
+    cache_dir().join(format!("kzg_k{}.srs", k))
+}
+
+/// load the params for `k` from the cache, generating and caching them if missing
+pub fn load_or_setup(k: u32) -> io::Result<ParamsKZG<Bn256>> {
+    let path = cache_path(k);
+    if path.exists() {
+        return ParamsKZG::read(&mut BufReader::new(File::open(path)?));
+    }
+
+    let params = prover::setup(k);
+    fs::create_dir_all(cache_dir())?;
+    // write to a temporary file first so an interrupted run never leaves a truncated cache
+    let tmp = path.with_extension("srs.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    params.write(&mut writer)?;
+    writer.flush()?;
+    fs::rename(tmp, path)?;
+    Ok(params)
+}
@@ -0,0 +1,159 @@
+//! proof and public input serialization
+//!
+//! a proof file is laid out as (all integers are u32 little endian):
+//!
+//! - magic `LH2P` and a version byte
+//! - number of instance columns, then for each column its length and the values as field reprs
+//! - proof length and the proof bytes
+
+use halo2_proofs::{arithmetic::FieldExt, halo2curves::group::ff::PrimeField};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"LH2P";
+const VERSION: u8 = 1;
+
+/// write `proof` and the `instances` it was created for to `path`
+pub fn write_proof<F: FieldExt>(
+    path: impl AsRef<Path>,
+    proof: &[u8],
+    instances: &[&[F]],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    write_len(&mut writer, instances.len())?;
+    for column in instances {
+        write_len(&mut writer, column.len())?;
+        for value in column.iter() {
+            writer.write_all(value.to_repr().as_ref())?;
+        }
+    }
+    write_len(&mut writer, proof.len())?;
+    writer.write_all(proof)?;
+    writer.flush()
+}
+
+/// read a proof and its instances written by [`write_proof`]
+///
+/// every length is checked against what is left of the file before anything is allocated
+pub fn read_proof<F: FieldExt>(path: impl AsRef<Path>) -> io::Result<(Vec<u8>, Vec<Vec<F>>)> {
+    let bytes = std::fs::read(path)?;
+    let mut reader = bytes.as_slice();
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a proof file"));
+    }
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported proof file version {}",
+            version[0]
+        )));
+    }
+
+    // every column takes at least its own length
+    let columns = read_len(&mut reader, 4)?;
+    let mut instances = Vec::with_capacity(columns);
+    for _ in 0..columns {
+        let len = read_len(&mut reader, F::Repr::default().as_ref().len())?;
+        let mut column = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut repr = F::Repr::default();
+            reader.read_exact(repr.as_mut())?;
+            let value = Option::from(F::from_repr(repr))
+                .ok_or_else(|| invalid_data("instance value is not a field element"))?;
+            column.push(value);
+        }
+        instances.push(column);
+    }
+
+    let mut proof = vec![0u8; read_len(&mut reader, 1)?];
+    reader.read_exact(&mut proof)?;
+    Ok((proof, instances))
+}
+
+fn write_len(writer: &mut impl Write, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| invalid_data("length does not fit in u32"))?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+/// a length of items of `size` bytes each, which have to fit in the rest of `reader`
+fn read_len(reader: &mut &[u8], size: usize) -> io::Result<usize> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    match len.checked_mul(size) {
+        Some(bytes) if bytes <= reader.len() => Ok(len),
+        _ => Err(invalid_data(format!(
+            "length {} runs past the end of the file",
+            len
+        ))),
+    }
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    fn tmp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("learn_halo2-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = tmp_path("round_trip.proof");
+        let proof = vec![1u8, 2, 3, 4, 5];
+        let instances = [Fr::from(0), Fr::from(1), Fr::from(5), -Fr::from(8)];
+        let empty: [Fr; 0] = [];
+
+        write_proof(&path, &proof, &[&instances[..], &empty[..]]).unwrap();
+        let (read, read_instances) = read_proof::<Fr>(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read, proof);
+        assert_eq!(read_instances, vec![instances.to_vec(), vec![]]);
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let path = tmp_path("version.proof");
+        write_proof::<Fr>(&path, &[], &[]).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[4] = VERSION + 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let err = read_proof::<Fr>(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reject_huge_lengths() {
+        let path = tmp_path("huge.proof");
+        let header = [&MAGIC[..], &[VERSION]].concat();
+        let huge = u32::MAX.to_le_bytes();
+        // the number of columns, the length of a column and the length of the proof
+        for bytes in [
+            [&header[..], &huge].concat(),
+            [&header[..], &1u32.to_le_bytes(), &huge].concat(),
+            [&header[..], &0u32.to_le_bytes(), &huge, &[0; 8]].concat(),
+        ] {
+            std::fs::write(&path, bytes).unwrap();
+            let err = read_proof::<Fr>(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,395 @@
+//! real proving pipeline
+//!
+//! keygen, `create_proof` and `verify_proof` with the KZG commitment scheme over bn256
+//! and a Blake2b transcript, opened with the GWC multi-open argument, or with SHPLONK
+//! by [`prove_shplonk`] and [`verify_shplonk`]. both use the same params and keys.
+//!
+//! [`ProvingBackend`] abstracts over the commitment scheme, so examples can pick
+//! [`Kzg`], [`KzgShplonk`] or (with the `ipa` feature) [`crate::prover_ipa::Ipa`] at
+//! runtime.
+//!
+//! [`cancellable`] runs keygen or proving until a [`CancelToken`] is cancelled or times
+//! out. halo2 cannot be interrupted from outside, so the token is checked between the
+//! phases and every few rows by [`crate::step::assign_rows`], through
+//! [`check_cancelled`].
+
+use crate::params;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, VerifyingKey,
+    },
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand_core::OsRng;
+use std::{
+    cell::RefCell,
+    fmt, io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::info_span;
+
+/// a commitment scheme the examples can prove with
+pub trait ProvingBackend {
+    type Scalar: FieldExt;
+    type Curve: CurveAffine<ScalarExt = Self::Scalar>;
+    type Params;
+
+    const NAME: &'static str;
+
+    /// load or generate params for `2^k` rows
+    fn setup(k: u32) -> io::Result<Self::Params>;
+
+    /// run `keygen_vk` and `keygen_pk` for `circuit`
+    fn keygen<C: Circuit<Self::Scalar>>(
+        params: &Self::Params,
+        circuit: &C,
+    ) -> Result<ProvingKey<Self::Curve>, Error>;
+
+    /// create a proof for `circuit`, `instances` holds the values of each instance column
+    fn prove<C: Circuit<Self::Scalar>>(
+        params: &Self::Params,
+        pk: &ProvingKey<Self::Curve>,
+        circuit: C,
+        instances: &[&[Self::Scalar]],
+    ) -> Result<Vec<u8>, Error>;
+
+    /// verify a proof created by [`ProvingBackend::prove`]
+    fn verify(
+        params: &Self::Params,
+        vk: &VerifyingKey<Self::Curve>,
+        proof: &[u8],
+        instances: &[&[Self::Scalar]],
+    ) -> Result<(), Error>;
+}
+
+/// KZG over bn256, params are cached by [`params::load_or_setup`]
+pub struct Kzg;
+
+impl ProvingBackend for Kzg {
+    type Scalar = Fr;
+    type Curve = G1Affine;
+    type Params = ParamsKZG<Bn256>;
+
+    const NAME: &'static str = "kzg";
+
+    fn setup(k: u32) -> io::Result<Self::Params> {
+        info_span!("setup").in_scope(|| params::load_or_setup(k))
+    }
+
+    fn keygen<C: Circuit<Fr>>(
+        params: &Self::Params,
+        circuit: &C,
+    ) -> Result<ProvingKey<G1Affine>, Error> {
+        keygen(params, circuit)
+    }
+
+    fn prove<C: Circuit<Fr>>(
+        params: &Self::Params,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[Fr]],
+    ) -> Result<Vec<u8>, Error> {
+        prove(params, pk, circuit, instances)
+    }
+
+    fn verify(
+        params: &Self::Params,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instances: &[&[Fr]],
+    ) -> Result<(), Error> {
+        verify(params, vk, proof, instances)
+    }
+}
+
+/// KZG over bn256 opened with SHPLONK, smaller proofs than [`Kzg`] for more prover work
+pub struct KzgShplonk;
+
+impl ProvingBackend for KzgShplonk {
+    type Scalar = Fr;
+    type Curve = G1Affine;
+    type Params = ParamsKZG<Bn256>;
+
+    const NAME: &'static str = "kzg-shplonk";
+
+    fn setup(k: u32) -> io::Result<Self::Params> {
+        Kzg::setup(k)
+    }
+
+    fn keygen<C: Circuit<Fr>>(
+        params: &Self::Params,
+        circuit: &C,
+    ) -> Result<ProvingKey<G1Affine>, Error> {
+        keygen(params, circuit)
+    }
+
+    fn prove<C: Circuit<Fr>>(
+        params: &Self::Params,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        instances: &[&[Fr]],
+    ) -> Result<Vec<u8>, Error> {
+        prove_shplonk(params, pk, circuit, instances)
+    }
+
+    fn verify(
+        params: &Self::Params,
+        vk: &VerifyingKey<G1Affine>,
+        proof: &[u8],
+        instances: &[&[Fr]],
+    ) -> Result<(), Error> {
+        verify_shplonk(params, vk, proof, instances)
+    }
+}
+
+/// generate (unsafe, random toxic waste) KZG params for `2^k` rows
+pub fn setup(k: u32) -> ParamsKZG<Bn256> {
+    ParamsKZG::<Bn256>::setup(k, OsRng)
+}
+
+/// run `keygen_vk` and `keygen_pk` for `circuit`
+pub fn keygen<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    circuit: &C,
+) -> Result<ProvingKey<G1Affine>, Error> {
+    let vk = info_span!("keygen_vk").in_scope(|| keygen_vk(params, circuit))?;
+    check_cancelled()?;
+    info_span!("keygen_pk").in_scope(|| keygen_pk(params, vk, circuit))
+}
+
+/// create a proof for `circuit`, `instances` holds the values of each instance column
+pub fn prove<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[&[Fr]],
+) -> Result<Vec<u8>, Error> {
+    let _span = info_span!("create_proof").entered();
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverGWC<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        _,
+    >(params, pk, &[circuit], &[instances], OsRng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// verify a proof created by [`prove`]
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instances: &[&[Fr]],
+) -> Result<(), Error> {
+    let _span = info_span!("verify_proof").entered();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierGWC<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        vk,
+        strategy,
+        &[instances],
+        &mut transcript,
+    )?;
+    Ok(())
+}
+
+/// [`prove`] with the SHPLONK multi-open argument
+pub fn prove_shplonk<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[&[Fr]],
+) -> Result<Vec<u8>, Error> {
+    let _span = info_span!("create_proof").entered();
+    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        KZGCommitmentScheme<Bn256>,
+        ProverSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        _,
+        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+        _,
+    >(params, pk, &[circuit], &[instances], OsRng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// verify a proof created by [`prove_shplonk`]
+pub fn verify_shplonk(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    instances: &[&[Fr]],
+) -> Result<(), Error> {
+    let _span = info_span!("verify_proof").entered();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(proof);
+    verify_proof::<
+        KZGCommitmentScheme<Bn256>,
+        VerifierSHPLONK<'_, Bn256>,
+        Challenge255<G1Affine>,
+        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        SingleStrategy<'_, Bn256>,
+    >(
+        params.verifier_params(),
+        vk,
+        strategy,
+        &[instances],
+        &mut transcript,
+    )?;
+    Ok(())
+}
+
+/// why [`cancellable`] gave up, or the error of the proving system
+#[derive(Debug)]
+pub enum ProveError {
+    Cancelled,
+    TimedOut,
+    Plonk(Error),
+}
+
+impl From<Error> for ProveError {
+    fn from(err: Error) -> Self {
+        Self::Plonk(err)
+    }
+}
+
+impl fmt::Display for ProveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "proving was cancelled"),
+            Self::TimedOut => write!(f, "proving timed out"),
+            Self::Plonk(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
+/// cooperative cancellation of a proving job, clones cancel together
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// also give up once `timeout` from now has passed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Some(Instant::now() + timeout);
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// [`ProveError::Cancelled`] or [`ProveError::TimedOut`] once the job should stop
+    pub fn check(&self) -> Result<(), ProveError> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(ProveError::Cancelled);
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(ProveError::TimedOut),
+            _ => Ok(()),
+        }
+    }
+}
+
+thread_local! {
+    /// token of the innermost [`cancellable`] call on this thread
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// [`Error::Synthesis`] if the token of the enclosing [`cancellable`] call says to stop,
+/// for circuits to check in long loops
+pub fn check_cancelled() -> Result<(), Error> {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(token) if token.check().is_err() => Err(Error::Synthesis),
+        _ => Ok(()),
+    })
+}
+
+/// run `job` (keygen or proving on this thread) until `cancel` says to stop, the error
+/// halo2 gives up with is replaced by why it was stopped
+pub fn cancellable<T>(
+    cancel: &CancelToken,
+    job: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, ProveError> {
+    cancel.check()?;
+    let outer = CURRENT.with(|current| current.replace(Some(cancel.clone())));
+    let result = job();
+    CURRENT.with(|current| *current.borrow_mut() = outer);
+    result.or_else(|err| {
+        cancel.check()?;
+        Err(err.into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::FibCircuit;
+    use halo2_proofs::dev::MockProver;
+
+    fn synthesize(cancel: &CancelToken) -> Result<(), ProveError> {
+        let circuit = FibCircuit::<Fr, 100> {
+            n: Fr::from(5),
+            n_0: Fr::zero(),
+            n_1: Fr::one(),
+        };
+        let k = FibCircuit::<Fr, 100>::k();
+        cancellable(cancel, || {
+            MockProver::run(k, &circuit, vec![vec![]]).map(|_| ())
+        })
+    }
+
+    #[test]
+    fn stops_synthesis() {
+        let cancel = CancelToken::new();
+        assert!(synthesize(&cancel).is_ok());
+
+        let timed_out = CancelToken::new().with_timeout(Duration::ZERO);
+        assert!(matches!(synthesize(&timed_out), Err(ProveError::TimedOut)));
+
+        // cancelled while assigning the rows
+        let circuit = FibCircuit::<Fr, 100>::default();
+        let k = FibCircuit::<Fr, 100>::k();
+        let result = cancellable(&cancel, || {
+            cancel.cancel();
+            MockProver::run(k, &circuit, vec![vec![]]).map(|_| ())
+        });
+        assert!(matches!(result, Err(ProveError::Cancelled)));
+        // outside of cancellable nothing is checked
+        assert!(check_cancelled().is_ok());
+    }
+}
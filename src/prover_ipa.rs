@@ -0,0 +1,95 @@
+//! IPA proving pipeline
+//!
+//! the same pipeline as [`crate::prover`], but over the pasta curves with the IPA
+//! commitment scheme, which needs no trusted setup.
+
+use crate::prover::{self, ProvingBackend};
+use halo2_proofs::{
+    halo2curves::pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, VerifyingKey,
+    },
+    poly::{
+        commitment::ParamsProver,
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand_core::OsRng;
+use std::io;
+use tracing::info_span;
+
+/// IPA over the pasta curves
+pub struct Ipa;
+
+impl ProvingBackend for Ipa {
+    type Scalar = Fp;
+    type Curve = EqAffine;
+    type Params = ParamsIPA<EqAffine>;
+
+    const NAME: &'static str = "ipa";
+
+    fn setup(k: u32) -> io::Result<Self::Params> {
+        // transparent setup, cheap enough to not need a cache
+        Ok(info_span!("setup").in_scope(|| ParamsIPA::new(k)))
+    }
+
+    fn keygen<C: Circuit<Fp>>(
+        params: &Self::Params,
+        circuit: &C,
+    ) -> Result<ProvingKey<EqAffine>, Error> {
+        let vk = info_span!("keygen_vk").in_scope(|| keygen_vk(params, circuit))?;
+        prover::check_cancelled()?;
+        info_span!("keygen_pk").in_scope(|| keygen_pk(params, vk, circuit))
+    }
+
+    fn prove<C: Circuit<Fp>>(
+        params: &Self::Params,
+        pk: &ProvingKey<EqAffine>,
+        circuit: C,
+        instances: &[&[Fp]],
+    ) -> Result<Vec<u8>, Error> {
+        let _span = info_span!("create_proof").entered();
+        let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+        create_proof::<
+            IPACommitmentScheme<EqAffine>,
+            ProverIPA<'_, EqAffine>,
+            Challenge255<EqAffine>,
+            _,
+            Blake2bWrite<Vec<u8>, EqAffine, Challenge255<EqAffine>>,
+            _,
+        >(params, pk, &[circuit], &[instances], OsRng, &mut transcript)?;
+        Ok(transcript.finalize())
+    }
+
+    fn verify(
+        params: &Self::Params,
+        vk: &VerifyingKey<EqAffine>,
+        proof: &[u8],
+        instances: &[&[Fp]],
+    ) -> Result<(), Error> {
+        let _span = info_span!("verify_proof").entered();
+        let strategy = SingleStrategy::new(params);
+        let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+        verify_proof::<
+            IPACommitmentScheme<EqAffine>,
+            VerifierIPA<'_, EqAffine>,
+            Challenge255<EqAffine>,
+            Blake2bRead<&[u8], EqAffine, Challenge255<EqAffine>>,
+            SingleStrategy<'_, EqAffine>,
+        >(
+            params.verifier_params(),
+            vk,
+            strategy,
+            &[instances],
+            &mut transcript,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+//! reusable fibonacci prover
+//!
+//! [`ProverSession::new`] runs keygen once, then [`ProverSession::prove`] proves any `n`
+//! the circuit fits with the same params and proving key. clones share them, so a session
+//! can be handed to every thread of a server instead of redoing keygen per request.
+//!
+//! the session proves with any [`ProvingBackend`], [`Kzg`] by default.
+//! [`ProverSession::with_observer`] reports the progress of every proof to a
+//! [`ProgressObserver`].
+
+use crate::{
+    fib::{self, FibCircuit, PublicInputs, DEFAULT_MAX_N},
+    prover::{Kzg, ProvingBackend},
+    telemetry::{ProgressLayer, ProgressObserver},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    halo2curves::bn256::Fr,
+    plonk::{Error, ProvingKey, VerifyingKey},
+};
+use std::sync::Arc;
+use tracing_subscriber::prelude::*;
+
+/// a proof of fib(n), with the instances it was created for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof<F = Fr> {
+    pub proof: Vec<u8>,
+    pub instances: Vec<Vec<F>>,
+}
+
+/// params and proving key of [`FibCircuit`], shared by every clone of the session
+pub struct ProverSession<B: ProvingBackend = Kzg> {
+    params: Arc<B::Params>,
+    pk: Arc<ProvingKey<B::Curve>>,
+    observer: Option<Arc<dyn ProgressObserver>>,
+}
+
+impl<B: ProvingBackend> Clone for ProverSession<B> {
+    fn clone(&self) -> Self {
+        Self {
+            params: self.params.clone(),
+            pk: self.pk.clone(),
+            observer: self.observer.clone(),
+        }
+    }
+}
+
+impl<B: ProvingBackend> ProverSession<B> {
+    /// run keygen with `params` for [`FibCircuit::k`] rows, e.g. from
+    /// [`ProvingBackend::setup`]
+    pub fn new(params: B::Params) -> Result<Self, Error> {
+        let pk = B::keygen(&params, &FibCircuit::<B::Scalar>::default())?;
+        Ok(Self {
+            params: Arc::new(params),
+            pk: Arc::new(pk),
+            observer: None,
+        })
+    }
+
+    /// report the phases and assigned rows of every proof to `observer`, which replaces the
+    /// global subscriber while proving
+    pub fn with_observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<B::Curve> {
+        self.pk.get_vk()
+    }
+
+    /// prove fib(n) with fib(0) = 0 and fib(1) = 1, [`Error::Synthesis`] if `n` does not
+    /// fit in the circuit
+    pub fn prove(&self, n: u64) -> Result<Proof<B::Scalar>, Error> {
+        self.prove_circuit(FibCircuit {
+            n: B::Scalar::from(n),
+            n_0: B::Scalar::zero(),
+            n_1: B::Scalar::one(),
+        })
+    }
+
+    /// prove `circuit` for the fib(n) it computes from its own fib(0) and fib(1),
+    /// [`Error::Synthesis`] if its `n` does not fit in the circuit
+    pub fn prove_circuit(&self, circuit: FibCircuit<B::Scalar>) -> Result<Proof<B::Scalar>, Error> {
+        let n = circuit.n.get_lower_128();
+        if circuit.n != B::Scalar::from_u128(n) || n >= DEFAULT_MAX_N as u128 {
+            return Err(Error::Synthesis);
+        }
+        let instances = PublicInputs::new()
+            .with_initials(circuit.n_0, circuit.n_1)
+            .with_n(circuit.n)
+            .with_result(fib::expected(circuit.n_0, circuit.n_1, n as u64))
+            .to_instance_columns();
+        let columns: Vec<&[B::Scalar]> = instances.iter().map(Vec::as_slice).collect();
+        let prove = || B::prove(&self.params, &self.pk, circuit, &columns);
+        let proof = match &self.observer {
+            Some(observer) => {
+                let layer = ProgressLayer(observer.clone());
+                tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), prove)
+            }
+            None => prove(),
+        }?;
+        Ok(Proof { proof, instances })
+    }
+
+    /// verify a proof created by [`ProverSession::prove`]
+    pub fn verify(&self, proof: &Proof<B::Scalar>) -> Result<(), Error> {
+        let columns: Vec<&[B::Scalar]> = proof.instances.iter().map(Vec::as_slice).collect();
+        B::verify(&self.params, self.vk(), &proof.proof, &columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::{self, KzgShplonk};
+    use std::thread;
+
+    #[test]
+    fn proves_from_many_threads() {
+        let session = ProverSession::<Kzg>::new(prover::setup(FibCircuit::<Fr>::k())).unwrap();
+        let handles: Vec<_> = [1, 5, 42]
+            .into_iter()
+            .map(|n| {
+                let session = session.clone();
+                thread::spawn(move || (n, session.prove(n).unwrap()))
+            })
+            .collect();
+        for handle in handles {
+            let (n, proof) = handle.join().unwrap();
+            assert_eq!(proof.instances[0][2], Fr::from(n));
+            session.verify(&proof).unwrap();
+        }
+
+        // a proof of fib(5) claimed for another result
+        let mut wrong = session.prove(5).unwrap();
+        wrong.instances[0][3] += Fr::one();
+        assert!(session.verify(&wrong).is_err());
+
+        assert!(matches!(
+            session.prove(DEFAULT_MAX_N as u64),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    fn proves_custom_initials_with_shplonk() {
+        let session =
+            ProverSession::<KzgShplonk>::new(prover::setup(FibCircuit::<Fr>::k())).unwrap();
+        let circuit = FibCircuit {
+            n: Fr::from(10),
+            n_0: Fr::from(2),
+            n_1: Fr::from(3),
+        };
+        let proof = session.prove_circuit(circuit).unwrap();
+        assert_eq!(
+            proof.instances[0][3],
+            fib::expected(Fr::from(2), Fr::from(3), 10)
+        );
+        session.verify(&proof).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+//! step based chips
+//!
+//! sequence circuits assign one row per step, each row computed from the one before.
+//! a [`StepChip`] describes the rows, [`synthesize`] drives it over a single region and
+//! [`assign_rows`] leaves exposing the cells to the caller.
+
+use crate::{prover, telemetry::ROWS_TARGET};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region},
+    plonk::{ConstraintSystem, Error},
+};
+use tracing::{info_span, trace};
+
+/// rows assigned between two progress events and cancellation checks of [`assign_rows`]
+const PROGRESS_ROWS: usize = 64;
+
+pub trait StepChip<F: FieldExt>: Sized {
+    type Config: Clone;
+    /// witness needed to assign the first row
+    type Input;
+    /// the cells assigned in one row
+    type Row;
+    /// the cells to be exposed as public inputs
+    type Public;
+
+    /// allocate the columns and create the gates
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    fn assign_first_row(
+        &self,
+        region: &mut Region<'_, F>,
+        input: &Self::Input,
+    ) -> Result<Self::Row, Error>;
+
+    /// assign the row at `offset + 1` from `row`, the row at `offset`
+    fn assign_step(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &Self::Row,
+    ) -> Result<Self::Row, Error>;
+
+    /// pick the cells to expose from all assigned rows
+    fn finalize(&self, rows: &[Self::Row]) -> Self::Public;
+
+    fn expose_public(&self, layouter: impl Layouter<F>, public: Self::Public) -> Result<(), Error>;
+}
+
+/// assign `rows` rows of `chip` starting from `input` and expose the public cells
+pub fn synthesize<F: FieldExt, S: StepChip<F>>(
+    chip: &S,
+    mut layouter: impl Layouter<F>,
+    input: &S::Input,
+    rows: usize,
+) -> Result<(), Error> {
+    let _span = info_span!("synthesize").entered();
+    let public = assign_rows(chip, layouter.namespace(|| "rows"), input, rows)?;
+    chip.expose_public(layouter.namespace(|| "expose public"), public)
+}
+
+/// assign `rows` rows of `chip` starting from `input` in a region of their own, returning the
+/// cells to expose, so several runs can share one circuit
+pub fn assign_rows<F: FieldExt, S: StepChip<F>>(
+    chip: &S,
+    mut layouter: impl Layouter<F>,
+    input: &S::Input,
+    rows: usize,
+) -> Result<S::Public, Error> {
+    layouter.assign_region(
+        || "rows",
+        |mut region| {
+            let _span = info_span!("assign_rows").entered();
+            let mut assigned = Vec::with_capacity(rows);
+            assigned.push(chip.assign_first_row(&mut region, input)?);
+            for offset in 0..rows - 1 {
+                let row = chip.assign_step(&mut region, offset, &assigned[offset])?;
+                assigned.push(row);
+                if assigned.len() % PROGRESS_ROWS == 0 {
+                    trace!(target: ROWS_TARGET, assigned = assigned.len(), total = rows);
+                    prover::check_cancelled()?;
+                }
+            }
+            trace!(target: ROWS_TARGET, assigned = rows, total = rows);
+            Ok(chip.finalize(&assigned))
+        },
+    )
+}
@@ -0,0 +1,116 @@
+//! on-disk proof store
+//!
+//! a proof only depends on the circuit, its `k` and the instances it is created for, so
+//! proving the same statement again can be skipped. [`ProofStore`] keeps one proof file per
+//! statement, named by the blake2b hash of the three and laid out as in [`crate::proof`].
+//!
+//! a stored proof is only as good as the params and keys it was created with, callers
+//! should verify it before handing it out.
+
+use crate::{params, proof};
+use blake2::{Blake2b512, Digest};
+use halo2_proofs::arithmetic::FieldExt;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+/// a directory of proofs keyed by (circuit, k, instances)
+#[derive(Debug, Clone)]
+pub struct ProofStore {
+    dir: PathBuf,
+}
+
+impl Default for ProofStore {
+    /// `proofs` in [`params::cache_dir`]
+    fn default() -> Self {
+        Self::new(params::cache_dir().join("proofs"))
+    }
+}
+
+impl ProofStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// path of the proof of `circuit` with `2^k` rows for `instances`
+    pub fn path<F: FieldExt>(&self, circuit: &str, k: u32, instances: &[&[F]]) -> PathBuf {
+        let mut hasher = Blake2b512::new();
+        // every part is length prefixed, so no two keys hash the same bytes
+        hasher.update((circuit.len() as u64).to_le_bytes());
+        hasher.update(circuit);
+        hasher.update(k.to_le_bytes());
+        hasher.update((instances.len() as u64).to_le_bytes());
+        for column in instances {
+            hasher.update((column.len() as u64).to_le_bytes());
+            for value in column.iter() {
+                hasher.update(value.to_repr());
+            }
+        }
+        let hash: String = hasher.finalize()[..16]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir.join(format!("{}.proof", hash))
+    }
+
+    /// the stored proof for the key, `None` if there is none or it was stored for other
+    /// instances
+    pub fn get<F: FieldExt>(
+        &self,
+        circuit: &str,
+        k: u32,
+        instances: &[&[F]],
+    ) -> io::Result<Option<Vec<u8>>> {
+        let (proof, stored) = match proof::read_proof::<F>(self.path(circuit, k, instances)) {
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let stored: Vec<&[F]> = stored.iter().map(Vec::as_slice).collect();
+        Ok((stored == instances).then_some(proof))
+    }
+
+    /// store `proof` for the key, replacing any previous one
+    pub fn put<F: FieldExt>(
+        &self,
+        circuit: &str,
+        k: u32,
+        instances: &[&[F]],
+        proof: &[u8],
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(circuit, k, instances);
+        // write to a temporary file first so readers never see a truncated proof
+        let tmp = path.with_extension("proof.tmp");
+        proof::write_proof(&tmp, proof, instances)?;
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    #[test]
+    fn keyed_by_circuit_k_and_instances() {
+        let dir = std::env::temp_dir().join(format!("learn_halo2-{}-store", std::process::id()));
+        let store = ProofStore::new(&dir);
+        let instances = [Fr::from(0), Fr::from(1), Fr::from(5), Fr::from(5)];
+        let other = [Fr::from(0), Fr::from(1), Fr::from(5), Fr::from(8)];
+
+        assert_eq!(store.get("fib", 9, &[&instances[..]]).unwrap(), None);
+        store.put("fib", 9, &[&instances[..]], &[1, 2, 3]).unwrap();
+        assert_eq!(
+            store.get("fib", 9, &[&instances[..]]).unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(store.get("fib", 10, &[&instances[..]]).unwrap(), None);
+        assert_eq!(store.get("fib-ipa", 9, &[&instances[..]]).unwrap(), None);
+        assert_eq!(store.get("fib", 9, &[&other[..]]).unwrap(), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
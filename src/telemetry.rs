@@ -0,0 +1,286 @@
+//! timing of the proving phases
+//!
+//! the pipeline is instrumented with `tracing` spans: `setup`, `keygen_vk`, `keygen_pk`,
+//! `create_proof` (witness commitments and openings), `verify_proof`, and inside the
+//! circuit `synthesize` and `assign_rows`. [`Timings`] is a subscriber layer summing up
+//! the time spent in each of them.
+//!
+//! halo2 synthesizes the circuit during keygen and proving alike, so `synthesize` and
+//! `assign_rows` are nested in the other phases and are counted once per call.
+//!
+//! a [`ProgressObserver`] is told about the same phases as they start and finish, and about
+//! the rows [`crate::step::assign_rows`] assigned so far, through a [`ProgressLayer`].
+//! `create_proof` covers both the witness commitments and the openings, halo2 does not
+//! split them.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    subscriber::SetGlobalDefaultError,
+    Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phase {
+    pub name: &'static str,
+    pub calls: usize,
+    pub total: Duration,
+}
+
+/// a layer recording the total time spent in each span, phases are kept in the
+/// order they were first closed
+#[derive(Debug, Clone, Default)]
+pub struct Timings(Arc<Mutex<Vec<Phase>>>);
+
+struct Started(Instant);
+
+impl Timings {
+    /// install a global subscriber recording timings and return a handle to them
+    pub fn init() -> Result<Self, SetGlobalDefaultError> {
+        let timings = Self::default();
+        tracing::subscriber::set_global_default(
+            tracing_subscriber::registry().with(timings.clone()),
+        )?;
+        Ok(timings)
+    }
+
+    pub fn phases(&self) -> Vec<Phase> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn record(&self, name: &'static str, elapsed: Duration) {
+        let mut phases = self.0.lock().unwrap();
+        match phases.iter_mut().find(|phase| phase.name == name) {
+            Some(phase) => {
+                phase.calls += 1;
+                phase.total += elapsed;
+            }
+            None => phases.push(Phase {
+                name,
+                calls: 1,
+                total: elapsed,
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for Timings
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(Started(start)) = span.extensions().get::<Started>() {
+                self.record(span.name(), start.elapsed());
+            }
+        }
+    }
+}
+
+impl ProgressObserver for Timings {
+    fn phase_finished(&self, phase: &'static str, elapsed: Duration) {
+        self.record(phase, elapsed);
+    }
+}
+
+/// target of the events reporting assigned rows, with `assigned` and `total` fields
+pub const ROWS_TARGET: &str = "learn_halo2::rows";
+
+/// callbacks on the progress of keygen and proving, all of them default to doing nothing
+pub trait ProgressObserver: Send + Sync {
+    fn phase_started(&self, _phase: &'static str) {}
+
+    fn phase_finished(&self, _phase: &'static str, _elapsed: Duration) {}
+
+    /// `assigned` of `total` rows of the current region are assigned. the floor planner
+    /// lays out a region before assigning it, so this counts up twice per synthesis
+    fn rows_assigned(&self, _assigned: usize, _total: usize) {}
+}
+
+impl<A: ProgressObserver, B: ProgressObserver> ProgressObserver for (A, B) {
+    fn phase_started(&self, phase: &'static str) {
+        self.0.phase_started(phase);
+        self.1.phase_started(phase);
+    }
+
+    fn phase_finished(&self, phase: &'static str, elapsed: Duration) {
+        self.0.phase_finished(phase, elapsed);
+        self.1.phase_finished(phase, elapsed);
+    }
+
+    fn rows_assigned(&self, assigned: usize, total: usize) {
+        self.0.rows_assigned(assigned, total);
+        self.1.rows_assigned(assigned, total);
+    }
+}
+
+impl<O: ProgressObserver + ?Sized> ProgressObserver for Arc<O> {
+    fn phase_started(&self, phase: &'static str) {
+        (**self).phase_started(phase);
+    }
+
+    fn phase_finished(&self, phase: &'static str, elapsed: Duration) {
+        (**self).phase_finished(phase, elapsed);
+    }
+
+    fn rows_assigned(&self, assigned: usize, total: usize) {
+        (**self).rows_assigned(assigned, total);
+    }
+}
+
+/// a layer forwarding the spans and row events to a [`ProgressObserver`]
+#[derive(Debug, Clone, Default)]
+pub struct ProgressLayer<O>(pub O);
+
+#[derive(Default)]
+struct Rows {
+    assigned: Option<u64>,
+    total: Option<u64>,
+}
+
+impl Visit for Rows {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "assigned" => self.assigned = Some(value),
+            "total" => self.total = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
+
+impl<S, O> Layer<S> for ProgressLayer<O>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    O: ProgressObserver + 'static,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started(Instant::now()));
+            self.0.phase_started(span.name());
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() != ROWS_TARGET {
+            return;
+        }
+        let mut rows = Rows::default();
+        event.record(&mut rows);
+        if let (Some(assigned), Some(total)) = (rows.assigned, rows.total) {
+            self.0.rows_assigned(assigned as usize, total as usize);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            if let Some(Started(start)) = span.extensions().get::<Started>() {
+                self.0.phase_finished(span.name(), start.elapsed());
+            }
+        }
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16} {:>6} {:>12}", "phase", "calls", "total (ms)")?;
+        for phase in self.phases() {
+            writeln!(
+                f,
+                "{:<16} {:>6} {:>12.3}",
+                phase.name,
+                phase.calls,
+                phase.total.as_secs_f64() * 1000.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::FibCircuit;
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use tracing::info_span;
+
+    #[test]
+    fn sums_up_spans() {
+        let timings = Timings::default();
+        let subscriber = tracing_subscriber::registry().with(timings.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..3 {
+                info_span!("keygen_vk").in_scope(|| info_span!("synthesize").in_scope(|| ()));
+            }
+            info_span!("create_proof").in_scope(|| ());
+        });
+
+        let phases: Vec<_> = timings
+            .phases()
+            .iter()
+            .map(|phase| (phase.name, phase.calls))
+            .collect();
+        assert_eq!(
+            phases,
+            [("synthesize", 3), ("keygen_vk", 3), ("create_proof", 1)]
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl ProgressObserver for Recorder {
+        fn phase_started(&self, phase: &'static str) {
+            self.0.lock().unwrap().push(format!("start {}", phase));
+        }
+
+        fn phase_finished(&self, phase: &'static str, _elapsed: Duration) {
+            self.0.lock().unwrap().push(format!("finish {}", phase));
+        }
+
+        fn rows_assigned(&self, assigned: usize, total: usize) {
+            let rows = format!("{}/{}", assigned, total);
+            self.0.lock().unwrap().push(rows);
+        }
+    }
+
+    #[test]
+    fn reports_phases_and_rows() {
+        let recorder = Arc::new(Recorder::default());
+        let subscriber = tracing_subscriber::registry().with(ProgressLayer(recorder.clone()));
+        let circuit = FibCircuit::<Fr, 100> {
+            n: Fr::from(5),
+            n_0: Fr::zero(),
+            n_1: Fr::one(),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            MockProver::run(FibCircuit::<Fr, 100>::k(), &circuit, vec![vec![]]).unwrap();
+        });
+
+        // the region is laid out, then assigned
+        let pass = [
+            "start assign_rows",
+            "64/100",
+            "100/100",
+            "finish assign_rows",
+        ];
+        let mut expected = vec!["start synthesize"];
+        expected.extend(pass);
+        expected.extend(pass);
+        expected.push("finish synthesize");
+        assert_eq!(*recorder.0.lock().unwrap(), expected);
+    }
+}
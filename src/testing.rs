@@ -0,0 +1,70 @@
+//! negative test helpers
+//!
+//! `verify().unwrap_err()` passes no matter which constraint failed, these check that
+//! the expected one did, so a test can't pass because of an unrelated mistake.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{FailureLocation, MockProver, VerifyFailure},
+};
+
+/// assert that a constraint of `gate` is not satisfied at `offset`
+pub fn assert_fails_gate<F: FieldExt>(prover: &MockProver<F>, gate: &str, offset: usize) {
+    // `metadata::Constraint` only exposes the gate name through `Display`,
+    // "Constraint 0 in gate 1 ('fib')"
+    let suffix = format!("('{}')", gate);
+    assert_fails(
+        prover,
+        &format!("gate '{}' at {}", gate, offset),
+        |failure| {
+            matches!(
+                failure,
+                VerifyFailure::ConstraintNotSatisfied { constraint, location, .. }
+                    if constraint.to_string().ends_with(&suffix) && at(location, offset)
+            )
+        },
+    );
+}
+
+/// assert that a lookup input at `offset` is not in its table
+pub fn assert_fails_lookup<F: FieldExt>(prover: &MockProver<F>, offset: usize) {
+    assert_fails(
+        prover,
+        &format!("lookup at {}", offset),
+        |failure| matches!(failure, VerifyFailure::Lookup { location, .. } if at(location, offset)),
+    );
+}
+
+/// assert that a copy constraint, e.g. to an instance, is not satisfied
+pub fn assert_fails_permutation<F: FieldExt>(prover: &MockProver<F>) {
+    assert_fails(prover, "permutation", |failure| {
+        matches!(failure, VerifyFailure::Permutation { .. })
+    });
+}
+
+fn assert_fails<F: FieldExt>(
+    prover: &MockProver<F>,
+    expected: &str,
+    pred: impl Fn(&VerifyFailure) -> bool,
+) {
+    match prover.verify() {
+        Ok(()) => panic!(
+            "expected {} to fail, but the circuit is satisfied",
+            expected
+        ),
+        Err(failures) => assert!(
+            failures.iter().any(pred),
+            "expected {} to fail, got {:#?}",
+            expected,
+            failures
+        ),
+    }
+}
+
+/// `offset` is relative to the region, rows outside of regions use the absolute row
+fn at(location: &FailureLocation, offset: usize) -> bool {
+    match location {
+        FailureLocation::InRegion { offset: o, .. } => *o == offset,
+        FailureLocation::OutsideRegion { row } => *row == offset,
+    }
+}
@@ -0,0 +1,259 @@
+//! witness trace
+//!
+//! records every `assign_advice` and `assign_fixed` made while synthesizing a circuit,
+//! without proving anything, and writes them as a table with one line per row and one
+//! column per circuit column, like the layout in README.md.
+//!
+//! values are written by [`util::display_field`]. table columns are only recorded up to
+//! their last assigned row, the padding halo2 adds after it is left out.
+
+use crate::util;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Advice,
+    Fixed,
+}
+
+/// one `assign_*` call
+#[derive(Debug, Clone)]
+pub struct Record<F> {
+    pub region: Option<String>,
+    pub kind: ColumnKind,
+    pub column: usize,
+    pub row: usize,
+    pub label: String,
+    /// `None` if the value is unknown, e.g. for `without_witnesses`
+    pub value: Option<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace<F> {
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub records: Vec<Record<F>>,
+    region: Option<String>,
+}
+
+/// synthesize `circuit` and record its assignments
+pub fn trace<F: FieldExt, C: Circuit<F>>(circuit: &C) -> Result<Trace<F>, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let mut trace = Trace {
+        advice_columns: cs.num_advice_columns(),
+        fixed_columns: cs.num_fixed_columns(),
+        records: vec![],
+        region: None,
+    };
+    C::FloorPlanner::synthesize(&mut trace, circuit, config, cs.constants().clone())?;
+    Ok(trace)
+}
+
+impl<F: FieldExt> Trace<F> {
+    /// the value assigned last to the cell, `None` if it is unassigned or unknown
+    pub fn get(&self, kind: ColumnKind, column: usize, row: usize) -> Option<F> {
+        self.records
+            .iter()
+            .rev()
+            .find(|record| record.kind == kind && record.column == column && record.row == row)
+            .and_then(|record| record.value)
+    }
+
+    /// write the table, separated by tabs for `.tsv` paths and commas otherwise
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let separator = match path.extension() {
+            Some(ext) if ext == "tsv" => '\t',
+            _ => ',',
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_table(&mut writer, separator)?;
+        writer.flush()
+    }
+
+    pub fn write_table(&self, mut writer: impl Write, separator: char) -> io::Result<()> {
+        let columns: Vec<_> = (0..self.advice_columns)
+            .map(|column| (ColumnKind::Advice, column))
+            .chain((0..self.fixed_columns).map(|column| (ColumnKind::Fixed, column)))
+            .collect();
+
+        let mut header = vec!["row".to_string()];
+        header.extend(columns.iter().map(|(kind, column)| match kind {
+            ColumnKind::Advice => format!("advice_{}", column),
+            ColumnKind::Fixed => format!("fixed_{}", column),
+        }));
+        writeln!(writer, "{}", header.join(&separator.to_string()))?;
+
+        let rows = self
+            .records
+            .iter()
+            .map(|record| record.row + 1)
+            .max()
+            .unwrap_or(0);
+        for row in 0..rows {
+            let mut line = vec![row.to_string()];
+            line.extend(columns.iter().map(|&(kind, column)| {
+                self.get(kind, column, row)
+                    .map(util::display_field)
+                    .unwrap_or_default()
+            }));
+            writeln!(writer, "{}", line.join(&separator.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn record<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        kind: ColumnKind,
+        column: usize,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let mut value = None;
+        to().into_field().evaluate().map(|v| value = Some(v));
+        self.records.push(Record {
+            region: self.region.clone(),
+            kind,
+            column,
+            row,
+            label: annotation().into(),
+            value,
+        });
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> Assignment<F> for Trace<F> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(name_fn().into());
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(annotation, ColumnKind::Advice, column.index(), row, to)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(annotation, ColumnKind::Fixed, column.index(), row, to)
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::FibCircuit;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    #[test]
+    fn fib_rows() {
+        let circuit: FibCircuit<Fr, 100> = FibCircuit {
+            n_0: Fr::zero(),
+            n_1: Fr::one(),
+            n: Fr::from(5),
+        };
+        let trace = trace(&circuit).unwrap();
+
+        let mut table = vec![];
+        trace.write_table(&mut table, ',').unwrap();
+        let table = String::from_utf8(table).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        // [n, l, r, n_inv, is_zero] and the range table
+        assert_eq!(
+            lines[0],
+            "row,advice_0,advice_1,advice_2,advice_3,advice_4,fixed_0"
+        );
+        assert_eq!(lines.len(), 1 + 100);
+        assert!(lines[1].starts_with("0,5,0,1,"));
+        assert_eq!(lines[6], "5,0,5,8,0,1,5");
+        assert_eq!(lines[100], "99,0,5,8,0,1,99");
+    }
+}
@@ -0,0 +1,91 @@
+//! helpers shared by the examples
+
+use crate::json;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+/// largest `k` [`min_k_for`] will try
+pub const MAX_K: u32 = 26;
+
+/// the smallest `k` `circuit` can be synthesized with
+///
+/// starts from the rows reserved for blinding and runs the `MockProver` with growing `k`
+/// until the circuit stops running out of rows. instances are left empty, only the
+/// sizing is checked, not whether the circuit is satisfied.
+pub fn min_k_for<F: FieldExt, C: Circuit<F>>(circuit: &C) -> Result<u32, Error> {
+    let mut cs = ConstraintSystem::default();
+    C::configure(&mut cs);
+    let instances = vec![vec![]; cs.num_instance_columns()];
+
+    let min_k = usize::BITS - cs.minimum_rows().leading_zeros();
+    for k in min_k..=MAX_K {
+        match MockProver::run(k, circuit, instances.clone()) {
+            Ok(_) => return Ok(k),
+            Err(Error::NotEnoughRowsAvailable { .. }) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(Error::NotEnoughRowsAvailable { current_k: MAX_K })
+}
+
+/// `value` in decimal if it, or its negation, fits in a `u64`, and hex otherwise
+pub fn display_field<F: FieldExt>(value: F) -> String {
+    let small = |value: F| {
+        Some(value.get_lower_128()).filter(|v| F::from_u128(*v) == value && *v <= u64::MAX as u128)
+    };
+    match (small(value), small(-value)) {
+        (Some(v), _) => v.to_string(),
+        (None, Some(v)) => format!("-{}", v),
+        (None, None) => json::encode(&value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fib::{FibCircuit, PublicInputs};
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    fn fib_circuit<const MAX_N: usize>() -> (FibCircuit<Fr, MAX_N>, Vec<Vec<Fr>>) {
+        let circuit = FibCircuit {
+            n: Fr::from(5),
+            n_0: Fr::from(0),
+            n_1: Fr::from(1),
+        };
+        let instances = PublicInputs::new()
+            .with_n(Fr::from(5))
+            .with_result(Fr::from(5))
+            .to_instance_columns();
+        (circuit, instances)
+    }
+
+    fn assert_min_k<const MAX_N: usize>() {
+        let (circuit, instances) = fib_circuit::<MAX_N>();
+        let k = min_k_for(&circuit).unwrap();
+        assert_eq!(k, FibCircuit::<Fr, MAX_N>::k());
+
+        MockProver::run(k, &circuit, instances.clone())
+            .unwrap()
+            .assert_satisfied();
+        assert!(matches!(
+            MockProver::run(k - 1, &circuit, instances),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+    }
+
+    #[test]
+    fn display_small_fields() {
+        assert_eq!(display_field(Fr::from(370)), "370");
+        assert_eq!(display_field(-Fr::one()), "-1");
+        assert!(display_field(Fr::from(2).invert().unwrap()).starts_with("0x"));
+    }
+
+    #[test]
+    fn chosen_k_proves() {
+        assert_min_k::<100>();
+        assert_min_k::<370>();
+    }
+}
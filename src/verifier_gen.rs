@@ -0,0 +1,137 @@
+//! EVM verifier generation
+//!
+//! generates a yul verifier for a circuit with [`snark_verifier`] and compiles it to
+//! deployment bytecode. the evm verifier reads the proof with a keccak transcript,
+//! so proofs for it have to be created by [`prove`] instead of [`crate::prover::prove`].
+
+use crate::prover;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
+    plonk::{create_proof, Circuit, Error, ProvingKey, VerifyingKey},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverGWC,
+    },
+    transcript::TranscriptWriterBuffer,
+};
+use rand_core::OsRng;
+use snark_verifier::{
+    loader::evm::{self, EvmLoader},
+    pcs::kzg::{Gwc19, Kzg},
+    system::halo2::{compile, transcript::evm::EvmTranscript, Config},
+    verifier::{self, PlonkVerifier},
+};
+use std::rc::Rc;
+
+type Plonk = verifier::Plonk<Kzg<Bn256, Gwc19>>;
+
+/// generate the yul source of a verifier for `vk`, `num_instance` is the length of each instance column
+pub fn gen_evm_verifier_yul(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+) -> String {
+    let svk = params.get_g()[0].into();
+    let dk = (params.g2(), params.s_g2()).into();
+    let protocol = compile(
+        params,
+        vk,
+        Config::kzg().with_num_instance(num_instance.clone()),
+    );
+
+    let loader = EvmLoader::new::<Fq, Fr>();
+    let protocol = protocol.loaded(&loader);
+    let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+
+    let instances = transcript.load_instances(num_instance);
+    let proof = Plonk::read_proof(&svk, &protocol, &instances, &mut transcript).unwrap();
+    Plonk::verify(&svk, &dk, &protocol, &instances, &proof).unwrap();
+
+    loader.yul_code()
+}
+
+/// generate the deployment bytecode of a verifier for `vk`
+pub fn gen_evm_verifier(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    num_instance: Vec<usize>,
+) -> Vec<u8> {
+    evm::compile_yul(&gen_evm_verifier_yul(params, vk, num_instance))
+}
+
+/// create a proof with the keccak transcript the evm verifier expects
+pub fn prove<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[&[Fr]],
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = TranscriptWriterBuffer::<_, G1Affine, _>::init(Vec::new());
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<_>, _, _, EvmTranscript<_, _, _, _>, _>(
+        params,
+        pk,
+        &[circuit],
+        &[instances],
+        OsRng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+/// calldata for calling the verifier with `proof` and the instance column values
+pub fn encode_calldata(instances: &[Vec<Fr>], proof: &[u8]) -> Vec<u8> {
+    evm::encode_calldata(instances, proof)
+}
+
+/// generate the verifier and a proof for `circuit`, returning (bytecode, calldata)
+pub fn gen_verifier_and_calldata<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    circuit: C,
+    instances: Vec<Vec<Fr>>,
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let pk = prover::keygen(params, &circuit)?;
+    let bytecode = gen_evm_verifier(
+        params,
+        pk.get_vk(),
+        instances.iter().map(Vec::len).collect(),
+    );
+    let columns: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let proof = prove(params, &pk, circuit, &columns)?;
+    Ok((bytecode, encode_calldata(&instances, &proof)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fib::{FibCircuit, PublicInputs},
+        params, util,
+    };
+    use snark_verifier::loader::evm::{Address, ExecutorBuilder};
+
+    #[test]
+    fn fib_verifier_on_evm() {
+        let circuit: FibCircuit<Fr> = FibCircuit {
+            n: Fr::from(5),
+            n_0: Fr::from(0),
+            n_1: Fr::from(1),
+        };
+        let params = params::load_or_setup(util::min_k_for(&circuit).unwrap()).unwrap();
+        let instances = PublicInputs::new()
+            .with_n(Fr::from(5))
+            .with_result(Fr::from(5))
+            .to_instance_columns();
+        let (bytecode, calldata) = gen_verifier_and_calldata(&params, circuit, instances).unwrap();
+
+        let mut evm = ExecutorBuilder::default()
+            .with_gas_limit(u64::MAX.into())
+            .build();
+        let caller = Address::from_low_u64_be(0xfe);
+        let verifier = evm
+            .deploy(caller, bytecode.into(), 0.into())
+            .address
+            .unwrap();
+        let result = evm.call_raw(caller, verifier, calldata.into(), 0.into());
+        assert!(!result.reverted);
+    }
+}
@@ -0,0 +1,637 @@
+//! tiny stack machine
+//!
+//! proves that running a program of [`Instruction`]s for `n` steps, from `pc = 0` and an
+//! empty stack, leaves `top` on the top of the stack. the stack is `DEPTH` columns deep,
+//! `stack[0]` being the top, a push drops the bottom and a pop shifts a 0 in.
+//!
+//! | row | n       | pc    | opcode | arg | stack          | flags       | q_step |
+//! |-----|---------|-------|--------|-----|----------------|-------------|--------|
+//! | 0   | n       | 0     | op[0]  | ... | 0, ..., 0      | decoded op  | 1      |
+//! | i   | n - i   | pc[i] | op[i]  | ... | stack[i]       | decoded op  | 1      |
+//! | T   | 0       | pc[n] |        |     | top, ...       |             | 0      |
+//!
+//! `n` counts the steps left down to 0 like the `n` of [`crate::fib`], and once it is 0 the
+//! machine is halted and the rows hold their state. `n[T]` is constrained to 0, so `n <= T`.
+//! while running, two lookups tie a row to the program:
+//!
+//! - `(pc, opcode, arg)` is in the ROM, a fixed table of the program loaded at keygen, so a
+//!   new program needs new keys but not a new circuit
+//! - `(opcode, flags)` is in the decoding table, so `flags` is the one-hot
+//!   `[is_push, is_add, is_mul, is_jump]` of the opcode
+//!
+//! both carry a tag of `q_step * (1 - is_zero(n))`, the rows not running look up the all 0
+//! first row of the tables. with the flags the gate is linear in the instructions:
+//!
+//! - `n' = n == 0 ? 0 : n - 1`
+//! - `pc' = n == 0 ? pc : (is_jump ? arg : pc + 1)`
+//! - `stack[0]' = n == 0 ? stack[0] : is_push * arg + is_add * (stack[0] + stack[1]) + is_mul * stack[0] * stack[1] + is_jump * stack[0]`
+//! - `stack[i]' = n == 0 ? stack[i] : is_push * stack[i - 1] + (is_add + is_mul) * stack[i + 1] + is_jump * stack[i]`
+
+use crate::constraints::ColumnNames;
+use crate::gadgets::{
+    is_zero::{IsZeroChip, IsZeroConfig},
+    select,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+        TableColumn, VirtualCells,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// columns of the stack
+pub const DEPTH: usize = 4;
+/// the flags of an opcode, in the order of [`Instruction::flags`]
+pub const OPCODES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Push(u64),
+    /// pop `a` and `b`, push `a + b`
+    Add,
+    /// pop `a` and `b`, push `a * b`
+    Mul,
+    Jump(u64),
+}
+
+impl Instruction {
+    /// 0 is left out, it tags the rows not running
+    pub fn opcode(&self) -> u64 {
+        match self {
+            Instruction::Push(_) => 1,
+            Instruction::Add => 2,
+            Instruction::Mul => 3,
+            Instruction::Jump(_) => 4,
+        }
+    }
+
+    pub fn arg(&self) -> u64 {
+        match self {
+            Instruction::Push(arg) | Instruction::Jump(arg) => *arg,
+            Instruction::Add | Instruction::Mul => 0,
+        }
+    }
+
+    /// `[is_push, is_add, is_mul, is_jump]`
+    pub fn flags(&self) -> [u64; OPCODES] {
+        let mut flags = [0; OPCODES];
+        flags[self.opcode() as usize - 1] = 1;
+        flags
+    }
+}
+
+/// the machine between two steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State<F> {
+    pub pc: u64,
+    pub stack: [F; DEPTH],
+}
+
+impl<F: FieldExt> State<F> {
+    pub fn new() -> Self {
+        Self {
+            pc: 0,
+            stack: [F::zero(); DEPTH],
+        }
+    }
+
+    /// run `instruction`, the one at `pc`
+    pub fn step(&self, instruction: Instruction) -> Self {
+        let mut stack = self.stack;
+        let pop = |stack: &mut [F; DEPTH]| {
+            let top = stack[0];
+            stack.rotate_left(1);
+            stack[DEPTH - 1] = F::zero();
+            top
+        };
+        let push = |stack: &mut [F; DEPTH], value: F| {
+            stack.rotate_right(1);
+            stack[0] = value;
+        };
+        let mut pc = self.pc + 1;
+        match instruction {
+            Instruction::Push(arg) => push(&mut stack, F::from(arg)),
+            Instruction::Add => {
+                let (a, b) = (pop(&mut stack), pop(&mut stack));
+                push(&mut stack, a + b);
+            }
+            Instruction::Mul => {
+                let (a, b) = (pop(&mut stack), pop(&mut stack));
+                push(&mut stack, a * b);
+            }
+            Instruction::Jump(target) => pc = target,
+        }
+        Self { pc, stack }
+    }
+}
+
+impl<F: FieldExt> Default for State<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the states before every step and after the last one, `None` when the pc leaves the program
+pub fn run<F: FieldExt>(program: &[Instruction], steps: u64) -> Option<Vec<State<F>>> {
+    let mut states = vec![State::new()];
+    for _ in 0..steps {
+        let state = states.last().unwrap();
+        let instruction = program.get(state.pc as usize)?;
+        states.push(state.step(*instruction));
+    }
+    Some(states)
+}
+
+#[derive(Debug, Clone)]
+pub struct ZkvmConfig {
+    pub n: Column<Advice>,
+    pub pc: Column<Advice>,
+    pub opcode: Column<Advice>,
+    pub arg: Column<Advice>,
+    pub stack: [Column<Advice>; DEPTH],
+    pub flags: [Column<Advice>; OPCODES],
+    // n == 0, with its n_inv column
+    pub n_is_zero: IsZeroConfig,
+    pub q_step: Selector,
+    /// tag, pc, opcode and arg of the instructions of the program
+    pub rom: [TableColumn; 4],
+    /// tag, opcode and flags of every opcode
+    pub decode: [TableColumn; 2 + OPCODES],
+    pub instance: Column<Instance>,
+}
+
+impl ZkvmConfig {
+    /// names for [`crate::constraints::dump_constraints_named`]
+    pub fn column_names(&self) -> ColumnNames {
+        let mut names = ColumnNames::new()
+            .with_advice(self.n, "n")
+            .with_advice(self.pc, "pc")
+            .with_advice(self.opcode, "opcode")
+            .with_advice(self.arg, "arg")
+            .with_advice(self.n_is_zero.value_inv, "n_inv")
+            .with_advice(self.n_is_zero.is_zero, "is_zero")
+            .with_instance(self.instance, "instance")
+            .with_selector(self.q_step, "q_step");
+        for (i, column) in self.stack.iter().enumerate() {
+            names = names.with_advice(*column, format!("stack[{}]", i));
+        }
+        for (column, name) in self
+            .flags
+            .iter()
+            .zip(["is_push", "is_add", "is_mul", "is_jump"])
+        {
+            names = names.with_advice(*column, name);
+        }
+        names
+    }
+}
+
+pub struct ZkvmChip<F: FieldExt> {
+    config: ZkvmConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ZkvmChip<F> {
+    pub fn construct(config: ZkvmConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> ZkvmConfig {
+        let n = meta.advice_column();
+        let pc = meta.advice_column();
+        let opcode = meta.advice_column();
+        let arg = meta.advice_column();
+        let stack = [(); DEPTH].map(|_| meta.advice_column());
+        let flags = [(); OPCODES].map(|_| meta.advice_column());
+        let n_inv = meta.advice_column();
+        let is_zero = meta.advice_column();
+        let q_step = meta.complex_selector();
+        let rom = [(); 4].map(|_| meta.lookup_table_column());
+        let decode = [(); 2 + OPCODES].map(|_| meta.lookup_table_column());
+        let instance = meta.instance_column();
+        let constant: Column<Fixed> = meta.fixed_column();
+        meta.enable_equality(n);
+        meta.enable_equality(pc);
+        for column in stack {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+
+        let n_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_step),
+            |meta| meta.query_advice(n, Rotation::cur()),
+            n_inv,
+            is_zero,
+        );
+        let running = |meta: &mut VirtualCells<'_, F>| {
+            let q_step = meta.query_selector(q_step);
+            q_step * (Expression::Constant(F::one()) - n_is_zero.expr(meta, Rotation::cur()))
+        };
+
+        meta.lookup(|meta| {
+            let running = running(meta);
+            let [tag, rom_pc, rom_opcode, rom_arg] = rom;
+            vec![
+                (running.clone(), tag),
+                (
+                    running.clone() * meta.query_advice(pc, Rotation::cur()),
+                    rom_pc,
+                ),
+                (
+                    running.clone() * meta.query_advice(opcode, Rotation::cur()),
+                    rom_opcode,
+                ),
+                (running * meta.query_advice(arg, Rotation::cur()), rom_arg),
+            ]
+        });
+        meta.lookup(|meta| {
+            let running = running(meta);
+            let mut lookup = vec![
+                (running.clone(), decode[0]),
+                (
+                    running.clone() * meta.query_advice(opcode, Rotation::cur()),
+                    decode[1],
+                ),
+            ];
+            for (flag, table) in flags.iter().zip(&decode[2..]) {
+                lookup.push((
+                    running.clone() * meta.query_advice(*flag, Rotation::cur()),
+                    *table,
+                ));
+            }
+            lookup
+        });
+
+        meta.create_gate("zkvm", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let halted = n_is_zero.expr(meta, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            let n_cur = meta.query_advice(n, Rotation::cur());
+            let n_next = meta.query_advice(n, Rotation::next());
+            let pc_cur = meta.query_advice(pc, Rotation::cur());
+            let pc_next = meta.query_advice(pc, Rotation::next());
+            let arg = meta.query_advice(arg, Rotation::cur());
+            let [is_push, is_add, is_mul, is_jump] =
+                flags.map(|flag| meta.query_advice(flag, Rotation::cur()));
+            let cur = stack.map(|column| meta.query_advice(column, Rotation::cur()));
+            let next = stack.map(|column| meta.query_advice(column, Rotation::next()));
+            let below = |i: usize| {
+                cur.get(i + 1)
+                    .cloned()
+                    .unwrap_or(Expression::Constant(F::zero()))
+            };
+
+            let mut constraints = vec![
+                // n' = n == 0 ? 0 : n - 1
+                next_is(
+                    &n_next,
+                    halted.clone(),
+                    Expression::Constant(F::zero()),
+                    n_cur - one.clone(),
+                ),
+                // pc' = n == 0 ? pc : (is_jump ? arg : pc + 1)
+                next_is(
+                    &pc_next,
+                    halted.clone(),
+                    pc_cur.clone(),
+                    select::expr(is_jump.clone(), arg.clone(), pc_cur + one),
+                ),
+                next_is(
+                    &next[0],
+                    halted.clone(),
+                    cur[0].clone(),
+                    is_push.clone() * arg
+                        + is_add.clone() * (cur[0].clone() + cur[1].clone())
+                        + is_mul.clone() * cur[0].clone() * cur[1].clone()
+                        + is_jump.clone() * cur[0].clone(),
+                ),
+            ];
+            for (i, next) in next.iter().enumerate().skip(1) {
+                constraints.push(next_is(
+                    next,
+                    halted.clone(),
+                    cur[i].clone(),
+                    is_push.clone() * cur[i - 1].clone()
+                        + (is_add.clone() + is_mul.clone()) * below(i)
+                        + is_jump.clone() * cur[i].clone(),
+                ));
+            }
+            constraints
+                .into_iter()
+                .map(|constraint| q_step.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        ZkvmConfig {
+            n,
+            pc,
+            opcode,
+            arg,
+            stack,
+            flags,
+            n_is_zero,
+            q_step,
+            rom,
+            decode,
+            instance,
+        }
+    }
+
+    /// the ROM of `program` and the decoding table
+    pub fn load_tables(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Instruction],
+    ) -> Result<(), Error> {
+        let rom = self.config.rom;
+        layouter.assign_table(
+            || "rom",
+            |mut table| {
+                let rows = [[0; 4]].into_iter().chain(
+                    program
+                        .iter()
+                        .enumerate()
+                        .map(|(pc, i)| [1, pc as u64, i.opcode(), i.arg()]),
+                );
+                for (row, values) in rows.enumerate() {
+                    for (column, value) in rom.iter().zip(values) {
+                        table.assign_cell(
+                            || "rom",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let decode = self.config.decode;
+        layouter.assign_table(
+            || "decode",
+            |mut table| {
+                let opcodes = [
+                    Instruction::Push(0),
+                    Instruction::Add,
+                    Instruction::Mul,
+                    Instruction::Jump(0),
+                ];
+                let rows = [[0; 2 + OPCODES]]
+                    .into_iter()
+                    .chain(opcodes.iter().map(|i| {
+                        let mut row = [0; 2 + OPCODES];
+                        row[0] = 1;
+                        row[1] = i.opcode();
+                        row[2..].copy_from_slice(&i.flags());
+                        row
+                    }));
+                for (row, values) in rows.enumerate() {
+                    for (column, value) in decode.iter().zip(values) {
+                        table.assign_cell(
+                            || "decode",
+                            *column,
+                            row,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// run `program` for `steps` of the `rows` steps of the region, `n` is copied from the
+    /// instance, the final `n` is constrained to 0 and the final top of the stack exposed
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        program: &[Instruction],
+        steps: u64,
+        rows: usize,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        // a pc out of the program stops the trace, the lookup of that row fails
+        let mut states = vec![State::<F>::new()];
+        for _ in 0..steps.min(rows as u64) {
+            let state = states.last().unwrap();
+            match program.get(state.pc as usize) {
+                Some(instruction) => states.push(state.step(*instruction)),
+                None => break,
+            }
+        }
+
+        let (n_last, top) = layouter.assign_region(
+            || "zkvm",
+            |mut region| {
+                let n =
+                    region.assign_advice_from_instance(|| "n", config.instance, 0, config.n, 0)?;
+                let mut n_last = n;
+                let mut top = None;
+                for row in 0..=rows {
+                    let state = states.get(row).or(states.last()).unwrap();
+                    if row > 0 {
+                        let n = F::from(steps.saturating_sub(row as u64));
+                        n_last = region.assign_advice(|| "n", config.n, row, || Value::known(n))?;
+                    }
+                    // the machine starts from pc = 0 and an empty stack
+                    let assign = |region: &mut Region<'_, F>, name: &str, column, value: F| {
+                        if row == 0 {
+                            region.assign_advice_from_constant(|| name, column, row, value)
+                        } else {
+                            region.assign_advice(|| name, column, row, || Value::known(value))
+                        }
+                    };
+                    assign(&mut region, "pc", config.pc, F::from(state.pc))?;
+                    for (i, column) in config.stack.iter().enumerate() {
+                        let name = format!("stack[{}]", i);
+                        let cell = assign(&mut region, &name, *column, state.stack[i])?;
+                        if i == 0 {
+                            top = Some(cell);
+                        }
+                    }
+                    if row == rows {
+                        break;
+                    }
+
+                    config.q_step.enable(&mut region, row)?;
+                    IsZeroChip::construct(config.n_is_zero).assign(
+                        &mut region,
+                        row,
+                        Value::known(F::from(steps.saturating_sub(row as u64))),
+                    )?;
+                    // halted rows, and a pc out of the program, decode to all 0
+                    let running = (row as u64) < steps;
+                    let instruction = program.get(state.pc as usize).filter(|_| running);
+                    let (opcode, arg, flags) = match instruction {
+                        Some(i) => (i.opcode(), i.arg(), i.flags()),
+                        None => (0, 0, [0; OPCODES]),
+                    };
+                    region.assign_advice(
+                        || "opcode",
+                        config.opcode,
+                        row,
+                        || Value::known(F::from(opcode)),
+                    )?;
+                    region.assign_advice(
+                        || "arg",
+                        config.arg,
+                        row,
+                        || Value::known(F::from(arg)),
+                    )?;
+                    for (column, flag) in config.flags.iter().zip(flags) {
+                        region.assign_advice(
+                            || "flag",
+                            *column,
+                            row,
+                            || Value::known(F::from(flag)),
+                        )?;
+                    }
+                }
+                Ok((n_last, top.unwrap()))
+            },
+        )?;
+
+        layouter.assign_region(
+            || "halted",
+            |mut region| region.constrain_constant(n_last.cell(), F::zero()),
+        )?;
+        layouter.constrain_instance(top.cell(), config.instance, 1)
+    }
+}
+
+/// `next = halted ? hold : run`
+fn next_is<F: FieldExt>(
+    next: &Expression<F>,
+    halted: Expression<F>,
+    hold: Expression<F>,
+    run: Expression<F>,
+) -> Expression<F> {
+    next.clone() - select::expr(halted, hold, run)
+}
+
+/// a program run for `n` steps, at most `T`, with the instance `[n, top]`
+pub struct ZkvmCircuit<F, const T: usize> {
+    pub program: Vec<Instruction>,
+    pub steps: u64,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const T: usize> ZkvmCircuit<F, T> {
+    pub fn new(program: Vec<Instruction>, steps: u64) -> Self {
+        Self {
+            program,
+            steps,
+            _marker: PhantomData,
+        }
+    }
+
+    /// the smallest `k` fitting the `T + 1` rows plus the rows reserved for blinding
+    pub fn k() -> u32 {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        let rows = T + 1 + cs.minimum_rows();
+        usize::BITS - (rows - 1).leading_zeros()
+    }
+}
+
+impl<F: FieldExt, const T: usize> Circuit<F> for ZkvmCircuit<F, T> {
+    type Config = ZkvmConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    /// the program is in the ROM, only the number of steps is a witness
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.program.clone(), 0)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ZkvmConfig {
+        ZkvmChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: ZkvmConfig, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = ZkvmChip::construct(config);
+        chip.load_tables(layouter.namespace(|| "tables"), &self.program)?;
+        chip.assign(layouter.namespace(|| "run"), &self.program, self.steps, T)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    /// 1, then doubled in a loop of 3 instructions
+    const POWERS_OF_2: [Instruction; 4] = [
+        Instruction::Push(1),
+        Instruction::Push(2),
+        Instruction::Mul,
+        Instruction::Jump(1),
+    ];
+    const T: usize = 16;
+
+    fn prover(program: &[Instruction], steps: u64, top: u64) -> MockProver<Fr> {
+        let circuit = ZkvmCircuit::<Fr, T>::new(program.to_vec(), steps);
+        let instance = vec![Fr::from(steps), Fr::from(top)];
+        MockProver::run(ZkvmCircuit::<Fr, T>::k(), &circuit, vec![instance]).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        let top = |steps| {
+            run::<Fr>(&POWERS_OF_2, steps)
+                .unwrap()
+                .last()
+                .unwrap()
+                .stack[0]
+        };
+        assert_eq!(top(0), Fr::zero());
+        assert_eq!(top(1), Fr::one());
+        assert_eq!(top(10), Fr::from(8));
+        assert_eq!(top(11), Fr::from(2));
+        assert_eq!(top(12), Fr::from(16));
+        assert!(run::<Fr>(&[Instruction::Jump(7)], 2).is_none());
+
+        let add = [Instruction::Push(3), Instruction::Push(4), Instruction::Add];
+        let states = run::<Fr>(&add, 3).unwrap();
+        assert_eq!(
+            states[3].stack,
+            [Fr::from(7), Fr::zero(), Fr::zero(), Fr::zero()]
+        );
+    }
+
+    #[test]
+    fn powers_of_2() {
+        prover(&POWERS_OF_2, 0, 0).assert_satisfied();
+        prover(&POWERS_OF_2, 10, 8).assert_satisfied();
+        // halted in the middle of the loop
+        prover(&POWERS_OF_2, 11, 2).assert_satisfied();
+        prover(&POWERS_OF_2, T as u64, 32).assert_satisfied();
+    }
+
+    #[test]
+    fn wrong_top() {
+        testing::assert_fails_permutation(&prover(&POWERS_OF_2, 10, 16));
+    }
+
+    #[test]
+    fn too_many_steps() {
+        // n is still 1 on the last row
+        testing::assert_fails_permutation(&prover(&POWERS_OF_2, T as u64 + 1, 32));
+    }
+
+    #[test]
+    fn pc_out_of_program() {
+        let program = [Instruction::Push(1), Instruction::Jump(5)];
+        testing::assert_fails_lookup(&prover(&program, 3, 1), 2);
+    }
+}